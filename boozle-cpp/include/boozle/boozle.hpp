@@ -0,0 +1,10 @@
+// The boozle C++ runtime. Header-only, C++17, and usable without exceptions or
+// RTTI. `fd_transport.hpp` needs POSIX and isn't included here.
+
+#pragma once
+
+#include "connection.hpp"
+#include "object.hpp"
+#include "protocol.hpp"
+#include "result.hpp"
+#include "wire.hpp"
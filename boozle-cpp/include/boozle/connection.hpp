@@ -0,0 +1,345 @@
+// A boozle connection over a `Transport` that carries one `Msg` per frame.
+//
+// Connections are single-threaded: `call` sends a request and then handles
+// incoming messages, including calls from the peer, until its response
+// arrives. `poll` and `serve` handle incoming messages while not calling.
+//
+// Each end starts with a `Hello`, which the other answers with a `Welcome` or
+// rejects. Requests are only sent once the peer's `Welcome` arrived.
+
+#pragma once
+
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <random>
+#include <string>
+#include <unordered_map>
+#include <unordered_set>
+#include <utility>
+
+#include "object.hpp"
+#include "protocol.hpp"
+#include "result.hpp"
+#include "wire.hpp"
+
+namespace boozle {
+
+class Transport {
+ public:
+  virtual ~Transport() = default;
+
+  // Sends one frame. Returns false if the transport failed.
+  virtual bool send(const Bytes& frame) = 0;
+
+  // Blocks until a frame arrives. Returns false at the end of input or if the
+  // transport failed.
+  virtual bool receive(Bytes& frame) = 0;
+};
+
+class Connection;
+
+// A handle to an object owned by the peer. Handles received from the peer
+// free the object when destroyed. The connection must outlive its handles.
+class RemoteObject : public Object {
+ public:
+  RemoteObject(Connection& connection, uint64_t id, bool owned) : connection_(connection), id_(id), owned_(owned) {}
+  RemoteObject(const RemoteObject&) = delete;
+  RemoteObject& operator=(const RemoteObject&) = delete;
+  ~RemoteObject() override;
+
+  uint64_t id() const { return id_; }
+
+  Result<std::optional<Value>> call(uint64_t method_id, std::optional<Value> argument, bool returns_object) override;
+
+  // Forwards calls, for when this handle is passed back to the peer.
+  Result<std::optional<Value>> dispatch(uint64_t method_id, std::optional<Value> argument) override {
+    return call(method_id, std::move(argument), false);
+  }
+
+ private:
+  Connection& connection_;
+  uint64_t id_;
+  bool owned_;
+};
+
+class Connection {
+ public:
+  // Introduces itself as `node_id`, random by default.
+  explicit Connection(Transport& transport, uint64_t node_id = random_node_id()) : transport_(transport) {
+    // Pipelined programs (`Eval`) aren't supported, so no features are offered.
+    hello_.node_id = node_id;
+  }
+  Connection(const Connection&) = delete;
+  Connection& operator=(const Connection&) = delete;
+
+  // Handles still held in the pool are destroyed with it, without sending
+  // `Free`s.
+  ~Connection() { closed_ = true; }
+
+  // A handle to an object the peer has exposed under `object_id`. It isn't
+  // freed when destroyed.
+  std::shared_ptr<RemoteObject> remote(uint64_t object_id) {
+    return std::make_shared<RemoteObject>(*this, object_id, false);
+  }
+
+  // Makes `object` callable by the peer and returns its ID.
+  uint64_t expose(std::shared_ptr<Object> object) {
+    uint64_t id = next_expose_id_--;
+    pool_.insert_or_assign(id, Value(std::move(object)));
+    return id;
+  }
+
+  // The number of objects and stored results the peer holds references to.
+  size_t exposed_count() const { return pool_.size(); }
+
+  bool closed() const { return closed_; }
+
+  // Why the handshake failed, if it did.
+  const std::string& handshake_error() const { return handshake_error_; }
+
+  // What the peer agreed to, once its `Welcome` arrived.
+  const std::optional<protocol::Welcome>& welcome() const { return welcome_; }
+
+  // Handles messages until the peer's `Welcome` arrives. Returns false if the
+  // connection closed first.
+  bool handshake() {
+    while (!welcome_) {
+      if (!poll()) return false;
+    }
+    return true;
+  }
+
+  // Blocks until a message arrives and handles it. Returns false once the
+  // connection is closed.
+  bool poll() {
+    if (closed_) return false;
+    if (!hello_sent_) {
+      hello_sent_ = true;
+      if (!send(hello_)) return false;
+    }
+    Bytes frame;
+    protocol::Msg msg;
+    if (!transport_.receive(frame) || !protocol::decode_msg(frame, msg)) {
+      closed_ = true;
+      return false;
+    }
+    if (protocol::Hello* hello = std::get_if<protocol::Hello>(&msg)) {
+      auto answer = protocol::negotiate(hello_, *hello);
+      if (protocol::Welcome* welcome = std::get_if<protocol::Welcome>(&answer)) {
+        accepted_ = true;
+        return send(std::move(*welcome));
+      }
+      protocol::Incompatible reason = *std::get_if<protocol::Incompatible>(&answer);
+      send(reason);
+      return fail("rejected the peer: " + protocol::describe(reason));
+    }
+    if (protocol::Welcome* welcome = std::get_if<protocol::Welcome>(&msg)) {
+      if (!protocol::accepts(hello_, *welcome)) return fail("the peer didn't follow the handshake");
+      welcome_ = std::move(*welcome);
+      return true;
+    }
+    if (protocol::Incompatible* reason = std::get_if<protocol::Incompatible>(&msg)) {
+      return fail("rejected by the peer: " + protocol::describe(*reason));
+    }
+    // Requests and responses only come once the peer's `Hello` was accepted.
+    if (!accepted_) return fail("the peer didn't follow the handshake");
+    if (protocol::Res* res = std::get_if<protocol::Res>(&msg)) {
+      // Responses to `Free` aren't waited for.
+      if (waiting_.erase(res->id) != 0) completed_.insert_or_assign(res->id, std::move(*res));
+    } else {
+      on_req(std::move(*std::get_if<protocol::Req>(&msg)));
+    }
+    return true;
+  }
+
+  // Handles messages until the connection closes.
+  void serve() {
+    while (poll()) {
+    }
+  }
+
+  // Used by `RemoteObject`.
+  Result<std::optional<Value>> call(uint64_t object_id, uint64_t method_id, std::optional<Value> argument,
+                                    bool returns_object) {
+    if (!handshake()) return CallError::Comm;
+    protocol::Call call;
+    call.object_id = object_id;
+    call.method_id = method_id;
+    if (argument) call.argument = to_wire(std::move(*argument));
+    if (returns_object) call.to_object_id = next_object_id_++;
+
+    uint64_t id = next_request_id_++;
+    if (!send(protocol::Req{id, std::move(call)})) return CallError::Comm;
+    waiting_.insert(id);
+    auto done = completed_.find(id);
+    while (done == completed_.end()) {
+      if (!poll()) {
+        waiting_.erase(id);
+        return CallError::Comm;
+      }
+      done = completed_.find(id);
+    }
+    protocol::Res res = std::move(done->second);
+    completed_.erase(done);
+
+    if (const protocol::Error* error = std::get_if<protocol::Error>(&res.ty)) return error_of(error->code);
+    protocol::Return* ret = std::get_if<protocol::Return>(&res.ty);
+    if (ret == nullptr) return CallError::InvalidResponse;
+    if (!ret->value) return std::optional<Value>();
+    return std::optional<Value>(from_wire(std::move(*ret->value)));
+  }
+
+  // Used by `RemoteObject`. Sends a `Free` without waiting for its response.
+  void free(uint64_t object_id) {
+    if (closed_) return;
+    send(protocol::Req{next_request_id_++, protocol::Free{object_id}});
+  }
+
+ private:
+  static uint64_t random_node_id() {
+    std::random_device device;
+    return (static_cast<uint64_t>(device()) << 32) | device();
+  }
+
+  // Closes the connection after a failed handshake. Returns false.
+  bool fail(std::string error) {
+    handshake_error_ = std::move(error);
+    closed_ = true;
+    return false;
+  }
+
+  bool send(protocol::Msg msg) {
+    if (!transport_.send(protocol::encode_msg(msg))) {
+      closed_ = true;
+      return false;
+    }
+    return true;
+  }
+
+  // Converts an outgoing value, exposing the object it holds.
+  protocol::Value to_wire(Value value) {
+    if (value.is_lit()) return protocol::Value::lit(value.lit());
+    return protocol::Value::ref(expose(value.object()));
+  }
+
+  // Converts an incoming value. References name objects in the peer's pool and
+  // are freed once no longer used.
+  Value from_wire(protocol::Value value) {
+    if (value.kind == protocol::Value::Kind::Lit) return Value(std::move(value.bytes));
+    return Value(std::shared_ptr<Object>(std::make_shared<RemoteObject>(*this, value.id, true)));
+  }
+
+  // The error a failed request is reported as, like the Rust implementation's.
+  static CallError error_of(protocol::ErrorCode code) {
+    switch (code) {
+      case protocol::ErrorCode::UnknownMethod:
+        return CallError::UnknownMethod;
+      case protocol::ErrorCode::InvalidArgument:
+        return CallError::InvalidArgument;
+      case protocol::ErrorCode::Internal:
+        return CallError::Internal;
+      case protocol::ErrorCode::DeadlineExceeded:
+        return CallError::Timeout;
+      case protocol::ErrorCode::LimitExceeded:
+        return CallError::LimitExceeded;
+      case protocol::ErrorCode::UnknownObject:
+      case protocol::ErrorCode::Application:
+        break;
+    }
+    return CallError::Failed;
+  }
+
+  // The code a failed call is reported to the peer with.
+  static protocol::ErrorCode code_of(CallError error) {
+    switch (error) {
+      case CallError::UnknownMethod:
+        return protocol::ErrorCode::UnknownMethod;
+      case CallError::InvalidArgument:
+        return protocol::ErrorCode::InvalidArgument;
+      case CallError::Internal:
+        return protocol::ErrorCode::Internal;
+      case CallError::LimitExceeded:
+        return protocol::ErrorCode::LimitExceeded;
+      case CallError::InvalidResponse:
+      case CallError::Failed:
+      case CallError::Comm:
+      case CallError::Timeout:
+      case CallError::Deadlock:
+        break;
+    }
+    return protocol::ErrorCode::Application;
+  }
+
+  // Calls run to completion before the next message is read, so deadlines
+  // can't be enforced and cancellations always arrive too late.
+  void on_req(protocol::Req req) {
+    if (std::holds_alternative<protocol::Cancel>(req.ty)) return;
+    protocol::Res res;
+    res.id = req.id;
+    if (protocol::Free* free = std::get_if<protocol::Free>(&req.ty)) {
+      if (pool_.erase(free->object_id) != 0) {
+        res.ty = std::monostate{};
+      } else {
+        res.ty = protocol::Error{protocol::ErrorCode::UnknownObject, "no object " + std::to_string(free->object_id)};
+      }
+    } else {
+      res.ty = on_call(*std::get_if<protocol::Call>(&req.ty));
+    }
+    send(std::move(res));
+  }
+
+  // Like the Rust implementation, failures are reported as errors, literal
+  // results are returned as they are, and object results are only returned
+  // when the caller asked for the result to be stored.
+  protocol::ResTy on_call(protocol::Call& call) {
+    auto entry = pool_.find(call.object_id);
+    if (entry == pool_.end() || entry->second.is_lit()) {
+      return protocol::Error{protocol::ErrorCode::UnknownObject, "no object " + std::to_string(call.object_id)};
+    }
+    std::shared_ptr<Object> target = entry->second.object();
+
+    std::optional<Value> argument;
+    if (call.argument) argument = from_wire(std::move(*call.argument));
+    Result<std::optional<Value>> result = target->dispatch(call.method_id, std::move(argument));
+    if (!result) return protocol::Error{code_of(result.error()), to_string(result.error())};
+    if (!*result) return protocol::Return{};
+
+    Value value = std::move(**result);
+    if (value.is_lit()) return protocol::Return{protocol::Value::lit(value.lit())};
+    if (call.to_object_id) {
+      pool_.insert_or_assign(*call.to_object_id, std::move(value));
+      return protocol::Return{protocol::Value::ref(*call.to_object_id)};
+    }
+    return protocol::Return{};
+  }
+
+  Transport& transport_;
+  protocol::Hello hello_;
+  bool hello_sent_ = false;
+  // Whether the peer's `Hello` was accepted.
+  bool accepted_ = false;
+  std::optional<protocol::Welcome> welcome_;
+  std::string handshake_error_;
+  // Objects and stored results the peer may refer to.
+  std::unordered_map<uint64_t, Value> pool_;
+  // Requests whose responses haven't arrived, and responses not yet collected.
+  std::unordered_set<uint64_t> waiting_;
+  std::unordered_map<uint64_t, protocol::Res> completed_;
+  uint64_t next_request_id_ = 1;
+  uint64_t next_object_id_ = 0;
+  // Keys of exposed objects count down from here, like the Rust pool's.
+  uint64_t next_expose_id_ = UINT64_MAX;
+  bool closed_ = false;
+};
+
+inline RemoteObject::~RemoteObject() {
+  if (owned_) connection_.free(id_);
+}
+
+inline Result<std::optional<Value>> RemoteObject::call(uint64_t method_id, std::optional<Value> argument,
+                                                       bool returns_object) {
+  return connection_.call(id_, method_id, std::move(argument), returns_object);
+}
+
+}  // namespace boozle
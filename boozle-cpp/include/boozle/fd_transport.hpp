@@ -0,0 +1,71 @@
+// A `Transport` over POSIX file descriptors, such as a pipe or a socket. Each
+// frame is a `u32` little-endian length followed by that many bytes, matching
+// `boozle::transport::stream` in Rust.
+
+#pragma once
+
+#include <errno.h>
+#include <unistd.h>
+
+#include <cstddef>
+#include <cstdint>
+
+#include "connection.hpp"
+#include "wire.hpp"
+
+namespace boozle {
+
+// Frames longer than this are treated as a broken stream.
+constexpr uint32_t MAX_FRAME = 16 * 1024 * 1024;
+
+class FdTransport : public Transport {
+ public:
+  // Reads frames from `in` and writes them to `out`, which may be the same
+  // descriptor. The descriptors aren't closed.
+  FdTransport(int in, int out) : in_(in), out_(out) {}
+
+  bool send(const Bytes& frame) override {
+    if (frame.size() > MAX_FRAME) return false;
+    uint8_t header[4];
+    for (size_t i = 0; i < 4; i++) header[i] = static_cast<uint8_t>(frame.size() >> (8 * i));
+    return write_all(header, sizeof(header)) && write_all(frame.data(), frame.size());
+  }
+
+  bool receive(Bytes& frame) override {
+    uint8_t header[4];
+    if (!read_exact(header, sizeof(header))) return false;
+    uint32_t size = 0;
+    for (size_t i = 0; i < 4; i++) size |= static_cast<uint32_t>(header[i]) << (8 * i);
+    if (size > MAX_FRAME) return false;
+    frame.resize(size);
+    return read_exact(frame.data(), size);
+  }
+
+ private:
+  bool write_all(const uint8_t* data, size_t size) {
+    while (size > 0) {
+      ssize_t written = ::write(out_, data, size);
+      if (written < 0 && errno == EINTR) continue;
+      if (written <= 0) return false;
+      data += written;
+      size -= static_cast<size_t>(written);
+    }
+    return true;
+  }
+
+  bool read_exact(uint8_t* data, size_t size) {
+    while (size > 0) {
+      ssize_t read = ::read(in_, data, size);
+      if (read < 0 && errno == EINTR) continue;
+      if (read <= 0) return false;
+      data += read;
+      size -= static_cast<size_t>(read);
+    }
+    return true;
+  }
+
+  int in_;
+  int out_;
+};
+
+}  // namespace boozle
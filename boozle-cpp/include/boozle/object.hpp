@@ -0,0 +1,75 @@
+// Values and objects as seen by generated code.
+
+#pragma once
+
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <utility>
+
+#include "result.hpp"
+#include "wire.hpp"
+
+namespace boozle {
+
+class Object;
+
+// A literal, as its encoded bytes, or an object.
+class Value {
+ public:
+  Value(Bytes lit) : lit_(std::move(lit)) {}
+  Value(std::shared_ptr<Object> object) : object_(std::move(object)) {}
+
+  bool is_lit() const { return object_ == nullptr; }
+  const Bytes& lit() const { return lit_; }
+  const std::shared_ptr<Object>& object() const { return object_; }
+
+ private:
+  Bytes lit_;
+  std::shared_ptr<Object> object_;
+};
+
+// Something that handles method calls: a local implementation (generated
+// `FooObject` classes) or a handle to the peer's object (`RemoteObject`).
+class Object {
+ public:
+  virtual ~Object() = default;
+
+  virtual Result<std::optional<Value>> dispatch(uint64_t method_id, std::optional<Value> argument) = 0;
+
+  // Like `dispatch`. Set `returns_object` for methods that return an object,
+  // so that a remote callee keeps the result and replies with a reference.
+  virtual Result<std::optional<Value>> call(uint64_t method_id, std::optional<Value> argument, bool returns_object) {
+    (void)returns_object;
+    return dispatch(method_id, std::move(argument));
+  }
+};
+
+// Base of generated interfaces.
+class Interface {
+ public:
+  virtual ~Interface() = default;
+
+  // The object a generated client calls, so that passing the client on
+  // doesn't wrap it again. Null for other implementations.
+  virtual std::shared_ptr<Object> boozle_object() const { return nullptr; }
+};
+
+// Encodes literals, in order, as one value.
+template <typename... T>
+Value lit(const T&... values) {
+  Writer writer;
+  (encode(writer, values), ...);
+  return Value(writer.finish());
+}
+
+// Decodes a literal into `out`, in order. Returns false unless `value` is a
+// literal that holds exactly those values.
+template <typename... T>
+bool read_lit(const std::optional<Value>& value, T&... out) {
+  if (!value || !value->is_lit()) return false;
+  Reader reader(value->lit());
+  return (decode(reader, out) && ...) && reader.done();
+}
+
+}  // namespace boozle
@@ -0,0 +1,506 @@
+// The boozle wire protocol, mirroring `boozle::protocol` in Rust. Every
+// transport frame is a bincode `Msg` whose payload is a bincode `Req` or `Res`.
+
+#pragma once
+
+#include <algorithm>
+#include <cstdint>
+#include <map>
+#include <optional>
+#include <string>
+#include <variant>
+#include <vector>
+
+#include "wire.hpp"
+
+namespace boozle {
+namespace protocol {
+
+// A value on the wire: literal bytes, or an object in the sender's pool.
+struct Value {
+  enum class Kind { Lit, Ref };
+
+  Kind kind = Kind::Lit;
+  Bytes bytes;
+  uint32_t owner = 0;
+  uint64_t id = 0;
+
+  static Value lit(Bytes bytes) {
+    Value value;
+    value.bytes = std::move(bytes);
+    return value;
+  }
+
+  static Value ref(uint64_t id) {
+    Value value;
+    value.kind = Kind::Ref;
+    value.id = id;
+    return value;
+  }
+};
+
+struct Call {
+  bool mutable_ = false;
+  uint64_t object_id = 0;
+  uint64_t method_id = 0;
+  std::optional<Value> argument;
+  // Asks the callee to keep the result in its pool under this ID.
+  std::optional<uint64_t> to_object_id;
+  // How many milliseconds the caller waits for the response, counted from when
+  // the call arrives.
+  std::optional<uint64_t> deadline;
+  // Key/value pairs the callee may look at, e.g. to trace or authenticate the
+  // call.
+  std::map<std::string, std::string> metadata;
+};
+
+struct Free {
+  uint64_t object_id = 0;
+};
+
+// Tells the receiver to stop handling request `req_id`, which won't get a
+// response.
+struct Cancel {
+  uint64_t req_id = 0;
+};
+
+struct Req {
+  uint64_t id = 0;
+  std::variant<Call, Free, Cancel> ty;
+};
+
+struct Return {
+  std::optional<Value> value;
+};
+
+// Mirrors `boozle::protocol::res::ErrorCode`, in order.
+enum class ErrorCode : uint32_t {
+  UnknownObject,
+  UnknownMethod,
+  InvalidArgument,
+  Application,
+  Internal,
+  DeadlineExceeded,
+  LimitExceeded,
+};
+
+struct Error {
+  ErrorCode code = ErrorCode::Internal;
+  std::string message;
+};
+
+// `Free` responses carry nothing, so they're represented by `std::monostate`.
+using ResTy = std::variant<Return, std::monostate, Error>;
+
+struct Res {
+  uint64_t id = 0;
+  ResTy ty;
+};
+
+// The newest and oldest protocol versions this implementation speaks.
+constexpr uint16_t VERSION = 1;
+constexpr uint16_t MIN_VERSION = 1;
+
+constexpr const char* CODEC_BINCODE = "bincode";
+constexpr const char* COMPRESSOR_NONE = "none";
+
+// Starts the handshake, mirroring `boozle::protocol::hello::Hello`.
+struct Hello {
+  uint16_t min_version = MIN_VERSION;
+  uint16_t max_version = VERSION;
+  uint64_t node_id = 0;
+  // Supported codecs and compressors, most preferred first.
+  std::vector<std::string> codecs{CODEC_BINCODE};
+  std::vector<std::string> compressors{COMPRESSOR_NONE};
+  std::vector<std::string> features;
+};
+
+// What the sender of a `Hello` may use for the rest of the connection.
+struct Welcome {
+  uint16_t version = 0;
+  // The node answering the `Hello`.
+  uint64_t node_id = 0;
+  std::string codec;
+  std::string compressor;
+  // The features both ends support.
+  std::vector<std::string> features;
+};
+
+// Why a `Hello` was rejected. `Version` carries the versions the rejecting end
+// supports in `min` and `max`.
+struct Incompatible {
+  enum class Kind : uint32_t { Version, Codec, Compressor };
+
+  Kind kind = Kind::Version;
+  uint16_t min = 0;
+  uint16_t max = 0;
+};
+
+using Msg = std::variant<Req, Res, Hello, Welcome, Incompatible>;
+
+namespace detail {
+
+inline bool contains(const std::vector<std::string>& values, const std::string& value) {
+  return std::find(values.begin(), values.end(), value) != values.end();
+}
+
+// The first of `theirs` that's also in `ours`.
+inline const std::string* first_common(const std::vector<std::string>& ours, const std::vector<std::string>& theirs) {
+  for (const std::string& value : theirs) {
+    if (contains(ours, value)) return &value;
+  }
+  return nullptr;
+}
+
+}  // namespace detail
+
+// Answers `theirs` given what `ours` supports, like the Rust implementation:
+// the highest common version, and the codec and compressor the sender of
+// `theirs` prefers most.
+inline std::variant<Welcome, Incompatible> negotiate(const Hello& ours, const Hello& theirs) {
+  uint16_t version = std::min(ours.max_version, theirs.max_version);
+  if (version < std::max(ours.min_version, theirs.min_version)) {
+    return Incompatible{Incompatible::Kind::Version, ours.min_version, ours.max_version};
+  }
+  const std::string* codec = detail::first_common(ours.codecs, theirs.codecs);
+  if (codec == nullptr) return Incompatible{Incompatible::Kind::Codec};
+  const std::string* compressor = detail::first_common(ours.compressors, theirs.compressors);
+  if (compressor == nullptr) return Incompatible{Incompatible::Kind::Compressor};
+  Welcome welcome{version, ours.node_id, *codec, *compressor, {}};
+  for (const std::string& feature : theirs.features) {
+    if (detail::contains(ours.features, feature)) welcome.features.push_back(feature);
+  }
+  return welcome;
+}
+
+inline std::string describe(const Incompatible& reason) {
+  switch (reason.kind) {
+    case Incompatible::Kind::Version:
+      return "no common protocol version, expected " + std::to_string(reason.min) + " to " + std::to_string(reason.max);
+    case Incompatible::Kind::Codec:
+      return "no common codec";
+    case Incompatible::Kind::Compressor:
+      return "no common compressor";
+  }
+  return "incompatible";
+}
+
+// Whether `welcome` is a valid answer to `hello`.
+inline bool accepts(const Hello& hello, const Welcome& welcome) {
+  if (welcome.version < hello.min_version || welcome.version > hello.max_version) return false;
+  if (!detail::contains(hello.codecs, welcome.codec) || !detail::contains(hello.compressors, welcome.compressor)) {
+    return false;
+  }
+  for (const std::string& feature : welcome.features) {
+    if (!detail::contains(hello.features, feature)) return false;
+  }
+  return true;
+}
+
+namespace detail {
+
+inline void write_value(Writer& writer, const Value& value) {
+  if (value.kind == Value::Kind::Lit) {
+    writer.u32(0);
+    writer.bytes(value.bytes);
+  } else {
+    writer.u32(1);
+    writer.u32(value.owner);
+    writer.u64(value.id);
+  }
+}
+
+inline bool read_value(Reader& reader, Value& value) {
+  uint32_t variant;
+  if (!reader.u32(variant)) return false;
+  switch (variant) {
+    case 0:
+      value.kind = Value::Kind::Lit;
+      return reader.bytes(value.bytes);
+    case 1:
+      value.kind = Value::Kind::Ref;
+      return reader.u32(value.owner) && reader.u64(value.id);
+    default:
+      return false;
+  }
+}
+
+inline bool read_option_tag(Reader& reader, bool& present) { return reader.boolean(present); }
+
+inline void write_strings(Writer& writer, const std::vector<std::string>& values) {
+  writer.u64(values.size());
+  for (const std::string& value : values) writer.string(value);
+}
+
+inline void write_metadata(Writer& writer, const std::map<std::string, std::string>& metadata) {
+  writer.u64(metadata.size());
+  for (const auto& [key, value] : metadata) {
+    writer.string(key);
+    writer.string(value);
+  }
+}
+
+inline bool read_metadata(Reader& reader, std::map<std::string, std::string>& metadata) {
+  uint64_t size;
+  if (!reader.u64(size)) return false;
+  metadata.clear();
+  for (uint64_t i = 0; i < size; i++) {
+    std::string key;
+    std::string value;
+    if (!reader.string(key) || !reader.string(value)) return false;
+    metadata.insert_or_assign(std::move(key), std::move(value));
+  }
+  return true;
+}
+
+inline bool read_strings(Reader& reader, std::vector<std::string>& values) {
+  uint64_t size;
+  if (!reader.u64(size)) return false;
+  values.clear();
+  for (uint64_t i = 0; i < size; i++) {
+    std::string value;
+    if (!reader.string(value)) return false;
+    values.push_back(std::move(value));
+  }
+  return true;
+}
+
+inline Bytes encode_hello(const Hello& hello) {
+  Writer writer;
+  writer.u16(hello.min_version);
+  writer.u16(hello.max_version);
+  writer.u64(hello.node_id);
+  write_strings(writer, hello.codecs);
+  write_strings(writer, hello.compressors);
+  write_strings(writer, hello.features);
+  return writer.finish();
+}
+
+inline bool decode_hello(Reader& reader, Hello& hello) {
+  return reader.u16(hello.min_version) && reader.u16(hello.max_version) && reader.u64(hello.node_id) &&
+         read_strings(reader, hello.codecs) && read_strings(reader, hello.compressors) &&
+         read_strings(reader, hello.features);
+}
+
+inline Bytes encode_welcome(const Welcome& welcome) {
+  Writer writer;
+  writer.u16(welcome.version);
+  writer.u64(welcome.node_id);
+  writer.string(welcome.codec);
+  writer.string(welcome.compressor);
+  write_strings(writer, welcome.features);
+  return writer.finish();
+}
+
+inline bool decode_welcome(Reader& reader, Welcome& welcome) {
+  return reader.u16(welcome.version) && reader.u64(welcome.node_id) && reader.string(welcome.codec) &&
+         reader.string(welcome.compressor) && read_strings(reader, welcome.features);
+}
+
+inline Bytes encode_incompatible(const Incompatible& reason) {
+  Writer writer;
+  writer.u32(static_cast<uint32_t>(reason.kind));
+  if (reason.kind == Incompatible::Kind::Version) {
+    writer.u16(reason.min);
+    writer.u16(reason.max);
+  }
+  return writer.finish();
+}
+
+inline bool decode_incompatible(Reader& reader, Incompatible& reason) {
+  uint32_t kind;
+  if (!reader.u32(kind) || kind > static_cast<uint32_t>(Incompatible::Kind::Compressor)) return false;
+  reason.kind = static_cast<Incompatible::Kind>(kind);
+  if (reason.kind != Incompatible::Kind::Version) return true;
+  return reader.u16(reason.min) && reader.u16(reason.max);
+}
+
+inline Bytes encode_req(const Req& req) {
+  Writer writer;
+  writer.u64(req.id);
+  if (const Call* call = std::get_if<Call>(&req.ty)) {
+    writer.u32(0);
+    writer.boolean(call->mutable_);
+    writer.u64(call->object_id);
+    writer.u64(call->method_id);
+    writer.boolean(call->argument.has_value());
+    if (call->argument) write_value(writer, *call->argument);
+    writer.boolean(call->to_object_id.has_value());
+    if (call->to_object_id) writer.u64(*call->to_object_id);
+    writer.boolean(call->deadline.has_value());
+    if (call->deadline) writer.u64(*call->deadline);
+    write_metadata(writer, call->metadata);
+  } else if (const Free* free = std::get_if<Free>(&req.ty)) {
+    writer.u32(1);
+    writer.u64(free->object_id);
+  } else {
+    writer.u32(3);
+    writer.u64(std::get_if<Cancel>(&req.ty)->req_id);
+  }
+  return writer.finish();
+}
+
+inline bool decode_req(Reader& reader, Req& req) {
+  uint32_t variant;
+  if (!reader.u64(req.id) || !reader.u32(variant)) return false;
+  if (variant == 0) {
+    Call call;
+    bool present;
+    if (!reader.boolean(call.mutable_) || !reader.u64(call.object_id) || !reader.u64(call.method_id)) return false;
+    if (!read_option_tag(reader, present)) return false;
+    if (present) {
+      call.argument.emplace();
+      if (!read_value(reader, *call.argument)) return false;
+    }
+    if (!read_option_tag(reader, present)) return false;
+    if (present) {
+      call.to_object_id.emplace();
+      if (!reader.u64(*call.to_object_id)) return false;
+    }
+    if (!read_option_tag(reader, present)) return false;
+    if (present) {
+      call.deadline.emplace();
+      if (!reader.u64(*call.deadline)) return false;
+    }
+    if (!read_metadata(reader, call.metadata)) return false;
+    req.ty = std::move(call);
+    return true;
+  }
+  if (variant == 1) {
+    Free free;
+    if (!reader.u64(free.object_id)) return false;
+    req.ty = free;
+    return true;
+  }
+  if (variant == 3) {
+    Cancel cancel;
+    if (!reader.u64(cancel.req_id)) return false;
+    req.ty = cancel;
+    return true;
+  }
+  return false;
+}
+
+// Variants of `Res`. `Eval` (2) isn't supported.
+constexpr uint32_t RES_CALL = 0;
+constexpr uint32_t RES_FREE = 1;
+constexpr uint32_t RES_ERROR = 3;
+
+inline Bytes encode_res(const Res& res) {
+  Writer writer;
+  writer.u64(res.id);
+  if (const Return* ret = std::get_if<Return>(&res.ty)) {
+    writer.u32(RES_CALL);
+    writer.boolean(ret->value.has_value());
+    if (ret->value) write_value(writer, *ret->value);
+  } else if (const Error* error = std::get_if<Error>(&res.ty)) {
+    writer.u32(RES_ERROR);
+    writer.u32(static_cast<uint32_t>(error->code));
+    writer.string(error->message);
+  } else {
+    writer.u32(RES_FREE);
+  }
+  return writer.finish();
+}
+
+inline bool decode_res(Reader& reader, Res& res) {
+  uint32_t variant;
+  if (!reader.u64(res.id) || !reader.u32(variant)) return false;
+  if (variant == RES_CALL) {
+    Return ret;
+    bool present;
+    if (!read_option_tag(reader, present)) return false;
+    if (present) {
+      ret.value.emplace();
+      if (!read_value(reader, *ret.value)) return false;
+    }
+    res.ty = std::move(ret);
+    return true;
+  }
+  if (variant == RES_FREE) {
+    res.ty = std::monostate{};
+    return true;
+  }
+  if (variant == RES_ERROR) {
+    Error error;
+    uint32_t code;
+    if (!reader.u32(code) || code > static_cast<uint32_t>(ErrorCode::LimitExceeded)) return false;
+    error.code = static_cast<ErrorCode>(code);
+    if (!reader.string(error.message)) return false;
+    res.ty = std::move(error);
+    return true;
+  }
+  return false;
+}
+
+}  // namespace detail
+
+constexpr uint32_t DIR_REQ = 0;
+constexpr uint32_t DIR_RES = 1;
+constexpr uint32_t DIR_HELLO = 2;
+constexpr uint32_t DIR_WELCOME = 3;
+constexpr uint32_t DIR_REJECT = 4;
+
+inline Bytes encode_msg(const Msg& msg) {
+  Writer writer;
+  if (const Req* req = std::get_if<Req>(&msg)) {
+    writer.u32(DIR_REQ);
+    writer.bytes(detail::encode_req(*req));
+  } else if (const Res* res = std::get_if<Res>(&msg)) {
+    writer.u32(DIR_RES);
+    writer.bytes(detail::encode_res(*res));
+  } else if (const Hello* hello = std::get_if<Hello>(&msg)) {
+    writer.u32(DIR_HELLO);
+    writer.bytes(detail::encode_hello(*hello));
+  } else if (const Welcome* welcome = std::get_if<Welcome>(&msg)) {
+    writer.u32(DIR_WELCOME);
+    writer.bytes(detail::encode_welcome(*welcome));
+  } else {
+    writer.u32(DIR_REJECT);
+    writer.bytes(detail::encode_incompatible(*std::get_if<Incompatible>(&msg)));
+  }
+  return writer.finish();
+}
+
+// Returns false if `bytes` isn't exactly one valid message.
+inline bool decode_msg(const Bytes& bytes, Msg& msg) {
+  Reader reader(bytes);
+  uint32_t dir;
+  Bytes data;
+  if (!reader.u32(dir) || !reader.bytes(data) || !reader.done()) return false;
+  Reader inner(data);
+  if (dir == DIR_REQ) {
+    Req req;
+    if (!detail::decode_req(inner, req) || !inner.done()) return false;
+    msg = std::move(req);
+    return true;
+  }
+  if (dir == DIR_RES) {
+    Res res;
+    if (!detail::decode_res(inner, res) || !inner.done()) return false;
+    msg = std::move(res);
+    return true;
+  }
+  if (dir == DIR_HELLO) {
+    Hello hello;
+    if (!detail::decode_hello(inner, hello) || !inner.done()) return false;
+    msg = std::move(hello);
+    return true;
+  }
+  if (dir == DIR_WELCOME) {
+    Welcome welcome;
+    if (!detail::decode_welcome(inner, welcome) || !inner.done()) return false;
+    msg = std::move(welcome);
+    return true;
+  }
+  if (dir == DIR_REJECT) {
+    Incompatible reason;
+    if (!detail::decode_incompatible(inner, reason) || !inner.done()) return false;
+    msg = reason;
+    return true;
+  }
+  return false;
+}
+
+}  // namespace protocol
+}  // namespace boozle
@@ -0,0 +1,90 @@
+// Results of boozle calls. The runtime doesn't use exceptions, so it can be
+// built with -fno-exceptions.
+
+#pragma once
+
+#include <optional>
+#include <utility>
+#include <variant>
+
+namespace boozle {
+
+// Mirrors `boozle::object::CallError` in Rust.
+enum class CallError {
+  InvalidResponse,
+  Failed,
+  Comm,
+  UnknownMethod,
+  InvalidArgument,
+  Timeout,
+  Internal,
+  LimitExceeded,
+  Deadlock,
+};
+
+inline const char* to_string(CallError error) {
+  switch (error) {
+    case CallError::InvalidResponse:
+      return "InvalidResponse";
+    case CallError::Failed:
+      return "Failed";
+    case CallError::Comm:
+      return "Comm";
+    case CallError::UnknownMethod:
+      return "UnknownMethod";
+    case CallError::InvalidArgument:
+      return "InvalidArgument";
+    case CallError::Timeout:
+      return "Timeout";
+    case CallError::Internal:
+      return "Internal";
+    case CallError::LimitExceeded:
+      return "LimitExceeded";
+    case CallError::Deadlock:
+      return "Deadlock";
+  }
+  return "Unknown";
+}
+
+// Either a `T` or the `CallError` that prevented it.
+template <typename T>
+class [[nodiscard]] Result {
+ public:
+  Result(T value) : inner_(std::in_place_index<0>, std::move(value)) {}
+  Result(CallError error) : inner_(std::in_place_index<1>, error) {}
+
+  bool ok() const { return inner_.index() == 0; }
+  explicit operator bool() const { return ok(); }
+
+  // Only valid if `ok()`.
+  T& value() { return *std::get_if<0>(&inner_); }
+  const T& value() const { return *std::get_if<0>(&inner_); }
+  T& operator*() { return value(); }
+  const T& operator*() const { return value(); }
+  T* operator->() { return &value(); }
+  const T* operator->() const { return &value(); }
+
+  // Only valid if `!ok()`.
+  CallError error() const { return *std::get_if<1>(&inner_); }
+
+ private:
+  std::variant<T, CallError> inner_;
+};
+
+template <>
+class [[nodiscard]] Result<void> {
+ public:
+  Result() = default;
+  Result(CallError error) : error_(error) {}
+
+  bool ok() const { return !error_.has_value(); }
+  explicit operator bool() const { return ok(); }
+
+  // Only valid if `!ok()`.
+  CallError error() const { return *error_; }
+
+ private:
+  std::optional<CallError> error_;
+};
+
+}  // namespace boozle
@@ -0,0 +1,190 @@
+// Reads and writes the bincode 1 format used by the Rust implementation:
+// little-endian fixed-size integers, `u64` length prefixes, `u32` enum variant
+// indices and a one-byte tag for `Option`.
+
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <string>
+#include <vector>
+
+namespace boozle {
+
+using Bytes = std::vector<uint8_t>;
+
+class Writer {
+ public:
+  void u8(uint8_t value) { data_.push_back(value); }
+  void u16(uint16_t value) { put(value); }
+  void u32(uint32_t value) { put(value); }
+  void u64(uint64_t value) { put(value); }
+  void i8(int8_t value) { put(static_cast<uint8_t>(value)); }
+  void i16(int16_t value) { put(static_cast<uint16_t>(value)); }
+  void i32(int32_t value) { put(static_cast<uint32_t>(value)); }
+  void i64(int64_t value) { put(static_cast<uint64_t>(value)); }
+
+  void f32(float value) {
+    uint32_t bits;
+    std::memcpy(&bits, &value, sizeof(bits));
+    put(bits);
+  }
+
+  void f64(double value) {
+    uint64_t bits;
+    std::memcpy(&bits, &value, sizeof(bits));
+    put(bits);
+  }
+
+  void boolean(bool value) { u8(value ? 1 : 0); }
+
+  // Writes bytes without a length prefix.
+  void raw(const uint8_t* data, size_t size) { data_.insert(data_.end(), data, data + size); }
+  void raw(const Bytes& bytes) { raw(bytes.data(), bytes.size()); }
+
+  // Writes a length-prefixed byte sequence (`Vec<u8>`, `Box<[u8]>`).
+  void bytes(const Bytes& bytes) {
+    u64(bytes.size());
+    raw(bytes);
+  }
+
+  void string(const std::string& value) {
+    u64(value.size());
+    raw(reinterpret_cast<const uint8_t*>(value.data()), value.size());
+  }
+
+  const Bytes& data() const { return data_; }
+  Bytes finish() { return std::move(data_); }
+
+ private:
+  template <typename U>
+  void put(U value) {
+    for (size_t i = 0; i < sizeof(U); i++) {
+      data_.push_back(static_cast<uint8_t>(value >> (8 * i)));
+    }
+  }
+
+  Bytes data_;
+};
+
+// Every read returns false, leaving its output unspecified, if the input is
+// too short or invalid.
+class Reader {
+ public:
+  Reader(const uint8_t* data, size_t size) : data_(data), size_(size) {}
+  explicit Reader(const Bytes& bytes) : Reader(bytes.data(), bytes.size()) {}
+
+  bool u8(uint8_t& value) { return get(value); }
+  bool u16(uint16_t& value) { return get(value); }
+  bool u32(uint32_t& value) { return get(value); }
+  bool u64(uint64_t& value) { return get(value); }
+  bool i8(int8_t& value) { return get_signed<uint8_t>(value); }
+  bool i16(int16_t& value) { return get_signed<uint16_t>(value); }
+  bool i32(int32_t& value) { return get_signed<uint32_t>(value); }
+  bool i64(int64_t& value) { return get_signed<uint64_t>(value); }
+
+  bool f32(float& value) {
+    uint32_t bits;
+    if (!get(bits)) return false;
+    std::memcpy(&value, &bits, sizeof(bits));
+    return true;
+  }
+
+  bool f64(double& value) {
+    uint64_t bits;
+    if (!get(bits)) return false;
+    std::memcpy(&value, &bits, sizeof(bits));
+    return true;
+  }
+
+  bool boolean(bool& value) {
+    uint8_t byte;
+    if (!u8(byte) || byte > 1) return false;
+    value = byte == 1;
+    return true;
+  }
+
+  // Reads `size` bytes without a length prefix.
+  bool raw(size_t size, Bytes& bytes) {
+    if (size > remaining()) return false;
+    bytes.assign(data_ + offset_, data_ + offset_ + size);
+    offset_ += size;
+    return true;
+  }
+
+  // Reads everything that's left.
+  bool rest(Bytes& bytes) { return raw(remaining(), bytes); }
+
+  bool bytes(Bytes& bytes) {
+    uint64_t size;
+    return u64(size) && size <= remaining() && raw(static_cast<size_t>(size), bytes);
+  }
+
+  bool string(std::string& value) {
+    Bytes bytes;
+    if (!this->bytes(bytes)) return false;
+    value.assign(bytes.begin(), bytes.end());
+    return true;
+  }
+
+  size_t remaining() const { return size_ - offset_; }
+  bool done() const { return offset_ == size_; }
+
+ private:
+  template <typename U>
+  bool get(U& value) {
+    if (sizeof(U) > remaining()) return false;
+    value = 0;
+    for (size_t i = 0; i < sizeof(U); i++) {
+      value |= static_cast<U>(static_cast<U>(data_[offset_ + i]) << (8 * i));
+    }
+    offset_ += sizeof(U);
+    return true;
+  }
+
+  template <typename U, typename S>
+  bool get_signed(S& value) {
+    U bits;
+    if (!get(bits)) return false;
+    value = static_cast<S>(bits);
+    return true;
+  }
+
+  const uint8_t* data_;
+  size_t size_;
+  size_t offset_ = 0;
+};
+
+// Literal types, as used by generated code. `Bytes` stands for a value the
+// generator has no mapping for and is passed through as its encoding, so it
+// must be the last of several values.
+inline void encode(Writer& writer, bool value) { writer.boolean(value); }
+inline void encode(Writer& writer, uint8_t value) { writer.u8(value); }
+inline void encode(Writer& writer, uint16_t value) { writer.u16(value); }
+inline void encode(Writer& writer, uint32_t value) { writer.u32(value); }
+inline void encode(Writer& writer, uint64_t value) { writer.u64(value); }
+inline void encode(Writer& writer, int8_t value) { writer.i8(value); }
+inline void encode(Writer& writer, int16_t value) { writer.i16(value); }
+inline void encode(Writer& writer, int32_t value) { writer.i32(value); }
+inline void encode(Writer& writer, int64_t value) { writer.i64(value); }
+inline void encode(Writer& writer, float value) { writer.f32(value); }
+inline void encode(Writer& writer, double value) { writer.f64(value); }
+inline void encode(Writer& writer, const std::string& value) { writer.string(value); }
+inline void encode(Writer& writer, const Bytes& value) { writer.raw(value); }
+
+inline bool decode(Reader& reader, bool& value) { return reader.boolean(value); }
+inline bool decode(Reader& reader, uint8_t& value) { return reader.u8(value); }
+inline bool decode(Reader& reader, uint16_t& value) { return reader.u16(value); }
+inline bool decode(Reader& reader, uint32_t& value) { return reader.u32(value); }
+inline bool decode(Reader& reader, uint64_t& value) { return reader.u64(value); }
+inline bool decode(Reader& reader, int8_t& value) { return reader.i8(value); }
+inline bool decode(Reader& reader, int16_t& value) { return reader.i16(value); }
+inline bool decode(Reader& reader, int32_t& value) { return reader.i32(value); }
+inline bool decode(Reader& reader, int64_t& value) { return reader.i64(value); }
+inline bool decode(Reader& reader, float& value) { return reader.f32(value); }
+inline bool decode(Reader& reader, double& value) { return reader.f64(value); }
+inline bool decode(Reader& reader, std::string& value) { return reader.string(value); }
+inline bool decode(Reader& reader, Bytes& value) { return reader.rest(value); }
+
+}  // namespace boozle
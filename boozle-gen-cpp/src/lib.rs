@@ -0,0 +1,550 @@
+//! Generates C++ headers for boozle protocols. Input is a schema descriptor
+//! (see `boozle_gen::descriptor`), so this also runs as a `boozle-gen` plugin.
+//!
+//! A protocol tree becomes a single header named after the root module, with
+//! one namespace per module. Keeping the tree in one header lets modules refer
+//! to each other without include cycles. For a trait `Foo` the header has:
+//!
+//! - `class Foo`, an abstract class to implement,
+//! - `class FooClient : public Foo`, calling an object, usually the peer's,
+//! - `class FooObject : public boozle::Object`, exposing a local `Foo`.
+//!
+//! The generated code needs the runtime in `boozle-cpp/include` and C++17.
+
+use boozle_gen::descriptor::{Descriptor, MethodDescriptor, ModuleDescriptor, TraitDescriptor, TypeRef};
+use boozle_gen::plugin::File;
+
+#[derive(Default)]
+pub struct Options {
+  /// The namespace of the root module, `::`-separated. Defaults to the root
+  /// module's name.
+  pub namespace: Option<String>,
+}
+
+impl Options {
+  /// Parses a plugin parameter: comma-separated `key=value` pairs. The only
+  /// key is `namespace`.
+  pub fn from_parameter(parameter: &str) -> Result<Self, String> {
+    let mut options = Self::default();
+    for pair in parameter.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+      let mut parts = pair.splitn(2, '=');
+      match (parts.next(), parts.next()) {
+        (Some("namespace"), Some(value)) => options.namespace = Some(value.to_string()),
+        _ => return Err(format!("unknown parameter {:?}", pair)),
+      }
+    }
+    Ok(options)
+  }
+}
+
+/// C++ keywords, which get a trailing `_` when used as names.
+const KEYWORDS: &[&str] = &[
+  "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break", "case", "catch",
+  "char", "char16_t", "char32_t", "class", "compl", "const", "const_cast", "constexpr", "continue", "decltype",
+  "default", "delete", "do", "double", "dynamic_cast", "else", "enum", "explicit", "export", "extern", "false",
+  "float", "for", "friend", "goto", "if", "inline", "int", "long", "mutable", "namespace", "new", "noexcept",
+  "not", "not_eq", "nullptr", "operator", "or", "or_eq", "private", "protected", "public", "register",
+  "reinterpret_cast", "return", "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct",
+  "switch", "template", "this", "thread_local", "throw", "true", "try", "typedef", "typeid", "typename", "union",
+  "unsigned", "using", "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+];
+
+fn ident(name: &str) -> String {
+  if KEYWORDS.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// How a .boozle type is represented in C++.
+enum Ty<'a> {
+  /// A literal. `raw` types are passed through as their encoding.
+  Lit { cpp: &'static str, raw: bool },
+  /// A trait, passed as an object.
+  Object(&'a [String]),
+}
+
+impl<'a> Ty<'a> {
+  fn new(ty: &'a TypeRef) -> Self {
+    let named = match ty {
+      TypeRef::Object { path } => return Self::Object(path.as_slice()),
+      TypeRef::Named { path } if path.len() == 1 => path[0].as_str(),
+      _ => "",
+    };
+    let cpp = match named {
+      "bool" => "bool",
+      "u8" => "uint8_t",
+      "u16" => "uint16_t",
+      "u32" => "uint32_t",
+      "u64" | "usize" => "uint64_t",
+      "i8" => "int8_t",
+      "i16" => "int16_t",
+      "i32" => "int32_t",
+      "i64" | "isize" => "int64_t",
+      "f32" => "float",
+      "f64" => "double",
+      "String" => "std::string",
+      // `type` declarations and anything else are passed through as their
+      // bincode encoding.
+      _ => {
+        return Self::Lit {
+          cpp: "boozle::Bytes",
+          raw: true,
+        }
+      }
+    };
+    Self::Lit { cpp, raw: false }
+  }
+}
+
+struct Gen {
+  /// Namespace of the root module, without a leading `::`.
+  root: Vec<String>,
+  out: String,
+}
+
+impl Gen {
+  fn line(&mut self, indent: usize, text: &str) {
+    if !text.is_empty() {
+      self.out.push_str("  ".repeat(indent).as_str());
+      self.out.push_str(text);
+    }
+    self.out.push('\n');
+  }
+
+  fn doc(&mut self, indent: usize, doc: &Option<String>) {
+    if let Some(doc) = doc {
+      for line in doc.lines() {
+        self.line(indent, format!("// {}", line).trim_end());
+      }
+    }
+  }
+
+  fn namespace(&self, module: &[String]) -> String {
+    let mut components = self.root.clone();
+    components.extend(module.iter().map(|name| ident(name)));
+    components.join("::")
+  }
+
+  /// The fully qualified name of the item generated for trait `path` with the
+  /// given suffix.
+  fn object(&self, path: &[String], suffix: &str) -> String {
+    let (name, module) = path.split_last().unwrap();
+    format!("::{}::{}{}", self.namespace(module), name, suffix)
+  }
+
+  fn value_type(&self, ty: &TypeRef) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { cpp, .. } => cpp.to_string(),
+      Ty::Object(path) => format!("std::shared_ptr<{}>", self.object(path, "")),
+    }
+  }
+
+  fn param_type(&self, ty: &TypeRef) -> String {
+    match Ty::new(ty) {
+      Ty::Lit {
+        cpp: cpp @ "std::string",
+        ..
+      }
+      | Ty::Lit {
+        cpp: cpp @ "boozle::Bytes",
+        ..
+      } => format!("const {}&", cpp),
+      _ => self.value_type(ty),
+    }
+  }
+
+  fn result_type(&self, method: &MethodDescriptor) -> String {
+    match &method.result {
+      Some(ty) => format!("boozle::Result<{}>", self.value_type(ty)),
+      None => "boozle::Result<void>".to_string(),
+    }
+  }
+
+  fn params(&self, method: &MethodDescriptor) -> String {
+    let params: Vec<String> = method
+      .params
+      .iter()
+      .map(|param| format!("{} {}", self.param_type(&param.ty), ident(param.name.as_str())))
+      .collect();
+    params.join(", ")
+  }
+
+  fn check(trait_: &TraitDescriptor, method: &MethodDescriptor) -> Result<(), String> {
+    let name = format!("{}.{}", trait_.name, method.name);
+    if method.params.len() < 2 {
+      return Ok(());
+    }
+    for (i, param) in method.params.iter().enumerate() {
+      match Ty::new(&param.ty) {
+        Ty::Object(_) => {
+          return Err(format!(
+            "`{}`: object parameter `{}` must be the method's only parameter",
+            name, param.name
+          ))
+        }
+        Ty::Lit { raw: true, .. } if i + 1 < method.params.len() => {
+          return Err(format!(
+            "`{}`: parameter `{}` has no C++ mapping and is passed as raw bytes, so it must come last",
+            name, param.name
+          ))
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn forward_declarations(&mut self, module: &ModuleDescriptor) {
+    if !module.traits.is_empty() {
+      let namespace = self.namespace(module.path.as_slice());
+      self.line(0, format!("namespace {} {{", namespace).as_str());
+      for trait_ in module.traits.iter() {
+        self.line(0, format!("class {};", trait_.name).as_str());
+        self.line(0, format!("class {}Client;", trait_.name).as_str());
+        self.line(0, format!("class {}Object;", trait_.name).as_str());
+      }
+      self.line(0, format!("}}  // namespace {}", namespace).as_str());
+      self.line(0, "");
+    }
+    for child in module.modules.iter() {
+      self.forward_declarations(child);
+    }
+  }
+
+  fn class_definitions(&mut self, trait_: &TraitDescriptor) {
+    let name = trait_.name.as_str();
+    self.doc(0, &trait_.doc);
+    self.line(0, format!("class {} : public boozle::Interface {{", name).as_str());
+    self.line(0, " public:");
+    for method in trait_.methods.iter() {
+      self.doc(1, &method.doc);
+      let decl = format!(
+        "virtual {} {}({}) = 0;",
+        self.result_type(method),
+        ident(method.name.as_str()),
+        self.params(method)
+      );
+      self.line(1, decl.as_str());
+    }
+    self.line(0, "};");
+    self.line(0, "");
+
+    self.line(0, format!("// Calls an object implementing `{}`, usually one owned by the peer.", name).as_str());
+    self.line(0, format!("class {}Client : public {} {{", name, name).as_str());
+    self.line(0, " public:");
+    self.line(
+      1,
+      format!(
+        "explicit {}Client(std::shared_ptr<boozle::Object> object) : object_(std::move(object)) {{}}",
+        name
+      )
+      .as_str(),
+    );
+    self.line(0, "");
+    self.line(1, "// Wraps an object value, failing with `error` if it's a literal or missing.");
+    self.line(
+      1,
+      format!(
+        "static boozle::Result<std::shared_ptr<{}>> from_value(std::optional<boozle::Value> value, boozle::CallError error);",
+        name
+      )
+      .as_str(),
+    );
+    self.line(0, "");
+    self.line(1, "std::shared_ptr<boozle::Object> boozle_object() const override { return object_; }");
+    self.line(0, "");
+    for method in trait_.methods.iter() {
+      let decl = format!(
+        "{} {}({}) override;",
+        self.result_type(method),
+        ident(method.name.as_str()),
+        self.params(method)
+      );
+      self.line(1, decl.as_str());
+    }
+    if !trait_.methods.is_empty() {
+      self.line(0, "");
+    }
+    self.line(0, " private:");
+    self.line(1, "std::shared_ptr<boozle::Object> object_;");
+    self.line(0, "};");
+    self.line(0, "");
+
+    self.line(0, format!("// Exposes a local `{}` to the peer.", name).as_str());
+    self.line(0, format!("class {}Object : public boozle::Object {{", name).as_str());
+    self.line(0, " public:");
+    self.line(
+      1,
+      format!(
+        "explicit {}Object(std::shared_ptr<{}> target) : target_(std::move(target)) {{}}",
+        name, name
+      )
+      .as_str(),
+    );
+    self.line(0, "");
+    self.line(1, "// Converts an implementation into a value. Generated clients aren't wrapped again.");
+    self.line(1, format!("static boozle::Value wrap(std::shared_ptr<{}> target);", name).as_str());
+    self.line(0, "");
+    self.line(
+      1,
+      "boozle::Result<std::optional<boozle::Value>> dispatch(uint64_t method_id, std::optional<boozle::Value> argument) override;",
+    );
+    self.line(0, "");
+    self.line(0, " private:");
+    self.line(1, format!("std::shared_ptr<{}> target_;", name).as_str());
+    self.line(0, "};");
+    self.line(0, "");
+  }
+
+  fn definitions(&mut self, module: &ModuleDescriptor) {
+    if !module.services.is_empty() || !module.traits.is_empty() {
+      let namespace = self.namespace(module.path.as_slice());
+      self.doc(0, &module.doc);
+      self.line(0, format!("namespace {} {{", namespace).as_str());
+      self.line(0, "");
+      for svc in module.services.iter() {
+        self.doc(0, &svc.doc);
+        self.line(0, format!("constexpr uint64_t {} = {}ull;", ident(svc.name.as_str()), svc.id).as_str());
+        self.line(0, "");
+      }
+      for trait_ in module.traits.iter() {
+        self.class_definitions(trait_);
+      }
+      self.line(0, format!("}}  // namespace {}", namespace).as_str());
+      self.line(0, "");
+    }
+    for child in module.modules.iter() {
+      self.definitions(child);
+    }
+  }
+
+  /// An expression converting `value` of type `ty` into a `boozle::Value`.
+  fn encode(&self, ty: &TypeRef, value: &str) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { .. } => format!("boozle::lit({})", value),
+      Ty::Object(path) => format!("{}::wrap({})", self.object(path, "Object"), value),
+    }
+  }
+
+  fn client_method(&mut self, name: &str, method: &MethodDescriptor) {
+    let signature = format!(
+      "inline {} {}Client::{}({}) {{",
+      self.result_type(method),
+      name,
+      ident(method.name.as_str()),
+      self.params(method)
+    );
+    self.line(0, signature.as_str());
+    let argument = match method.params.as_slice() {
+      [] => "std::nullopt".to_string(),
+      [param] => self.encode(&param.ty, ident(param.name.as_str()).as_str()),
+      params => {
+        let names: Vec<String> = params.iter().map(|param| ident(param.name.as_str())).collect();
+        format!("boozle::lit({})", names.join(", "))
+      }
+    };
+    let returns_object = matches!(&method.result, Some(TypeRef::Object { .. }));
+    self.line(
+      1,
+      format!("auto ret = object_->call({}ull, {}, {});", method.id, argument, returns_object).as_str(),
+    );
+    self.line(1, "if (!ret) return ret.error();");
+    match &method.result {
+      None => self.line(1, "return {};"),
+      Some(ty) => match Ty::new(ty) {
+        Ty::Lit { cpp, .. } => {
+          self.line(1, format!("{} result{{}};", cpp).as_str());
+          self.line(1, "if (!boozle::read_lit(*ret, result)) return boozle::CallError::InvalidResponse;");
+          self.line(1, "return result;");
+        }
+        Ty::Object(path) => {
+          let client = self.object(path, "Client");
+          self.line(
+            1,
+            format!(
+              "return {}::from_value(std::move(*ret), boozle::CallError::InvalidResponse);",
+              client
+            )
+            .as_str(),
+          );
+        }
+      },
+    }
+    self.line(0, "}");
+    self.line(0, "");
+  }
+
+  fn dispatch_arm(&mut self, method: &MethodDescriptor) {
+    self.line(2, format!("case {}ull: {{", method.id).as_str());
+    match method.params.as_slice() {
+      [] => {}
+      [param] if matches!(Ty::new(&param.ty), Ty::Object(_)) => {
+        let path = match Ty::new(&param.ty) {
+          Ty::Object(path) => path,
+          Ty::Lit { .. } => unreachable!(),
+        };
+        let client = self.object(path, "Client");
+        let name = ident(param.name.as_str());
+        self.line(
+          3,
+          format!(
+            "auto {} = {}::from_value(std::move(argument), boozle::CallError::InvalidArgument);",
+            name, client
+          )
+          .as_str(),
+        );
+        self.line(3, format!("if (!{}) return {}.error();", name, name).as_str());
+      }
+      params => {
+        for param in params {
+          let decl = format!("{} {}{{}};", self.value_type(&param.ty), ident(param.name.as_str()));
+          self.line(3, decl.as_str());
+        }
+        let names: Vec<String> = params.iter().map(|param| ident(param.name.as_str())).collect();
+        self.line(
+          3,
+          format!(
+            "if (!boozle::read_lit(argument, {})) return boozle::CallError::InvalidArgument;",
+            names.join(", ")
+          )
+          .as_str(),
+        );
+      }
+    }
+    let args: Vec<String> = method
+      .params
+      .iter()
+      .map(|param| match Ty::new(&param.ty) {
+        Ty::Object(_) => format!("*{}", ident(param.name.as_str())),
+        Ty::Lit { .. } => ident(param.name.as_str()),
+      })
+      .collect();
+    self.line(
+      3,
+      format!("auto result = target_->{}({});", ident(method.name.as_str()), args.join(", ")).as_str(),
+    );
+    self.line(3, "if (!result) return result.error();");
+    match &method.result {
+      None => self.line(3, "return std::optional<boozle::Value>();"),
+      Some(ty) => {
+        let encode = self.encode(ty, "*result");
+        self.line(3, format!("return std::optional<boozle::Value>({});", encode).as_str());
+      }
+    }
+    self.line(2, "}");
+  }
+
+  fn implementations(&mut self, module: &ModuleDescriptor) {
+    if !module.traits.is_empty() {
+      let namespace = self.namespace(module.path.as_slice());
+      self.line(0, format!("namespace {} {{", namespace).as_str());
+      self.line(0, "");
+      for trait_ in module.traits.iter() {
+        let name = trait_.name.as_str();
+        self.line(
+          0,
+          format!(
+            "inline boozle::Result<std::shared_ptr<{}>> {}Client::from_value(std::optional<boozle::Value> value, boozle::CallError error) {{",
+            name, name
+          )
+          .as_str(),
+        );
+        self.line(1, "if (!value || value->is_lit()) return error;");
+        self.line(
+          1,
+          format!(
+            "return std::shared_ptr<{}>(std::make_shared<{}Client>(value->object()));",
+            name, name
+          )
+          .as_str(),
+        );
+        self.line(0, "}");
+        self.line(0, "");
+        for method in trait_.methods.iter() {
+          self.client_method(name, method);
+        }
+
+        self.line(0, format!("inline boozle::Value {}Object::wrap(std::shared_ptr<{}> target) {{", name, name).as_str());
+        self.line(1, "if (auto object = target->boozle_object()) return boozle::Value(std::move(object));");
+        self.line(
+          1,
+          format!(
+            "return boozle::Value(std::shared_ptr<boozle::Object>(std::make_shared<{}Object>(std::move(target))));",
+            name
+          )
+          .as_str(),
+        );
+        self.line(0, "}");
+        self.line(0, "");
+
+        self.line(
+          0,
+          format!(
+            "inline boozle::Result<std::optional<boozle::Value>> {}Object::dispatch(uint64_t method_id, std::optional<boozle::Value> argument) {{",
+            name
+          )
+          .as_str(),
+        );
+        if trait_.methods.iter().all(|method| method.params.is_empty()) {
+          self.line(1, "(void)argument;");
+        }
+        self.line(1, "switch (method_id) {");
+        for method in trait_.methods.iter() {
+          self.dispatch_arm(method);
+        }
+        self.line(2, "default:");
+        self.line(3, "return boozle::CallError::UnknownMethod;");
+        self.line(1, "}");
+        self.line(0, "}");
+        self.line(0, "");
+      }
+      self.line(0, format!("}}  // namespace {}", namespace).as_str());
+      self.line(0, "");
+    }
+    for child in module.modules.iter() {
+      self.implementations(child);
+    }
+  }
+}
+
+fn check_module(module: &ModuleDescriptor) -> Result<(), String> {
+  for trait_ in module.traits.iter() {
+    for method in trait_.methods.iter() {
+      Gen::check(trait_, method)?;
+    }
+  }
+  module.modules.iter().try_for_each(check_module)
+}
+
+/// Generates a header for the tree described by `descriptor`.
+pub fn generate(descriptor: &Descriptor, options: &Options) -> Result<Vec<File>, String> {
+  let root = &descriptor.root;
+  check_module(root)?;
+  let namespace = options.namespace.clone().unwrap_or_else(|| ident(root.name.as_str()));
+  let mut gen = Gen {
+    root: namespace.split("::").map(str::to_string).collect(),
+    out: String::new(),
+  };
+  gen.line(0, "// Generated by boozle-gen-cpp. Do not edit.");
+  gen.line(0, "");
+  gen.line(0, "#pragma once");
+  gen.line(0, "");
+  for header in ["cstdint", "memory", "optional", "string", "utility"].iter() {
+    gen.line(0, format!("#include <{}>", header).as_str());
+  }
+  gen.line(0, "");
+  gen.line(0, "#include \"boozle/boozle.hpp\"");
+  gen.line(0, "");
+  gen.forward_declarations(root);
+  gen.definitions(root);
+  gen.implementations(root);
+  while gen.out.ends_with("\n\n") {
+    gen.out.pop();
+  }
+  Ok(vec![File {
+    path: format!("{}.hpp", root.name),
+    content: gen.out,
+  }])
+}
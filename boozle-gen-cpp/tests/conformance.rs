@@ -0,0 +1,134 @@
+//! Runs a C++ peer built from the generated header and the runtime in
+//! `boozle-cpp` against a Rust `Connection`, over the peer's stdin and stdout.
+//! Needs a C++17 compiler, taken from `$CXX` or `c++`. Ignored by default, run
+//! it with `cargo test -p boozle-gen-cpp --test conformance -- --ignored`.
+//! Fails without a compiler.
+
+use actix::Actor;
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::CallError;
+use boozle::pool::PoolHelpers;
+use boozle::transport::stream;
+use boozle::value::LocalValue;
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::write_files;
+use boozle_gen_cpp::{generate, Options};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn increment(&self) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(1, Ordering::SeqCst) + 1)
+  }
+
+  async fn get(&self) -> Result<u32, CallError> {
+    Ok(self.value.load(Ordering::SeqCst))
+  }
+}
+
+#[derive(Debug)]
+pub struct Calc;
+
+#[boozle::object]
+impl Calc {
+  async fn add(&self, a: i64, b: i64) -> Result<i64, CallError> {
+    Ok(a + b)
+  }
+
+  async fn greet(&self, name: String) -> Result<String, CallError> {
+    Ok(format!("hello, {}", name))
+  }
+
+  async fn mix(&self, a: bool, b: u8, c: u64, d: f64) -> Result<f64, CallError> {
+    Ok(a as u8 as f64 + b as f64 + c as f64 + d)
+  }
+
+  async fn ping(&self) -> Result<(), CallError> {
+    Ok(())
+  }
+
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+
+  async fn adopt(&self, counter: CounterClient) -> Result<bool, CallError> {
+    Ok(counter.increment().await.is_ok())
+  }
+}
+
+fn cxx() -> Option<String> {
+  let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+  match Command::new(&cxx).arg("--version").stdout(Stdio::null()).status() {
+    Ok(status) if status.success() => Some(cxx),
+    _ => None,
+  }
+}
+
+/// Generates the header into `dir` and compiles the peer. Returns its path.
+fn build(dir: &Path, cxx: &str) -> PathBuf {
+  std::fs::create_dir_all(dir).unwrap();
+  let root = dir.join("calc.boozle");
+  std::fs::write(&root, format!("{}\n{}", Counter::BOOZLE, Calc::BOOZLE)).unwrap();
+  let descriptor = Descriptor::new(&boozle_gen::load(&root).unwrap()).unwrap();
+  write_files(dir, generate(&descriptor, &Options::default()).unwrap().as_slice()).unwrap();
+
+  let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+  let peer = dir.join("peer");
+  let status = Command::new(cxx)
+    .args(["-std=c++17", "-Wall", "-Wextra", "-Werror", "-o"])
+    .arg(&peer)
+    .arg("-I")
+    .arg(manifest.parent().unwrap().join("boozle-cpp/include"))
+    .arg("-I")
+    .arg(dir)
+    .arg(manifest.join("tests/conformance/peer.cpp"))
+    .status()
+    .unwrap();
+  assert!(status.success(), "compiling the peer failed");
+  peer
+}
+
+#[test]
+#[ignore]
+fn cpp_peer() {
+  let cxx = cxx().expect("needs a C++17 compiler, set CXX");
+
+  let dir = std::env::temp_dir().join(format!("boozle-gen-cpp-conformance-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  let peer = build(&dir, cxx.as_str());
+
+  let mut system = actix::System::new("conformance");
+  let status = system.block_on(async move {
+    // The connection exists before the peer does, so the peer can be told
+    // where to find the `Calc`.
+    let (tx, outgoing) = mpsc::channel(16);
+    let (incoming, rx) = mpsc::channel(16);
+    let connection = Connection::new(tx, rx).start();
+    let pool = connection.pool().await;
+    let key = pool.expose(LocalValue::from_object(Calc)).await.unwrap().key;
+
+    let mut child = tokio::process::Command::new(peer)
+      .arg(key.to_string())
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .kill_on_drop(true)
+      .spawn()
+      .unwrap();
+    stream::bridge(child.stdout.take().unwrap(), child.stdin.take().unwrap(), incoming, outgoing);
+    tokio::time::timeout(Duration::from_secs(60), child).await
+  });
+  let status = status.expect("C++ peer timed out").unwrap();
+  assert!(status.success(), "C++ peer failed");
+}
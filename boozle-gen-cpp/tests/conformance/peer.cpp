@@ -0,0 +1,69 @@
+// C++ half of the conformance test in `conformance.rs`. Talks to the Rust
+// `Connection` over stdin and stdout, calling the `Calc` exposed under the ID
+// given as the first argument through the generated client.
+
+#include <cstdio>
+#include <cstdlib>
+#include <memory>
+#include <string>
+
+#include "boozle/fd_transport.hpp"
+#include "calc.hpp"
+
+namespace {
+
+#define CHECK(condition)                                                                 \
+  do {                                                                                   \
+    if (!(condition)) {                                                                  \
+      std::fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+      std::exit(1);                                                                      \
+    }                                                                                    \
+  } while (false)
+
+class LocalCounter : public calc::Counter {
+ public:
+  boozle::Result<uint32_t> increment() override { return ++value; }
+  boozle::Result<uint32_t> get() override { return value; }
+
+  uint32_t value = 0;
+};
+
+}  // namespace
+
+int main(int argc, char** argv) {
+  CHECK(argc == 2);
+  boozle::FdTransport transport(0, 1);
+  boozle::Connection connection(transport);
+  calc::CalcClient calc(connection.remote(std::strtoull(argv[1], nullptr, 10)));
+
+  auto sum = calc.add(40, 2);
+  CHECK(sum && *sum == 42);
+  // The first call waited for the handshake.
+  CHECK(connection.welcome() && connection.welcome()->version == boozle::protocol::VERSION);
+  sum = calc.add(-5, 2);
+  CHECK(sum && *sum == -3);
+  auto greeting = calc.greet("boozle");
+  CHECK(greeting && *greeting == "hello, boozle");
+  auto mixed = calc.mix(true, 2, 3, 0.5);
+  CHECK(mixed && *mixed == 6.5);
+  CHECK(calc.ping());
+
+  {
+    // Objects returned by the peer are kept in its pool until released.
+    auto counter = calc.counter(10);
+    CHECK(counter);
+    auto value = (*counter)->increment();
+    CHECK(value && *value == 11);
+    value = (*counter)->get();
+    CHECK(value && *value == 11);
+  }
+
+  // Local objects passed to the peer are exposed until it frees them.
+  auto local = std::make_shared<LocalCounter>();
+  auto adopted = calc.adopt(local);
+  CHECK(adopted && *adopted);
+  CHECK(local->value == 1);
+  while (connection.exposed_count() != 0) CHECK(connection.poll());
+
+  return 0;
+}
@@ -0,0 +1,72 @@
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::File;
+use boozle_gen_cpp::{generate, Options};
+
+/// Generates a header for `files`, given as (path, text) with the root first.
+fn run(name: &str, files: &[(&str, &str)], options: &Options) -> Result<Vec<File>, String> {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-cpp-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, text) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+  let root = boozle_gen::load(dir.join(files[0].0)).unwrap();
+  generate(&Descriptor::new(&root).unwrap(), options)
+}
+
+#[test]
+fn nests_modules_in_one_header() {
+  let files = run(
+    "modules",
+    &[
+      ("root.boozle", "mod a;\ntrait Counter {\n  fn get() -> u64;\n}\n"),
+      (
+        "a/mod.boozle",
+        "trait Factory {\n  fn create(name: String) -> super.Counter;\n  fn delete(counter: super.Counter);\n}\nsvc factory: Factory;\n",
+      ),
+    ],
+    &Options::from_parameter("namespace=acme::root").unwrap(),
+  )
+  .unwrap();
+
+  assert_eq!(files.len(), 1);
+  assert_eq!(files[0].path, "root.hpp");
+  let header = files[0].content.as_str();
+  assert!(header.contains("namespace acme::root::a {"));
+  assert!(header.contains("virtual boozle::Result<uint64_t> get() = 0;"));
+  assert!(header
+    .contains("virtual boozle::Result<std::shared_ptr<::acme::root::Counter>> create(const std::string& name) = 0;"));
+  assert!(header.contains("virtual boozle::Result<void> delete_(std::shared_ptr<::acme::root::Counter> counter) = 0;"));
+  assert!(header.contains("constexpr uint64_t factory = "));
+  // Forward declarations come before any class that refers to them.
+  assert!(header.find("class Counter;").unwrap() < header.find("class Factory :").unwrap());
+}
+
+#[test]
+fn rejects_unsupported_params() {
+  let err = run(
+    "objects",
+    &[(
+      "root.boozle",
+      "trait Counter {\n}\ntrait Bad {\n  fn f(counter: Counter flag: bool);\n}\n",
+    )],
+    &Options::default(),
+  )
+  .unwrap_err();
+  assert!(err.contains("must be the method's only parameter"), "{}", err);
+
+  let err = run(
+    "raw",
+    &[("root.boozle", "type Blob;\ntrait Bad {\n  fn f(blob: Blob flag: bool);\n}\n")],
+    &Options::default(),
+  )
+  .unwrap_err();
+  assert!(err.contains("must come last"), "{}", err);
+}
+
+#[test]
+fn rejects_unknown_parameters() {
+  assert!(Options::from_parameter("").unwrap().namespace.is_none());
+  assert!(Options::from_parameter("runtime=boozle").is_err());
+}
@@ -0,0 +1,487 @@
+//! Generates a browsable API reference for boozle protocols, as HTML or
+//! Markdown. Input is a schema descriptor (see `boozle_gen::descriptor`), so
+//! this also runs as a `boozle-gen` plugin.
+//!
+//! Each module gets a page: the root is `index.html` and a child `a.b` is
+//! `a.b.html`. A page lists the module's children, services, types and traits
+//! with their docs, attributes and IDs. Signatures are written as in .boozle,
+//! with references to traits and types of the tree linked to their page.
+
+use boozle_gen::descriptor::{AttrDescriptor, Descriptor, MethodDescriptor, ModuleDescriptor, TypeRef};
+use boozle_gen::plugin::File;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Html,
+  Markdown,
+}
+
+impl Format {
+  fn extension(&self) -> &'static str {
+    match self {
+      Self::Html => "html",
+      Self::Markdown => "md",
+    }
+  }
+}
+
+pub struct Options {
+  pub format: Format,
+  /// Heads every page. Defaults to the name of the root module.
+  pub title: Option<String>,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      format: Format::Html,
+      title: None,
+    }
+  }
+}
+
+impl Options {
+  /// Parses a plugin parameter: comma-separated `key=value` pairs. Keys are
+  /// `format`, which is `html` or `markdown`, and `title`.
+  pub fn from_parameter(parameter: &str) -> Result<Self, String> {
+    let mut options = Self::default();
+    for pair in parameter.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+      let mut parts = pair.splitn(2, '=');
+      match (parts.next(), parts.next()) {
+        (Some("format"), Some("html")) => options.format = Format::Html,
+        (Some("format"), Some("markdown")) | (Some("format"), Some("md")) => options.format = Format::Markdown,
+        (Some("title"), Some(value)) => options.title = Some(value.to_string()),
+        _ => return Err(format!("unknown parameter {:?}", pair)),
+      }
+    }
+    Ok(options)
+  }
+}
+
+/// A piece of a signature.
+enum Fragment {
+  Text(String),
+  /// A reference to `text`, defined at `href`.
+  Link {
+    text: String,
+    href: String,
+  },
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Module paths are written as in .boozle, e.g. `a.b`.
+fn dotted(path: &[String]) -> String {
+  path.join(".")
+}
+
+fn format_id(id: u64) -> String {
+  format!("0x{:016x}", id)
+}
+
+fn format_attr(attr: &AttrDescriptor) -> String {
+  let args: Vec<String> = attr
+    .args
+    .iter()
+    .map(|(key, value)| format!("{} = \"{}\"", key, value))
+    .collect();
+  if args.is_empty() {
+    format!("#[{}]", attr.name)
+  } else {
+    format!("#[{}({})]", attr.name, args.join(", "))
+  }
+}
+
+/// Attributes worth showing. Docs are rendered as text instead.
+fn shown_attrs(attrs: &[AttrDescriptor]) -> Vec<String> {
+  attrs
+    .iter()
+    .filter(|attr| attr.name != "doc")
+    .map(format_attr)
+    .collect()
+}
+
+struct Renderer<'a> {
+  options: &'a Options,
+  title: String,
+}
+
+impl<'a> Renderer<'a> {
+  fn page_name(&self, path: &[String]) -> String {
+    let name = if path.is_empty() {
+      "index".to_string()
+    } else {
+      dotted(path)
+    };
+    format!("{}.{}", name, self.options.format.extension())
+  }
+
+  fn module_title(&self, module: &ModuleDescriptor) -> String {
+    if module.path.is_empty() {
+      self.title.clone()
+    } else {
+      dotted(&module.path)
+    }
+  }
+
+  /// A reference to an item of the tree, given by its full path and kind.
+  fn item_href(&self, path: &[String], kind: &str) -> String {
+    let (name, module) = path.split_last().unwrap();
+    format!("{}#{}.{}", self.page_name(module), kind, name)
+  }
+
+  fn type_fragment(&self, ty: &TypeRef) -> Fragment {
+    match ty {
+      TypeRef::Object { path } => Fragment::Link {
+        text: path.last().cloned().unwrap_or_default(),
+        href: self.item_href(path, "trait"),
+      },
+      TypeRef::Declared { path } => Fragment::Link {
+        text: path.last().cloned().unwrap_or_default(),
+        href: self.item_href(path, "type"),
+      },
+      TypeRef::Named { path } => Fragment::Text(dotted(path)),
+    }
+  }
+
+  fn signature(&self, method: &MethodDescriptor) -> Vec<Fragment> {
+    let mut fragments = vec![Fragment::Text(format!("fn {}(", method.name))];
+    for (i, param) in method.params.iter().enumerate() {
+      let separator = if i > 0 { " " } else { "" };
+      fragments.push(Fragment::Text(format!("{}{}: ", separator, param.name)));
+      fragments.push(self.type_fragment(&param.ty));
+    }
+    fragments.push(Fragment::Text(")".to_string()));
+    if let Some(result) = &method.result {
+      fragments.push(Fragment::Text(" -> ".to_string()));
+      fragments.push(self.type_fragment(result));
+    }
+    fragments
+  }
+
+  fn code(&self, fragments: &[Fragment]) -> String {
+    match self.options.format {
+      Format::Html => {
+        let inner: String = fragments
+          .iter()
+          .map(|fragment| match fragment {
+            Fragment::Text(text) => escape(text),
+            Fragment::Link { text, href } => format!("<a href=\"{}\">{}</a>", escape(href), escape(text)),
+          })
+          .collect();
+        format!("<code>{}</code>", inner)
+      }
+      // Links can't be inside code spans, so each link and each run of text
+      // gets its own.
+      Format::Markdown => {
+        let mut out = String::new();
+        let mut run = String::new();
+        for fragment in fragments.iter() {
+          match fragment {
+            Fragment::Text(text) => run.push_str(text),
+            Fragment::Link { text, href } => {
+              if !run.is_empty() {
+                out.push_str(format!("`{}`", std::mem::take(&mut run)).as_str());
+              }
+              out.push_str(format!("[`{}`]({})", text, href).as_str());
+            }
+          }
+        }
+        if !run.is_empty() {
+          out.push_str(format!("`{}`", run).as_str());
+        }
+        out
+      }
+    }
+  }
+
+  fn heading(&self, out: &mut String, level: usize, anchor: Option<&str>, text: &str) {
+    match self.options.format {
+      Format::Html => {
+        let id = match anchor {
+          Some(anchor) => format!(" id=\"{}\"", escape(anchor)),
+          None => String::new(),
+        };
+        out.push_str(format!("<h{0}{1}>{2}</h{0}>\n", level, id, text).as_str());
+      }
+      Format::Markdown => {
+        if let Some(anchor) = anchor {
+          out.push_str(format!("<a id=\"{}\"></a>\n\n", anchor).as_str());
+        }
+        out.push_str(format!("{} {}\n\n", "#".repeat(level), text).as_str());
+      }
+    }
+  }
+
+  fn paragraph(&self, out: &mut String, text: &str) {
+    match self.options.format {
+      Format::Html => out.push_str(format!("<p>{}</p>\n", text).as_str()),
+      Format::Markdown => out.push_str(format!("{}\n\n", text).as_str()),
+    }
+  }
+
+  /// Docs are Markdown. In HTML, blank lines separate paragraphs and
+  /// everything else is shown as written.
+  fn doc(&self, out: &mut String, doc: &Option<String>) {
+    let doc = match doc {
+      Some(doc) => doc,
+      None => return,
+    };
+    match self.options.format {
+      Format::Html => {
+        for paragraph in doc
+          .split("\n\n")
+          .map(str::trim)
+          .filter(|paragraph| !paragraph.is_empty())
+        {
+          out.push_str(format!("<p class=\"doc\">{}</p>\n", escape(paragraph)).as_str());
+        }
+      }
+      Format::Markdown => out.push_str(format!("{}\n\n", doc.trim()).as_str()),
+    }
+  }
+
+  fn attrs(&self, out: &mut String, attrs: &[AttrDescriptor]) {
+    let attrs: Vec<String> = shown_attrs(attrs)
+      .into_iter()
+      .map(|attr| self.code(&[Fragment::Text(attr)]))
+      .collect();
+    if !attrs.is_empty() {
+      self.paragraph(out, attrs.join(" ").as_str());
+    }
+  }
+
+  fn list(&self, out: &mut String, items: &[String]) {
+    match self.options.format {
+      Format::Html => {
+        out.push_str("<ul>\n");
+        for item in items.iter() {
+          out.push_str(format!("<li>{}</li>\n", item).as_str());
+        }
+        out.push_str("</ul>\n");
+      }
+      Format::Markdown => {
+        for item in items.iter() {
+          out.push_str(format!("- {}\n", item).as_str());
+        }
+        out.push('\n');
+      }
+    }
+  }
+
+  fn link(&self, text: &str, href: &str) -> String {
+    match self.options.format {
+      Format::Html => format!("<a href=\"{}\">{}</a>", escape(href), escape(text)),
+      Format::Markdown => format!("[{}]({})", text, href),
+    }
+  }
+
+  fn module_link(&self, module: &ModuleDescriptor) -> String {
+    self.link(
+      self.module_title(module).as_str(),
+      self.page_name(&module.path).as_str(),
+    )
+  }
+
+  /// A one-line summary of `doc`: its first paragraph.
+  fn summary(&self, doc: &Option<String>) -> String {
+    let summary = doc
+      .as_deref()
+      .and_then(|doc| doc.split("\n\n").next())
+      .map(|summary| summary.split_whitespace().collect::<Vec<&str>>().join(" "))
+      .unwrap_or_default();
+    match self.options.format {
+      Format::Html => escape(summary.as_str()),
+      Format::Markdown => summary,
+    }
+  }
+
+  fn module_body(&self, root: &ModuleDescriptor, module: &ModuleDescriptor) -> String {
+    let mut out = String::new();
+
+    if !module.path.is_empty() {
+      let mut crumbs = vec![self.module_link(root)];
+      let mut current = root;
+      for name in module.path[..module.path.len() - 1].iter() {
+        current = match current.modules.iter().find(|child| &child.name == name) {
+          Some(child) => child,
+          None => break,
+        };
+        crumbs.push(self.module_link(current));
+      }
+      self.paragraph(&mut out, crumbs.join(" / ").as_str());
+    }
+
+    let title = match self.options.format {
+      Format::Html => escape(self.module_title(module).as_str()),
+      Format::Markdown => self.module_title(module),
+    };
+    let kind = if module.path.is_empty() { "Protocol" } else { "Module" };
+    self.heading(&mut out, 1, None, format!("{} {}", kind, title).as_str());
+    self.attrs(&mut out, &module.attrs);
+    self.doc(&mut out, &module.doc);
+
+    if !module.modules.is_empty() {
+      self.heading(&mut out, 2, Some("modules"), "Modules");
+      let items: Vec<String> = module
+        .modules
+        .iter()
+        .map(|child| {
+          let summary = self.summary(&child.doc);
+          if summary.is_empty() {
+            self.module_link(child)
+          } else {
+            format!("{}: {}", self.module_link(child), summary)
+          }
+        })
+        .collect();
+      self.list(&mut out, &items);
+    }
+
+    if !module.services.is_empty() {
+      self.heading(&mut out, 2, Some("services"), "Services");
+      for svc in module.services.iter() {
+        let anchor = format!("svc.{}", svc.name);
+        self.heading(
+          &mut out,
+          3,
+          Some(anchor.as_str()),
+          self
+            .code(&[
+              Fragment::Text(format!("svc {}: ", svc.name)),
+              self.type_fragment(&svc.ty),
+            ])
+            .as_str(),
+        );
+        self.attrs(&mut out, &svc.attrs);
+        self.paragraph(
+          &mut out,
+          format!("Service ID {}", self.code(&[Fragment::Text(format_id(svc.id))])).as_str(),
+        );
+        self.doc(&mut out, &svc.doc);
+      }
+    }
+
+    if !module.types.is_empty() {
+      self.heading(&mut out, 2, Some("types"), "Types");
+      for ty in module.types.iter() {
+        let anchor = format!("type.{}", ty.name);
+        self.heading(
+          &mut out,
+          3,
+          Some(anchor.as_str()),
+          self.code(&[Fragment::Text(format!("type {};", ty.name))]).as_str(),
+        );
+        self.attrs(&mut out, &ty.attrs);
+        self.doc(&mut out, &ty.doc);
+      }
+    }
+
+    if !module.traits.is_empty() {
+      self.heading(&mut out, 2, Some("traits"), "Traits");
+      for trait_ in module.traits.iter() {
+        let anchor = format!("trait.{}", trait_.name);
+        self.heading(
+          &mut out,
+          3,
+          Some(anchor.as_str()),
+          self.code(&[Fragment::Text(format!("trait {}", trait_.name))]).as_str(),
+        );
+        self.attrs(&mut out, &trait_.attrs);
+        self.doc(&mut out, &trait_.doc);
+        for method in trait_.methods.iter() {
+          let anchor = format!("method.{}.{}", trait_.name, method.name);
+          self.heading(
+            &mut out,
+            4,
+            Some(anchor.as_str()),
+            self.code(&self.signature(method)).as_str(),
+          );
+          self.attrs(&mut out, &method.attrs);
+          self.paragraph(
+            &mut out,
+            format!("Method ID {}", self.code(&[Fragment::Text(format_id(method.id))])).as_str(),
+          );
+          self.doc(&mut out, &method.doc);
+        }
+      }
+    }
+
+    out
+  }
+
+  /// Links to every module, indented by depth.
+  fn nav(&self, module: &ModuleDescriptor, current: &[String], out: &mut String) {
+    let link = if module.path == current {
+      format!("<strong>{}</strong>", self.module_link(module))
+    } else {
+      self.module_link(module)
+    };
+    out.push_str(format!("<li>{}", link).as_str());
+    if !module.modules.is_empty() {
+      out.push_str("\n<ul>\n");
+      for child in module.modules.iter() {
+        self.nav(child, current, out);
+      }
+      out.push_str("</ul>\n");
+    }
+    out.push_str("</li>\n");
+  }
+
+  fn page(&self, root: &ModuleDescriptor, module: &ModuleDescriptor) -> String {
+    let body = self.module_body(root, module);
+    match self.options.format {
+      Format::Markdown => body,
+      Format::Html => {
+        let mut nav = String::new();
+        self.nav(root, &module.path, &mut nav);
+        format!(
+          "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title} - {page}</title>\n<style>\n{style}</style>\n</head>\n<body>\n<nav>\n<ul>\n{nav}</ul>\n</nav>\n<main>\n{body}</main>\n</body>\n</html>\n",
+          title = escape(self.title.as_str()),
+          page = escape(self.module_title(module).as_str()),
+          style = STYLE,
+          nav = nav,
+          body = body,
+        )
+      }
+    }
+  }
+
+  fn pages(&self, root: &ModuleDescriptor, module: &ModuleDescriptor, files: &mut Vec<File>) {
+    files.push(File {
+      path: self.page_name(&module.path),
+      content: self.page(root, module),
+    });
+    for child in module.modules.iter() {
+      self.pages(root, child, files);
+    }
+  }
+}
+
+const STYLE: &str = "body { display: flex; margin: 0; font-family: sans-serif; line-height: 1.5; }
+nav { min-width: 14em; padding: 1em; background: #f4f4f4; }
+nav ul { list-style: none; padding-left: 1em; margin: 0; }
+main { padding: 1em 2em; max-width: 60em; }
+code { font-family: monospace; }
+h3, h4 { margin-bottom: 0.25em; }
+h4 { font-weight: normal; }
+.attr { color: #666; }
+.doc { white-space: pre-line; }
+";
+
+/// Renders one page per module of `descriptor`.
+pub fn generate(descriptor: &Descriptor, options: &Options) -> Result<Vec<File>, String> {
+  let renderer = Renderer {
+    options,
+    title: options.title.clone().unwrap_or_else(|| descriptor.root.name.clone()),
+  };
+  let mut files = Vec::new();
+  renderer.pages(&descriptor.root, &descriptor.root, &mut files);
+  Ok(files)
+}
@@ -0,0 +1,16 @@
+//! The `boozle-gen-doc` plugin. Run it through `boozle-gen doc`, or
+//! `boozle-gen plugin` with `--parameter format=markdown` for Markdown.
+
+use boozle_gen::plugin;
+use boozle_gen_doc::{generate, Options};
+
+fn main() {
+  let result = plugin::serve(|request| {
+    let options = Options::from_parameter(request.parameter.as_str())?;
+    generate(&request.descriptor, &options)
+  });
+  if let Err(err) = result {
+    eprintln!("{}", err);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,105 @@
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::File;
+use boozle_gen::{method_id, svc_id};
+use boozle_gen_doc::{generate, Options};
+
+const ROOT: &str = r#"/// Key-value storage.
+mod store;
+
+/// Units of time.
+#[rust(type = "std::time::Duration")]
+type Duration;
+
+/// Entry point of the <calc> protocol.
+///
+/// Second paragraph.
+trait Root {
+  /// Opens the store.
+  fn store() -> store.Store;
+  fn wait(for: Duration);
+}
+
+svc root: Root;
+"#;
+
+const STORE: &str = r#"/// A store.
+trait Store {
+  fn get(key: String) -> String;
+  fn parent() -> super.Root;
+}
+"#;
+
+/// Generates docs for a tree with a root and a `store` child module.
+fn run(name: &str, options: &Options) -> Vec<File> {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-doc-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("calc.boozle"), ROOT).unwrap();
+  std::fs::write(dir.join("store.boozle"), STORE).unwrap();
+  let root = boozle_gen::load(dir.join("calc.boozle")).unwrap();
+  generate(&Descriptor::new(&root).unwrap(), options).unwrap()
+}
+
+fn content<'a>(files: &'a [File], path: &str) -> &'a str {
+  files
+    .iter()
+    .find(|file| file.path == path)
+    .map(|file| file.content.as_str())
+    .unwrap_or_else(|| panic!("{} wasn't generated", path))
+}
+
+#[test]
+fn renders_html_pages() {
+  let files = run("html", &Options::default());
+  assert_eq!(files.len(), 2);
+
+  let index = content(&files, "index.html");
+  assert!(index.contains("<title>calc - calc</title>"));
+  assert!(index.contains("<h1>Protocol calc</h1>"));
+  assert!(index.contains("<li><a href=\"store.html\">store</a>: Key-value storage.</li>"));
+  assert!(index.contains(
+    "<p class=\"doc\">Entry point of the &lt;calc&gt; protocol.</p>\n<p class=\"doc\">Second paragraph.</p>"
+  ));
+  assert!(index.contains("<h3 id=\"type.Duration\"><code>type Duration;</code></h3>\n<p><code>#[rust(type = &quot;std::time::Duration&quot;)]</code></p>"));
+  assert!(index.contains(
+    "<h4 id=\"method.Root.store\"><code>fn store() -&gt; <a href=\"store.html#trait.Store\">Store</a></code></h4>"
+  ));
+  assert!(index.contains("<code>fn wait(for: <a href=\"index.html#type.Duration\">Duration</a>)</code>"));
+  assert!(index.contains(format!("Method ID <code>0x{:016x}</code>", method_id("Root", "store")).as_str()));
+  assert!(index.contains(format!("Service ID <code>0x{:016x}</code>", svc_id("root")).as_str()));
+  assert!(index.contains("<p class=\"doc\">Opens the store.</p>"));
+
+  let store = content(&files, "store.html");
+  assert!(store.contains("<p><a href=\"index.html\">calc</a></p>\n<h1>Module store</h1>"));
+  assert!(store.contains("<code>fn get(key: String) -&gt; String</code>"));
+  assert!(store.contains("<code>fn parent() -&gt; <a href=\"index.html#trait.Root\">Root</a></code>"));
+  // The current page is highlighted in the navigation.
+  assert!(store.contains("<li><strong><a href=\"store.html\">store</a></strong></li>"));
+}
+
+#[test]
+fn renders_markdown_pages() {
+  let files = run(
+    "markdown",
+    &Options::from_parameter("format=markdown,title=Calculator").unwrap(),
+  );
+  let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+  assert_eq!(paths, ["index.md", "store.md"]);
+
+  let index = content(&files, "index.md");
+  assert!(index.starts_with("# Protocol Calculator\n\n"));
+  assert!(index.contains("- [store](store.md): Key-value storage.\n"));
+  assert!(index.contains("<a id=\"svc.root\"></a>\n\n### `svc root: `[`Root`](index.md#trait.Root)\n\n"));
+  assert!(index.contains("#### `fn wait(for: `[`Duration`](index.md#type.Duration)`)`\n\n"));
+  assert!(index.contains("Entry point of the <calc> protocol.\n\nSecond paragraph.\n\n"));
+
+  let store = content(&files, "store.md");
+  assert!(store.starts_with("[Calculator](index.md)\n\n# Module store\n\n"));
+  assert!(store.contains("#### `fn get(key: String) -> String`\n\n"));
+}
+
+#[test]
+fn rejects_unknown_parameters() {
+  assert!(Options::from_parameter("format=pdf").is_err());
+  assert!(Options::from_parameter("theme=dark").is_err());
+}
@@ -0,0 +1,485 @@
+//! Generates Python clients for boozle protocols. Input is a schema descriptor
+//! (see `boozle_gen::descriptor`), so this also runs as a `boozle-gen` plugin.
+//!
+//! The tree becomes a package: the root module is `__init__.py`, a child `a`
+//! is `a/__init__.py` and its child `b` is `a/b/__init__.py`. Modules import
+//! their children last and each other through `importlib`, so references
+//! between modules don't create import cycles. For a trait `Foo` the
+//! generated code has:
+//!
+//! - `class Foo`, an abstract base class whose methods are coroutines,
+//! - `class FooClient(Foo)`, calling a remote object,
+//! - `class FooObject`, exposing a local `Foo` to the peer.
+//!
+//! The generated code imports the runtime in `boozle-py`.
+
+use boozle_gen::descriptor::{Descriptor, MethodDescriptor, ModuleDescriptor, TraitDescriptor, TypeRef};
+use boozle_gen::plugin::File;
+use std::collections::BTreeSet;
+
+pub struct Options {
+  /// Module the generated code imports the runtime from.
+  pub runtime: String,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      runtime: "boozle".to_string(),
+    }
+  }
+}
+
+impl Options {
+  /// Parses a plugin parameter: comma-separated `key=value` pairs. The only
+  /// key is `runtime`.
+  pub fn from_parameter(parameter: &str) -> Result<Self, String> {
+    let mut options = Self::default();
+    for pair in parameter.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+      let mut parts = pair.splitn(2, '=');
+      match (parts.next(), parts.next()) {
+        (Some("runtime"), Some(value)) => options.runtime = value.to_string(),
+        _ => return Err(format!("unknown parameter {:?}", pair)),
+      }
+    }
+    Ok(options)
+  }
+}
+
+/// Python keywords, and names the generated code uses, which get a trailing
+/// `_` when used as names.
+const RESERVED: &[&str] = &[
+  "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+  "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+  "nonlocal", "not", "or", "pass", "raise", "return", "self", "try", "while", "with", "yield",
+];
+
+fn ident(name: &str) -> String {
+  if RESERVED.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// How a .boozle type is represented in Python.
+enum Ty<'a> {
+  /// A literal, encoded by the runtime codec `codec`.
+  Lit { py: &'static str, codec: &'static str },
+  /// A trait, passed as an object.
+  Object(&'a [String]),
+}
+
+impl<'a> Ty<'a> {
+  fn new(ty: &'a TypeRef) -> Self {
+    let named = match ty {
+      TypeRef::Object { path } => return Self::Object(path.as_slice()),
+      TypeRef::Named { path } if path.len() == 1 => path[0].as_str(),
+      _ => "",
+    };
+    let (py, codec) = match named {
+      "bool" => ("bool", "boolean"),
+      "u8" => ("int", "u8"),
+      "u16" => ("int", "u16"),
+      "u32" => ("int", "u32"),
+      "u64" | "usize" => ("int", "u64"),
+      "i8" => ("int", "i8"),
+      "i16" => ("int", "i16"),
+      "i32" => ("int", "i32"),
+      "i64" | "isize" => ("int", "i64"),
+      "f32" => ("float", "f32"),
+      "f64" => ("float", "f64"),
+      "String" => ("str", "string"),
+      // `type` declarations and anything else are passed through as their
+      // bincode encoding.
+      _ => ("bytes", "raw"),
+    };
+    Self::Lit { py, codec }
+  }
+}
+
+/// A docstring holding `doc`, indented by `indent` levels.
+fn docstring(indent: usize, doc: &str) -> String {
+  let indent = "    ".repeat(indent);
+  let doc = doc.replace('\\', "\\\\").replace("\"\"\"", "\\\"\"\"");
+  let mut lines = doc.lines();
+  let first = lines.next().unwrap_or("");
+  let rest: Vec<&str> = lines.collect();
+  if rest.is_empty() {
+    return format!("{}\"\"\"{}\"\"\"\n", indent, first);
+  }
+  let mut out = format!("{}\"\"\"{}\n", indent, first);
+  for line in rest {
+    if line.trim().is_empty() {
+      out.push('\n');
+    } else {
+      out.push_str(format!("{}{}\n", indent, line.trim_end()).as_str());
+    }
+  }
+  out.push_str(format!("{}\"\"\"\n", indent).as_str());
+  out
+}
+
+/// Generates code for one module, referring to items of others through
+/// module aliases.
+struct ModuleGen<'a> {
+  module: &'a ModuleDescriptor,
+  /// Modules referred to, by path.
+  imports: BTreeSet<Vec<String>>,
+  out: String,
+}
+
+impl<'a> ModuleGen<'a> {
+  fn alias(path: &[String]) -> String {
+    let mut alias = String::from("_mod");
+    for component in path {
+      alias.push('_');
+      alias.push_str(ident(component).as_str());
+    }
+    alias
+  }
+
+  /// Names item `name` of the module at `module` from this one.
+  fn item(&mut self, module: &[String], name: &str) -> String {
+    if module == self.module.path.as_slice() {
+      name.to_string()
+    } else {
+      self.imports.insert(module.to_vec());
+      format!("{}.{}", Self::alias(module), name)
+    }
+  }
+
+  /// Names the item generated for trait `path` with the given suffix.
+  fn object(&mut self, path: &[String], suffix: &str) -> String {
+    let (name, module) = path.split_last().unwrap();
+    self.item(module, format!("{}{}", name, suffix).as_str())
+  }
+
+  fn py_type(&mut self, ty: &TypeRef) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { py, .. } => py.to_string(),
+      Ty::Object(path) => self.object(path, ""),
+    }
+  }
+
+  fn line(&mut self, indent: usize, text: &str) {
+    if !text.is_empty() {
+      self.out.push_str("    ".repeat(indent).as_str());
+      self.out.push_str(text);
+    }
+    self.out.push('\n');
+  }
+
+  fn doc(&mut self, indent: usize, doc: &Option<String>) {
+    if let Some(doc) = doc {
+      self.out.push_str(docstring(indent, doc).as_str());
+    }
+  }
+
+  fn check(trait_: &TraitDescriptor, method: &MethodDescriptor) -> Result<(), String> {
+    let name = format!("{}.{}", trait_.name, method.name);
+    if method.params.len() < 2 {
+      return Ok(());
+    }
+    for (i, param) in method.params.iter().enumerate() {
+      match Ty::new(&param.ty) {
+        Ty::Object(_) => {
+          return Err(format!(
+            "`{}`: object parameter `{}` must be the method's only parameter",
+            name, param.name
+          ))
+        }
+        Ty::Lit { codec: "raw", .. } if i + 1 < method.params.len() => {
+          return Err(format!(
+            "`{}`: parameter `{}` has no Python mapping and is passed as raw bytes, so it must come last",
+            name, param.name
+          ))
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn params(&mut self, method: &MethodDescriptor) -> String {
+    let mut params = vec!["self".to_string()];
+    for param in method.params.iter() {
+      let ty = self.py_type(&param.ty);
+      params.push(format!("{}: {}", ident(param.name.as_str()), ty));
+    }
+    params.join(", ")
+  }
+
+  fn result(&mut self, method: &MethodDescriptor) -> String {
+    match &method.result {
+      Some(ty) => self.py_type(ty),
+      None => "None".to_string(),
+    }
+  }
+
+  /// An expression converting `value` of type `ty` into a `boozle.Value`.
+  fn encode(&mut self, ty: &TypeRef, value: &str) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { codec, .. } => format!("boozle.lit(boozle.{}, {})", codec, value),
+      Ty::Object(path) => {
+        let object = self.object(path, "Object");
+        format!("boozle.object_value({}, {})", value, object)
+      }
+    }
+  }
+
+  /// An expression converting the `boozle.Value` `value` into `ty`.
+  fn decode(&mut self, ty: &TypeRef, value: &str, kind: &str) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { codec, .. } => format!("boozle.from_lit(boozle.{}, {}, \"{}\")", codec, value, kind),
+      Ty::Object(path) => {
+        let client = self.object(path, "Client");
+        format!("{}.from_value({}, \"{}\")", client, value, kind)
+      }
+    }
+  }
+
+  fn client_method(&mut self, method: &MethodDescriptor) {
+    let params = self.params(method);
+    let result = self.result(method);
+    self.line(1, format!("async def {}({}) -> {}:", ident(method.name.as_str()), params, result).as_str());
+    self.doc(2, &method.doc);
+    let argument = match method.params.as_slice() {
+      [] => "None".to_string(),
+      [param] => self.encode(&param.ty, ident(param.name.as_str()).as_str()),
+      params => {
+        let fields: Vec<String> = params
+          .iter()
+          .filter_map(|param| match Ty::new(&param.ty) {
+            Ty::Lit { codec, .. } => Some(format!("(boozle.{}, {})", codec, ident(param.name.as_str()))),
+            Ty::Object(_) => None,
+          })
+          .collect();
+        format!("boozle.write_lit({})", fields.join(", "))
+      }
+    };
+    let returns_object = matches!(&method.result, Some(TypeRef::Object { .. }));
+    let call = format!(
+      "self.remote.call({}, {}{})",
+      method.id,
+      argument,
+      if returns_object { ", True" } else { "" }
+    );
+    match &method.result {
+      None => self.line(2, format!("await {}", call).as_str()),
+      Some(ty) => {
+        self.line(2, format!("ret = await {}", call).as_str());
+        let decode = self.decode(ty, "ret", "InvalidResponse");
+        self.line(2, format!("return {}", decode).as_str());
+      }
+    }
+  }
+
+  fn dispatch_arm(&mut self, method: &MethodDescriptor) {
+    self.line(2, format!("if method_id == {}:", method.id).as_str());
+    match method.params.as_slice() {
+      [] => {}
+      [param] => {
+        let decode = self.decode(&param.ty, "argument", "InvalidArgument");
+        self.line(3, format!("{} = {}", ident(param.name.as_str()), decode).as_str());
+      }
+      params => {
+        let names: Vec<String> = params.iter().map(|param| ident(param.name.as_str())).collect();
+        let codecs: Vec<String> = params
+          .iter()
+          .filter_map(|param| match Ty::new(&param.ty) {
+            Ty::Lit { codec, .. } => Some(format!("boozle.{}", codec)),
+            Ty::Object(_) => None,
+          })
+          .collect();
+        self.line(
+          3,
+          format!(
+            "{} = boozle.read_lit(argument, \"InvalidArgument\", {})",
+            names.join(", "),
+            codecs.join(", ")
+          )
+          .as_str(),
+        );
+      }
+    }
+    let args: Vec<String> = method.params.iter().map(|param| ident(param.name.as_str())).collect();
+    let call = format!("self.target.{}({})", ident(method.name.as_str()), args.join(", "));
+    match &method.result {
+      None => {
+        self.line(3, format!("await {}", call).as_str());
+        self.line(3, "return None");
+      }
+      Some(ty) => {
+        self.line(3, format!("result = await {}", call).as_str());
+        let encode = self.encode(ty, "result");
+        self.line(3, format!("return {}", encode).as_str());
+      }
+    }
+  }
+
+  fn trait_(&mut self, trait_: &TraitDescriptor) -> Result<(), String> {
+    for method in trait_.methods.iter() {
+      Self::check(trait_, method)?;
+    }
+    let name = trait_.name.as_str();
+
+    self.line(0, "");
+    self.line(0, "");
+    self.line(0, format!("class {}(abc.ABC):", name).as_str());
+    self.doc(1, &trait_.doc);
+    if trait_.doc.is_none() && trait_.methods.is_empty() {
+      self.line(1, "pass");
+    }
+    for (i, method) in trait_.methods.iter().enumerate() {
+      if i > 0 || trait_.doc.is_some() {
+        self.line(0, "");
+      }
+      let params = self.params(method);
+      let result = self.result(method);
+      self.line(1, "@abc.abstractmethod");
+      self.line(1, format!("async def {}({}) -> {}:", ident(method.name.as_str()), params, result).as_str());
+      if method.doc.is_some() {
+        self.doc(2, &method.doc);
+      } else {
+        self.line(2, "...");
+      }
+    }
+
+    self.line(0, "");
+    self.line(0, "");
+    self.line(0, format!("class {}Client({}):", name, name).as_str());
+    self.line(1, "\"\"\"Calls a remote object implementing the trait of the same name.\"\"\"");
+    self.line(0, "");
+    self.line(1, "def __init__(self, remote: boozle.RemoteObject) -> None:");
+    self.line(2, "self.remote = remote");
+    self.line(0, "");
+    self.line(1, "@staticmethod");
+    self.line(
+      1,
+      format!(
+        "def from_value(value: boozle.Value, kind: str = \"InvalidResponse\") -> {}Client:",
+        name
+      )
+      .as_str(),
+    );
+    self.line(2, "\"\"\"Wraps an object value, raising a `CallError` of `kind` if it isn't a remote object.\"\"\"");
+    self.line(2, format!("return {}Client(boozle.to_remote(value, kind))", name).as_str());
+    self.line(0, "");
+    self.line(1, "def release(self) -> None:");
+    self.line(2, "\"\"\"Tells the peer this handle is no longer needed.\"\"\"");
+    self.line(2, "self.remote.release()");
+    for method in trait_.methods.iter() {
+      self.line(0, "");
+      self.client_method(method);
+    }
+
+    self.line(0, "");
+    self.line(0, "");
+    self.line(0, format!("class {}Object:", name).as_str());
+    self.line(1, format!("\"\"\"Exposes a local `{}` to the peer.\"\"\"", name).as_str());
+    self.line(0, "");
+    self.line(1, format!("def __init__(self, target: {}) -> None:", name).as_str());
+    self.line(2, "self.target = target");
+    self.line(0, "");
+    self.line(
+      1,
+      "async def boozle_dispatch(self, method_id: int, argument: boozle.Value) -> boozle.Value:",
+    );
+    for method in trait_.methods.iter() {
+      self.dispatch_arm(method);
+    }
+    self.line(2, "raise boozle.CallError(\"UnknownMethod\")");
+    Ok(())
+  }
+
+  fn generate(mut self, options: &Options) -> Result<String, String> {
+    for svc in self.module.services.iter() {
+      self.line(0, "");
+      self.line(0, format!("{}: int = {}", ident(svc.name.as_str()), svc.id).as_str());
+      self.doc(0, &svc.doc);
+    }
+    for trait_ in self.module.traits.iter() {
+      self.trait_(trait_)?;
+    }
+    if !self.module.modules.is_empty() {
+      // Imported last, so children can refer to this module's items.
+      let children: Vec<String> = self.module.modules.iter().map(|child| ident(child.name.as_str())).collect();
+      self.line(0, "");
+      self.line(0, format!("from . import {}  # noqa: E402", children.join(", ")).as_str());
+    }
+
+    let mut header = String::new();
+    if let Some(doc) = &self.module.doc {
+      header.push_str(docstring(0, doc).as_str());
+      header.push('\n');
+    }
+    header.push_str("# Generated by boozle-gen-py. Do not edit.\n\n");
+    header.push_str("from __future__ import annotations\n");
+    if !self.module.traits.is_empty() {
+      header.push('\n');
+      header.push_str("import abc\n");
+      if !self.imports.is_empty() {
+        header.push_str("import importlib\n");
+      }
+      header.push('\n');
+      if options.runtime == "boozle" {
+        header.push_str("import boozle\n");
+      } else {
+        header.push_str(format!("import {} as boozle\n", options.runtime).as_str());
+      }
+    }
+    if !self.imports.is_empty() {
+      header.push('\n');
+    }
+    let depth = self.module.path.len();
+    for path in self.imports.iter() {
+      // Relative to this package: one dot for itself, one more per ancestor.
+      let components: Vec<String> = path.iter().map(|component| ident(component)).collect();
+      let target = format!("{}{}", ".".repeat(depth + 1), components.join("."));
+      header.push_str(
+        format!(
+          "{} = importlib.import_module(\"{}\", __name__)\n",
+          Self::alias(path),
+          target
+        )
+        .as_str(),
+      );
+    }
+    let mut out = header + self.out.as_str();
+    while out.ends_with("\n\n") {
+      out.pop();
+    }
+    Ok(out)
+  }
+}
+
+fn file_path(module: &ModuleDescriptor) -> String {
+  let mut components: Vec<String> = module.path.iter().map(|component| ident(component)).collect();
+  components.push("__init__.py".to_string());
+  components.join("/")
+}
+
+fn generate_module(module: &ModuleDescriptor, options: &Options, files: &mut Vec<File>) -> Result<(), String> {
+  let gen = ModuleGen {
+    module,
+    imports: BTreeSet::new(),
+    out: String::new(),
+  };
+  files.push(File {
+    path: file_path(module),
+    content: gen.generate(options)?,
+  });
+  for child in module.modules.iter() {
+    generate_module(child, options, files)?;
+  }
+  Ok(())
+}
+
+/// Generates one package directory per module of `descriptor`. Write the
+/// files into a directory named after the package.
+pub fn generate(descriptor: &Descriptor, options: &Options) -> Result<Vec<File>, String> {
+  let mut files = Vec::new();
+  generate_module(&descriptor.root, options, &mut files)?;
+  Ok(files)
+}
@@ -0,0 +1,77 @@
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::File;
+use boozle_gen_py::{generate, Options};
+
+/// Generates code for `files`, given as (path, text) with the root first.
+fn run(name: &str, files: &[(&str, &str)], options: &Options) -> Result<Vec<File>, String> {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-py-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, text) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+  let root = boozle_gen::load(dir.join(files[0].0)).unwrap();
+  generate(&Descriptor::new(&root).unwrap(), options)
+}
+
+fn content<'a>(files: &'a [File], path: &str) -> &'a str {
+  files
+    .iter()
+    .find(|file| file.path == path)
+    .map(|file| file.content.as_str())
+    .unwrap_or_else(|| panic!("{} wasn't generated", path))
+}
+
+#[test]
+fn lays_out_packages() {
+  let files = run(
+    "modules",
+    &[
+      ("root.boozle", "mod a;\ntrait Counter {\n  fn get() -> u64;\n}\n"),
+      (
+        "a/mod.boozle",
+        "mod b;\ntrait Factory {\n  fn create(name: String) -> super.Counter;\n  fn import(from: u32 to: u32);\n}\nsvc factory: Factory;\n",
+      ),
+      ("a/b.boozle", "trait Empty {\n}\n"),
+    ],
+    &Options::from_parameter("runtime=vendor.boozle").unwrap(),
+  )
+  .unwrap();
+
+  let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+  assert_eq!(paths, vec!["__init__.py", "a/__init__.py", "a/b/__init__.py"]);
+
+  let root = content(&files, "__init__.py");
+  assert!(root.contains("import vendor.boozle as boozle"));
+  assert!(root.contains("async def get(self) -> int:"));
+  assert!(root.ends_with("from . import a  # noqa: E402\n"));
+
+  let a = content(&files, "a/__init__.py");
+  assert!(a.contains("_mod = importlib.import_module(\"..\", __name__)"));
+  assert!(a.contains("async def create(self, name: str) -> _mod.Counter:"));
+  assert!(a.contains("return _mod.CounterClient.from_value(ret, \"InvalidResponse\")"));
+  assert!(a.contains("async def import_(self, from_: int, to: int) -> None:"));
+  assert!(a.contains("from_, to = boozle.read_lit(argument, \"InvalidArgument\", boozle.u32, boozle.u32)"));
+  assert!(a.contains("factory: int = "));
+}
+
+#[test]
+fn rejects_object_among_params() {
+  let err = run(
+    "objects",
+    &[(
+      "root.boozle",
+      "trait Counter {\n  fn get() -> u64;\n}\ntrait Registry {\n  fn register(name: String counter: Counter);\n}\n",
+    )],
+    &Options::default(),
+  )
+  .unwrap_err();
+  assert!(err.contains("must be the method's only parameter"), "{}", err);
+}
+
+#[test]
+fn rejects_unknown_parameters() {
+  assert_eq!(Options::from_parameter("").unwrap().runtime, "boozle");
+  assert!(Options::from_parameter("namespace=x").is_err());
+}
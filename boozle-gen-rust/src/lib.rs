@@ -1,41 +1,157 @@
-use boozle_parser::{Decl, Fn, Mod, Param, Path, Svc, Trait, Type, Unit};
-use digest::Digest;
+use boozle_gen::descriptor::resolve_path;
+use boozle_gen::{svc_id, LoadError, Module};
+use boozle_parser::{find_attr, Decl, Fn, Mod, Param, Path, Svc, Trait, Type, TypeDecl, Unit};
 use proc_macro2::{Span, TokenStream};
-use quote::quote;
-use sha2::Sha256;
-use std::collections::HashMap;
-use std::convert::TryInto;
+use quote::{format_ident, quote};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use syn;
 
-fn generate_mod(module: &Mod) -> TokenStream {
+pub use boozle_gen::method_id;
+
+pub mod snippets;
+
+use snippets::Slot;
+
+/// Controls which optional items the generator emits.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+  /// Generate a `FooBlockingClient` for every trait `Foo`, for use from
+  /// synchronous code. Calls run on the runtime of a `boozle::blocking::Handle`.
+  pub blocking: bool,
+  /// Generate a `MockFoo` for every trait `Foo`. Mocks record their calls,
+  /// answer with programmed responses, and implement `Object`. Clones share
+  /// their state, so an exposed mock can be inspected through a clone.
+  pub mocks: bool,
+  /// Maps .boozle types onto existing Rust types, e.g. `"Uuid"` to
+  /// `"uuid::Uuid"`. Keys are paths as written in .boozle (`net.Uuid`). A
+  /// `type` declaration is also looked up by its bare name. Entries take
+  /// precedence over `#[rust(type = "...")]` attributes. Mapped types must
+  /// implement `Serialize` and `DeserializeOwned`.
+  pub types: HashMap<String, String>,
+}
+
+/// State shared by the generator while walking a unit.
+#[derive(Clone)]
+struct Context<'a> {
+  options: &'a Options,
+  /// Full paths of every trait declared in the unit. Types that refer to one
+  /// of these are passed by reference (as live objects) rather than by value.
+  traits: HashSet<Vec<String>>,
+  /// Path of the module being generated, excluding the root.
+  module: Vec<String>,
+}
+
+impl<'a> Context<'a> {
+  fn new(unit: &Unit, options: &'a Options) -> Self {
+    let mut traits = HashSet::new();
+    collect_traits(&mut Vec::new(), &unit.decls, &mut traits);
+    Self {
+      options,
+      traits,
+      module: Vec::new(),
+    }
+  }
+
+  fn from_module(module: &Module, options: &'a Options) -> Self {
+    let mut traits = HashSet::new();
+    module.walk(&mut |path, module| {
+      let mut path = path.iter().map(|name| name.to_string()).collect();
+      collect_traits(&mut path, &module.decls, &mut traits)
+    });
+    Self {
+      options,
+      traits,
+      module: Vec::new(),
+    }
+  }
+
+  /// Returns the context for generating the child module `name`.
+  fn child(&self, name: &str) -> Self {
+    let mut ctx = self.clone();
+    ctx.module.push(name.to_string());
+    ctx
+  }
+
+  /// Returns the `Options::types` mapping for a `type` declaration in the
+  /// current module, trying its qualified name before its bare name.
+  fn mapped_decl(&self, name: &str) -> Option<TokenStream> {
+    let mut qualified = self.module.clone();
+    qualified.push(name.to_string());
+    self
+      .mapped_type(qualified.join(".").as_str())
+      .or_else(|| self.mapped_type(name))
+  }
+
+  /// Returns the ID of `fn_` of `trait_`, which is declared in the current
+  /// module.
+  fn method_id(&self, trait_: &Trait, fn_: &Fn) -> u64 {
+    let mut path = self.module.clone();
+    path.push(trait_.name.text.clone());
+    method_id(path.join(".").as_str(), fn_.name.text.as_str())
+  }
+
+  /// Returns the Rust type `key` is mapped to by `Options::types`.
+  fn mapped_type(&self, key: &str) -> Option<TokenStream> {
+    self.options.types.get(key).map(|ty| parse_rust_type(ty.as_str()))
+  }
+
+  /// Whether `ty`, as written in the current module, names a trait.
+  fn is_object(&self, ty: &Type) -> bool {
+    match resolve_path(&self.module, &ty.path) {
+      Some(path) => self.traits.contains(&path),
+      None => false,
+    }
+  }
+}
+
+/// Collects the full paths of the traits in `decls`, which are declared in the
+/// module at `path`.
+fn collect_traits(path: &mut Vec<String>, decls: &[Decl], traits: &mut HashSet<Vec<String>>) {
+  for decl in decls.iter() {
+    match decl {
+      Decl::Trait(trait_) => {
+        let mut full = path.clone();
+        full.push(trait_.name.text.clone());
+        traits.insert(full);
+      }
+      Decl::Mod(module) => {
+        if let Some(decls) = &module.decls {
+          path.push(module.name.text.clone());
+          collect_traits(path, decls, traits);
+          path.pop();
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+fn generate_mod(ctx: &Context, module: &Mod) -> TokenStream {
   let ident = syn::Ident::new(module.name.text.as_str(), Span::call_site());
   match &module.decls {
     Some(decls) => {
+      let ctx = ctx.child(module.name.text.as_str());
       let mut children = TokenStream::new();
       for decl in decls.iter() {
-        children.extend(generate_decl(decl));
+        children.extend(generate_decl(&ctx, decl));
       }
       quote! {
-        mod #ident {
+        pub mod #ident {
           #children
         }
       }
     }
-    None => quote! { mod #ident; },
+    None => quote! { pub mod #ident; },
   }
 }
 
-fn id(text: &str) -> u64 {
-  let digest = Sha256::digest(text.as_bytes());
-  let (top, _) = digest.split_at(std::mem::size_of::<u64>());
-  u64::from_ne_bytes(top.try_into().unwrap())
-}
-
 fn generate_svc(svc: &Svc) -> TokenStream {
   let ident = syn::Ident::new(svc.name.text.as_str(), Span::call_site());
-  let id = id(svc.name.text.as_str());
+  let id = svc_id(svc.name.text.as_str());
   quote! {
-    u64 #ident = #id;
+    #[allow(non_upper_case_globals)]
+    pub const #ident: u64 = #id;
   }
 }
 
@@ -50,52 +166,673 @@ fn generate_path(path: &Path) -> TokenStream {
   }
 }
 
-fn generate_type(ty: &Type) -> TokenStream {
+/// Generates the path to a type derived from an object type, e.g. `a.Foo`
+/// with the suffix `Client` becomes `a::FooClient`.
+fn generate_client_path(path: &Path, suffix: &str) -> TokenStream {
+  let (last, rest) = path.components.split_last().unwrap();
+  let rest: Vec<syn::Ident> = rest
+    .iter()
+    .map(|id| syn::Ident::new(id.text.as_str(), Span::call_site()))
+    .collect();
+  let last = format_ident!("{}{}", last.text, suffix);
+  quote! {
+    #(#rest::)* #last
+  }
+}
+
+/// Parses a user-supplied Rust type. Invalid types become a compile error in
+/// the generated code.
+fn parse_rust_type(text: &str) -> TokenStream {
+  match syn::parse_str::<syn::Type>(text) {
+    Ok(ty) => quote! { #ty },
+    Err(err) => {
+      let message = format!("boozle: invalid Rust type `{}`: {}", text, err);
+      quote! { compile_error!(#message) }
+    }
+  }
+}
+
+/// Checks at compile time that a mapped type can be sent as a literal.
+fn generate_lit_assertion(ty: &TokenStream) -> TokenStream {
+  quote! {
+    const _: fn() = boozle::value::assert_lit::<#ty>;
+  }
+}
+
+/// Generates the Rust side of `type Foo;`: an alias for the type given by
+/// `Options::types` or a `#[rust(type = "...")]` attribute.
+fn generate_type_decl(ctx: &Context, type_: &TypeDecl) -> TokenStream {
+  let ident = syn::Ident::new(type_.name.text.as_str(), Span::call_site());
+  let ty = match ctx.mapped_decl(type_.name.text.as_str()) {
+    Some(ty) => ty,
+    None => match find_attr(&type_.attrs, "rust").and_then(|attr| attr.arg("type")) {
+      Some(ty) => parse_rust_type(ty),
+      None => {
+        let message = format!(
+          "boozle: no Rust type for `{}`, add #[rust(type = \"...\")] or a type mapping",
+          type_.name.text
+        );
+        return quote! { compile_error!(#message); };
+      }
+    },
+  };
+  let assertion = generate_lit_assertion(&ty);
+  quote! {
+    pub type #ident = #ty;
+    #assertion
+  }
+}
+
+/// Assertions for every entry of `Options::types`, emitted once at the root.
+fn generate_type_map_assertions(ctx: &Context) -> TokenStream {
+  let mut keys: Vec<&String> = ctx.options.types.keys().collect();
+  keys.sort();
+  keys
+    .into_iter()
+    .filter_map(|key| ctx.mapped_type(key.as_str()))
+    .map(|ty| generate_lit_assertion(&ty))
+    .collect()
+}
+
+fn generate_type(ctx: &Context, ty: &Type) -> TokenStream {
+  if let Some(mapped) = ctx.mapped_type(ty.path.to_string().as_str()) {
+    return mapped;
+  }
+  if ctx.is_object(ty) {
+    return generate_client_path(&ty.path, "Client");
+  }
   let path = generate_path(&ty.path);
   quote! {
     #path
   }
 }
 
-fn generate_param(param: &Param) -> TokenStream {
+fn generate_param(ctx: &Context, param: &Param) -> TokenStream {
   let ident = syn::Ident::new(param.name.text.as_str(), Span::call_site());
-  let ty = generate_type(&param.ty);
+  let ty = generate_type(ctx, &param.ty);
   quote! {
     #ident : #ty
   }
 }
 
-fn generate_fn(fn_: &Fn) -> TokenStream {
+fn generate_result(ctx: &Context, result: &Option<Type>) -> TokenStream {
+  let ty = match result {
+    Some(ty) => generate_type(ctx, ty),
+    None => quote! { () },
+  };
+  quote! {
+    Result<#ty, boozle::object::CallError>
+  }
+}
+
+fn generate_fn(ctx: &Context, fn_: &Fn) -> TokenStream {
   let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
-  let params: Vec<TokenStream> = fn_.params.iter().map(generate_param).collect();
+  let params: Vec<TokenStream> = fn_.params.iter().map(|p| generate_param(ctx, p)).collect();
+  let result = generate_result(ctx, &fn_.result);
+  quote! {
+    async fn #ident(&self, #(#params),*) -> #result;
+  }
+}
+
+fn param_idents(fn_: &Fn) -> Vec<syn::Ident> {
+  fn_
+    .params
+    .iter()
+    .map(|param| syn::Ident::new(param.name.text.as_str(), Span::call_site()))
+    .collect()
+}
+
+/// The parameters of `fn_`, as the generated code passes them.
+fn param_slots(ctx: &Context, fn_: &Fn) -> Vec<(syn::Ident, Slot)> {
+  param_idents(fn_)
+    .into_iter()
+    .zip(fn_.params.iter())
+    .map(|(ident, param)| {
+      let slot = Slot {
+        ty: generate_type(ctx, &param.ty),
+        object: ctx.is_object(&param.ty),
+      };
+      (ident, slot)
+    })
+    .collect()
+}
+
+fn result_slot(ctx: &Context, fn_: &Fn) -> Option<Slot> {
+  fn_.result.as_ref().map(|ty| Slot {
+    ty: generate_type(ctx, ty),
+    object: ctx.is_object(ty),
+  })
+}
+
+/// Objects can only be passed as a method's only parameter, as several
+/// parameters are sent as one literal tuple.
+fn check_object_params(ctx: &Context, fn_: &Fn) -> Option<TokenStream> {
+  if fn_.params.len() < 2 {
+    return None;
+  }
+  let param = fn_.params.iter().find(|param| ctx.is_object(&param.ty))?;
+  let message = format!(
+    "boozle: object parameter `{}` of `{}` must be the method's only parameter",
+    param.name.text, fn_.name.text
+  );
+  Some(quote! { compile_error!(#message) })
+}
+
+fn generate_client_fn(ctx: &Context, trait_: &Trait, fn_: &Fn) -> TokenStream {
+  let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
+  let params: Vec<TokenStream> = fn_.params.iter().map(|p| generate_param(ctx, p)).collect();
+  let result = generate_result(ctx, &fn_.result);
+  let method_id = ctx.method_id(trait_, fn_);
+  let body = match check_object_params(ctx, fn_) {
+    Some(error) => quote! { #error },
+    None => snippets::client_body(method_id, false, &param_slots(ctx, fn_), result_slot(ctx, fn_).as_ref()),
+  };
+  quote! {
+    async fn #ident(&self, #(#params),*) -> #result {
+      #body
+    }
+  }
+}
+
+fn generate_client(ctx: &Context, trait_: &Trait) -> TokenStream {
+  let trait_ident = syn::Ident::new(trait_.name.text.as_str(), Span::call_site());
+  let ident = format_ident!("{}Client", trait_.name.text);
+  let name = ident.to_string();
+  let members: Vec<TokenStream> = trait_
+    .members
+    .iter()
+    .map(|fn_| generate_client_fn(ctx, trait_, fn_))
+    .collect();
   quote! {
-    fn #ident(#(#params),*);
+    /// A handle to an object implementing the trait of the same name. The
+    /// object may live on this node or on a remote one.
+    #[derive(Clone)]
+    pub struct #ident {
+      actor: actix::Addr<boozle::object::ObjectActor>,
+    }
+
+    impl #ident {
+      pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+      }
+
+      /// Wraps an object value. Returns `None` if the value is a literal.
+      pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+      }
+
+      pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+      }
+    }
+
+    impl std::fmt::Debug for #ident {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(#name).finish()
+      }
+    }
+
+    #[async_trait::async_trait]
+    impl #trait_ident for #ident {
+      #(#members)*
+    }
   }
 }
 
-fn generate_trait(trait_: &Trait) -> TokenStream {
+fn generate_dispatch_arm(ctx: &Context, trait_: &Trait, fn_: &Fn) -> TokenStream {
+  let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
+  let method_id = ctx.method_id(trait_, fn_);
+  snippets::dispatch_arm(
+    method_id,
+    quote! { target },
+    &ident,
+    &param_slots(ctx, fn_),
+    result_slot(ctx, fn_).as_ref(),
+  )
+}
+
+/// Generates `FooObject<T>`, which exposes any implementation of `Foo` as a
+/// boozle `Object`.
+fn generate_object(ctx: &Context, trait_: &Trait) -> TokenStream {
+  let trait_ident = syn::Ident::new(trait_.name.text.as_str(), Span::call_site());
+  let ident = format_ident!("{}Object", trait_.name.text);
+  let arms: Vec<TokenStream> = trait_
+    .members
+    .iter()
+    .map(|fn_| generate_dispatch_arm(ctx, trait_, fn_))
+    .collect();
+  quote! {
+    #[derive(Debug)]
+    pub struct #ident<T>(pub T);
+
+    impl<T> #ident<T>
+    where
+      T: #trait_ident,
+    {
+      /// Decodes `call` and invokes the matching method of `target`.
+      #[allow(unused_variables)]
+      pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+          #(#arms)*
+          _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+      }
+    }
+
+    #[async_trait::async_trait]
+    impl<T> boozle::object::Object for #ident<T>
+    where
+      T: #trait_ident + std::fmt::Debug + Send + Sync,
+    {
+      async fn call(
+        &self,
+        call: boozle::object::Call,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+      }
+
+      async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self
+          .call(boozle::object::Call::new(call_mut.method_id, call_mut.argument))
+          .await
+      }
+
+      fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+      }
+    }
+  }
+}
+
+fn generate_blocking_type(ctx: &Context, ty: &Type) -> TokenStream {
+  if ctx.is_object(ty) {
+    generate_client_path(&ty.path, "BlockingClient")
+  } else {
+    generate_type(ctx, ty)
+  }
+}
+
+fn generate_blocking_fn(ctx: &Context, fn_: &Fn) -> TokenStream {
+  let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
+  let idents = param_idents(fn_);
+  let params: Vec<TokenStream> = fn_
+    .params
+    .iter()
+    .zip(idents.iter())
+    .map(|(param, ident)| {
+      let ty = generate_blocking_type(ctx, &param.ty);
+      quote! { #ident: #ty }
+    })
+    .collect();
+  // Blocking clients are unwrapped into their async counterparts before
+  // being moved onto the runtime.
+  let args: Vec<TokenStream> = fn_
+    .params
+    .iter()
+    .zip(idents.iter())
+    .map(|(param, ident)| {
+      if ctx.is_object(&param.ty) {
+        quote! { #ident.into_async() }
+      } else {
+        quote! { #ident }
+      }
+    })
+    .collect();
+  let (result_ty, wrap) = match &fn_.result {
+    None => (quote! { () }, TokenStream::new()),
+    Some(ty) => {
+      let rust_ty = generate_blocking_type(ctx, ty);
+      let wrap = if ctx.is_object(ty) {
+        quote! { .map(|client| #rust_ty::new(client, self.handle.clone())) }
+      } else {
+        TokenStream::new()
+      };
+      (rust_ty, wrap)
+    }
+  };
+  quote! {
+    pub fn #ident(&self, #(#params),*) -> Result<#result_ty, boozle::object::CallError> {
+      let client = self.client.clone();
+      self
+        .handle
+        .call(move || async move { client.#ident(#(#args),*).await })
+        #wrap
+    }
+  }
+}
+
+/// Generates `FooBlockingClient`, which wraps a `FooClient` with synchronous
+/// methods that block the calling thread.
+fn generate_blocking_client(ctx: &Context, trait_: &Trait) -> TokenStream {
+  let client = format_ident!("{}Client", trait_.name.text);
+  let ident = format_ident!("{}BlockingClient", trait_.name.text);
+  let name = ident.to_string();
+  let members: Vec<TokenStream> = trait_
+    .members
+    .iter()
+    .map(|fn_| generate_blocking_fn(ctx, fn_))
+    .collect();
+  quote! {
+    #[derive(Clone)]
+    pub struct #ident {
+      client: #client,
+      handle: boozle::blocking::Handle,
+    }
+
+    impl #ident {
+      pub fn new(client: #client, handle: boozle::blocking::Handle) -> Self {
+        Self { client, handle }
+      }
+
+      /// Wraps an object value. Returns `None` if the value is a literal.
+      pub fn from_value(
+        value: boozle::value::LocalValue,
+        handle: boozle::blocking::Handle,
+      ) -> Option<Self> {
+        #client::from_value(value).map(|client| Self::new(client, handle))
+      }
+
+      pub fn into_async(self) -> #client {
+        self.client
+      }
+
+      #(#members)*
+    }
+
+    impl std::fmt::Debug for #ident {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(#name).finish()
+      }
+    }
+  }
+}
+
+/// The type a mock records for each call: nothing, the lone parameter, or a
+/// tuple of all parameters.
+fn generate_mock_args(ctx: &Context, fn_: &Fn) -> TokenStream {
+  let tys: Vec<TokenStream> = fn_.params.iter().map(|param| generate_type(ctx, &param.ty)).collect();
+  match tys.as_slice() {
+    [ty] => ty.clone(),
+    tys => quote! { (#(#tys),*) },
+  }
+}
+
+/// Generates `MockFoo`, an implementation of `Foo` with a
+/// `boozle::mock::MockMethod` for each method.
+fn generate_mock(ctx: &Context, trait_: &Trait) -> TokenStream {
+  let trait_ident = syn::Ident::new(trait_.name.text.as_str(), Span::call_site());
+  let object = format_ident!("{}Object", trait_.name.text);
+  let ident = format_ident!("Mock{}", trait_.name.text);
+  let fields: Vec<TokenStream> = trait_
+    .members
+    .iter()
+    .map(|fn_| {
+      let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
+      let args = generate_mock_args(ctx, fn_);
+      let result = match &fn_.result {
+        Some(ty) => generate_type(ctx, ty),
+        None => quote! { () },
+      };
+      quote! { pub #ident: boozle::mock::MockMethod<#args, #result> }
+    })
+    .collect();
+  let methods: Vec<TokenStream> = trait_
+    .members
+    .iter()
+    .map(|fn_| {
+      let ident = syn::Ident::new(fn_.name.text.as_str(), Span::call_site());
+      let params: Vec<TokenStream> = fn_.params.iter().map(|p| generate_param(ctx, p)).collect();
+      let idents = param_idents(fn_);
+      let result = generate_result(ctx, &fn_.result);
+      let args = match idents.as_slice() {
+        [ident] => quote! { #ident },
+        idents => quote! { (#(#idents),*) },
+      };
+      quote! {
+        async fn #ident(&self, #(#params),*) -> #result {
+          self.#ident.invoke(#args)
+        }
+      }
+    })
+    .collect();
+  quote! {
+    #[derive(Debug, Default, Clone)]
+    pub struct #ident {
+      #(#fields,)*
+    }
+
+    impl #ident {
+      pub fn new() -> Self {
+        Self::default()
+      }
+    }
+
+    #[async_trait::async_trait]
+    impl #trait_ident for #ident {
+      #(#methods)*
+    }
+
+    #[async_trait::async_trait]
+    impl boozle::object::Object for #ident {
+      async fn call(
+        &self,
+        call: boozle::object::Call,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        #object::dispatch(self, call).await
+      }
+
+      async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self
+          .call(boozle::object::Call::new(call_mut.method_id, call_mut.argument))
+          .await
+      }
+
+      fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+      }
+    }
+  }
+}
+
+fn generate_trait(ctx: &Context, trait_: &Trait) -> TokenStream {
   let ident = syn::Ident::new(trait_.name.text.as_str(), Span::call_site());
-  let members: Vec<TokenStream> = trait_.members.iter().map(generate_fn).collect();
+  let members: Vec<TokenStream> = trait_.members.iter().map(|fn_| generate_fn(ctx, fn_)).collect();
+  let client = generate_client(ctx, trait_);
+  let object = generate_object(ctx, trait_);
+  let blocking_client = if ctx.options.blocking {
+    generate_blocking_client(ctx, trait_)
+  } else {
+    TokenStream::new()
+  };
+  let mock = if ctx.options.mocks {
+    generate_mock(ctx, trait_)
+  } else {
+    TokenStream::new()
+  };
   quote! {
-    trait #ident {
+    #[async_trait::async_trait]
+    pub trait #ident: Send + Sync {
       #(#members)*
     }
+
+    #client
+    #object
+    #blocking_client
+    #mock
   }
 }
 
-fn generate_decl(decl: &Decl) -> TokenStream {
+fn generate_decl(ctx: &Context, decl: &Decl) -> TokenStream {
   match decl {
-    Decl::Mod(module) => generate_mod(&module),
+    Decl::Mod(module) => generate_mod(ctx, &module),
     Decl::Svc(svc) => generate_svc(&svc),
-    Decl::Trait(trait_) => generate_trait(&trait_),
+    Decl::Trait(trait_) => generate_trait(ctx, &trait_),
+    Decl::Type(type_) => generate_type_decl(ctx, type_),
     _ => TokenStream::new(),
   }
 }
 
-fn generate_unit(unit: &Unit) -> TokenStream {
-  let mut stream = TokenStream::new();
+fn generate_unit(unit: &Unit, options: &Options) -> TokenStream {
+  let ctx = Context::new(unit, options);
+  let mut stream = generate_type_map_assertions(&ctx);
   for decl in unit.decls.iter() {
-    stream.extend(generate_decl(&decl));
+    stream.extend(generate_decl(&ctx, &decl));
   }
   stream
 }
+
+/// Generates Rust bindings for a parsed unit.
+///
+/// For every trait `Foo` this emits the trait itself, a `FooClient` that
+/// implements `Foo` by calling into a (possibly remote) object, and a
+/// `FooObject<T>` that exposes a local implementation of `Foo` as an `Object`.
+/// The generated code expects `boozle`, `actix` and `async_trait`
+/// to be available as dependencies.
+pub fn generate(unit: &Unit) -> TokenStream {
+  generate_with_options(unit, &Options::default())
+}
+
+/// Like `generate`, but with control over optional output.
+pub fn generate_with_options(unit: &Unit, options: &Options) -> TokenStream {
+  generate_unit(unit, options)
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Load(LoadError),
+  Io(PathBuf, std::io::Error),
+  Rustfmt(String),
+}
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Load(err) => write!(f, "{}", err),
+      Self::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+      Self::Rustfmt(err) => write!(f, "rustfmt failed: {}", err),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<LoadError> for Error {
+  fn from(err: LoadError) -> Self {
+    Self::Load(err)
+  }
+}
+
+/// Returns the file a module is written to, relative to the output directory.
+/// The root module is `mod.rs`, and its descendants follow Rust's
+/// non-`mod.rs` layout: `a.rs`, `a/b.rs`, and so on.
+fn module_file(path: &[&str]) -> PathBuf {
+  match path.split_last() {
+    None => PathBuf::from("mod.rs"),
+    Some((last, parents)) => {
+      let mut file: PathBuf = parents.iter().collect();
+      file.push(format!("{}.rs", last));
+      file
+    }
+  }
+}
+
+/// Generates one file per module of a protocol tree. Each file holds the
+/// module's own declarations and a `pub mod` item for each of its children.
+/// Paths are relative to the output directory (see `write_tree`).
+pub fn generate_files(root: &Module, options: &Options) -> Vec<(PathBuf, TokenStream)> {
+  let ctx = Context::from_module(root, options);
+  let mut files = Vec::new();
+  root.walk(&mut |path, module| {
+    let mut ctx = ctx.clone();
+    ctx.module = path.iter().map(|name| name.to_string()).collect();
+    let mut stream = if path.is_empty() {
+      generate_type_map_assertions(&ctx)
+    } else {
+      TokenStream::new()
+    };
+    for child in module.children.iter() {
+      let ident = syn::Ident::new(child.name.as_str(), Span::call_site());
+      stream.extend(quote! { pub mod #ident; });
+    }
+    for decl in module.decls.iter() {
+      stream.extend(generate_decl(&ctx, decl));
+    }
+    files.push((module_file(path), stream));
+  });
+  files
+}
+
+/// Rewrites the `#[doc = r" ..."]` attributes produced by `quote!` as `///`
+/// comments. Expects one attribute per line, as left by rustfmt.
+fn doc_comments(text: &str) -> String {
+  let mut output = String::with_capacity(text.len());
+  for line in text.lines() {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    match trimmed
+      .strip_prefix("#[doc = r\"")
+      .and_then(|rest| rest.strip_suffix("\"]"))
+    {
+      Some(doc) => {
+        output.push_str(indent);
+        output.push_str("///");
+        output.push_str(doc);
+      }
+      None => output.push_str(line),
+    }
+    output.push('\n');
+  }
+  output
+}
+
+fn rustfmt(files: &[PathBuf]) -> Result<(), Error> {
+  let rustfmt = std::env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+  let output = std::process::Command::new(rustfmt)
+    .arg("--edition")
+    .arg("2018")
+    .args(files)
+    .output()
+    .map_err(|err| Error::Rustfmt(err.to_string()))?;
+  if !output.status.success() {
+    return Err(Error::Rustfmt(String::from_utf8_lossy(&output.stderr).to_string()));
+  }
+  Ok(())
+}
+
+/// Loads the protocol tree rooted at the .boozle file `root` and writes its
+/// bindings to `out_dir`, one rustfmt-formatted file per module. The entry
+/// point is `out_dir/mod.rs`. Returns the paths of the files written.
+///
+/// rustfmt is run from `PATH`, or from the `RUSTFMT` environment variable if set.
+pub fn write_tree<P, Q>(root: P, out_dir: Q, options: &Options) -> Result<Vec<PathBuf>, Error>
+where
+  P: AsRef<std::path::Path>,
+  Q: AsRef<std::path::Path>,
+{
+  let module = boozle_gen::load(root)?;
+  let mut written = Vec::new();
+  for (file, stream) in generate_files(&module, options) {
+    let path = out_dir.as_ref().join(file);
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|err| Error::Io(parent.to_path_buf(), err))?;
+    }
+    std::fs::write(&path, stream.to_string()).map_err(|err| Error::Io(path.clone(), err))?;
+    written.push(path);
+  }
+  rustfmt(&written)?;
+  for path in written.iter() {
+    let text = std::fs::read_to_string(path).map_err(|err| Error::Io(path.clone(), err))?;
+    std::fs::write(path, doc_comments(text.as_str())).map_err(|err| Error::Io(path.clone(), err))?;
+  }
+  Ok(written)
+}
@@ -0,0 +1,165 @@
+//! Pieces of generated code shared with `#[boozle::object]`, so objects and
+//! clients made either way encode their calls the same way.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// A parameter or result of a method, as generated code sees it.
+pub struct Slot {
+  pub ty: TokenStream,
+  /// Whether it's passed as a live object, in which case `ty` is a client type.
+  pub object: bool,
+}
+
+/// Generates an expression that packs a method's parameters into the single
+/// `Option<LocalValue>` argument of a call.
+///
+/// A lone parameter is sent as-is. Several parameters are sent as a bincode
+/// tuple, which means only literal types may appear in that position.
+pub fn encode_argument(params: &[(syn::Ident, Slot)]) -> TokenStream {
+  match params {
+    [] => quote! { None },
+    [(ident, slot)] if slot.object => quote! { Some(#ident.into_value()) },
+    [(ident, _)] => quote! { Some(boozle::value::LocalValue::from_lit(&#ident)) },
+    params => {
+      let idents = params.iter().map(|(ident, _)| ident);
+      quote! { Some(boozle::value::LocalValue::from_lit(&(#(#idents),*))) }
+    }
+  }
+}
+
+/// The inverse of `encode_argument`. Binds each parameter from an
+/// `Option<LocalValue>` named `argument`.
+pub fn decode_argument(params: &[(syn::Ident, Slot)]) -> TokenStream {
+  match params {
+    [] => TokenStream::new(),
+    [(ident, slot)] => {
+      let ty = &slot.ty;
+      if slot.object {
+        quote! {
+          let #ident = argument
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(#ty::new)
+            .ok_or(boozle::object::CallError::InvalidArgument)?;
+        }
+      } else {
+        quote! {
+          let #ident: #ty = argument
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit)
+            .ok_or(boozle::object::CallError::InvalidArgument)?;
+        }
+      }
+    }
+    params => {
+      let idents = params.iter().map(|(ident, _)| ident);
+      let tys = params.iter().map(|(_, slot)| &slot.ty);
+      quote! {
+        let (#(#idents),*): (#(#tys),*) = argument
+          .as_ref()
+          .and_then(boozle::value::LocalValue::to_lit)
+          .ok_or(boozle::object::CallError::InvalidArgument)?;
+      }
+    }
+  }
+}
+
+/// Generates an expression that turns the `Return` named `ret` into the
+/// method's result type.
+pub fn decode_result(result: Option<&Slot>) -> TokenStream {
+  match result {
+    None => quote! { Ok(()) },
+    Some(slot) => {
+      let ty = &slot.ty;
+      if slot.object {
+        quote! {
+          ret
+            .result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(#ty::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+        }
+      } else {
+        quote! {
+          ret
+            .result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<#ty>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+        }
+      }
+    }
+  }
+}
+
+/// The inverse of `decode_result`. Converts a value named `result` into an
+/// `Option<LocalValue>`.
+pub fn encode_result(result: Option<&Slot>) -> TokenStream {
+  match result {
+    None => quote! { None },
+    Some(slot) if slot.object => quote! { Some(result.into_value()) },
+    Some(_) => quote! { Some(boozle::value::LocalValue::from_lit(&result)) },
+  }
+}
+
+/// Generates the body of a client method: calls the method of the object
+/// held in `self.actor` and decodes its result.
+pub fn client_body(
+  method_id: u64,
+  mutable: bool,
+  params: &[(syn::Ident, Slot)],
+  result: Option<&Slot>,
+) -> TokenStream {
+  let argument = encode_argument(params);
+  let decode = decode_result(result);
+  let call = if mutable {
+    quote! {
+      .call_mut(boozle::object::CallMut {
+        method_id: #method_id,
+        argument,
+      })
+    }
+  } else {
+    quote! { .call(boozle::object::Call::new(#method_id, argument)) }
+  };
+  // Methods without a result ignore the return value.
+  let ret = match result {
+    Some(_) => quote! { ret },
+    None => quote! { _ },
+  };
+  quote! {
+    use boozle::object::ObjectActorHelpers;
+    let argument = #argument;
+    let #ret = self
+      .actor
+      #call
+      .await?;
+    #decode
+  }
+}
+
+/// Generates the match arm dispatching `method_id` to the method `ident` of
+/// `target`, decoding the `argument` of the call.
+pub fn dispatch_arm(
+  method_id: u64,
+  target: TokenStream,
+  ident: &syn::Ident,
+  params: &[(syn::Ident, Slot)],
+  result: Option<&Slot>,
+) -> TokenStream {
+  let decode = decode_argument(params);
+  let idents = params.iter().map(|(ident, _)| ident);
+  let encode = encode_result(result);
+  let call = quote! { #target.#ident(#(#idents),*).await? };
+  let call = match result {
+    Some(_) => quote! { let result = #call; },
+    None => quote! { #call; },
+  };
+  quote! {
+    #method_id => {
+      #decode
+      #call
+      Ok(boozle::object::Return { result: #encode })
+    }
+  }
+}
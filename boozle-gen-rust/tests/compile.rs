@@ -0,0 +1,136 @@
+//! Builds the expected output in `tests/expected` against the boozle runtime,
+//! so broken generated code fails here even when the snapshots are up to date.
+
+// Generated items are public API that these tests don't all use.
+#![allow(dead_code)]
+
+#[path = "expected/attributes/mod.rs"]
+mod attributes;
+#[path = "expected/basic/mod.rs"]
+mod basic;
+#[path = "expected/blocking/mod.rs"]
+mod blocking;
+#[path = "expected/mocks/mod.rs"]
+mod mocks;
+#[path = "expected/modules/mod.rs"]
+mod modules;
+#[path = "expected/objects/mod.rs"]
+mod objects;
+
+use actix::{Actor, Addr};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::CallError;
+use boozle::pool::PoolHelpers;
+use boozle::value::LocalValue;
+use mocks::{MockStore, Store, StoreClient};
+
+/// Connects two new connections and returns the client end and the server end.
+fn connect() -> (Addr<Connection>, Addr<Connection>) {
+  let (client_tx, server_rx) = tokio::sync::mpsc::channel(16);
+  let (server_tx, client_rx) = tokio::sync::mpsc::channel(16);
+  (
+    Connection::new(client_tx, client_rx).start(),
+    Connection::new(server_tx, server_rx).start(),
+  )
+}
+
+#[test]
+fn client_calls_mock() {
+  actix::System::new("compile").block_on(async {
+    let mock = MockStore::new();
+    mock.get.respond_with(|key| Ok(format!("value of {}", key)));
+    mock.put.respond(Ok(()));
+    let client = StoreClient::from_value(LocalValue::from_object(mock)).unwrap();
+
+    assert_eq!(client.get("a".to_string()).await.unwrap(), "value of a");
+    client.put("a".to_string(), "b".to_string()).await.unwrap();
+    match client.clear().await {
+      Err(CallError::Failed) => {}
+      other => panic!("unexpected result: {:?}", other),
+    }
+  });
+}
+
+#[test]
+fn responders_can_use_their_mock() {
+  let mock = MockStore::new();
+  let get = mock.get.clone();
+  // Switches to a second responder after the first call.
+  mock.get.respond_with(move |key| {
+    get.respond_with(|_| Ok("second".to_string()));
+    Ok(format!("first {}", key))
+  });
+  assert_eq!(mock.get.invoke("a".to_string()).unwrap(), "first a");
+  assert_eq!(mock.get.invoke("b".to_string()).unwrap(), "second");
+
+  // A panicking responder leaves the mock usable.
+  mock.get.respond_with(|_| panic!("responder panicked"));
+  let get = mock.get.clone();
+  assert!(std::panic::catch_unwind(move || get.invoke("c".to_string())).is_err());
+  mock.get.respond(Ok("queued".to_string()));
+  assert_eq!(mock.get.invoke("d".to_string()).unwrap(), "queued");
+  assert_eq!(mock.get.calls(), ["a", "b", "d"]);
+}
+
+#[test]
+fn client_calls_mock_remotely() {
+  actix::System::new("compile").block_on(async {
+    let (client, server) = connect();
+    let mock = MockStore::new();
+    let key = server
+      .pool()
+      .await
+      .expose(LocalValue::from_object(mock.clone()))
+      .await
+      .unwrap()
+      .key;
+    let store = StoreClient::from_value(client.remote(key)).unwrap();
+
+    mock.get.respond(Ok("b".to_string()));
+    mock.get.respond(Err(CallError::InvalidArgument));
+    assert_eq!(store.get("a".to_string()).await.unwrap(), "b");
+    assert!(store.get("c".to_string()).await.is_err());
+    // Nothing was programmed for `put`, but the call is still recorded.
+    assert!(store.put("a".to_string(), "d".to_string()).await.is_err());
+
+    assert_eq!(mock.get.calls(), ["a", "c"]);
+    assert_eq!(mock.put.calls(), [("a".to_string(), "d".to_string())]);
+    assert_eq!(mock.clear.call_count(), 0);
+  });
+}
+
+/// A `#[boozle::object]` implementing the generated `Counter` interface, with a
+/// method taking `&mut self`.
+#[derive(Debug, Default)]
+pub struct Tally {
+  value: u32,
+}
+
+#[boozle::object(name = "Counter")]
+impl Tally {
+  async fn increment(&mut self, by: u32) -> Result<u32, CallError> {
+    self.value += by;
+    Ok(self.value)
+  }
+
+  async fn get(&self) -> Result<u32, CallError> {
+    Ok(self.value)
+  }
+}
+
+#[test]
+fn client_calls_macro_object_remotely() {
+  use objects::{Counter, CounterClient};
+
+  actix::System::new("compile").block_on(async {
+    let (client, server) = connect();
+    let tally = LocalValue::from_object(Tally::default());
+    let key = server.pool().await.expose(tally).await.unwrap().key;
+    let counter = CounterClient::from_value(client.remote(key)).unwrap();
+
+    // Generated clients can't tell `&mut self` methods apart.
+    assert_eq!(counter.increment(2).await.unwrap(), 2);
+    assert_eq!(counter.increment(3).await.unwrap(), 5);
+    assert_eq!(counter.get().await.unwrap(), 5);
+  });
+}
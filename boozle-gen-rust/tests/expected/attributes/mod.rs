@@ -0,0 +1,101 @@
+const _: fn() = boozle::value::assert_lit::<String>;
+pub mod time;
+pub type Duration = std::time::Duration;
+const _: fn() = boozle::value::assert_lit::<std::time::Duration>;
+pub type Name = String;
+const _: fn() = boozle::value::assert_lit::<String>;
+#[async_trait::async_trait]
+pub trait Directory: Send + Sync {
+    async fn lookup(&self, name: String) -> Result<time::Instant, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct DirectoryClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl DirectoryClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for DirectoryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectoryClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Directory for DirectoryClient {
+    async fn lookup(&self, name: String) -> Result<time::Instant, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&name));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(8058247235326970170u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<time::Instant>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct DirectoryObject<T>(pub T);
+impl<T> DirectoryObject<T>
+where
+    T: Directory,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            8058247235326970170u64 => {
+                let name: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.lookup(name).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for DirectoryObject<T>
+where
+    T: Directory + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
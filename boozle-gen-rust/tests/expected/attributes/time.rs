@@ -0,0 +1,137 @@
+pub type Instant = std::time::SystemTime;
+const _: fn() = boozle::value::assert_lit::<std::time::SystemTime>;
+#[async_trait::async_trait]
+pub trait Clock: Send + Sync {
+    async fn now(&self) -> Result<Instant, boozle::object::CallError>;
+    async fn sleep(&self, duration: super::Duration) -> Result<Instant, boozle::object::CallError>;
+    async fn owner(&self) -> Result<super::Name, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct ClockClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl ClockClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for ClockClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClockClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Clock for ClockClient {
+    async fn now(&self) -> Result<Instant, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(7412549748998894780u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<Instant>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn sleep(&self, duration: super::Duration) -> Result<Instant, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&duration));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(14253936000422255359u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<Instant>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn owner(&self) -> Result<super::Name, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(17773120796350841380u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<super::Name>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct ClockObject<T>(pub T);
+impl<T> ClockObject<T>
+where
+    T: Clock,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            7412549748998894780u64 => {
+                let result = target.now().await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            14253936000422255359u64 => {
+                let duration: super::Duration = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.sleep(duration).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            17773120796350841380u64 => {
+                let result = target.owner().await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for ClockObject<T>
+where
+    T: Clock + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[allow(non_upper_case_globals)]
+pub const clock: u64 = 7264592308103682520u64;
@@ -0,0 +1,176 @@
+#[async_trait::async_trait]
+pub trait Calculator: Send + Sync {
+    async fn reset(&self) -> Result<(), boozle::object::CallError>;
+    async fn total(&self) -> Result<i64, boozle::object::CallError>;
+    async fn add(&self, value: i64) -> Result<i64, boozle::object::CallError>;
+    async fn mul_add(&self, a: i64, b: i64, c: i64) -> Result<i64, boozle::object::CallError>;
+    async fn name(&self, prefix: String) -> Result<String, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct CalculatorClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl CalculatorClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for CalculatorClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CalculatorClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Calculator for CalculatorClient {
+    async fn reset(&self) -> Result<(), boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let _ = self
+            .actor
+            .call(boozle::object::Call::new(15076959804775556550u64, argument))
+            .await?;
+        Ok(())
+    }
+    async fn total(&self) -> Result<i64, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(9081060960125293511u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<i64>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn add(&self, value: i64) -> Result<i64, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&value));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(4662819953504970657u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<i64>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn mul_add(&self, a: i64, b: i64, c: i64) -> Result<i64, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&(a, b, c)));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(16165244123686009639u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<i64>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn name(&self, prefix: String) -> Result<String, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&prefix));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(4423288891330308980u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<String>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct CalculatorObject<T>(pub T);
+impl<T> CalculatorObject<T>
+where
+    T: Calculator,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            15076959804775556550u64 => {
+                target.reset().await?;
+                Ok(boozle::object::Return { result: None })
+            }
+            9081060960125293511u64 => {
+                let result = target.total().await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            4662819953504970657u64 => {
+                let value: i64 = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.add(value).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            16165244123686009639u64 => {
+                let (a, b, c): (i64, i64, i64) = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.mul_add(a, b, c).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            4423288891330308980u64 => {
+                let prefix: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.name(prefix).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for CalculatorObject<T>
+where
+    T: Calculator + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[allow(non_upper_case_globals)]
+pub const calculator: u64 = 2308388862128674002u64;
@@ -0,0 +1,229 @@
+#[async_trait::async_trait]
+pub trait Node: Send + Sync {
+    async fn value(&self) -> Result<i32, boozle::object::CallError>;
+    async fn set_value(&self, value: i32) -> Result<(), boozle::object::CallError>;
+    async fn child(&self, index: u32) -> Result<NodeClient, boozle::object::CallError>;
+    async fn adopt(&self, child: NodeClient) -> Result<u32, boozle::object::CallError>;
+    async fn sum(&self, a: i32, b: i32) -> Result<i32, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct NodeClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl NodeClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for NodeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Node for NodeClient {
+    async fn value(&self) -> Result<i32, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(1284124678814312154u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<i32>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn set_value(&self, value: i32) -> Result<(), boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&value));
+        let _ = self
+            .actor
+            .call(boozle::object::Call::new(1395227903918123848u64, argument))
+            .await?;
+        Ok(())
+    }
+    async fn child(&self, index: u32) -> Result<NodeClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&index));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(11384086497168537536u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(NodeClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn adopt(&self, child: NodeClient) -> Result<u32, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(child.into_value());
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(11369595123206257067u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<u32>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn sum(&self, a: i32, b: i32) -> Result<i32, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&(a, b)));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(4977789335553907212u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<i32>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct NodeObject<T>(pub T);
+impl<T> NodeObject<T>
+where
+    T: Node,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            1284124678814312154u64 => {
+                let result = target.value().await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            1395227903918123848u64 => {
+                let value: i32 = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                target.set_value(value).await?;
+                Ok(boozle::object::Return { result: None })
+            }
+            11384086497168537536u64 => {
+                let index: u32 = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.child(index).await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            11369595123206257067u64 => {
+                let child = argument
+                    .and_then(boozle::value::LocalValue::into_actor)
+                    .map(NodeClient::new)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.adopt(child).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            4977789335553907212u64 => {
+                let (a, b): (i32, i32) = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.sum(a, b).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for NodeObject<T>
+where
+    T: Node + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[derive(Clone)]
+pub struct NodeBlockingClient {
+    client: NodeClient,
+    handle: boozle::blocking::Handle,
+}
+impl NodeBlockingClient {
+    pub fn new(client: NodeClient, handle: boozle::blocking::Handle) -> Self {
+        Self { client, handle }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(
+        value: boozle::value::LocalValue,
+        handle: boozle::blocking::Handle,
+    ) -> Option<Self> {
+        NodeClient::from_value(value).map(|client| Self::new(client, handle))
+    }
+    pub fn into_async(self) -> NodeClient {
+        self.client
+    }
+    pub fn value(&self) -> Result<i32, boozle::object::CallError> {
+        let client = self.client.clone();
+        self.handle
+            .call(move || async move { client.value().await })
+    }
+    pub fn set_value(&self, value: i32) -> Result<(), boozle::object::CallError> {
+        let client = self.client.clone();
+        self.handle
+            .call(move || async move { client.set_value(value).await })
+    }
+    pub fn child(&self, index: u32) -> Result<NodeBlockingClient, boozle::object::CallError> {
+        let client = self.client.clone();
+        self.handle
+            .call(move || async move { client.child(index).await })
+            .map(|client| NodeBlockingClient::new(client, self.handle.clone()))
+    }
+    pub fn adopt(&self, child: NodeBlockingClient) -> Result<u32, boozle::object::CallError> {
+        let client = self.client.clone();
+        self.handle
+            .call(move || async move { client.adopt(child.into_async()).await })
+    }
+    pub fn sum(&self, a: i32, b: i32) -> Result<i32, boozle::object::CallError> {
+        let client = self.client.clone();
+        self.handle
+            .call(move || async move { client.sum(a, b).await })
+    }
+}
+impl std::fmt::Debug for NodeBlockingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeBlockingClient").finish()
+    }
+}
@@ -0,0 +1,199 @@
+#[async_trait::async_trait]
+pub trait Store: Send + Sync {
+    async fn get(&self, key: String) -> Result<String, boozle::object::CallError>;
+    async fn put(&self, key: String, value: String) -> Result<(), boozle::object::CallError>;
+    async fn clear(&self) -> Result<(), boozle::object::CallError>;
+    async fn open(&self, name: String) -> Result<StoreClient, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct StoreClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl StoreClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for StoreClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Store for StoreClient {
+    async fn get(&self, key: String) -> Result<String, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&key));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(16518780810375632168u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<String>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn put(&self, key: String, value: String) -> Result<(), boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&(key, value)));
+        let _ = self
+            .actor
+            .call(boozle::object::Call::new(2326359557158939130u64, argument))
+            .await?;
+        Ok(())
+    }
+    async fn clear(&self) -> Result<(), boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let _ = self
+            .actor
+            .call(boozle::object::Call::new(14217155225957244270u64, argument))
+            .await?;
+        Ok(())
+    }
+    async fn open(&self, name: String) -> Result<StoreClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&name));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(187068211201381572u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(StoreClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct StoreObject<T>(pub T);
+impl<T> StoreObject<T>
+where
+    T: Store,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            16518780810375632168u64 => {
+                let key: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.get(key).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            2326359557158939130u64 => {
+                let (key, value): (String, String) = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                target.put(key, value).await?;
+                Ok(boozle::object::Return { result: None })
+            }
+            14217155225957244270u64 => {
+                target.clear().await?;
+                Ok(boozle::object::Return { result: None })
+            }
+            187068211201381572u64 => {
+                let name: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.open(name).await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for StoreObject<T>
+where
+    T: Store + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[derive(Debug, Default, Clone)]
+pub struct MockStore {
+    pub get: boozle::mock::MockMethod<String, String>,
+    pub put: boozle::mock::MockMethod<(String, String), ()>,
+    pub clear: boozle::mock::MockMethod<(), ()>,
+    pub open: boozle::mock::MockMethod<String, StoreClient>,
+}
+impl MockStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+#[async_trait::async_trait]
+impl Store for MockStore {
+    async fn get(&self, key: String) -> Result<String, boozle::object::CallError> {
+        self.get.invoke(key)
+    }
+    async fn put(&self, key: String, value: String) -> Result<(), boozle::object::CallError> {
+        self.put.invoke((key, value))
+    }
+    async fn clear(&self) -> Result<(), boozle::object::CallError> {
+        self.clear.invoke(())
+    }
+    async fn open(&self, name: String) -> Result<StoreClient, boozle::object::CallError> {
+        self.open.invoke(name)
+    }
+}
+#[async_trait::async_trait]
+impl boozle::object::Object for MockStore {
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        StoreObject::dispatch(self, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
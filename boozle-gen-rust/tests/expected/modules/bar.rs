@@ -0,0 +1,3 @@
+pub mod baz;
+#[allow(non_upper_case_globals)]
+pub const baz: u64 = 18095519711107261882u64;
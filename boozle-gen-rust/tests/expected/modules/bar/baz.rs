@@ -0,0 +1,126 @@
+pub type Foo = String;
+const _: fn() = boozle::value::assert_lit::<String>;
+#[async_trait::async_trait]
+pub trait Baz: Send + Sync {
+    async fn echo(&self, text: String) -> Result<String, boozle::object::CallError>;
+    async fn tag(
+        &self,
+        foo: Foo,
+    ) -> Result<super::super::foo::FooClient, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct BazClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl BazClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for BazClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BazClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Baz for BazClient {
+    async fn echo(&self, text: String) -> Result<String, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&text));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(9296650621221290621u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<String>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn tag(
+        &self,
+        foo: Foo,
+    ) -> Result<super::super::foo::FooClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&foo));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(7511179637844420044u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(super::super::foo::FooClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct BazObject<T>(pub T);
+impl<T> BazObject<T>
+where
+    T: Baz,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            9296650621221290621u64 => {
+                let text: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.echo(text).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            7511179637844420044u64 => {
+                let foo: Foo = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.tag(foo).await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for BazObject<T>
+where
+    T: Baz + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
@@ -0,0 +1,4 @@
+pub mod bar;
+pub mod foo;
+#[allow(non_upper_case_globals)]
+pub const root: u64 = 3537153178712216392u64;
@@ -0,0 +1,206 @@
+pub mod registry;
+#[async_trait::async_trait]
+pub trait Counter: Send + Sync {
+    async fn increment(&self, by: u32) -> Result<u32, boozle::object::CallError>;
+    async fn get(&self) -> Result<u32, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct CounterClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl CounterClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for CounterClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CounterClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Counter for CounterClient {
+    async fn increment(&self, by: u32) -> Result<u32, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&by));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(2013403751146642969u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<u32>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn get(&self) -> Result<u32, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(7713228467363895118u64, argument))
+            .await?;
+        ret.result
+            .as_ref()
+            .and_then(boozle::value::LocalValue::to_lit::<u32>)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct CounterObject<T>(pub T);
+impl<T> CounterObject<T>
+where
+    T: Counter,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            2013403751146642969u64 => {
+                let by: u32 = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.increment(by).await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            7713228467363895118u64 => {
+                let result = target.get().await?;
+                Ok(boozle::object::Return {
+                    result: Some(boozle::value::LocalValue::from_lit(&result)),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for CounterObject<T>
+where
+    T: Counter + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[async_trait::async_trait]
+pub trait Factory: Send + Sync {
+    async fn registry(&self) -> Result<registry::RegistryClient, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct FactoryClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl FactoryClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for FactoryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FactoryClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Factory for FactoryClient {
+    async fn registry(&self) -> Result<registry::RegistryClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = None;
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(2091142492697590230u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(registry::RegistryClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct FactoryObject<T>(pub T);
+impl<T> FactoryObject<T>
+where
+    T: Factory,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            2091142492697590230u64 => {
+                let result = target.registry().await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for FactoryObject<T>
+where
+    T: Factory + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
@@ -0,0 +1,152 @@
+#[async_trait::async_trait]
+pub trait Registry: Send + Sync {
+    async fn create(&self, name: String)
+        -> Result<super::CounterClient, boozle::object::CallError>;
+    async fn register(
+        &self,
+        counter: super::CounterClient,
+    ) -> Result<(), boozle::object::CallError>;
+    async fn lookup(&self, name: String)
+        -> Result<super::CounterClient, boozle::object::CallError>;
+}
+/// A handle to an object implementing the trait of the same name. The
+/// object may live on this node or on a remote one.
+#[derive(Clone)]
+pub struct RegistryClient {
+    actor: actix::Addr<boozle::object::ObjectActor>,
+}
+impl RegistryClient {
+    pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+    }
+    /// Wraps an object value. Returns `None` if the value is a literal.
+    pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+    }
+    pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+    }
+}
+impl std::fmt::Debug for RegistryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryClient").finish()
+    }
+}
+#[async_trait::async_trait]
+impl Registry for RegistryClient {
+    async fn create(
+        &self,
+        name: String,
+    ) -> Result<super::CounterClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&name));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(9984444130826516799u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(super::CounterClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+    async fn register(
+        &self,
+        counter: super::CounterClient,
+    ) -> Result<(), boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(counter.into_value());
+        let _ = self
+            .actor
+            .call(boozle::object::Call::new(6598567586777381062u64, argument))
+            .await?;
+        Ok(())
+    }
+    async fn lookup(
+        &self,
+        name: String,
+    ) -> Result<super::CounterClient, boozle::object::CallError> {
+        use boozle::object::ObjectActorHelpers;
+        let argument = Some(boozle::value::LocalValue::from_lit(&name));
+        let ret = self
+            .actor
+            .call(boozle::object::Call::new(16205321001581043372u64, argument))
+            .await?;
+        ret.result
+            .and_then(boozle::value::LocalValue::into_actor)
+            .map(super::CounterClient::new)
+            .ok_or(boozle::object::CallError::InvalidResponse)
+    }
+}
+#[derive(Debug)]
+pub struct RegistryObject<T>(pub T);
+impl<T> RegistryObject<T>
+where
+    T: Registry,
+{
+    /// Decodes `call` and invokes the matching method of `target`.
+    #[allow(unused_variables)]
+    pub async fn dispatch(
+        target: &T,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+            9984444130826516799u64 => {
+                let name: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.create(name).await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            6598567586777381062u64 => {
+                let counter = argument
+                    .and_then(boozle::value::LocalValue::into_actor)
+                    .map(super::CounterClient::new)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                target.register(counter).await?;
+                Ok(boozle::object::Return { result: None })
+            }
+            16205321001581043372u64 => {
+                let name: String = argument
+                    .as_ref()
+                    .and_then(boozle::value::LocalValue::to_lit)
+                    .ok_or(boozle::object::CallError::InvalidArgument)?;
+                let result = target.lookup(name).await?;
+                Ok(boozle::object::Return {
+                    result: Some(result.into_value()),
+                })
+            }
+            _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+    }
+}
+#[async_trait::async_trait]
+impl<T> boozle::object::Object for RegistryObject<T>
+where
+    T: Registry + std::fmt::Debug + Send + Sync,
+{
+    async fn call(
+        &self,
+        call: boozle::object::Call,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        Self::dispatch(&self.0, call).await
+    }
+    async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+    ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        self.call(boozle::object::Call::new(
+            call_mut.method_id,
+            call_mut.argument,
+        ))
+        .await
+    }
+    fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+    }
+}
+#[allow(non_upper_case_globals)]
+pub const registry: u64 = 11012814075499455623u64;
@@ -0,0 +1,101 @@
+//! Compares the generator's output for every case in `tests/corpus` with the
+//! checked-in files under `tests/expected`.
+//!
+//! A case is a directory holding a root `<case>.boozle` and any modules it
+//! loads. Set `BOOZLE_BLESS=1` to overwrite the expected files with the current
+//! output, then review the diff.
+
+use boozle_gen_rust::{write_tree, Options};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+fn options(case: &str) -> Options {
+  let mut options = Options::default();
+  match case {
+    "attributes" => {
+      options.types.insert("Name".to_string(), "String".to_string());
+    }
+    "blocking" => options.blocking = true,
+    "mocks" => options.mocks = true,
+    _ => {}
+  }
+  options
+}
+
+const CASES: &[&str] = &["attributes", "basic", "blocking", "mocks", "modules", "objects"];
+
+fn tests_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+/// Reads every file below `dir`, keyed by its path relative to `dir`.
+fn read_dir(dir: &Path) -> BTreeMap<PathBuf, String> {
+  fn read_inner(root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, String>) {
+    let entries = match std::fs::read_dir(dir) {
+      Ok(entries) => entries,
+      Err(_) => return,
+    };
+    for entry in entries {
+      let path = entry.unwrap().path();
+      if path.is_dir() {
+        read_inner(root, path.as_path(), files);
+      } else {
+        let text = std::fs::read_to_string(&path).unwrap();
+        files.insert(path.strip_prefix(root).unwrap().to_path_buf(), text);
+      }
+    }
+  }
+
+  let mut files = BTreeMap::new();
+  read_inner(dir, dir, &mut files);
+  files
+}
+
+fn generate(case: &str) -> BTreeMap<PathBuf, String> {
+  let input = tests_dir().join("corpus").join(case).join(format!("{}.boozle", case));
+  let out_dir = std::env::temp_dir()
+    .join(format!("boozle-gen-rust-snapshots-{}", std::process::id()))
+    .join(case);
+  let _ = std::fs::remove_dir_all(&out_dir);
+  if let Err(err) = write_tree(&input, &out_dir, &options(case)) {
+    panic!("{}: {}", case, err);
+  }
+  let files = read_dir(out_dir.as_path());
+  let _ = std::fs::remove_dir_all(&out_dir);
+  files
+}
+
+fn bless(dir: &Path, files: &BTreeMap<PathBuf, String>) {
+  let _ = std::fs::remove_dir_all(dir);
+  for (file, text) in files.iter() {
+    let path = dir.join(file);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+}
+
+#[test]
+fn snapshots() {
+  let bless_all = std::env::var_os("BOOZLE_BLESS").is_some();
+  let mut failures = Vec::new();
+  for case in CASES.iter() {
+    let actual = generate(case);
+    let expected_dir = tests_dir().join("expected").join(case);
+    if bless_all {
+      bless(expected_dir.as_path(), &actual);
+      continue;
+    }
+    let expected = read_dir(expected_dir.as_path());
+    let files: BTreeSet<&PathBuf> = actual.keys().chain(expected.keys()).collect();
+    for file in files.into_iter() {
+      if actual.get(file) != expected.get(file) {
+        failures.push(format!("{}/{}", case, file.display()));
+      }
+    }
+  }
+  assert!(
+    failures.is_empty(),
+    "generated code differs from tests/expected: {}\nrerun with BOOZLE_BLESS=1 to update",
+    failures.join(", ")
+  );
+}
@@ -0,0 +1,468 @@
+//! Generates TypeScript clients for boozle protocols. Input is a schema
+//! descriptor (see `boozle_gen::descriptor`), so this also runs as a
+//! `boozle-gen` plugin.
+//!
+//! Each module becomes one file: the root is `index.ts`, a child `a` is `a.ts`
+//! and its child `b` is `a/b.ts`. Child modules are re-exported as namespaces.
+//! For a trait `Foo` the generated code has:
+//!
+//! - `interface Foo`, whose methods return promises,
+//! - `class FooClient implements Foo`, calling a remote object,
+//! - `class FooObject`, exposing a local `Foo` to the peer.
+//!
+//! The generated code imports the runtime in `boozle-ts`.
+
+use boozle_gen::descriptor::{Descriptor, MethodDescriptor, ModuleDescriptor, TraitDescriptor, TypeRef};
+use boozle_gen::plugin::File;
+use std::collections::BTreeSet;
+
+pub struct Options {
+  /// Module specifier the generated code imports the runtime from. A relative
+  /// specifier is taken relative to the output directory.
+  pub runtime: String,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      runtime: "boozle".to_string(),
+    }
+  }
+}
+
+impl Options {
+  /// Parses a plugin parameter: comma-separated `key=value` pairs. The only
+  /// key is `runtime`.
+  pub fn from_parameter(parameter: &str) -> Result<Self, String> {
+    let mut options = Self::default();
+    for pair in parameter.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+      let mut parts = pair.splitn(2, '=');
+      match (parts.next(), parts.next()) {
+        (Some("runtime"), Some(value)) => options.runtime = value.to_string(),
+        _ => return Err(format!("unknown parameter {:?}", pair)),
+      }
+    }
+    Ok(options)
+  }
+}
+
+/// Reserved words that can't name a parameter. Methods may use them.
+const RESERVED: &[&str] = &[
+  "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+  "do", "else", "enum", "eval", "export", "extends", "false", "finally", "for", "function", "if", "implements",
+  "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private", "protected", "public",
+  "return", "static", "super", "switch", "this", "throw", "true", "try", "typeof", "var", "void", "while",
+  "with", "yield",
+];
+
+fn ident(name: &str) -> String {
+  if RESERVED.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// How a .boozle type is represented in TypeScript.
+enum Ty<'a> {
+  /// A literal, encoded by the runtime codec `codec`.
+  Lit { ts: &'static str, codec: &'static str },
+  /// A trait, passed as an object.
+  Object(&'a [String]),
+}
+
+impl<'a> Ty<'a> {
+  fn new(ty: &'a TypeRef) -> Self {
+    let named = match ty {
+      TypeRef::Object { path } => return Self::Object(path.as_slice()),
+      TypeRef::Named { path } if path.len() == 1 => path[0].as_str(),
+      _ => "",
+    };
+    let (ts, codec) = match named {
+      "bool" => ("boolean", "bool"),
+      "u8" => ("number", "u8"),
+      "u16" => ("number", "u16"),
+      "u32" => ("number", "u32"),
+      "u64" | "usize" => ("bigint", "u64"),
+      "i8" => ("number", "i8"),
+      "i16" => ("number", "i16"),
+      "i32" => ("number", "i32"),
+      "i64" | "isize" => ("bigint", "i64"),
+      "f32" => ("number", "f32"),
+      "f64" => ("number", "f64"),
+      "String" => ("string", "string"),
+      // `type` declarations and anything else are passed through as their
+      // bincode encoding.
+      _ => ("Uint8Array", "raw"),
+    };
+    Self::Lit { ts, codec }
+  }
+}
+
+/// Generates code for one module, referring to items of others through
+/// namespace imports.
+struct ModuleGen<'a> {
+  module: &'a ModuleDescriptor,
+  /// Modules referred to, by path.
+  imports: BTreeSet<Vec<String>>,
+  out: String,
+}
+
+impl<'a> ModuleGen<'a> {
+  /// Number of directories between this module's file and the output directory.
+  fn depth(&self) -> usize {
+    self.module.path.len().saturating_sub(1)
+  }
+
+  fn prefix(&self) -> String {
+    match self.depth() {
+      0 => "./".to_string(),
+      depth => "../".repeat(depth),
+    }
+  }
+
+  fn alias(path: &[String]) -> String {
+    if path.is_empty() {
+      "__root".to_string()
+    } else {
+      format!("__{}", path.join("_"))
+    }
+  }
+
+  /// Names item `name` of the module at `module` from this one.
+  fn item(&mut self, module: &[String], name: &str) -> String {
+    if module == self.module.path.as_slice() {
+      name.to_string()
+    } else {
+      self.imports.insert(module.to_vec());
+      format!("{}.{}", Self::alias(module), name)
+    }
+  }
+
+  /// Names the item generated for trait `path` with the given suffix.
+  fn object(&mut self, path: &[String], suffix: &str) -> String {
+    let (name, module) = path.split_last().unwrap();
+    self.item(module, format!("{}{}", name, suffix).as_str())
+  }
+
+  fn ts_type(&mut self, ty: &TypeRef) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { ts, .. } => ts.to_string(),
+      Ty::Object(path) => self.object(path, ""),
+    }
+  }
+
+  fn line(&mut self, indent: usize, text: &str) {
+    if !text.is_empty() {
+      self.out.push_str("  ".repeat(indent).as_str());
+      self.out.push_str(text);
+    }
+    self.out.push('\n');
+  }
+
+  fn doc(&mut self, indent: usize, doc: &Option<String>) {
+    if let Some(doc) = doc {
+      self.line(indent, "/**");
+      for line in doc.lines() {
+        // `*/` would end the comment early.
+        let line = line.replace("*/", "*\\/");
+        self.line(indent, format!(" * {}", line).trim_end());
+      }
+      self.line(indent, " */");
+    }
+  }
+
+  fn check(trait_: &TraitDescriptor, method: &MethodDescriptor) -> Result<(), String> {
+    let name = format!("{}.{}", trait_.name, method.name);
+    if method.params.len() < 2 {
+      return Ok(());
+    }
+    for (i, param) in method.params.iter().enumerate() {
+      match Ty::new(&param.ty) {
+        Ty::Object(_) => {
+          return Err(format!(
+            "`{}`: object parameter `{}` must be the method's only parameter",
+            name, param.name
+          ))
+        }
+        Ty::Lit { codec: "raw", .. } if i + 1 < method.params.len() => {
+          return Err(format!(
+            "`{}`: parameter `{}` has no TypeScript mapping and is passed as raw bytes, so it must come last",
+            name, param.name
+          ))
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn params(&mut self, method: &MethodDescriptor) -> String {
+    let params: Vec<String> = method
+      .params
+      .iter()
+      .map(|param| format!("{}: {}", ident(param.name.as_str()), self.ts_type(&param.ty)))
+      .collect();
+    params.join(", ")
+  }
+
+  fn result(&mut self, method: &MethodDescriptor) -> String {
+    match &method.result {
+      Some(ty) => self.ts_type(ty),
+      None => "void".to_string(),
+    }
+  }
+
+  /// An expression converting `value` of type `ty` into a `boozle.LocalValue`.
+  fn encode(&mut self, ty: &TypeRef, value: &str) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { codec, .. } => format!("boozle.lit(boozle.{}, {})", codec, value),
+      Ty::Object(path) => {
+        let object = self.object(path, "Object");
+        format!("boozle.objectValue({}, (target) => new {}(target))", value, object)
+      }
+    }
+  }
+
+  /// An expression converting the `boozle.LocalValue` `value` into `ty`.
+  fn decode(&mut self, ty: &TypeRef, value: &str, kind: &str) -> String {
+    match Ty::new(ty) {
+      Ty::Lit { codec, .. } => format!("boozle.fromLit(boozle.{}, {}, \"{}\")", codec, value, kind),
+      Ty::Object(path) => {
+        let client = self.object(path, "Client");
+        format!("{}.fromValue({}, \"{}\")", client, value, kind)
+      }
+    }
+  }
+
+  fn client_method(&mut self, method: &MethodDescriptor) {
+    let params = self.params(method);
+    let result = self.result(method);
+    self.doc(1, &method.doc);
+    self.line(1, format!("async {}({}): Promise<{}> {{", method.name, params, result).as_str());
+    let argument = match method.params.as_slice() {
+      [] => "undefined".to_string(),
+      [param] => self.encode(&param.ty, ident(param.name.as_str()).as_str()),
+      params => {
+        self.line(2, "const argument = boozle.writeLit((writer) => {");
+        for param in params {
+          if let Ty::Lit { codec, .. } = Ty::new(&param.ty) {
+            self.line(3, format!("boozle.{}.encode(writer, {});", codec, ident(param.name.as_str())).as_str());
+          }
+        }
+        self.line(2, "});");
+        "argument".to_string()
+      }
+    };
+    let returns_object = matches!(&method.result, Some(TypeRef::Object { .. }));
+    let call = format!(
+      "this.remote.call({}n, {}{})",
+      method.id,
+      argument,
+      if returns_object { ", true" } else { "" }
+    );
+    match &method.result {
+      None => self.line(2, format!("await {};", call).as_str()),
+      Some(ty) => {
+        self.line(2, format!("const ret = await {};", call).as_str());
+        let decode = self.decode(ty, "ret", "InvalidResponse");
+        self.line(2, format!("return {};", decode).as_str());
+      }
+    }
+    self.line(1, "}");
+  }
+
+  fn dispatch_arm(&mut self, method: &MethodDescriptor) {
+    self.line(3, format!("case {}n: {{", method.id).as_str());
+    match method.params.as_slice() {
+      [] => {}
+      [param] => {
+        let decode = self.decode(&param.ty, "argument", "InvalidArgument");
+        self.line(4, format!("const {} = {};", ident(param.name.as_str()), decode).as_str());
+      }
+      params => {
+        let names: Vec<String> = params.iter().map(|param| ident(param.name.as_str())).collect();
+        let reads: Vec<String> = params
+          .iter()
+          .filter_map(|param| match Ty::new(&param.ty) {
+            Ty::Lit { codec, .. } => Some(format!("boozle.{}.decode(reader)", codec)),
+            Ty::Object(_) => None,
+          })
+          .collect();
+        self.line(
+          4,
+          format!(
+            "const [{}] = boozle.readLit(argument, \"InvalidArgument\", (reader) => [{}] as const);",
+            names.join(", "),
+            reads.join(", ")
+          )
+          .as_str(),
+        );
+      }
+    }
+    let args: Vec<String> = method.params.iter().map(|param| ident(param.name.as_str())).collect();
+    let call = format!("this.target.{}({})", method.name, args.join(", "));
+    match &method.result {
+      None => {
+        self.line(4, format!("await {};", call).as_str());
+        self.line(4, "return undefined;");
+      }
+      Some(ty) => {
+        self.line(4, format!("const result = await {};", call).as_str());
+        let encode = self.encode(ty, "result");
+        self.line(4, format!("return {};", encode).as_str());
+      }
+    }
+    self.line(3, "}");
+  }
+
+  fn trait_(&mut self, trait_: &TraitDescriptor) -> Result<(), String> {
+    for method in trait_.methods.iter() {
+      Self::check(trait_, method)?;
+    }
+    let name = trait_.name.as_str();
+
+    self.doc(0, &trait_.doc);
+    self.line(0, format!("export interface {} {{", name).as_str());
+    for method in trait_.methods.iter() {
+      let params = self.params(method);
+      let result = self.result(method);
+      self.doc(1, &method.doc);
+      self.line(1, format!("{}({}): Promise<{}>;", method.name, params, result).as_str());
+    }
+    self.line(0, "}");
+    self.line(0, "");
+
+    self.line(0, "/** Calls a remote object implementing the trait of the same name. */");
+    self.line(0, format!("export class {}Client implements {} {{", name, name).as_str());
+    self.line(1, "readonly remote: boozle.RemoteObject;");
+    self.line(0, "");
+    self.line(1, "constructor(remote: boozle.RemoteObject) {");
+    self.line(2, "this.remote = remote;");
+    self.line(1, "}");
+    self.line(0, "");
+    self.line(1, "/** Wraps an object value, failing with `kind` if it isn't a remote object. */");
+    self.line(
+      1,
+      format!(
+        "static fromValue(value: boozle.LocalValue | undefined, kind: boozle.CallErrorKind = \"InvalidResponse\"): {}Client {{",
+        name
+      )
+      .as_str(),
+    );
+    self.line(2, format!("return new {}Client(boozle.toRemote(value, kind));", name).as_str());
+    self.line(1, "}");
+    self.line(0, "");
+    self.line(1, "/** Tells the peer this handle is no longer needed. */");
+    self.line(1, "release(): Promise<void> {");
+    self.line(2, "return this.remote.release();");
+    self.line(1, "}");
+    for method in trait_.methods.iter() {
+      self.line(0, "");
+      self.client_method(method);
+    }
+    self.line(0, "}");
+    self.line(0, "");
+
+    self.line(0, format!("/** Exposes a local `{}` to the peer. */", name).as_str());
+    self.line(0, format!("export class {}Object implements boozle.LocalObject {{", name).as_str());
+    self.line(1, format!("readonly target: {};", name).as_str());
+    self.line(0, "");
+    self.line(1, format!("constructor(target: {}) {{", name).as_str());
+    self.line(2, "this.target = target;");
+    self.line(1, "}");
+    self.line(0, "");
+    self.line(
+      1,
+      "async dispatch(methodId: bigint, argument: boozle.LocalValue | undefined): Promise<boozle.LocalValue | undefined> {",
+    );
+    self.line(2, "switch (methodId) {");
+    for method in trait_.methods.iter() {
+      self.dispatch_arm(method);
+    }
+    self.line(3, "default:");
+    self.line(4, "throw new boozle.CallError(\"UnknownMethod\");");
+    self.line(2, "}");
+    self.line(1, "}");
+    self.line(0, "}");
+    self.line(0, "");
+    Ok(())
+  }
+
+  fn generate(mut self, options: &Options) -> Result<String, String> {
+    for module in self.module.modules.iter() {
+      let from = module.path[self.depth()..].join("/");
+      self.line(0, format!("export * as {} from \"./{}\";", module.name, from).as_str());
+    }
+    if !self.module.modules.is_empty() {
+      self.line(0, "");
+    }
+    for svc in self.module.services.iter() {
+      self.doc(0, &svc.doc);
+      self.line(0, format!("export const {} = {}n;", svc.name, svc.id).as_str());
+      self.line(0, "");
+    }
+    for trait_ in self.module.traits.iter() {
+      self.trait_(trait_)?;
+    }
+
+    let prefix = self.prefix();
+    let runtime = if options.runtime.starts_with('.') {
+      let relative = format!("{}{}", prefix, options.runtime.trim_start_matches("./"));
+      relative.replacen("./../", "../", 1)
+    } else {
+      options.runtime.clone()
+    };
+    let mut header = String::from("// Generated by boozle-gen-ts. Do not edit.\n\n");
+    if !self.module.traits.is_empty() {
+      header.push_str(format!("import * as boozle from \"{}\";\n", runtime).as_str());
+    }
+    for path in self.imports.iter() {
+      let target = if path.is_empty() {
+        "index".to_string()
+      } else {
+        path.join("/")
+      };
+      header.push_str(format!("import * as {} from \"{}{}\";\n", Self::alias(path), prefix, target).as_str());
+    }
+    if header.ends_with(";\n") {
+      header.push('\n');
+    }
+    let mut out = header + self.out.as_str();
+    while out.ends_with("\n\n") {
+      out.pop();
+    }
+    Ok(out)
+  }
+}
+
+fn file_path(module: &ModuleDescriptor) -> String {
+  if module.path.is_empty() {
+    "index.ts".to_string()
+  } else {
+    format!("{}.ts", module.path.join("/"))
+  }
+}
+
+fn generate_module(module: &ModuleDescriptor, options: &Options, files: &mut Vec<File>) -> Result<(), String> {
+  let gen = ModuleGen {
+    module,
+    imports: BTreeSet::new(),
+    out: String::new(),
+  };
+  files.push(File {
+    path: file_path(module),
+    content: gen.generate(options)?,
+  });
+  for child in module.modules.iter() {
+    generate_module(child, options, files)?;
+  }
+  Ok(())
+}
+
+/// Generates one file per module of `descriptor`.
+pub fn generate(descriptor: &Descriptor, options: &Options) -> Result<Vec<File>, String> {
+  let mut files = Vec::new();
+  generate_module(&descriptor.root, options, &mut files)?;
+  Ok(files)
+}
@@ -0,0 +1,16 @@
+//! The `boozle-gen-ts` plugin. Run it through `boozle-gen plugin`, optionally
+//! with `--parameter runtime=<module>`.
+
+use boozle_gen::plugin;
+use boozle_gen_ts::{generate, Options};
+
+fn main() {
+  let result = plugin::serve(|request| {
+    let options = Options::from_parameter(request.parameter.as_str())?;
+    generate(&request.descriptor, &options)
+  });
+  if let Err(err) = result {
+    eprintln!("{}", err);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,177 @@
+// Generated by boozle-gen-ts. Do not edit.
+
+import * as boozle from "./runtime";
+import * as __store from "./store";
+
+export * as store from "./store";
+
+export const factory = 7541137103383087110n;
+
+/**
+ * Counts things.
+ *
+ * Matches `a/*\/b` and `*\/`, which must not end this comment.
+ */
+export interface Counter {
+  /**
+   * Adds `by` and returns the new value.
+   */
+  increment(by: number): Promise<bigint>;
+  reset(): Promise<void>;
+  touch(at: Uint8Array): Promise<boolean>;
+  mix(a: number, b: number, c: bigint, d: number, e: number, f: number, g: number, function_: string): Promise<void>;
+}
+
+/** Calls a remote object implementing the trait of the same name. */
+export class CounterClient implements Counter {
+  readonly remote: boozle.RemoteObject;
+
+  constructor(remote: boozle.RemoteObject) {
+    this.remote = remote;
+  }
+
+  /** Wraps an object value, failing with `kind` if it isn't a remote object. */
+  static fromValue(value: boozle.LocalValue | undefined, kind: boozle.CallErrorKind = "InvalidResponse"): CounterClient {
+    return new CounterClient(boozle.toRemote(value, kind));
+  }
+
+  /** Tells the peer this handle is no longer needed. */
+  release(): Promise<void> {
+    return this.remote.release();
+  }
+
+  /**
+   * Adds `by` and returns the new value.
+   */
+  async increment(by: number): Promise<bigint> {
+    const ret = await this.remote.call(2013403751146642969n, boozle.lit(boozle.u32, by));
+    return boozle.fromLit(boozle.u64, ret, "InvalidResponse");
+  }
+
+  async reset(): Promise<void> {
+    await this.remote.call(180472086535209963n, undefined);
+  }
+
+  async touch(at: Uint8Array): Promise<boolean> {
+    const ret = await this.remote.call(7157742630753866513n, boozle.lit(boozle.raw, at));
+    return boozle.fromLit(boozle.bool, ret, "InvalidResponse");
+  }
+
+  async mix(a: number, b: number, c: bigint, d: number, e: number, f: number, g: number, function_: string): Promise<void> {
+    const argument = boozle.writeLit((writer) => {
+      boozle.i8.encode(writer, a);
+      boozle.i16.encode(writer, b);
+      boozle.i64.encode(writer, c);
+      boozle.f32.encode(writer, d);
+      boozle.f64.encode(writer, e);
+      boozle.u8.encode(writer, f);
+      boozle.u16.encode(writer, g);
+      boozle.string.encode(writer, function_);
+    });
+    await this.remote.call(14543286379190011046n, argument);
+  }
+}
+
+/** Exposes a local `Counter` to the peer. */
+export class CounterObject implements boozle.LocalObject {
+  readonly target: Counter;
+
+  constructor(target: Counter) {
+    this.target = target;
+  }
+
+  async dispatch(methodId: bigint, argument: boozle.LocalValue | undefined): Promise<boozle.LocalValue | undefined> {
+    switch (methodId) {
+      case 2013403751146642969n: {
+        const by = boozle.fromLit(boozle.u32, argument, "InvalidArgument");
+        const result = await this.target.increment(by);
+        return boozle.lit(boozle.u64, result);
+      }
+      case 180472086535209963n: {
+        await this.target.reset();
+        return undefined;
+      }
+      case 7157742630753866513n: {
+        const at = boozle.fromLit(boozle.raw, argument, "InvalidArgument");
+        const result = await this.target.touch(at);
+        return boozle.lit(boozle.bool, result);
+      }
+      case 14543286379190011046n: {
+        const [a, b, c, d, e, f, g, function_] = boozle.readLit(argument, "InvalidArgument", (reader) => [boozle.i8.decode(reader), boozle.i16.decode(reader), boozle.i64.decode(reader), boozle.f32.decode(reader), boozle.f64.decode(reader), boozle.u8.decode(reader), boozle.u16.decode(reader), boozle.string.decode(reader)] as const);
+        await this.target.mix(a, b, c, d, e, f, g, function_);
+        return undefined;
+      }
+      default:
+        throw new boozle.CallError("UnknownMethod");
+    }
+  }
+}
+
+export interface Factory {
+  counter(name: string): Promise<Counter>;
+  adopt(counter: Counter): Promise<void>;
+  store(): Promise<__store.Store>;
+}
+
+/** Calls a remote object implementing the trait of the same name. */
+export class FactoryClient implements Factory {
+  readonly remote: boozle.RemoteObject;
+
+  constructor(remote: boozle.RemoteObject) {
+    this.remote = remote;
+  }
+
+  /** Wraps an object value, failing with `kind` if it isn't a remote object. */
+  static fromValue(value: boozle.LocalValue | undefined, kind: boozle.CallErrorKind = "InvalidResponse"): FactoryClient {
+    return new FactoryClient(boozle.toRemote(value, kind));
+  }
+
+  /** Tells the peer this handle is no longer needed. */
+  release(): Promise<void> {
+    return this.remote.release();
+  }
+
+  async counter(name: string): Promise<Counter> {
+    const ret = await this.remote.call(2458396013111362480n, boozle.lit(boozle.string, name), true);
+    return CounterClient.fromValue(ret, "InvalidResponse");
+  }
+
+  async adopt(counter: Counter): Promise<void> {
+    await this.remote.call(11285151363145631329n, boozle.objectValue(counter, (target) => new CounterObject(target)));
+  }
+
+  async store(): Promise<__store.Store> {
+    const ret = await this.remote.call(14806310975793532396n, undefined, true);
+    return __store.StoreClient.fromValue(ret, "InvalidResponse");
+  }
+}
+
+/** Exposes a local `Factory` to the peer. */
+export class FactoryObject implements boozle.LocalObject {
+  readonly target: Factory;
+
+  constructor(target: Factory) {
+    this.target = target;
+  }
+
+  async dispatch(methodId: bigint, argument: boozle.LocalValue | undefined): Promise<boozle.LocalValue | undefined> {
+    switch (methodId) {
+      case 2458396013111362480n: {
+        const name = boozle.fromLit(boozle.string, argument, "InvalidArgument");
+        const result = await this.target.counter(name);
+        return boozle.objectValue(result, (target) => new CounterObject(target));
+      }
+      case 11285151363145631329n: {
+        const counter = CounterClient.fromValue(argument, "InvalidArgument");
+        await this.target.adopt(counter);
+        return undefined;
+      }
+      case 14806310975793532396n: {
+        const result = await this.target.store();
+        return boozle.objectValue(result, (target) => new __store.StoreObject(target));
+      }
+      default:
+        throw new boozle.CallError("UnknownMethod");
+    }
+  }
+}
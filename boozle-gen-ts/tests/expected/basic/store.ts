@@ -0,0 +1,63 @@
+// Generated by boozle-gen-ts. Do not edit.
+
+import * as boozle from "./runtime";
+import * as __root from "./index";
+
+export interface Store {
+  get(key: string): Promise<string>;
+  counter(): Promise<__root.Counter>;
+}
+
+/** Calls a remote object implementing the trait of the same name. */
+export class StoreClient implements Store {
+  readonly remote: boozle.RemoteObject;
+
+  constructor(remote: boozle.RemoteObject) {
+    this.remote = remote;
+  }
+
+  /** Wraps an object value, failing with `kind` if it isn't a remote object. */
+  static fromValue(value: boozle.LocalValue | undefined, kind: boozle.CallErrorKind = "InvalidResponse"): StoreClient {
+    return new StoreClient(boozle.toRemote(value, kind));
+  }
+
+  /** Tells the peer this handle is no longer needed. */
+  release(): Promise<void> {
+    return this.remote.release();
+  }
+
+  async get(key: string): Promise<string> {
+    const ret = await this.remote.call(2385950102538146963n, boozle.lit(boozle.string, key));
+    return boozle.fromLit(boozle.string, ret, "InvalidResponse");
+  }
+
+  async counter(): Promise<__root.Counter> {
+    const ret = await this.remote.call(10664434878195391422n, undefined, true);
+    return __root.CounterClient.fromValue(ret, "InvalidResponse");
+  }
+}
+
+/** Exposes a local `Store` to the peer. */
+export class StoreObject implements boozle.LocalObject {
+  readonly target: Store;
+
+  constructor(target: Store) {
+    this.target = target;
+  }
+
+  async dispatch(methodId: bigint, argument: boozle.LocalValue | undefined): Promise<boozle.LocalValue | undefined> {
+    switch (methodId) {
+      case 2385950102538146963n: {
+        const key = boozle.fromLit(boozle.string, argument, "InvalidArgument");
+        const result = await this.target.get(key);
+        return boozle.lit(boozle.string, result);
+      }
+      case 10664434878195391422n: {
+        const result = await this.target.counter();
+        return boozle.objectValue(result, (target) => new __root.CounterObject(target));
+      }
+      default:
+        throw new boozle.CallError("UnknownMethod");
+    }
+  }
+}
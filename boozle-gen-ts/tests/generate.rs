@@ -0,0 +1,86 @@
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::File;
+use boozle_gen_ts::{generate, Options};
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-ts-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Generates code for `files`, given as (path, text) with the root first.
+fn run(name: &str, files: &[(&str, &str)], options: &Options) -> Result<Vec<File>, String> {
+  let dir = temp_dir(name);
+  for (path, text) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+  let root = boozle_gen::load(dir.join(files[0].0)).unwrap();
+  generate(&Descriptor::new(&root).unwrap(), options)
+}
+
+fn content<'a>(files: &'a [File], path: &str) -> &'a str {
+  files
+    .iter()
+    .find(|file| file.path == path)
+    .map(|file| file.content.as_str())
+    .unwrap_or_else(|| panic!("{} wasn't generated", path))
+}
+
+#[test]
+fn lays_out_modules() {
+  let files = run(
+    "modules",
+    &[
+      ("root.boozle", "mod a;\ntrait Counter {\n  fn get() -> u64;\n}\n"),
+      (
+        "a/mod.boozle",
+        "mod b;\ntrait Factory {\n  fn create(name: String) -> super.Counter;\n}\nsvc factory: Factory;\n",
+      ),
+      ("a/b.boozle", "trait Empty {\n}\n"),
+    ],
+    &Options::from_parameter("runtime=./runtime").unwrap(),
+  )
+  .unwrap();
+
+  let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+  assert_eq!(paths, vec!["index.ts", "a.ts", "a/b.ts"]);
+
+  let root = content(&files, "index.ts");
+  assert!(root.contains("export * as a from \"./a\";"));
+  assert!(root.contains("import * as boozle from \"./runtime\";"));
+  assert!(root.contains("get(): Promise<bigint>;"));
+
+  let a = content(&files, "a.ts");
+  assert!(a.contains("export * as b from \"./a/b\";"));
+  assert!(a.contains("import * as __root from \"./index\";"));
+  assert!(a.contains("create(name: string): Promise<__root.Counter>;"));
+  assert!(a.contains("return __root.CounterClient.fromValue(ret, \"InvalidResponse\");"));
+  assert!(a.contains("export const factory = "));
+
+  let b = content(&files, "a/b.ts");
+  assert!(b.contains("import * as boozle from \"../runtime\";"));
+}
+
+#[test]
+fn rejects_object_among_params() {
+  let err = run(
+    "object-params",
+    &[(
+      "root.boozle",
+      "trait Counter {\n  fn get() -> u64;\n}\ntrait Registry {\n  fn register(name: String counter: Counter);\n}\n",
+    )],
+    &Options::default(),
+  )
+  .unwrap_err();
+  assert!(err.contains("Registry.register"), "{}", err);
+}
+
+#[test]
+fn rejects_unknown_parameters() {
+  assert!(Options::from_parameter("lang=ts").is_err());
+  assert_eq!(Options::from_parameter("").unwrap().runtime, "boozle");
+}
@@ -0,0 +1,197 @@
+//! Calls a Rust `Connection` from Node through generated TypeScript. Needs
+//! `node` (with `WebSocket`, Node 20 or later) and the TypeScript compiler,
+//! taken from `$TSC` or `boozle-ts/node_modules/.bin/tsc` after `npm install`
+//! there. Ignored by default, run it with
+//! `cargo test -p boozle-gen-ts --test roundtrip -- --ignored`. Fails if either
+//! is missing.
+
+use boozle::connection::ConnectionHelpers;
+use boozle::object::CallError;
+use boozle::pool::PoolHelpers;
+use boozle::transport::websocket;
+use boozle::value::LocalValue;
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::write_files;
+use boozle_gen_ts::{generate, Options};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn increment(&self) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(1, Ordering::SeqCst) + 1)
+  }
+
+  async fn get(&self) -> Result<u32, CallError> {
+    Ok(self.value.load(Ordering::SeqCst))
+  }
+}
+
+#[derive(Debug)]
+pub struct Calc;
+
+#[boozle::object]
+impl Calc {
+  async fn add(&self, a: i64, b: i64) -> Result<i64, CallError> {
+    Ok(a + b)
+  }
+
+  async fn greet(&self, name: String) -> Result<String, CallError> {
+    Ok(format!("hello, {}", name))
+  }
+
+  async fn mix(&self, a: bool, b: u8, c: u64, d: f64) -> Result<f64, CallError> {
+    Ok(a as u8 as f64 + b as f64 + c as f64 + d)
+  }
+
+  async fn ping(&self) -> Result<(), CallError> {
+    Ok(())
+  }
+
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+
+  async fn adopt(&self, counter: CounterClient) -> Result<bool, CallError> {
+    Ok(counter.increment().await.is_ok())
+  }
+}
+
+fn repo() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+fn tsc() -> Option<PathBuf> {
+  let tsc = std::env::var_os("TSC")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| repo().join("boozle-ts/node_modules/.bin/tsc"));
+  if tsc.exists() {
+    Some(tsc)
+  } else {
+    None
+  }
+}
+
+/// Flags node needs for a global `WebSocket`, or `None` if it has none.
+fn node_flags() -> Option<Vec<&'static str>> {
+  let script = "process.exit(typeof WebSocket === 'function' ? 0 : 1)";
+  for flags in [vec![], vec!["--experimental-websocket"]].iter() {
+    let status = Command::new("node").args(flags).arg("-e").arg(script).status().ok()?;
+    if status.success() {
+      return Some(flags.clone());
+    }
+  }
+  None
+}
+
+/// Lays out the runtime, the generated client and the test script in `dir`
+/// and compiles them to `dir/dist`.
+fn build(dir: &Path, tsc: &Path) {
+  let src = dir.join("src");
+  std::fs::create_dir_all(src.join("boozle")).unwrap();
+  for entry in std::fs::read_dir(repo().join("boozle-ts/src")).unwrap() {
+    let path = entry.unwrap().path();
+    std::fs::copy(&path, src.join("boozle").join(path.file_name().unwrap())).unwrap();
+  }
+  std::fs::copy(
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roundtrip/test.ts"),
+    src.join("test.ts"),
+  )
+  .unwrap();
+
+  let root = dir.join("calc.boozle");
+  std::fs::write(&root, format!("{}\n{}", Counter::BOOZLE, Calc::BOOZLE)).unwrap();
+  let descriptor = Descriptor::new(&boozle_gen::load(&root).unwrap()).unwrap();
+  let options = Options {
+    runtime: "../boozle".to_string(),
+  };
+  write_files(src.join("gen"), generate(&descriptor, &options).unwrap().as_slice()).unwrap();
+
+  let types = repo().join("boozle-ts/node_modules/@types");
+  let config = format!(
+    r#"{{
+  "compilerOptions": {{
+    "target": "ES2020",
+    "module": "commonjs",
+    "lib": ["ES2021", "DOM"],
+    "strict": true,
+    "rootDir": "src",
+    "outDir": "dist",
+    "typeRoots": [{:?}],
+    "types": ["node"]
+  }}
+}}
+"#,
+    types.display().to_string()
+  );
+  std::fs::write(dir.join("tsconfig.json"), config).unwrap();
+  let status = Command::new(tsc).arg("-p").arg(dir).status().unwrap();
+  assert!(status.success(), "tsc failed");
+}
+
+/// Serves on a new thread. Returns the port, and a receiver for the ID under
+/// which the first client can reach a `Calc`.
+fn serve() -> (u16, std::sync::mpsc::Receiver<u64>) {
+  let (port_tx, port_rx) = std::sync::mpsc::channel();
+  let (key_tx, key_rx) = std::sync::mpsc::channel();
+  std::thread::spawn(move || {
+    let mut system = actix::System::new("roundtrip");
+    system.block_on(async move {
+      let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+      port_tx.send(listener.local_addr().unwrap().port()).unwrap();
+      let (stream, _) = listener.accept().await.unwrap();
+      let connection = websocket::accept(stream).await.unwrap();
+      let pool = connection.pool().await;
+      let key = pool.expose(LocalValue::from_object(Calc)).await.unwrap().key;
+      key_tx.send(key).unwrap();
+      std::future::pending::<()>().await;
+    });
+  });
+  (port_rx.recv().unwrap(), key_rx)
+}
+
+#[test]
+#[ignore]
+fn node_client() {
+  let tsc = tsc().expect("needs tsc, set TSC or npm install in boozle-ts");
+  let flags = node_flags().expect("needs node with WebSocket support");
+
+  let dir = std::env::temp_dir().join(format!("boozle-gen-ts-roundtrip-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  build(&dir, &tsc);
+
+  let (port, key) = serve();
+  let mut node = Command::new("node")
+    .args(flags)
+    .arg(dir.join("dist/test.js"))
+    .arg(port.to_string())
+    .stdin(Stdio::piped())
+    .spawn()
+    .unwrap();
+  let key = key.recv_timeout(Duration::from_secs(30)).unwrap();
+  writeln!(node.stdin.take().unwrap(), "{}", key).unwrap();
+
+  let deadline = Instant::now() + Duration::from_secs(60);
+  let status = loop {
+    if let Some(status) = node.try_wait().unwrap() {
+      break status;
+    }
+    if Instant::now() > deadline {
+      let _ = node.kill();
+      panic!("node client timed out");
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  };
+  assert!(status.success(), "node client failed");
+}
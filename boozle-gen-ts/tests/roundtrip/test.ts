@@ -0,0 +1,77 @@
+// Client half of the round-trip test in `roundtrip.rs`. Connects to the Rust
+// server on the port given as the first argument, reads the ID of its `Calc`
+// from stdin and exercises it through the generated client.
+
+import * as assert from "assert";
+import * as boozle from "./boozle";
+import { CalcClient, Counter } from "./gen";
+
+class LocalCounter implements Counter {
+  value = 0;
+
+  async increment(): Promise<number> {
+    this.value += 1;
+    return this.value;
+  }
+
+  async get(): Promise<number> {
+    return this.value;
+  }
+}
+
+function readLine(): Promise<string> {
+  return new Promise((resolve) => {
+    let data = "";
+    process.stdin.setEncoding("utf8");
+    process.stdin.on("data", (chunk: string) => {
+      data += chunk;
+      const end = data.indexOf("\n");
+      if (end >= 0) {
+        process.stdin.pause();
+        resolve(data.slice(0, end));
+      }
+    });
+  });
+}
+
+async function eventually(condition: () => boolean): Promise<void> {
+  for (let i = 0; i < 100 && !condition(); i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+  }
+  assert.ok(condition());
+}
+
+async function main(): Promise<void> {
+  const connection = await boozle.Connection.connect(`ws://127.0.0.1:${process.argv[2]}`, { timeoutMs: 10000 });
+  const calc = new CalcClient(connection.remote(BigInt(await readLine())));
+  assert.strictEqual((await connection.handshake).version, boozle.VERSION);
+
+  assert.strictEqual(await calc.add(40n, 2n), 42n);
+  assert.strictEqual(await calc.add(-5n, 2n), -3n);
+  assert.strictEqual(await calc.greet("boozle"), "hello, boozle");
+  assert.strictEqual(await calc.mix(true, 2, 3n, 0.5), 6.5);
+  await calc.ping();
+
+  // Objects returned by the server are kept in its pool until released.
+  const counter = await calc.counter(10);
+  assert.strictEqual(await counter.increment(), 11);
+  assert.strictEqual(await counter.get(), 11);
+  await counter.release();
+  await assert.rejects(counter.get(), (err: unknown) => err instanceof boozle.CallError && err.kind === "Failed");
+
+  // Local objects passed to the server are exposed until it frees them.
+  const local = new LocalCounter();
+  assert.strictEqual(await calc.adopt(local), true);
+  assert.strictEqual(local.value, 1);
+  await eventually(() => connection.exposedCount === 0);
+
+  connection.close();
+}
+
+main().then(
+  () => process.exit(0),
+  (err) => {
+    console.error(err);
+    process.exit(1);
+  }
+);
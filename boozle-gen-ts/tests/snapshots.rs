@@ -0,0 +1,90 @@
+//! Compares the generator's output for every case in `tests/corpus` with the
+//! checked-in files under `tests/expected`, so changes to the output show up
+//! without Node or the TypeScript compiler.
+//!
+//! A case is a directory holding a root `<case>.boozle` and any modules it
+//! loads. Set `BOOZLE_BLESS=1` to overwrite the expected files with the current
+//! output, then review the diff.
+
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen_ts::{generate, Options};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+const CASES: &[&str] = &["basic"];
+
+fn tests_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+/// Reads every file below `dir`, keyed by its path relative to `dir`.
+fn read_dir(dir: &Path) -> BTreeMap<PathBuf, String> {
+  fn read_inner(root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, String>) {
+    let entries = match std::fs::read_dir(dir) {
+      Ok(entries) => entries,
+      Err(_) => return,
+    };
+    for entry in entries {
+      let path = entry.unwrap().path();
+      if path.is_dir() {
+        read_inner(root, path.as_path(), files);
+      } else {
+        let text = std::fs::read_to_string(&path).unwrap();
+        files.insert(path.strip_prefix(root).unwrap().to_path_buf(), text);
+      }
+    }
+  }
+
+  let mut files = BTreeMap::new();
+  read_inner(dir, dir, &mut files);
+  files
+}
+
+fn generate_case(case: &str) -> BTreeMap<PathBuf, String> {
+  let input = tests_dir().join("corpus").join(case).join(format!("{}.boozle", case));
+  let root = boozle_gen::load(&input).unwrap_or_else(|err| panic!("{}: {}", case, err));
+  let descriptor = Descriptor::new(&root).unwrap_or_else(|err| panic!("{}: {}", case, err));
+  let options = Options {
+    runtime: "./runtime".to_string(),
+  };
+  let files = generate(&descriptor, &options).unwrap_or_else(|err| panic!("{}: {}", case, err));
+  files
+    .into_iter()
+    .map(|file| (PathBuf::from(file.path), file.content))
+    .collect()
+}
+
+fn bless(dir: &Path, files: &BTreeMap<PathBuf, String>) {
+  let _ = std::fs::remove_dir_all(dir);
+  for (file, text) in files.iter() {
+    let path = dir.join(file);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+}
+
+#[test]
+fn snapshots() {
+  let bless_all = std::env::var_os("BOOZLE_BLESS").is_some();
+  let mut failures = Vec::new();
+  for case in CASES.iter() {
+    let actual = generate_case(case);
+    let expected_dir = tests_dir().join("expected").join(case);
+    if bless_all {
+      bless(expected_dir.as_path(), &actual);
+      continue;
+    }
+    let expected = read_dir(expected_dir.as_path());
+    let files: BTreeSet<&PathBuf> = actual.keys().chain(expected.keys()).collect();
+    for file in files.into_iter() {
+      if actual.get(file) != expected.get(file) {
+        failures.push(format!("{}/{}", case, file.display()));
+      }
+    }
+  }
+  assert!(
+    failures.is_empty(),
+    "generated code differs from tests/expected: {}\nrerun with BOOZLE_BLESS=1 to update",
+    failures.join(", ")
+  );
+}
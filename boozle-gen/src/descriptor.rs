@@ -0,0 +1,382 @@
+//! A language-neutral description of a protocol tree, for generators that
+//! don't link against the parser.
+//!
+//! A descriptor is encoded either as JSON or in a compact binary form (bincode
+//! 1 with its default options: little-endian, fixed-size integers, `u64` length
+//! prefixes). In JSON, IDs are decimal strings because many JSON readers can't
+//! hold a full `u64`. Type references are resolved against the tree, so
+//! generators don't need to implement name lookup.
+
+use super::{method_id, svc_id, LoadError, Module};
+use boozle_parser::{Attr, Decl, Fn, Path, Trait, Type};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Bumped whenever the descriptor format changes incompatibly.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Descriptor {
+  pub version: u32,
+  pub root: ModuleDescriptor,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttrDescriptor {
+  pub name: String,
+  /// `key = "value"` arguments, in source order.
+  pub args: Vec<(String, String)>,
+}
+
+/// How a type reference resolved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypeRef {
+  /// A trait of the tree, passed as a live object. `path` is relative to the
+  /// root module.
+  Object { path: Vec<String> },
+  /// A `type` declaration of the tree, whose representation is up to the
+  /// generator (see its attributes). `path` is relative to the root module.
+  Declared { path: Vec<String> },
+  /// A primitive such as `String` or `u32`.
+  Named { path: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamDescriptor {
+  pub name: String,
+  pub ty: TypeRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodDescriptor {
+  pub name: String,
+  #[serde(with = "id")]
+  pub id: u64,
+  pub attrs: Vec<AttrDescriptor>,
+  pub doc: Option<String>,
+  pub params: Vec<ParamDescriptor>,
+  pub result: Option<TypeRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraitDescriptor {
+  pub name: String,
+  pub attrs: Vec<AttrDescriptor>,
+  pub doc: Option<String>,
+  pub methods: Vec<MethodDescriptor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SvcDescriptor {
+  pub name: String,
+  #[serde(with = "id")]
+  pub id: u64,
+  pub attrs: Vec<AttrDescriptor>,
+  pub doc: Option<String>,
+  pub ty: TypeRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeDescriptor {
+  pub name: String,
+  pub attrs: Vec<AttrDescriptor>,
+  pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UseDescriptor {
+  pub path: Vec<String>,
+  pub attrs: Vec<AttrDescriptor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDescriptor {
+  pub name: String,
+  /// Names of the module's ancestors and itself, excluding the root.
+  pub path: Vec<String>,
+  pub attrs: Vec<AttrDescriptor>,
+  pub doc: Option<String>,
+  pub traits: Vec<TraitDescriptor>,
+  pub services: Vec<SvcDescriptor>,
+  pub types: Vec<TypeDescriptor>,
+  pub uses: Vec<UseDescriptor>,
+  pub modules: Vec<ModuleDescriptor>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+  Json(serde_json::Error),
+  Binary(bincode::Error),
+  /// The descriptor was written by an incompatible version of this crate.
+  Version(u32),
+}
+
+impl std::fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Json(err) => write!(f, "invalid descriptor: {}", err),
+      Self::Binary(err) => write!(f, "invalid descriptor: {}", err),
+      Self::Version(version) => write!(
+        f,
+        "unsupported descriptor version {} (expected {})",
+        version, VERSION
+      ),
+    }
+  }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encodes IDs as decimal strings in human-readable formats and as `u64`
+/// otherwise.
+mod id {
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(id: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+      serializer.serialize_str(id.to_string().as_str())
+    } else {
+      serializer.serialize_u64(*id)
+    }
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    if deserializer.is_human_readable() {
+      String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    } else {
+      u64::deserialize(deserializer)
+    }
+  }
+}
+
+/// Returns the text of every `#[doc(text = "...")]` attribute, one per line.
+fn doc(attrs: &[Attr]) -> Option<String> {
+  let lines: Vec<&str> = attrs
+    .iter()
+    .filter(|attr| attr.name.text == "doc")
+    .filter_map(|attr| attr.arg("text"))
+    .collect();
+  if lines.is_empty() {
+    None
+  } else {
+    Some(lines.join("\n"))
+  }
+}
+
+fn attrs(attrs: &[Attr]) -> Vec<AttrDescriptor> {
+  attrs
+    .iter()
+    .map(|attr| AttrDescriptor {
+      name: attr.name.text.clone(),
+      args: attr
+        .args
+        .iter()
+        .map(|arg| (arg.key.text.clone(), arg.value.clone()))
+        .collect(),
+    })
+    .collect()
+}
+
+/// The types every generator knows. Any other name must be declared in the tree.
+const PRIMITIVES: &[&str] = &[
+  "bool", "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "f32", "f64",
+  "char", "String",
+];
+
+fn components(path: &Path) -> Vec<String> {
+  path.components.iter().map(|id| id.text.clone()).collect()
+}
+
+/// The full path of `path` as written in the module at `module`. Paths are
+/// relative to the enclosing module, and each leading `super` moves up one
+/// level. Returns `None` if that goes above the root.
+pub fn resolve_path(module: &[String], path: &Path) -> Option<Vec<String>> {
+  let written = components(path);
+  let mut full = module.to_vec();
+  let mut rest = written.as_slice();
+  while let Some((first, tail)) = rest.split_first() {
+    if first != "super" {
+      break;
+    }
+    full.pop()?;
+    rest = tail;
+  }
+  full.extend(rest.iter().cloned());
+  Some(full)
+}
+
+/// Full paths of every trait and `type` declaration in the tree.
+struct Names {
+  traits: HashSet<Vec<String>>,
+  types: HashSet<Vec<String>>,
+}
+
+impl Names {
+  fn new(root: &Module) -> Self {
+    let mut names = Self {
+      traits: HashSet::new(),
+      types: HashSet::new(),
+    };
+    root.walk(&mut |path, module| {
+      for decl in module.decls.iter() {
+        let (set, name) = match decl {
+          Decl::Trait(trait_) => (&mut names.traits, &trait_.name),
+          Decl::Type(type_) => (&mut names.types, &type_.name),
+          _ => continue,
+        };
+        let mut full: Vec<String> = path.iter().map(|name| name.to_string()).collect();
+        full.push(name.text.clone());
+        set.insert(full);
+      }
+    });
+    names
+  }
+
+  /// Resolves `ty` as written in the module at `module` (see `resolve_path`).
+  /// `location` names what refers to the type, for the error.
+  fn resolve(&self, module: &[String], location: &str, ty: &Type) -> Result<TypeRef, LoadError> {
+    let written = components(&ty.path);
+    if let [name] = written.as_slice() {
+      if PRIMITIVES.contains(&name.as_str()) {
+        return Ok(TypeRef::Named { path: written });
+      }
+    }
+    let unresolved = || LoadError::UnresolvedType(dotted(module, location), ty.path.to_string());
+    let full = resolve_path(module, &ty.path).ok_or_else(unresolved)?;
+    if self.traits.contains(&full) {
+      Ok(TypeRef::Object { path: full })
+    } else if self.types.contains(&full) {
+      Ok(TypeRef::Declared { path: full })
+    } else {
+      Err(unresolved())
+    }
+  }
+}
+
+/// Joins a module path and a name within it with dots, e.g. `store.Store.get`.
+fn dotted(module: &[String], name: &str) -> String {
+  let mut parts: Vec<&str> = module.iter().map(String::as_str).collect();
+  parts.push(name);
+  parts.join(".")
+}
+
+fn method(
+  names: &Names,
+  module: &[String],
+  trait_: &Trait,
+  fn_: &Fn,
+) -> Result<MethodDescriptor, LoadError> {
+  let location = format!("{}.{}", trait_.name.text, fn_.name.text);
+  let mut params = Vec::new();
+  for param in fn_.params.iter() {
+    params.push(ParamDescriptor {
+      name: param.name.text.clone(),
+      ty: names.resolve(module, location.as_str(), &param.ty)?,
+    });
+  }
+  let result = match &fn_.result {
+    Some(ty) => Some(names.resolve(module, location.as_str(), ty)?),
+    None => None,
+  };
+  Ok(MethodDescriptor {
+    name: fn_.name.text.clone(),
+    id: method_id(dotted(module, trait_.name.text.as_str()).as_str(), fn_.name.text.as_str()),
+    attrs: attrs(&fn_.attrs),
+    doc: doc(&fn_.attrs),
+    params,
+    result,
+  })
+}
+
+fn module(
+  names: &Names,
+  path: Vec<String>,
+  module_: &Module,
+) -> Result<ModuleDescriptor, LoadError> {
+  let mut descriptor = ModuleDescriptor {
+    name: module_.name.clone(),
+    path: path.clone(),
+    attrs: attrs(&module_.attrs),
+    doc: doc(&module_.attrs),
+    traits: Vec::new(),
+    services: Vec::new(),
+    types: Vec::new(),
+    uses: Vec::new(),
+    modules: Vec::new(),
+  };
+  for decl in module_.decls.iter() {
+    match decl {
+      Decl::Trait(trait_) => descriptor.traits.push(TraitDescriptor {
+        name: trait_.name.text.clone(),
+        attrs: attrs(&trait_.attrs),
+        doc: doc(&trait_.attrs),
+        methods: trait_
+          .members
+          .iter()
+          .map(|fn_| method(names, path.as_slice(), trait_, fn_))
+          .collect::<Result<_, _>>()?,
+      }),
+      Decl::Svc(svc) => descriptor.services.push(SvcDescriptor {
+        name: svc.name.text.clone(),
+        id: svc_id(svc.name.text.as_str()),
+        attrs: attrs(&svc.attrs),
+        doc: doc(&svc.attrs),
+        ty: names.resolve(path.as_slice(), svc.name.text.as_str(), &svc.ty)?,
+      }),
+      Decl::Type(type_) => descriptor.types.push(TypeDescriptor {
+        name: type_.name.text.clone(),
+        attrs: attrs(&type_.attrs),
+        doc: doc(&type_.attrs),
+      }),
+      Decl::Use(use_) => descriptor.uses.push(UseDescriptor {
+        path: components(&use_.path),
+        attrs: attrs(&use_.attrs),
+      }),
+      // `Module` never holds `Decl::Mod`.
+      Decl::Mod(_) => {}
+    }
+  }
+  for child in module_.children.iter() {
+    let mut child_path = path.clone();
+    child_path.push(child.name.clone());
+    descriptor.modules.push(module(names, child_path, child)?);
+  }
+  Ok(descriptor)
+}
+
+impl Descriptor {
+  /// Describes a loaded protocol tree (see `load`). Fails if a type isn't a
+  /// primitive, a trait or a `type` declaration of the tree.
+  pub fn new(root: &Module) -> Result<Self, LoadError> {
+    let names = Names::new(root);
+    Ok(Self {
+      version: VERSION,
+      root: module(&names, Vec::new(), root)?,
+    })
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+
+  pub fn to_binary(&self) -> Vec<u8> {
+    bincode::serialize(self).unwrap()
+  }
+
+  pub fn from_json(text: &str) -> Result<Self, DecodeError> {
+    let descriptor: Self = serde_json::from_str(text).map_err(DecodeError::Json)?;
+    descriptor.check_version()
+  }
+
+  pub fn from_binary(bytes: &[u8]) -> Result<Self, DecodeError> {
+    let descriptor: Self = bincode::deserialize(bytes).map_err(DecodeError::Binary)?;
+    descriptor.check_version()
+  }
+
+  fn check_version(self) -> Result<Self, DecodeError> {
+    if self.version != VERSION {
+      return Err(DecodeError::Version(self.version));
+    }
+    Ok(self)
+  }
+}
@@ -1,119 +1,165 @@
-// use boozle_parser::{parse, Unit};
-// use std::collections::HashMap;
-// use std::io::{Error, Read};
-
-// use std::fs::File;
-
-// use std::path::{Path, PathBuf};
-
-// #[derive(Debug)]
-// pub enum Entry<T> {
-//   Directory(Directory<T>),
-//   File(T),
-// }
-
-// impl<T> Entry<T> {
-//   pub fn map<O>(&self, f: fn(value: &T) -> O) -> Entry<O> {
-//     match self {
-//       Self::File(value) => Entry::File(f(value)),
-//       Self::Directory(dir) => Entry::Directory(Directory(
-//         dir
-//           .0
-//           .iter()
-//           .map(|(key, value)| (key.clone(), value.map(f)))
-//           .collect(),
-//       )),
-//     }
-//   }
-// }
-
-// #[derive(Debug)]
-// pub struct Directory<T>(HashMap<String, Entry<T>>);
-
-// pub struct Forest<T> {
-//   roots: Vec<Tree<T>>,
-// }
-
-// impl<T> Forest<T> {
-//   pub fn new<I: IntoIterator<Item = Tree<T>>>(roots: I) -> Self {
-//     Self {
-//       roots: roots.into_iter().collect(),
-//     }
-//   }
-
-//   pub fn iter(&self) -> impl Iterator<Item = &Tree<T>> {
-//     self.roots.iter()
-//   }
-
-//   pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tree<T>> {
-//     self.roots.iter_mut()
-//   }
-// }
-
-// impl<T> IntoIterator for Forest<T> {
-//   type Item = Tree<T>;
-//   type IntoIter = <Vec<Tree<T>> as IntoIterator>::IntoIter;
-
-//   fn into_iter(self) -> Self::IntoIter {
-//     self.roots.into_iter()
-//   }
-// }
-
-// pub struct Tree<T> {
-//   root: T,
-//   children: Vec<Tree<T>>,
-// }
-
-// impl<T> Tree<T> {
-//   pub fn leaf(root: T) -> Self {
-//     Self {
-//       root,
-//       children: Vec::new(),
-//     }
-//   }
-
-//   pub fn new<C: IntoIterator<Item = Tree<T>>>(root: T, children: C) -> Self {
-//     Self {
-//       root,
-//       children: children.into_iter().collect(),
-//     }
-//   }
-
-//   pub fn map<O>(&self, f: fn(&T) -> O) -> Tree<O> {
-//     Tree {
-//       root: f(&self.root),
-//       children: self.children.iter().map(|child| child.map(f)).collect(),
-//     }
-//   }
-
-//   pub fn map_into<O>(self, f: fn(T) -> O) -> Tree<O> {
-//     Tree {
-//       root: f(self.root),
-//       children: self
-//         .children
-//         .into_iter()
-//         .map(|child| child.map_into(f))
-//         .collect(),
-//     }
-//   }
-// }
-
-// fn submods(unit: Unit) -> Vec<PathBuf> {
-//   unit.
-// }
-
-// fn load_units<I>(paths: I) -> Result<Forest<Unit>, Error>
-// where
-//   I: IntoIterator,
-//   I::Item: AsRef<Path>,
-// {
-//   for path in paths.into_iter() {
-//     let file = File::open(path)?;
-//     let mut contents = String::new();
-//     file.read_to_string(&mut contents);
-//     let file_stem = path.file_stem().unwrap().to_str().unwrap();
-//     let parse = boozle_parser::parse(file_stem, contents.as_str()).unwrap();
-
-//   }
-//   Forest::new(vec![])
-// }
+//! Language-independent support for code generators.
+
+use boozle_parser::{parse, Attr, Decl};
+use digest::Digest;
+use sha2::Sha256;
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+
+pub mod descriptor;
+pub mod plugin;
+
+/// The first eight bytes of the SHA-256 of `text`, as a little-endian integer.
+/// Generators embed IDs as integer literals, so every runtime agrees on them
+/// whatever machine generated the code.
+fn id(text: &str) -> u64 {
+  let digest = Sha256::digest(text.as_bytes());
+  let (top, _) = digest.split_at(std::mem::size_of::<u64>());
+  u64::from_le_bytes(top.try_into().unwrap())
+}
+
+/// Returns the ID used on the wire for `method` of the trait at `trait_path`,
+/// the trait's full path with modules separated by dots (`store.Store`), so
+/// same-named traits of different modules get different IDs.
+pub fn method_id(trait_path: &str, method: &str) -> u64 {
+  id(format!("{}.{}", trait_path, method).as_str())
+}
+
+/// Returns the ID used on the wire for the service `name`.
+pub fn svc_id(name: &str) -> u64 {
+  id(name)
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+  Io(PathBuf, std::io::Error),
+  Parse(PathBuf, String),
+  /// An out-of-line `mod foo;` whose file couldn't be found. Both candidate
+  /// paths are listed.
+  MissingModule(PathBuf, PathBuf),
+  /// A type name that doesn't resolve, and where it's used.
+  UnresolvedType(String, String),
+}
+
+impl std::fmt::Display for LoadError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+      Self::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+      Self::MissingModule(file, dir) => write!(
+        f,
+        "module not found, expected {} or {}",
+        file.display(),
+        dir.display()
+      ),
+      Self::UnresolvedType(location, ty) => write!(f, "{}: unresolved type `{}`", location, ty),
+    }
+  }
+}
+
+impl std::error::Error for LoadError {}
+
+/// A module of a .boozle protocol tree, with its submodules split out of its
+/// declarations.
+///
+/// Inline (`mod foo { ... }`) and out-of-line (`mod foo;`) submodules are
+/// treated the same. `decls` never contains a `Decl::Mod`.
+#[derive(Debug)]
+pub struct Module {
+  /// Attributes of the `mod` declaration. Always empty for the root module.
+  pub attrs: Vec<Attr>,
+  pub name: String,
+  pub decls: Vec<Decl>,
+  pub children: Vec<Module>,
+}
+
+impl Module {
+  /// Visits this module and all of its descendants, depth first. `path` holds
+  /// the names of the module's ancestors, excluding the root.
+  pub fn walk<'a, F>(&'a self, f: &mut F)
+  where
+    F: FnMut(&[&'a str], &'a Module),
+  {
+    fn walk_inner<'a, F>(module: &'a Module, path: &mut Vec<&'a str>, f: &mut F)
+    where
+      F: FnMut(&[&'a str], &'a Module),
+    {
+      f(path.as_slice(), module);
+      for child in module.children.iter() {
+        path.push(child.name.as_str());
+        walk_inner(child, path, f);
+        path.pop();
+      }
+    }
+
+    walk_inner(self, &mut Vec::new(), f);
+  }
+}
+
+/// Splits module declarations out of `decls`. Out-of-line modules are loaded
+/// from `dir`, following Rust's rules: `mod foo;` is `dir/foo.boozle` or
+/// `dir/foo/mod.boozle`.
+fn split(decls: Vec<Decl>, dir: &Path) -> Result<(Vec<Decl>, Vec<Module>), LoadError> {
+  let mut rest = Vec::new();
+  let mut children = Vec::new();
+  for decl in decls.into_iter() {
+    match decl {
+      Decl::Mod(module) => {
+        let name = module.name.text;
+        let child_dir = dir.join(name.as_str());
+        let child = match module.decls {
+          Some(decls) => {
+            let (decls, grandchildren) = split(decls, child_dir.as_path())?;
+            Module {
+              attrs: module.attrs,
+              name,
+              decls,
+              children: grandchildren,
+            }
+          }
+          None => {
+            let file = dir.join(format!("{}.boozle", name));
+            let mod_file = child_dir.join("mod.boozle");
+            let path = if file.is_file() {
+              file
+            } else if mod_file.is_file() {
+              mod_file
+            } else {
+              return Err(LoadError::MissingModule(file, mod_file));
+            };
+            let mut child = load_file(name, path.as_path(), child_dir.as_path())?;
+            child.attrs = module.attrs;
+            child
+          }
+        };
+        children.push(child);
+      }
+      decl => rest.push(decl),
+    }
+  }
+  Ok((rest, children))
+}
+
+fn load_file(name: String, path: &Path, dir: &Path) -> Result<Module, LoadError> {
+  let text = std::fs::read_to_string(path).map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+  let unit = parse(name, text.as_str()).map_err(|err| LoadError::Parse(path.to_path_buf(), err.to_string()))?;
+  let (decls, children) = split(unit.decls, dir)?;
+  Ok(Module {
+    attrs: Vec::new(),
+    name: unit.name,
+    decls,
+    children,
+  })
+}
+
+/// Loads the protocol tree rooted at the .boozle file `path`, including every
+/// out-of-line module it declares. The root module is named after the file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Module, LoadError> {
+  let path = path.as_ref();
+  let name = path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let dir = path.parent().unwrap_or_else(|| Path::new(""));
+  load_file(name, path, dir)
+}
@@ -0,0 +1,79 @@
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::{self, Encoding, Request};
+use std::process::exit;
+
+const USAGE: &str = "usage:
+  boozle-gen descriptor [--binary] <root.boozle> [output]
+  boozle-gen plugin <program> [--binary] [--parameter <text>] <root.boozle> <out_dir>
+  boozle-gen doc [--parameter <text>] <root.boozle> <out_dir>";
+
+/// The plugin `doc` runs, found on `PATH`.
+const DOC_PLUGIN: &str = "boozle-gen-doc";
+
+fn usage() -> ! {
+  eprintln!("{}", USAGE);
+  exit(2);
+}
+
+fn fail<E: std::fmt::Display>(err: E) -> ! {
+  eprintln!("{}", err);
+  exit(1);
+}
+
+/// Splits `--binary` and `--parameter <text>` from the positional arguments.
+fn parse_flags(args: &[String]) -> (Encoding, String, Vec<&str>) {
+  let mut encoding = Encoding::Json;
+  let mut parameter = String::new();
+  let mut positional = Vec::new();
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--binary" => encoding = Encoding::Binary,
+      "--parameter" => parameter = args.next().unwrap_or_else(|| usage()).clone(),
+      _ => positional.push(arg.as_str()),
+    }
+  }
+  (encoding, parameter, positional)
+}
+
+fn load(path: &str) -> Descriptor {
+  let module = boozle_gen::load(path).unwrap_or_else(|err| fail(err));
+  Descriptor::new(&module).unwrap_or_else(|err| fail(err))
+}
+
+fn generate(program: &str, input: &str, out_dir: &str, parameter: String, encoding: Encoding) {
+  let request = Request {
+    descriptor: load(input),
+    parameter,
+  };
+  let files = plugin::run(program, &request, encoding).unwrap_or_else(|err| fail(err));
+  plugin::write_files(out_dir, files.as_slice()).unwrap_or_else(|err| fail(err));
+}
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let (command, rest) = args.split_first().unwrap_or_else(|| usage());
+  let (encoding, parameter, positional) = parse_flags(rest);
+
+  match (command.as_str(), positional.as_slice()) {
+    ("descriptor", [input]) | ("descriptor", [input, _]) => {
+      let descriptor = load(input);
+      let bytes = match encoding {
+        Encoding::Json => descriptor.to_json().into_bytes(),
+        Encoding::Binary => descriptor.to_binary(),
+      };
+      match positional.get(1) {
+        Some(output) => std::fs::write(output, bytes).unwrap_or_else(|err| fail(err)),
+        None => {
+          use std::io::Write;
+          std::io::stdout()
+            .write_all(bytes.as_slice())
+            .unwrap_or_else(|err| fail(err));
+        }
+      }
+    }
+    ("plugin", [program, input, out_dir]) => generate(program, input, out_dir, parameter, encoding),
+    ("doc", [input, out_dir]) => generate(DOC_PLUGIN, input, out_dir, parameter, encoding),
+    _ => usage(),
+  }
+}
@@ -0,0 +1,215 @@
+//! Out-of-process code generators.
+//!
+//! A plugin is an executable. The host writes a `Request` to its stdin and
+//! closes it. The plugin writes a `Response` to its stdout and exits with
+//! status 0, reporting generation errors through `Response::error` rather than
+//! its exit status. Both messages use the encoding named by the
+//! `BOOZLE_PLUGIN_ENCODING` environment variable: `json` or `binary` (see
+//! `descriptor` for details of each). Anything the plugin writes to stderr is
+//! passed through to the user.
+
+use super::descriptor::{DecodeError, Descriptor, VERSION};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub const ENCODING_VAR: &str = "BOOZLE_PLUGIN_ENCODING";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  Json,
+  Binary,
+}
+
+impl Encoding {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Json => "json",
+      Self::Binary => "binary",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "json" => Some(Self::Json),
+      "binary" => Some(Self::Binary),
+      _ => None,
+    }
+  }
+
+  fn encode<T: Serialize>(&self, value: &T) -> Vec<u8> {
+    match self {
+      Self::Json => serde_json::to_vec(value).unwrap(),
+      Self::Binary => bincode::serialize(value).unwrap(),
+    }
+  }
+
+  fn decode<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+    match self {
+      Self::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+      Self::Binary => bincode::deserialize(bytes).map_err(|err| err.to_string()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+  pub descriptor: Descriptor,
+  /// Free-form options for the plugin, passed through from the command line.
+  pub parameter: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct File {
+  /// Relative to the output directory, `/`-separated. Must not leave it.
+  pub path: String,
+  pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Response {
+  pub files: Vec<File>,
+  /// Set if generation failed, in which case `files` is ignored.
+  pub error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+  Io(std::io::Error),
+  /// The plugin exited unsuccessfully. Holds its exit status.
+  Exit(std::process::ExitStatus),
+  /// The plugin's output couldn't be decoded.
+  InvalidResponse(String),
+  /// The host's request couldn't be decoded, or has an unsupported version.
+  InvalidRequest(String),
+  /// The plugin reported an error.
+  Failed(String),
+  /// A generated file's path is absolute or leaves the output directory.
+  InvalidPath(String),
+  /// The host didn't set `BOOZLE_PLUGIN_ENCODING`, or set it to something unknown.
+  InvalidEncoding,
+}
+
+impl std::fmt::Display for PluginError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(err) => write!(f, "{}", err),
+      Self::Exit(status) => write!(f, "plugin exited with {}", status),
+      Self::InvalidResponse(err) => write!(f, "invalid plugin response: {}", err),
+      Self::InvalidRequest(err) => write!(f, "invalid plugin request: {}", err),
+      Self::Failed(err) => write!(f, "plugin failed: {}", err),
+      Self::InvalidPath(path) => write!(f, "plugin generated invalid path {:?}", path),
+      Self::InvalidEncoding => write!(f, "{} must be json or binary", ENCODING_VAR),
+    }
+  }
+}
+
+impl std::error::Error for PluginError {}
+
+impl From<std::io::Error> for PluginError {
+  fn from(err: std::io::Error) -> Self {
+    Self::Io(err)
+  }
+}
+
+/// Runs the plugin `program` and returns the files it generated.
+pub fn run<P: AsRef<Path>>(program: P, request: &Request, encoding: Encoding) -> Result<Vec<File>, PluginError> {
+  let mut child = Command::new(program.as_ref())
+    .env(ENCODING_VAR, encoding.name())
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::inherit())
+    .spawn()?;
+
+  // Write from another thread so a plugin that answers before reading all of
+  // its input can't deadlock us.
+  let mut stdin = child.stdin.take().unwrap();
+  let input = encoding.encode(request);
+  let writer = std::thread::spawn(move || stdin.write_all(input.as_slice()));
+
+  let mut output = Vec::new();
+  child.stdout.take().unwrap().read_to_end(&mut output)?;
+  let status = child.wait()?;
+  // A plugin may legitimately exit without reading everything.
+  let _ = writer.join();
+  if !status.success() {
+    return Err(PluginError::Exit(status));
+  }
+
+  let response: Response = encoding
+    .decode(output.as_slice())
+    .map_err(PluginError::InvalidResponse)?;
+  if let Some(err) = response.error {
+    return Err(PluginError::Failed(err));
+  }
+  for file in response.files.iter() {
+    relative_path(file.path.as_str())?;
+  }
+  Ok(response.files)
+}
+
+/// Converts a plugin-supplied path, rejecting any that could escape the output
+/// directory.
+fn relative_path(path: &str) -> Result<PathBuf, PluginError> {
+  let relative = Path::new(path);
+  let valid = !path.is_empty()
+    && relative
+      .components()
+      .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+  if !valid {
+    return Err(PluginError::InvalidPath(path.to_string()));
+  }
+  Ok(relative.to_path_buf())
+}
+
+/// Writes generated files below `out_dir`, creating directories as needed.
+/// Returns the paths written.
+pub fn write_files<P: AsRef<Path>>(out_dir: P, files: &[File]) -> Result<Vec<PathBuf>, PluginError> {
+  let mut written = Vec::new();
+  for file in files.iter() {
+    let path = out_dir.as_ref().join(relative_path(file.path.as_str())?);
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, file.content.as_bytes())?;
+    written.push(path);
+  }
+  Ok(written)
+}
+
+/// Implements the plugin side of the protocol: reads the request from stdin,
+/// calls `generate`, and writes the response to stdout. An `Err` from
+/// `generate` is reported to the host as a generation error.
+pub fn serve<F>(generate: F) -> Result<(), PluginError>
+where
+  F: FnOnce(Request) -> Result<Vec<File>, String>,
+{
+  let encoding = std::env::var(ENCODING_VAR)
+    .ok()
+    .and_then(|name| Encoding::from_name(name.as_str()))
+    .ok_or(PluginError::InvalidEncoding)?;
+
+  let mut input = Vec::new();
+  std::io::stdin().read_to_end(&mut input)?;
+  let request: Request = encoding
+    .decode(input.as_slice())
+    .map_err(PluginError::InvalidRequest)?;
+  if request.descriptor.version != VERSION {
+    return Err(PluginError::InvalidRequest(
+      DecodeError::Version(request.descriptor.version).to_string(),
+    ));
+  }
+
+  let response = match generate(request) {
+    Ok(files) => Response { files, error: None },
+    Err(err) => Response {
+      files: Vec::new(),
+      error: Some(err),
+    },
+  };
+  let mut stdout = std::io::stdout();
+  stdout.write_all(encoding.encode(&response).as_slice())?;
+  stdout.flush()?;
+  Ok(())
+}
@@ -0,0 +1,185 @@
+use boozle_gen::descriptor::{DecodeError, Descriptor, TypeRef};
+use boozle_gen::{load, method_id, svc_id, LoadError};
+use std::path::PathBuf;
+
+const ROOT: &str = r#"
+#[doc(text = "Units of time.")]
+#[rust(type = "std::time::Duration")]
+type Duration;
+
+mod store {
+  trait Store {
+    #[doc(text = "Looks up a key.")]
+    #[doc(text = "Fails if it's missing.")]
+    fn get(key: String) -> String;
+    fn child() -> Store;
+    fn expire(after: super.Duration);
+    fn parent() -> super.Root;
+  }
+
+  svc store: Store;
+}
+
+/// The "root" object.
+///
+///   Indented.
+trait Root {
+  fn store() -> store.Store;
+}
+"#;
+
+fn write_root(name: &str, text: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-descriptor-{}-{}", name, std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("root.boozle");
+  std::fs::write(&path, text).unwrap();
+  path
+}
+
+fn try_descriptor(name: &str, text: &str) -> Result<Descriptor, LoadError> {
+  let path = write_root(name, text);
+  let descriptor = Descriptor::new(&load(&path).unwrap());
+  let _ = std::fs::remove_dir_all(path.parent().unwrap());
+  descriptor
+}
+
+fn descriptor(name: &str) -> Descriptor {
+  try_descriptor(name, ROOT).unwrap()
+}
+
+fn path(components: &[&str]) -> Vec<String> {
+  components.iter().map(|component| component.to_string()).collect()
+}
+
+#[test]
+fn describes_tree() {
+  let descriptor = descriptor("tree");
+  let root = &descriptor.root;
+  assert_eq!(root.types[0].doc.as_deref(), Some("Units of time."));
+  assert_eq!(
+    root.types[0].attrs[1].args,
+    vec![("type".to_string(), "std::time::Duration".to_string())]
+  );
+
+  let store = &root.modules[0];
+  assert_eq!(store.path, path(&["store"]));
+  assert_eq!(store.services[0].id, svc_id("store"));
+  assert_eq!(
+    store.services[0].ty,
+    TypeRef::Object {
+      path: path(&["store", "Store"])
+    }
+  );
+
+  let methods = &store.traits[0].methods;
+  assert_eq!(methods[0].id, method_id("store.Store", "get"));
+  assert_eq!(
+    methods[0].doc.as_deref(),
+    Some("Looks up a key.\nFails if it's missing.")
+  );
+  assert_eq!(
+    methods[0].params[0].ty,
+    TypeRef::Named {
+      path: path(&["String"])
+    }
+  );
+  assert_eq!(
+    methods[1].result,
+    Some(TypeRef::Object {
+      path: path(&["store", "Store"])
+    })
+  );
+  assert_eq!(
+    methods[2].params[0].ty,
+    TypeRef::Declared {
+      path: path(&["Duration"])
+    }
+  );
+  assert_eq!(methods[2].result, None);
+  assert_eq!(methods[3].result, Some(TypeRef::Object { path: path(&["Root"]) }));
+
+  assert_eq!(
+    root.traits[0].doc.as_deref(),
+    Some("The \"root\" object.\n\n  Indented.")
+  );
+  let root_methods = &root.traits[0].methods;
+  assert_eq!(
+    root_methods[0].result,
+    Some(TypeRef::Object {
+      path: path(&["store", "Store"])
+    })
+  );
+}
+
+#[test]
+fn prints_doc_comments() {
+  let unit = boozle_parser::parse("root".to_string(), ROOT).unwrap();
+  let text = unit.to_string();
+  assert!(text.contains("/// The \"root\" object.\n///\n///   Indented.\ntrait Root {\n"));
+  assert!(text.contains("    /// Looks up a key.\n    /// Fails if it's missing.\n"));
+  assert_eq!(
+    boozle_parser::parse("root".to_string(), text.as_str())
+      .unwrap()
+      .to_string(),
+    text
+  );
+}
+
+#[test]
+fn round_trips() {
+  let descriptor = descriptor("round-trip");
+  let json = descriptor.to_json();
+  // IDs are strings in JSON.
+  assert!(json.contains(format!("\"{}\"", method_id("store.Store", "get")).as_str()));
+  assert_eq!(Descriptor::from_json(json.as_str()).unwrap(), descriptor);
+  assert_eq!(
+    Descriptor::from_binary(descriptor.to_binary().as_slice()).unwrap(),
+    descriptor
+  );
+}
+
+#[test]
+fn rejects_other_versions() {
+  let mut descriptor = descriptor("version");
+  descriptor.version += 1;
+  match Descriptor::from_json(descriptor.to_json().as_str()) {
+    Err(DecodeError::Version(_)) => {}
+    other => panic!("unexpected result: {:?}", other),
+  }
+}
+
+#[test]
+fn rejects_unresolved_types() {
+  let cases = [
+    ("trait Foo { fn get() -> Strng; }", "Foo.get: unresolved type `Strng`"),
+    (
+      "mod inner { trait Foo { fn set(value: Duration); } }",
+      "inner.Foo.set: unresolved type `Duration`",
+    ),
+    ("trait Foo { fn up(foo: super.Foo); }", "Foo.up: unresolved type `super.Foo`"),
+    ("mod inner { } svc missing: inner.Missing;", "missing: unresolved type `inner.Missing`"),
+  ];
+  for (i, (text, error)) in cases.iter().enumerate() {
+    match try_descriptor(format!("unresolved-{}", i).as_str(), text) {
+      Err(err @ LoadError::UnresolvedType(_, _)) => assert_eq!(err.to_string(), *error),
+      other => panic!("unexpected result for {}: {:?}", text, other),
+    }
+  }
+}
+
+#[test]
+fn tells_same_named_traits_apart() {
+  let descriptor = try_descriptor(
+    "same-named",
+    "mod a { trait Foo { fn f(); } }\nmod b { trait Foo { fn f(); } }\ntrait Foo { fn f(); }\n",
+  )
+  .unwrap();
+  let root = &descriptor.root;
+  let ids = [
+    root.traits[0].methods[0].id,
+    root.modules[0].traits[0].methods[0].id,
+    root.modules[1].traits[0].methods[0].id,
+  ];
+  assert_eq!(ids, [method_id("Foo", "f"), method_id("a.Foo", "f"), method_id("b.Foo", "f")]);
+  assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+}
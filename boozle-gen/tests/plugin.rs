@@ -0,0 +1,86 @@
+#![cfg(unix)]
+
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::{run, write_files, Encoding, File, PluginError, Request};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("boozle-gen-plugin-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Writes a plugin that saves its request and encoding in `dir` and prints
+/// `response`.
+fn plugin(dir: &Path, response: &str) -> PathBuf {
+  let path = dir.join("plugin.sh");
+  let script = format!(
+    "#!/bin/sh\ncat > '{dir}/request'\necho \"$BOOZLE_PLUGIN_ENCODING\" > '{dir}/encoding'\nprintf '%s' '{response}'\n",
+    dir = dir.display(),
+    response = response
+  );
+  std::fs::write(&path, script).unwrap();
+  std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+  path
+}
+
+fn request(dir: &Path) -> Request {
+  let root = dir.join("root.boozle");
+  std::fs::write(&root, "trait Ping {\n  fn ping(n: u32) -> u32;\n}\n").unwrap();
+  Request {
+    descriptor: Descriptor::new(&boozle_gen::load(&root).unwrap()).unwrap(),
+    parameter: "lang=test".to_string(),
+  }
+}
+
+#[test]
+fn runs_plugin() {
+  let dir = temp_dir("run");
+  let program = plugin(dir.as_path(), r#"{"files":[{"path":"a/b.txt","content":"hello"}],"error":null}"#);
+  let request = request(dir.as_path());
+
+  let files = run(&program, &request, Encoding::Json).unwrap();
+  assert_eq!(
+    files,
+    vec![File {
+      path: "a/b.txt".to_string(),
+      content: "hello".to_string()
+    }]
+  );
+
+  let sent: Request = serde_json::from_slice(std::fs::read(dir.join("request")).unwrap().as_slice()).unwrap();
+  assert_eq!(sent, request);
+  assert_eq!(std::fs::read_to_string(dir.join("encoding")).unwrap(), "json\n");
+
+  let out_dir = dir.join("out");
+  write_files(&out_dir, files.as_slice()).unwrap();
+  assert_eq!(std::fs::read_to_string(out_dir.join("a/b.txt")).unwrap(), "hello");
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn reports_plugin_errors() {
+  let dir = temp_dir("error");
+  let program = plugin(dir.as_path(), r#"{"files":[],"error":"unsupported"}"#);
+  match run(&program, &request(dir.as_path()), Encoding::Json) {
+    Err(PluginError::Failed(err)) => assert_eq!(err, "unsupported"),
+    other => panic!("unexpected result: {:?}", other),
+  }
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rejects_escaping_paths() {
+  let dir = temp_dir("escape");
+  for path in ["../evil", "/etc/evil", "a/../../evil", ""].iter() {
+    let response = format!(r#"{{"files":[{{"path":"{}","content":""}}],"error":null}}"#, path);
+    let program = plugin(dir.as_path(), response.as_str());
+    match run(&program, &request(dir.as_path()), Encoding::Json) {
+      Err(PluginError::InvalidPath(_)) => {}
+      other => panic!("{}: unexpected result: {:?}", path, other),
+    }
+  }
+  let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,410 @@
+//! Converts Protocol Buffers service definitions into .boozle declarations.
+//!
+//! Each proto3 `service` becomes a .boozle trait along with a `svc` of it,
+//! named after the service in snake case. Rpcs become methods taking the
+//! request as `request` and returning the response. `google.protobuf.Empty`
+//! maps to no parameter or no result. The package becomes nested modules.
+//!
+//! Messages are opaque to boozle, so each message used by a rpc is declared
+//! as `type Foo;`, tagged with `#[proto(message = "...")]`, and needs a type
+//! mapping in the generated code, e.g. to the type generated by prost.
+//!
+//! Streams are live objects. For a stream of `Foo` the importer declares
+//!
+//! ```text
+//! trait FooSink {
+//!   fn send(item: Foo);
+//! }
+//!
+//! trait FooSource {
+//!   fn read(sink: FooSink);
+//! }
+//! ```
+//!
+//! A streamed response is sent to a `responses: FooSink` parameter, ending
+//! when the call returns. A streamed request is a `requests: FooSource`
+//! parameter, which the callee reads by passing a sink of its own. Anything
+//! that can't be mapped is skipped and reported as a `Warning`.
+
+mod parse;
+
+pub use parse::{parse, File, MessageType, ParseError, Rpc, Service};
+
+pub use boozle_import::{id, snake_case, Import, Warning};
+
+use boozle_import::{named, param};
+use boozle_parser::ast;
+use boozle_parser::Unit;
+use std::collections::HashMap;
+
+const EMPTY: &str = "google.protobuf.Empty";
+
+/// A message type resolved to the .boozle type declared for it.
+struct Resolved {
+  /// The message's fully qualified name, without a leading `.`.
+  full_name: String,
+  name: String,
+}
+
+struct Importer<'a> {
+  file: &'a File,
+  package: String,
+  /// What each declared .boozle name stands for, to catch collisions.
+  names: HashMap<String, String>,
+  types: Vec<ast::Decl>,
+  sinks: Vec<String>,
+  sources: Vec<String>,
+  warnings: Vec<Warning>,
+}
+
+impl<'a> Importer<'a> {
+  fn warn(&mut self, location: String, message: String) {
+    self.warnings.push(Warning { location, message });
+  }
+
+  fn qualify(&self, name: &str) -> String {
+    if self.package.is_empty() {
+      name.to_string()
+    } else {
+      format!("{}.{}", self.package, name)
+    }
+  }
+
+  /// Resolves a type name as protoc would from within the package, returning
+  /// `None` for `google.protobuf.Empty`.
+  fn resolve(&self, name: &str) -> Result<Option<Resolved>, String> {
+    let candidates: Vec<String> = match name.strip_prefix('.') {
+      Some(absolute) => vec![absolute.to_string()],
+      None => {
+        let scopes: Vec<&str> = self.package.split('.').filter(|scope| !scope.is_empty()).collect();
+        (0..=scopes.len())
+          .rev()
+          .map(|i| {
+            let mut candidate = scopes[..i].join(".");
+            if !candidate.is_empty() {
+              candidate.push('.');
+            }
+            candidate.push_str(name);
+            candidate
+          })
+          .collect()
+      }
+    };
+
+    for candidate in candidates.iter() {
+      if let Some(message) = self
+        .file
+        .messages
+        .iter()
+        .find(|message| self.qualify(message) == *candidate)
+      {
+        return Ok(Some(Resolved {
+          full_name: candidate.clone(),
+          name: message.replace('.', ""),
+        }));
+      }
+      if self.file.enums.iter().any(|enum_| self.qualify(enum_) == *candidate) {
+        return Err(format!("`{}` is an enum, not a message", name));
+      }
+    }
+
+    let full_name = name.trim_start_matches('.').to_string();
+    if full_name == EMPTY {
+      return Ok(None);
+    }
+    Ok(Some(Resolved {
+      name: full_name.rsplit('.').next().unwrap_or_default().to_string(),
+      full_name,
+    }))
+  }
+
+  /// Claims `name` for `what`. Fails if something else already has it.
+  fn claim(&mut self, name: &str, what: &str) -> Result<(), String> {
+    if id(name).is_none() {
+      return Err(format!("`{}` isn't a valid .boozle identifier", name));
+    }
+    match self.names.get(name) {
+      Some(existing) if existing != what => Err(format!("{} and {} would both be named `{}`", existing, what, name)),
+      Some(_) => Ok(()),
+      None => {
+        self.names.insert(name.to_string(), what.to_string());
+        Ok(())
+      }
+    }
+  }
+
+  fn declare_type(&mut self, location: &str, ty: &Resolved) -> Result<(), String> {
+    let what = format!("message `{}`", ty.full_name);
+    let new = !self.names.contains_key(&ty.name);
+    self.claim(ty.name.as_str(), what.as_str())?;
+    if new {
+      if !self
+        .file
+        .messages
+        .iter()
+        .any(|message| self.qualify(message) == ty.full_name)
+      {
+        self.warn(
+          location.to_string(),
+          format!(
+            "`{}` isn't defined in this file, declared as opaque type `{}`",
+            ty.full_name, ty.name
+          ),
+        );
+      }
+      self.types.push(ast::Decl::Type(ast::TypeDecl {
+        attrs: vec![ast::Attr {
+          name: ast::Id {
+            text: "proto".to_string(),
+          },
+          args: vec![ast::AttrArg {
+            key: ast::Id {
+              text: "message".to_string(),
+            },
+            value: ty.full_name.clone(),
+          }],
+        }],
+        name: ast::Id { text: ty.name.clone() },
+      }));
+    }
+    Ok(())
+  }
+
+  fn declare_sink(&mut self, item: &str) -> Result<String, String> {
+    let name = format!("{}Sink", item);
+    self.claim(name.as_str(), format!("the sink of `{}`", item).as_str())?;
+    if !self.sinks.iter().any(|sink| sink == item) {
+      self.sinks.push(item.to_string());
+    }
+    Ok(name)
+  }
+
+  fn declare_source(&mut self, item: &str) -> Result<String, String> {
+    let name = format!("{}Source", item);
+    self.claim(name.as_str(), format!("the source of `{}`", item).as_str())?;
+    self.declare_sink(item)?;
+    if !self.sources.iter().any(|source| source == item) {
+      self.sources.push(item.to_string());
+    }
+    Ok(name)
+  }
+
+  /// Declares the types of a rpc, returning its parameters and result.
+  fn signature(
+    &mut self,
+    location: &str,
+    rpc: &Rpc,
+    input: Option<Resolved>,
+    output: Option<Resolved>,
+  ) -> Result<(Vec<ast::Param>, Option<ast::Type>), String> {
+    let mut params = Vec::new();
+    let mut result = None;
+    if let Some(input) = input {
+      self.declare_type(location, &input)?;
+      if rpc.input.stream {
+        params.push(param("requests", self.declare_source(input.name.as_str())?.as_str()));
+      } else {
+        params.push(param("request", input.name.as_str()));
+      }
+    }
+    if let Some(output) = output {
+      self.declare_type(location, &output)?;
+      if rpc.output.stream {
+        params.push(param("responses", self.declare_sink(output.name.as_str())?.as_str()));
+      } else {
+        result = Some(named(output.name.as_str()));
+      }
+    }
+    Ok((params, result))
+  }
+
+  fn rpc(&mut self, location: &str, rpc: &Rpc) -> Option<ast::Fn> {
+    let location = format!("{}.{}", location, rpc.name);
+    let name = match id(snake_case(rpc.name.as_str()).as_str()) {
+      Some(name) => name,
+      None => {
+        self.warn(location, "skipped, name isn't a valid .boozle identifier".to_string());
+        return None;
+      }
+    };
+    if rpc.has_options {
+      self.warn(location.clone(), "options ignored".to_string());
+    }
+
+    let resolve = |ty: &MessageType| match self.resolve(ty.name.as_str()) {
+      Ok(None) if ty.stream => Err(format!("streams of `{}` can't be mapped", EMPTY)),
+      result => result,
+    };
+    let (input, output) = match (resolve(&rpc.input), resolve(&rpc.output)) {
+      (Ok(input), Ok(output)) => (input, output),
+      (input, output) => {
+        for error in input.err().into_iter().chain(output.err()) {
+          self.warn(location.clone(), format!("skipped, {}", error));
+        }
+        return None;
+      }
+    };
+
+    let mapped = self.signature(location.as_str(), rpc, input, output);
+
+    match mapped {
+      Ok((params, result)) => Some(ast::Fn {
+        attrs: Vec::new(),
+        name,
+        params,
+        result,
+      }),
+      Err(error) => {
+        self.warn(location, format!("skipped, {}", error));
+        None
+      }
+    }
+  }
+
+  fn service(&mut self, service: &Service) -> Vec<ast::Decl> {
+    let location = self.qualify(service.name.as_str());
+    let svc = snake_case(service.name.as_str());
+    let claimed = self
+      .claim(service.name.as_str(), format!("service `{}`", location).as_str())
+      .and_then(|_| id(svc.as_str()).ok_or_else(|| format!("`{}` isn't a valid .boozle identifier", svc)));
+    let svc = match claimed {
+      Ok(svc) => svc,
+      Err(error) => {
+        self.warn(location, format!("skipped, {}", error));
+        return Vec::new();
+      }
+    };
+    if service.has_options {
+      self.warn(location.clone(), "options ignored".to_string());
+    }
+
+    let members = service
+      .rpcs
+      .iter()
+      .filter_map(|rpc| self.rpc(location.as_str(), rpc))
+      .collect();
+    vec![
+      ast::Decl::Trait(ast::Trait {
+        attrs: Vec::new(),
+        name: ast::Id {
+          text: service.name.clone(),
+        },
+        members,
+      }),
+      ast::Decl::Svc(ast::Svc {
+        attrs: Vec::new(),
+        name: svc,
+        ty: named(service.name.as_str()),
+      }),
+    ]
+  }
+
+  fn streams(&self) -> Vec<ast::Decl> {
+    let sinks = self.sinks.iter().map(|item| ast::Trait {
+      attrs: Vec::new(),
+      name: ast::Id {
+        text: format!("{}Sink", item),
+      },
+      members: vec![ast::Fn {
+        attrs: Vec::new(),
+        name: ast::Id {
+          text: "send".to_string(),
+        },
+        params: vec![param("item", item.as_str())],
+        result: None,
+      }],
+    });
+    let sources = self.sources.iter().map(|item| ast::Trait {
+      attrs: Vec::new(),
+      name: ast::Id {
+        text: format!("{}Source", item),
+      },
+      members: vec![ast::Fn {
+        attrs: Vec::new(),
+        name: ast::Id {
+          text: "read".to_string(),
+        },
+        params: vec![param("sink", format!("{}Sink", item).as_str())],
+        result: None,
+      }],
+    });
+    sinks.chain(sources).map(ast::Decl::Trait).collect()
+  }
+}
+
+/// Imports the services of a parsed .proto file as a unit named `name`.
+pub fn import(name: String, file: &File) -> Import {
+  let mut importer = Importer {
+    file,
+    package: file.package.clone().unwrap_or_default(),
+    names: HashMap::new(),
+    types: Vec::new(),
+    sinks: Vec::new(),
+    sources: Vec::new(),
+    warnings: Vec::new(),
+  };
+
+  match file.syntax.as_deref() {
+    Some("proto3") => {}
+    Some(syntax) => importer.warn(
+      name.clone(),
+      format!("syntax is \"{}\", only proto3 is supported, importing anyway", syntax),
+    ),
+    None => importer.warn(
+      name.clone(),
+      "no syntax given, which means proto2, only proto3 is supported, importing anyway".to_string(),
+    ),
+  }
+  for extend in file.extends.iter() {
+    importer.warn(
+      importer.qualify(extend.trim_start_matches('.')),
+      "skipped, extensions can't be mapped".to_string(),
+    );
+  }
+
+  let services: Vec<ast::Decl> = file
+    .services
+    .iter()
+    .flat_map(|service| importer.service(service))
+    .collect();
+  let mut decls = std::mem::take(&mut importer.types);
+  decls.extend(importer.streams());
+  decls.extend(services);
+
+  let mut modules = Vec::new();
+  for component in importer.package.split('.').filter(|component| !component.is_empty()) {
+    match id(component) {
+      Some(module) => modules.push(module),
+      None => {
+        let location = importer.package.clone();
+        importer.warn(
+          location,
+          format!(
+            "`{}` isn't a valid .boozle identifier, package not mapped to modules",
+            component
+          ),
+        );
+        modules.clear();
+        break;
+      }
+    }
+  }
+  for module in modules.into_iter().rev() {
+    decls = vec![ast::Decl::Mod(ast::Mod {
+      attrs: Vec::new(),
+      name: module,
+      decls: Some(decls),
+    })];
+  }
+
+  Import {
+    unit: Unit { name, decls },
+    warnings: importer.warnings,
+  }
+}
+
+/// Like `import`, but parses the .proto source first.
+pub fn import_str(name: String, text: &str) -> Result<Import, ParseError> {
+  let file = parse(text)?;
+  Ok(import(name, &file))
+}
@@ -0,0 +1,437 @@
+//! A parser for the parts of a .proto file that matter to boozle: the package,
+//! the names of messages and enums, and services. Message bodies are skipped
+//! apart from nested declarations.
+
+#[derive(Debug)]
+pub struct ParseError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+/// A message type named in a rpc, e.g. `stream .foo.Bar`.
+#[derive(Debug)]
+pub struct MessageType {
+  pub name: String,
+  pub stream: bool,
+}
+
+#[derive(Debug)]
+pub struct Rpc {
+  pub name: String,
+  pub input: MessageType,
+  pub output: MessageType,
+  pub has_options: bool,
+}
+
+#[derive(Debug)]
+pub struct Service {
+  pub name: String,
+  pub rpcs: Vec<Rpc>,
+  pub has_options: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct File {
+  pub syntax: Option<String>,
+  pub package: Option<String>,
+  pub imports: Vec<String>,
+  /// Messages and enums by their name within the package, e.g. `Outer.Inner`.
+  pub messages: Vec<String>,
+  pub enums: Vec<String>,
+  pub services: Vec<Service>,
+  /// Top-level `extend` blocks, which are skipped.
+  pub extends: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+  Ident(String),
+  Str(String),
+  /// Numbers and anything else that's only ever skipped.
+  Other(String),
+  Punct(char),
+}
+
+fn lex(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+  let mut tokens = Vec::new();
+  let mut chars = text.chars().peekable();
+  let mut line = 1;
+  while let Some(&c) = chars.peek() {
+    match c {
+      '\n' => {
+        line += 1;
+        chars.next();
+      }
+      c if c.is_whitespace() => {
+        chars.next();
+      }
+      '/' => {
+        chars.next();
+        match chars.next() {
+          Some('/') => {
+            while let Some(&c) = chars.peek() {
+              if c == '\n' {
+                break;
+              }
+              chars.next();
+            }
+          }
+          Some('*') => {
+            let start = line;
+            let mut last = ' ';
+            loop {
+              match chars.next() {
+                Some('/') if last == '*' => break,
+                Some(c) => {
+                  if c == '\n' {
+                    line += 1;
+                  }
+                  last = c;
+                }
+                None => {
+                  return Err(ParseError {
+                    line: start,
+                    message: "unterminated comment".to_string(),
+                  })
+                }
+              }
+            }
+          }
+          _ => {
+            return Err(ParseError {
+              line,
+              message: "unexpected `/`".to_string(),
+            })
+          }
+        }
+      }
+      '"' | '\'' => {
+        chars.next();
+        let mut value = String::new();
+        loop {
+          match chars.next() {
+            Some(end) if end == c => break,
+            Some('\\') => {
+              if let Some(escaped) = chars.next() {
+                value.push('\\');
+                value.push(escaped);
+              }
+            }
+            Some('\n') | None => {
+              return Err(ParseError {
+                line,
+                message: "unterminated string".to_string(),
+              })
+            }
+            Some(c) => value.push(c),
+          }
+        }
+        tokens.push((Token::Str(value), line));
+      }
+      c if c.is_ascii_alphabetic() || c == '_' => {
+        let mut ident = String::new();
+        while let Some(&c) = chars.peek() {
+          if !(c.is_ascii_alphanumeric() || c == '_') {
+            break;
+          }
+          ident.push(c);
+          chars.next();
+        }
+        tokens.push((Token::Ident(ident), line));
+      }
+      c if c.is_ascii_digit() => {
+        let mut number = String::new();
+        while let Some(&c) = chars.peek() {
+          if !(c.is_ascii_alphanumeric() || c == '.' || c == '_') {
+            break;
+          }
+          number.push(c);
+          chars.next();
+        }
+        tokens.push((Token::Other(number), line));
+      }
+      c => {
+        tokens.push((Token::Punct(c), line));
+        chars.next();
+      }
+    }
+  }
+  Ok(tokens)
+}
+
+struct Parser {
+  tokens: Vec<(Token, usize)>,
+  position: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position).map(|(token, _)| token)
+  }
+
+  fn line(&self) -> usize {
+    match self.tokens.get(self.position).or_else(|| self.tokens.last()) {
+      Some((_, line)) => *line,
+      None => 1,
+    }
+  }
+
+  fn error<T>(&self, message: String) -> Result<T, ParseError> {
+    Err(ParseError {
+      line: self.line(),
+      message,
+    })
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.peek().cloned();
+    if token.is_some() {
+      self.position += 1;
+    }
+    token
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(&Token::Punct(c)) {
+      self.position += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    match self.peek() {
+      Some(Token::Ident(ident)) if ident == keyword => {
+        self.position += 1;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  fn expect(&mut self, c: char) -> Result<(), ParseError> {
+    if self.eat(c) {
+      Ok(())
+    } else {
+      self.error(format!("expected `{}`", c))
+    }
+  }
+
+  fn ident(&mut self) -> Result<String, ParseError> {
+    match self.peek().cloned() {
+      Some(Token::Ident(value)) => {
+        self.position += 1;
+        Ok(value)
+      }
+      _ => self.error("expected an identifier".to_string()),
+    }
+  }
+
+  fn string(&mut self) -> Result<String, ParseError> {
+    match self.peek().cloned() {
+      Some(Token::Str(value)) => {
+        self.position += 1;
+        Ok(value)
+      }
+      _ => self.error("expected a string".to_string()),
+    }
+  }
+
+  /// A dotted name such as `foo.Bar` or `.foo.Bar`.
+  fn full_ident(&mut self) -> Result<String, ParseError> {
+    let mut name = String::new();
+    if self.eat('.') {
+      name.push('.');
+    }
+    name.push_str(self.ident()?.as_str());
+    while self.eat('.') {
+      name.push('.');
+      name.push_str(self.ident()?.as_str());
+    }
+    Ok(name)
+  }
+
+  /// Skips a statement: everything up to a `;`, or up to and including a
+  /// block if the statement has one.
+  fn skip_statement(&mut self) -> Result<(), ParseError> {
+    let mut depth = 0usize;
+    loop {
+      match self.next() {
+        Some(Token::Punct(';')) if depth == 0 => return Ok(()),
+        Some(Token::Punct('{')) | Some(Token::Punct('[')) | Some(Token::Punct('(')) => depth += 1,
+        Some(Token::Punct(close @ '}')) | Some(Token::Punct(close @ ']')) | Some(Token::Punct(close @ ')')) => {
+          if depth == 0 {
+            self.position -= 1;
+            return self.error("unbalanced brackets".to_string());
+          }
+          depth -= 1;
+          if depth == 0 && close == '}' {
+            self.eat(';');
+            return Ok(());
+          }
+        }
+        Some(_) => {}
+        None => return self.error("unexpected end of file".to_string()),
+      }
+    }
+  }
+
+  fn message(&mut self, scope: &str, file: &mut File) -> Result<(), ParseError> {
+    let name = format!("{}{}", scope, self.ident()?);
+    self.expect('{')?;
+    file.messages.push(name.clone());
+    let scope = format!("{}.", name);
+    while !self.eat('}') {
+      if self.peek().is_none() {
+        return self.error("unexpected end of file".to_string());
+      }
+      if self.eat_keyword("message") {
+        self.message(scope.as_str(), file)?;
+      } else if self.eat_keyword("enum") {
+        self.enum_(scope.as_str(), file)?;
+      } else if !self.eat(';') {
+        self.skip_statement()?;
+      }
+    }
+    Ok(())
+  }
+
+  fn enum_(&mut self, scope: &str, file: &mut File) -> Result<(), ParseError> {
+    let name = format!("{}{}", scope, self.ident()?);
+    if self.peek() != Some(&Token::Punct('{')) {
+      return self.error("expected `{`".to_string());
+    }
+    file.enums.push(name);
+    self.skip_statement()
+  }
+
+  fn message_type(&mut self) -> Result<MessageType, ParseError> {
+    self.expect('(')?;
+    // `stream` is only a keyword if a type follows it.
+    let stream = match self.tokens.get(self.position + 1) {
+      Some((Token::Punct(')'), _)) => false,
+      _ => self.eat_keyword("stream"),
+    };
+    let name = self.full_ident()?;
+    self.expect(')')?;
+    Ok(MessageType { name, stream })
+  }
+
+  fn rpc(&mut self) -> Result<Rpc, ParseError> {
+    let name = self.ident()?;
+    let input = self.message_type()?;
+    if !self.eat_keyword("returns") {
+      return self.error("expected `returns`".to_string());
+    }
+    let output = self.message_type()?;
+    let has_options = if self.eat(';') {
+      false
+    } else if self.peek() == Some(&Token::Punct('{')) {
+      self.position += 1;
+      let mut has_options = false;
+      while !self.eat('}') {
+        if self.peek().is_none() {
+          return self.error("unexpected end of file".to_string());
+        }
+        if !self.eat(';') {
+          has_options = true;
+          self.skip_statement()?;
+        }
+      }
+      self.eat(';');
+      has_options
+    } else {
+      return self.error("expected `;` or `{`".to_string());
+    };
+    Ok(Rpc {
+      name,
+      input,
+      output,
+      has_options,
+    })
+  }
+
+  fn service(&mut self) -> Result<Service, ParseError> {
+    let name = self.ident()?;
+    self.expect('{')?;
+    let mut service = Service {
+      name,
+      rpcs: Vec::new(),
+      has_options: false,
+    };
+    while !self.eat('}') {
+      if self.eat_keyword("rpc") {
+        service.rpcs.push(self.rpc()?);
+      } else if self.eat_keyword("option") {
+        service.has_options = true;
+        self.skip_statement()?;
+      } else if !self.eat(';') {
+        return self.error("expected `rpc` or `option`".to_string());
+      }
+    }
+    Ok(service)
+  }
+
+  fn file(&mut self) -> Result<File, ParseError> {
+    let mut file = File::default();
+    while let Some(token) = self.next() {
+      let keyword = match token {
+        Token::Ident(keyword) => keyword,
+        Token::Punct(';') => continue,
+        _ => {
+          self.position -= 1;
+          return self.error("expected a declaration".to_string());
+        }
+      };
+      match keyword.as_str() {
+        "syntax" | "edition" => {
+          self.expect('=')?;
+          file.syntax = Some(self.string()?);
+          self.expect(';')?;
+        }
+        "package" => {
+          file.package = Some(self.full_ident()?);
+          self.expect(';')?;
+        }
+        "import" => {
+          if !self.eat_keyword("public") {
+            self.eat_keyword("weak");
+          }
+          file.imports.push(self.string()?);
+          self.expect(';')?;
+        }
+        "option" => self.skip_statement()?,
+        "message" => self.message("", &mut file)?,
+        "enum" => self.enum_("", &mut file)?,
+        "service" => {
+          let service = self.service()?;
+          file.services.push(service);
+        }
+        "extend" => {
+          file.extends.push(self.full_ident()?);
+          self.skip_statement()?;
+        }
+        _ => {
+          self.position -= 1;
+          return self.error(format!("unexpected `{}`", keyword));
+        }
+      }
+    }
+    Ok(file)
+  }
+}
+
+pub fn parse(text: &str) -> Result<File, ParseError> {
+  let mut parser = Parser {
+    tokens: lex(text)?,
+    position: 0,
+  };
+  parser.file()
+}
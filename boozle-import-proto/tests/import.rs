@@ -0,0 +1,181 @@
+use boozle_import_proto::{import_str, snake_case};
+
+const ROUTE_GUIDE: &str = r#"
+// Adapted from the gRPC route guide example.
+syntax = "proto3";
+
+option java_package = "io.grpc.examples.routeguide";
+
+package routeguide;
+
+import "google/protobuf/empty.proto";
+import "google/protobuf/timestamp.proto";
+
+service RouteGuide {
+  rpc GetFeature(Point) returns (Feature) {}
+  rpc ListFeatures(Rectangle) returns (stream Feature) {}
+  rpc RecordRoute(stream Point) returns (RouteSummary) {}
+  rpc RouteChat(stream RouteNote) returns (stream RouteNote) {
+    option deprecated = true;
+  }
+  rpc Reset(google.protobuf.Empty) returns (.google.protobuf.Empty);
+  rpc Now(google.protobuf.Empty) returns (google.protobuf.Timestamp);
+  rpc Kind(Point) returns (Feature.Kind);
+}
+
+message Point {
+  int32 latitude = 1;
+  int32 longitude = 2;
+}
+
+message Rectangle {
+  Point lo = 1;
+  Point hi = 2;
+}
+
+message Feature {
+  enum Kind {
+    UNKNOWN = 0;
+  }
+  string name = 1 [json_name = "name"];
+  oneof location {
+    Point point = 2;
+  }
+  map<string, int32> tags = 3;
+}
+
+message RouteNote {
+  Point location = 1;
+  string message = 2;
+}
+
+message RouteSummary {
+  int32 point_count = 1;
+  /* The elapsed time. */
+  int32 elapsed_time = 4;
+}
+"#;
+
+#[test]
+fn imports_services() {
+  let import = import_str("route_guide".to_string(), ROUTE_GUIDE).unwrap();
+  let text = import.unit.to_string();
+  assert_eq!(
+    text,
+    r#"mod routeguide {
+  #[proto(message = "routeguide.Point")]
+  type Point;
+
+  #[proto(message = "routeguide.Feature")]
+  type Feature;
+
+  #[proto(message = "routeguide.Rectangle")]
+  type Rectangle;
+
+  #[proto(message = "routeguide.RouteSummary")]
+  type RouteSummary;
+
+  #[proto(message = "routeguide.RouteNote")]
+  type RouteNote;
+
+  #[proto(message = "google.protobuf.Timestamp")]
+  type Timestamp;
+
+  trait FeatureSink {
+    fn send(item: Feature);
+  }
+
+  trait PointSink {
+    fn send(item: Point);
+  }
+
+  trait RouteNoteSink {
+    fn send(item: RouteNote);
+  }
+
+  trait PointSource {
+    fn read(sink: PointSink);
+  }
+
+  trait RouteNoteSource {
+    fn read(sink: RouteNoteSink);
+  }
+
+  trait RouteGuide {
+    fn get_feature(request: Point) -> Feature;
+    fn list_features(request: Rectangle responses: FeatureSink);
+    fn record_route(requests: PointSource) -> RouteSummary;
+    fn route_chat(requests: RouteNoteSource responses: RouteNoteSink);
+    fn reset();
+    fn now() -> Timestamp;
+  }
+
+  svc route_guide: RouteGuide;
+}
+"#
+  );
+  boozle_parser::parse("route_guide".to_string(), text.as_str()).unwrap();
+
+  let warnings: Vec<String> = import.warnings.iter().map(|warning| warning.to_string()).collect();
+  assert_eq!(
+    warnings,
+    [
+      "routeguide.RouteGuide.RouteChat: options ignored",
+      "routeguide.RouteGuide.Now: `google.protobuf.Timestamp` isn't defined in this file, declared as opaque type `Timestamp`",
+      "routeguide.RouteGuide.Kind: skipped, `Feature.Kind` is an enum, not a message",
+    ]
+  );
+}
+
+#[test]
+fn warns_about_collisions_and_proto2() {
+  let import = import_str(
+    "clash".to_string(),
+    r#"
+    package a.b;
+    message Inner {}
+    message Outer { message Inner {} }
+    message OuterInner {}
+    service Clash {
+      rpc First(Inner) returns (Outer.Inner);
+      rpc Second(.a.b.OuterInner) returns (b.Inner);
+      rpc Third(stream google.protobuf.Empty) returns (Inner);
+    }
+    "#,
+  )
+  .unwrap();
+  let text = import.unit.to_string();
+  assert!(text.starts_with("mod a {\n  mod b {\n"));
+  assert!(text.contains("    fn first(request: Inner) -> OuterInner;\n"));
+  assert!(!text.contains("fn second"));
+  assert!(!text.contains("fn third"));
+
+  let warnings: Vec<String> = import.warnings.iter().map(|warning| warning.to_string()).collect();
+  assert_eq!(
+    warnings,
+    [
+      "clash: no syntax given, which means proto2, only proto3 is supported, importing anyway",
+      "a.b.Clash.Second: skipped, message `a.b.Outer.Inner` and message `a.b.OuterInner` would both be named `OuterInner`",
+      "a.b.Clash.Third: skipped, streams of `google.protobuf.Empty` can't be mapped",
+    ]
+  );
+}
+
+#[test]
+fn reports_parse_errors() {
+  let err = import_str(
+    "bad".to_string(),
+    "syntax = \"proto3\";\n\nservice Broken {\n  rpc Get(A) (B);\n}\n",
+  )
+  .err()
+  .unwrap();
+  assert_eq!(err.to_string(), "line 4: expected `returns`");
+}
+
+#[test]
+fn converts_names_to_snake_case() {
+  assert_eq!(snake_case("GetFeature"), "get_feature");
+  assert_eq!(snake_case("GetHTTPStatus"), "get_http_status");
+  assert_eq!(snake_case("ListV2Items"), "list_v2_items");
+  assert_eq!(snake_case("already_snake"), "already_snake");
+}
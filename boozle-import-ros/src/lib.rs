@@ -0,0 +1,404 @@
+//! Converts the interfaces of a ROS package into a .boozle module.
+//!
+//! The package's services and actions become methods of a trait named after
+//! the package, e.g. `Turtlesim`, which is also declared as a `svc`. A
+//! request's fields become the method's parameters, and a response with one
+//! field becomes its result. Requests and responses that can't be taken
+//! apart like this are passed whole as opaque types, e.g. `SpawnRequest`.
+//!
+//! Messages are opaque to boozle, so each message is declared as
+//! `type Foo;`, tagged with its ROS 2 name as `#[ros(message = "...")]`, and
+//! needs a type mapping in the generated code. Arrays and `wstring` have no
+//! .boozle equivalent.
+//!
+//! An action becomes a method taking the goal and a sink for its feedback,
+//! and returning its result once the goal completes:
+//!
+//! ```text
+//! trait NavigateFeedbackSink {
+//!   fn send(item: NavigateFeedback);
+//! }
+//!
+//! trait Nav {
+//!   fn navigate(x: f64 y: f64 feedback: NavigateFeedbackSink) -> NavigateResult;
+//! }
+//! ```
+//!
+//! Cancelling a goal has no equivalent yet. Anything that can't be mapped is
+//! skipped or passed whole, and reported as a `Warning`.
+
+mod parse;
+
+pub use parse::{load, parse, Field, FieldType, Interface, Kind, LoadError, Package, ParseError};
+
+pub use boozle_import::{id, snake_case, Import, Warning};
+
+use boozle_import::{named, param};
+use boozle_parser::ast;
+use boozle_parser::Unit;
+use std::collections::HashMap;
+
+/// Converts a package name such as `nav2_msgs` to `Nav2Msgs`.
+pub fn camel_case(name: &str) -> String {
+  name
+    .split('_')
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+      }
+    })
+    .collect()
+}
+
+/// The .boozle type of a ROS built-in type.
+fn builtin(base: &str) -> Option<&'static str> {
+  Some(match base {
+    "bool" => "bool",
+    "byte" | "char" | "uint8" => "u8",
+    "int8" => "i8",
+    "uint16" => "u16",
+    "int16" => "i16",
+    "uint32" => "u32",
+    "int32" => "i32",
+    "uint64" => "u64",
+    "int64" => "i64",
+    "float32" => "f32",
+    "float64" => "f64",
+    "string" => "String",
+    _ => return None,
+  })
+}
+
+/// The ROS 2 name of a message type used by package `package`, e.g.
+/// `geometry_msgs/msg/Point`.
+fn message_name(package: &str, base: &str) -> String {
+  // ROS 1 built-ins, which are messages in ROS 2.
+  match base {
+    "Header" => return "std_msgs/msg/Header".to_string(),
+    "time" => return "builtin_interfaces/msg/Time".to_string(),
+    "duration" => return "builtin_interfaces/msg/Duration".to_string(),
+    _ => {}
+  }
+  let parts: Vec<&str> = base.split('/').collect();
+  match parts.as_slice() {
+    [name] => format!("{}/msg/{}", package, name),
+    [package, name] => format!("{}/msg/{}", package, name),
+    _ => base.to_string(),
+  }
+}
+
+/// A field type resolved to a .boozle type.
+struct Resolved {
+  name: String,
+  /// The ROS 2 name of the message, unless it's a built-in type.
+  message: Option<String>,
+}
+
+struct Importer<'a> {
+  package: &'a Package,
+  /// What each declared .boozle name stands for, to catch collisions.
+  names: HashMap<String, String>,
+  types: Vec<ast::Decl>,
+  sinks: Vec<String>,
+  methods: Vec<String>,
+  warnings: Vec<Warning>,
+}
+
+impl<'a> Importer<'a> {
+  fn warn(&mut self, location: String, message: String) {
+    self.warnings.push(Warning { location, message });
+  }
+
+  /// Checks that `name` is free for `what`, or already taken by it.
+  fn check(&self, name: &str, what: &str) -> Result<(), String> {
+    if id(name).is_none() {
+      return Err(format!("`{}` isn't a valid .boozle identifier", name));
+    }
+    match self.names.get(name) {
+      Some(existing) if existing != what => Err(format!("{} and {} would both be named `{}`", existing, what, name)),
+      _ => Ok(()),
+    }
+  }
+
+  fn resolve(&self, ty: &FieldType) -> Result<Resolved, String> {
+    if ty.array {
+      return Err(format!("`{}` has no .boozle equivalent", ty));
+    }
+    if let Some(name) = builtin(ty.base.as_str()) {
+      return Ok(Resolved {
+        name: name.to_string(),
+        message: None,
+      });
+    }
+    if ty.base == "wstring" {
+      return Err(format!("`{}` has no .boozle equivalent", ty));
+    }
+    let message = message_name(self.package.name.as_str(), ty.base.as_str());
+    let name = message.rsplit('/').next().unwrap_or_default().to_string();
+    self.check(name.as_str(), format!("`{}`", message).as_str())?;
+    Ok(Resolved {
+      name,
+      message: Some(message),
+    })
+  }
+
+  /// Declares `name` as an opaque type for the ROS type `message`.
+  fn declare(&mut self, name: &str, message: &str) -> Result<(), String> {
+    let what = format!("`{}`", message);
+    self.check(name, what.as_str())?;
+    if self.names.contains_key(name) {
+      return Ok(());
+    }
+    self.names.insert(name.to_string(), what);
+    self.types.push(ast::Decl::Type(ast::TypeDecl {
+      attrs: vec![ast::Attr {
+        name: ast::Id {
+          text: "ros".to_string(),
+        },
+        args: vec![ast::AttrArg {
+          key: ast::Id {
+            text: "message".to_string(),
+          },
+          value: message.to_string(),
+        }],
+      }],
+      name: ast::Id { text: name.to_string() },
+    }));
+    Ok(())
+  }
+
+  fn use_type(&mut self, ty: &Resolved) -> Result<(), String> {
+    match &ty.message {
+      Some(message) => self.declare(ty.name.as_str(), message.as_str()),
+      None => Ok(()),
+    }
+  }
+
+  /// The .boozle name and ROS 2 name of a section of `interface` passed whole,
+  /// e.g. `SpawnRequest` and `turtlesim/srv/Spawn_Request`.
+  fn section_type(&self, interface: &Interface, section: &str) -> (String, String) {
+    (
+      format!("{}{}", interface.name, section),
+      format!(
+        "{}/{}/{}_{}",
+        self.package.name,
+        interface.kind.dir(),
+        interface.name,
+        section
+      ),
+    )
+  }
+
+  /// Maps the fields of a request or goal to parameters. If any can't be
+  /// mapped, the whole section is taken as a parameter named `whole`.
+  /// `reserved` are names the method uses for other parameters.
+  fn params(
+    &mut self,
+    location: &str,
+    interface: &Interface,
+    section: &str,
+    whole: &str,
+    reserved: &[&str],
+  ) -> Result<Vec<ast::Param>, String> {
+    let fields = &interface.sections[0];
+    let mut resolved = Vec::new();
+    let mut problem = None;
+    for field in fields.iter() {
+      if id(field.name.as_str()).is_none() || reserved.contains(&field.name.as_str()) {
+        problem = Some(format!("field `{}` can't be a parameter name", field.name));
+        break;
+      }
+      match self.resolve(&field.ty) {
+        Ok(ty) => resolved.push((field.name.as_str(), ty)),
+        Err(error) => {
+          problem = Some(format!("field `{}`: {}", field.name, error));
+          break;
+        }
+      }
+    }
+
+    match problem {
+      None => {
+        let mut params = Vec::new();
+        for (name, ty) in resolved {
+          self.use_type(&ty)?;
+          params.push(param(name, ty.name.as_str()));
+        }
+        Ok(params)
+      }
+      Some(problem) => {
+        let (name, message) = self.section_type(interface, section);
+        self.declare(name.as_str(), message.as_str())?;
+        self.warn(
+          location.to_string(),
+          format!("{}, taking the {} as opaque type `{}`", problem, whole, name),
+        );
+        Ok(vec![param(whole, name.as_str())])
+      }
+    }
+  }
+
+  /// Maps a response or result to the method's result: nothing if it's empty,
+  /// its field if it has one, and otherwise the whole section.
+  fn result(&mut self, interface: &Interface, section: &str) -> Result<Option<ast::Type>, String> {
+    let fields = &interface.sections[1];
+    if fields.is_empty() {
+      return Ok(None);
+    }
+    if let [field] = fields.as_slice() {
+      if let Ok(ty) = self.resolve(&field.ty) {
+        self.use_type(&ty)?;
+        return Ok(Some(named(ty.name.as_str())));
+      }
+    }
+    let (name, message) = self.section_type(interface, section);
+    self.declare(name.as_str(), message.as_str())?;
+    Ok(Some(named(name.as_str())))
+  }
+
+  fn feedback(&mut self, interface: &Interface) -> Result<String, String> {
+    let (item, message) = self.section_type(interface, "Feedback");
+    let name = format!("{}Sink", item);
+    let what = format!("the sink of `{}`", message);
+    self.check(name.as_str(), what.as_str())?;
+    self.declare(item.as_str(), message.as_str())?;
+    if !self.names.contains_key(&name) {
+      self.names.insert(name.clone(), what);
+      self.sinks.push(item);
+    }
+    Ok(name)
+  }
+
+  fn method(&mut self, interface: &Interface) -> Option<ast::Fn> {
+    let location = format!("{}/{}/{}", self.package.name, interface.kind.dir(), interface.name);
+    let name = match id(snake_case(interface.name.as_str()).as_str()) {
+      Some(name) => name,
+      None => {
+        self.warn(location, "skipped, name isn't a valid .boozle identifier".to_string());
+        return None;
+      }
+    };
+    if self.methods.contains(&name.text) {
+      self.warn(
+        location,
+        format!("skipped, another interface is also named `{}`", name.text),
+      );
+      return None;
+    }
+    self.methods.push(name.text.clone());
+
+    let signature = match interface.kind {
+      Kind::Srv => self
+        .params(location.as_str(), interface, "Request", "request", &[])
+        .and_then(|params| Ok((params, self.result(interface, "Response")?))),
+      Kind::Action => self
+        .params(location.as_str(), interface, "Goal", "goal", &["feedback"])
+        .and_then(|mut params| {
+          params.push(param("feedback", self.feedback(interface)?.as_str()));
+          Ok((params, self.result(interface, "Result")?))
+        }),
+      Kind::Msg => return None,
+    };
+    match signature {
+      Ok((params, result)) => Some(ast::Fn {
+        attrs: Vec::new(),
+        name,
+        params,
+        result,
+      }),
+      Err(error) => {
+        self.warn(location, format!("skipped, {}", error));
+        None
+      }
+    }
+  }
+
+  fn sinks(&self) -> Vec<ast::Decl> {
+    self
+      .sinks
+      .iter()
+      .map(|item| {
+        ast::Decl::Trait(ast::Trait {
+          attrs: Vec::new(),
+          name: ast::Id {
+            text: format!("{}Sink", item),
+          },
+          members: vec![ast::Fn {
+            attrs: Vec::new(),
+            name: ast::Id {
+              text: "send".to_string(),
+            },
+            params: vec![param("item", item.as_str())],
+            result: None,
+          }],
+        })
+      })
+      .collect()
+  }
+}
+
+/// Imports the interfaces of a loaded ROS package as a unit named after it.
+pub fn import(package: &Package) -> Import {
+  let mut importer = Importer {
+    package,
+    names: HashMap::new(),
+    types: Vec::new(),
+    sinks: Vec::new(),
+    methods: Vec::new(),
+    warnings: Vec::new(),
+  };
+
+  for interface in package
+    .interfaces
+    .iter()
+    .filter(|interface| interface.kind == Kind::Msg)
+  {
+    let message = format!("{}/msg/{}", package.name, interface.name);
+    if let Err(error) = importer.declare(interface.name.as_str(), message.as_str()) {
+      importer.warn(message, format!("skipped, {}", error));
+    }
+  }
+
+  let members: Vec<ast::Fn> = package
+    .interfaces
+    .iter()
+    .filter_map(|interface| importer.method(interface))
+    .collect();
+
+  let mut decls = std::mem::take(&mut importer.types);
+  decls.extend(importer.sinks());
+  if package.interfaces.iter().any(|interface| interface.kind != Kind::Msg) {
+    let trait_name = camel_case(package.name.as_str());
+    let what = format!("package `{}`", package.name);
+    let names = importer.check(trait_name.as_str(), what.as_str()).and_then(|_| {
+      id(package.name.as_str()).ok_or_else(|| format!("`{}` isn't a valid .boozle identifier", package.name))
+    });
+    match names {
+      Ok(svc) => {
+        decls.push(ast::Decl::Trait(ast::Trait {
+          attrs: Vec::new(),
+          name: ast::Id {
+            text: trait_name.clone(),
+          },
+          members,
+        }));
+        decls.push(ast::Decl::Svc(ast::Svc {
+          attrs: Vec::new(),
+          name: svc,
+          ty: named(trait_name.as_str()),
+        }));
+      }
+      Err(error) => importer.warn(package.name.clone(), format!("services and actions skipped, {}", error)),
+    }
+  }
+
+  Import {
+    unit: Unit {
+      name: package.name.clone(),
+      decls,
+    },
+    warnings: importer.warnings,
+  }
+}
@@ -0,0 +1,21 @@
+use std::path::Path;
+use std::process::exit;
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.is_empty() || args.len() > 2 {
+    eprintln!("usage: boozle-import-ros <package dir> [output.boozle]");
+    exit(2);
+  }
+
+  let package = match boozle_import_ros::load(Path::new(&args[0])) {
+    Ok(package) => package,
+    Err(err) => {
+      eprintln!("{}", err);
+      exit(1);
+    }
+  };
+  let import = boozle_import_ros::import(&package);
+
+  import.write(args.get(1).map(String::as_str));
+}
@@ -0,0 +1,225 @@
+//! Reads ROS interface definitions: `.msg`, `.srv` and `.action` files, which
+//! are the same in ROS 1 and ROS 2 apart from a few built-in types.
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub struct ParseError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+  Io(PathBuf, std::io::Error),
+  Parse(PathBuf, ParseError),
+}
+
+impl std::fmt::Display for LoadError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+      Self::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+    }
+  }
+}
+
+/// The type of a field as written, e.g. `geometry_msgs/Point[<=3]`.
+#[derive(Debug)]
+pub struct FieldType {
+  /// The type without array or string bounds, e.g. `geometry_msgs/Point`.
+  pub base: String,
+  pub array: bool,
+}
+
+impl std::fmt::Display for FieldType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.base)?;
+    if self.array {
+      write!(f, "[]")?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug)]
+pub struct Field {
+  pub ty: FieldType,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+  Msg,
+  Srv,
+  Action,
+}
+
+impl Kind {
+  /// The directory and extension of interfaces of this kind.
+  pub fn dir(&self) -> &'static str {
+    match self {
+      Self::Msg => "msg",
+      Self::Srv => "srv",
+      Self::Action => "action",
+    }
+  }
+
+  /// How many `---` separated sections a file of this kind has.
+  fn sections(&self) -> usize {
+    match self {
+      Self::Msg => 1,
+      Self::Srv => 2,
+      Self::Action => 3,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Interface {
+  pub name: String,
+  pub kind: Kind,
+  /// The fields of each section. Messages have one, services a request and a
+  /// response, and actions a goal, a result and feedback.
+  pub sections: Vec<Vec<Field>>,
+}
+
+#[derive(Debug)]
+pub struct Package {
+  pub name: String,
+  pub interfaces: Vec<Interface>,
+}
+
+fn is_name(text: &str) -> bool {
+  matches!(text.chars().next(), Some(first) if first.is_ascii_alphabetic())
+    && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn field_type(text: &str) -> Option<FieldType> {
+  let (base, array) = match text.find('[') {
+    Some(open) if text.ends_with(']') => {
+      let bound = &text[open + 1..text.len() - 1];
+      let bound = bound.strip_prefix("<=").unwrap_or(bound);
+      if !bound.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+      }
+      (&text[..open], true)
+    }
+    Some(_) => return None,
+    None => (text, false),
+  };
+  // Bounded strings, e.g. `string<=10`.
+  let base = match base.find("<=") {
+    Some(bound) => &base[..bound],
+    None => base,
+  };
+  if !base.split('/').all(is_name) {
+    return None;
+  }
+  Some(FieldType {
+    base: base.to_string(),
+    array,
+  })
+}
+
+/// Parses the fields of an interface file of `kind`. Constants are skipped.
+pub fn parse(kind: Kind, text: &str) -> Result<Vec<Vec<Field>>, ParseError> {
+  let mut sections = vec![Vec::new()];
+  for (i, line) in text.lines().enumerate() {
+    let error = |message: String| ParseError { line: i + 1, message };
+    let line = match line.find('#') {
+      Some(comment) => &line[..comment],
+      None => line,
+    }
+    .trim();
+    if line.is_empty() {
+      continue;
+    }
+    if line == "---" {
+      sections.push(Vec::new());
+      continue;
+    }
+
+    let mut words = line.split_whitespace();
+    let ty = words.next().unwrap_or_default();
+    let rest = words.collect::<Vec<&str>>().join(" ");
+    if rest.is_empty() {
+      return Err(error("expected a type and a name".to_string()));
+    }
+    let ty = field_type(ty).ok_or_else(|| error(format!("invalid type `{}`", ty)))?;
+    let end = rest.find(|c: char| c == '=' || c.is_whitespace()).unwrap_or(rest.len());
+    let (name, after) = rest.split_at(end);
+    // Constants, e.g. `uint8 RED=0`. Anything else after the name is a
+    // default value.
+    if after.trim_start().starts_with('=') {
+      continue;
+    }
+    if !is_name(name) {
+      return Err(error(format!("invalid field name `{}`", name)));
+    }
+    sections.last_mut().unwrap().push(Field {
+      ty,
+      name: name.to_string(),
+    });
+  }
+
+  if sections.len() != kind.sections() {
+    return Err(ParseError {
+      line: text.lines().count(),
+      message: format!(
+        "expected {} sections separated by `---`, found {}",
+        kind.sections(),
+        sections.len()
+      ),
+    });
+  }
+  Ok(sections)
+}
+
+/// Loads the interfaces of the ROS package at `dir`, from its `msg`, `srv`
+/// and `action` directories. The package is named after `dir`.
+pub fn load<P: AsRef<Path>>(dir: P) -> Result<Package, LoadError> {
+  let dir = dir.as_ref();
+  let name = std::fs::canonicalize(dir)
+    .map_err(|err| LoadError::Io(dir.to_path_buf(), err))?
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+
+  let mut interfaces = Vec::new();
+  for kind in [Kind::Msg, Kind::Srv, Kind::Action].iter() {
+    let kind_dir = dir.join(kind.dir());
+    if !kind_dir.is_dir() {
+      continue;
+    }
+    let mut paths = Vec::new();
+    let entries = std::fs::read_dir(&kind_dir).map_err(|err| LoadError::Io(kind_dir.clone(), err))?;
+    for entry in entries {
+      let path = entry.map_err(|err| LoadError::Io(kind_dir.clone(), err))?.path();
+      if path.extension().and_then(|extension| extension.to_str()) == Some(kind.dir()) {
+        paths.push(path);
+      }
+    }
+    paths.sort();
+
+    for path in paths {
+      let text = std::fs::read_to_string(&path).map_err(|err| LoadError::Io(path.clone(), err))?;
+      let sections = parse(*kind, text.as_str()).map_err(|err| LoadError::Parse(path.clone(), err))?;
+      interfaces.push(Interface {
+        name: path
+          .file_stem()
+          .map(|stem| stem.to_string_lossy().to_string())
+          .unwrap_or_default(),
+        kind: *kind,
+        sections,
+      });
+    }
+  }
+  Ok(Package { name, interfaces })
+}
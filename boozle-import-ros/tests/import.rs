@@ -0,0 +1,130 @@
+use boozle_import_ros::{import, load, parse, Kind};
+use std::path::PathBuf;
+
+/// Writes a ROS package named `name` from (path, text) pairs.
+fn package(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = std::env::temp_dir()
+    .join(format!("boozle-import-ros-{}", std::process::id()))
+    .join(name);
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, text) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+  }
+  dir
+}
+
+#[test]
+fn imports_services_and_actions() {
+  let dir = package(
+    "turtlesim",
+    &[
+      ("package.xml", "<package/>\n"),
+      ("msg/Pose.msg", "float32 x\nfloat32 y\nfloat32 theta\n"),
+      (
+        "msg/Color.msg",
+        "# An RGB color.\nuint8 RED=0\nuint8 r\nuint8 g\nuint8 b 255\n",
+      ),
+      ("srv/Kill.srv", "string name\n---\n"),
+      (
+        "srv/Spawn.srv",
+        "float32 x\nfloat32 y\nfloat32 theta\nstring<=32 name # Optional.\n---\nstring name\n",
+      ),
+      ("srv/SetPen.srv", "Color color\nuint8 width\nbool off\n---\n"),
+      (
+        "srv/Teleport.srv",
+        "geometry_msgs/Pose2D[] path\n---\nPose pose\nHeader header\n",
+      ),
+      (
+        "action/RotateAbsolute.action",
+        "float32 theta\n---\nfloat32 delta\n---\nfloat32 remaining\n",
+      ),
+    ],
+  );
+  let package = load(&dir).unwrap();
+  let import = import(&package);
+  let text = import.unit.to_string();
+  assert_eq!(import.unit.name, "turtlesim");
+  assert_eq!(
+    text,
+    r#"#[ros(message = "turtlesim/msg/Color")]
+type Color;
+
+#[ros(message = "turtlesim/msg/Pose")]
+type Pose;
+
+#[ros(message = "turtlesim/srv/Teleport_Request")]
+type TeleportRequest;
+
+#[ros(message = "turtlesim/srv/Teleport_Response")]
+type TeleportResponse;
+
+#[ros(message = "turtlesim/action/RotateAbsolute_Feedback")]
+type RotateAbsoluteFeedback;
+
+trait RotateAbsoluteFeedbackSink {
+  fn send(item: RotateAbsoluteFeedback);
+}
+
+trait Turtlesim {
+  fn kill(name: String);
+  fn set_pen(color: Color width: u8 off: bool);
+  fn spawn(x: f32 y: f32 theta: f32 name: String) -> String;
+  fn teleport(request: TeleportRequest) -> TeleportResponse;
+  fn rotate_absolute(theta: f32 feedback: RotateAbsoluteFeedbackSink) -> f32;
+}
+
+svc turtlesim: Turtlesim;
+"#
+  );
+  boozle_parser::parse("turtlesim".to_string(), text.as_str()).unwrap();
+
+  let warnings: Vec<String> = import.warnings.iter().map(|warning| warning.to_string()).collect();
+  assert_eq!(
+    warnings,
+    ["turtlesim/srv/Teleport: field `path`: `geometry_msgs/Pose2D[]` has no .boozle equivalent, taking the request as opaque type `TeleportRequest`"]
+  );
+}
+
+#[test]
+fn warns_about_collisions() {
+  let dir = package(
+    "nav",
+    &[
+      ("msg/Goal.msg", "float64 x\n"),
+      ("srv/Go.srv", "other_msgs/Goal target\n---\n"),
+      ("srv/Stop.srv", "---\n"),
+      ("action/Stop.action", "---\n---\n"),
+      ("action/Follow.action", "string feedback\n---\n---\nfloat64 distance\n"),
+    ],
+  );
+  let import = import(&load(&dir).unwrap());
+  let text = import.unit.to_string();
+  assert!(text.contains(
+    "trait Nav {\n  fn go(request: GoRequest);\n  fn stop();\n  fn follow(goal: FollowGoal feedback: FollowFeedbackSink);\n}\n"
+  ));
+
+  let warnings: Vec<String> = import.warnings.iter().map(|warning| warning.to_string()).collect();
+  assert_eq!(
+    warnings,
+    [
+      "nav/srv/Go: field `target`: `nav/msg/Goal` and `other_msgs/msg/Goal` would both be named `Goal`, taking the request as opaque type `GoRequest`",
+      "nav/action/Follow: field `feedback` can't be a parameter name, taking the goal as opaque type `FollowGoal`",
+      "nav/action/Stop: skipped, another interface is also named `stop`",
+    ]
+  );
+}
+
+#[test]
+fn reports_parse_errors() {
+  let err = parse(Kind::Srv, "int32 a\nint32 b\n").err().unwrap();
+  assert_eq!(
+    err.to_string(),
+    "line 2: expected 2 sections separated by `---`, found 1"
+  );
+
+  let dir = package("broken", &[("msg/Bad.msg", "int32 a\nint32[x] b\n")]);
+  let err = load(&dir).err().unwrap().to_string();
+  assert!(err.ends_with("Bad.msg: line 2: invalid type `int32[x]`"), "{}", err);
+}
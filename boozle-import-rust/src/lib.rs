@@ -0,0 +1,487 @@
+//! Derives .boozle declarations from Rust source.
+//!
+//! Traits annotated with `#[boozle::export]` become .boozle traits, and
+//! `#[boozle::export(svc = "name")]` additionally declares a service of that
+//! trait. Inline modules containing exported traits are mirrored as .boozle
+//! modules. Generic types of literals, e.g. `Vec<u8>`, are declared as opaque
+//! types tagged with `#[rust(type = "...")]`. Anything that can't be expressed
+//! in .boozle is skipped and reported as a `Warning`.
+
+pub use boozle_import::{Import, Warning};
+
+use boozle_parser::ast;
+use boozle_parser::Unit;
+use quote::ToTokens;
+use std::collections::HashSet;
+
+/// Returns `text` as a .boozle identifier, or `None` if the grammar wouldn't
+/// accept it. Raw identifiers such as `r#match` lose their prefix.
+pub fn id(text: &str) -> Option<ast::Id> {
+  boozle_parser::id(text.trim_start_matches("r#"))
+}
+
+/// Formats a type the way it was written, for use in messages.
+pub fn type_name(ty: &syn::Type) -> String {
+  ty.to_token_stream().to_string().replace(' ', "")
+}
+
+/// Maps a Rust type onto the equivalent .boozle type.
+///
+/// `object` decides whether a type refers to a boozle object, returning the
+/// name of its trait if so. Other types must be plain paths without generic
+/// arguments. References are mapped to the type they refer to, and `str` to
+/// `String`, since both serialize the same way.
+pub fn map_type<F>(ty: &syn::Type, object: &F) -> Option<ast::Type>
+where
+  F: Fn(&syn::Type) -> Option<String>,
+{
+  if let Some(name) = object(ty) {
+    return Some(ast::Type {
+      path: ast::Path {
+        components: vec![id(name.as_str())?],
+      },
+    });
+  }
+
+  match ty {
+    syn::Type::Reference(reference) => map_type(&reference.elem, object),
+    syn::Type::Paren(paren) => map_type(&paren.elem, object),
+    syn::Type::Group(group) => map_type(&group.elem, object),
+    syn::Type::Path(path) if path.qself.is_none() => {
+      if path.path.is_ident("str") {
+        return Some(ast::Type {
+          path: ast::Path {
+            components: vec![id("String")?],
+          },
+        });
+      }
+      let mut components = Vec::new();
+      for segment in path.path.segments.iter() {
+        if !segment.arguments.is_empty() {
+          return None;
+        }
+        components.push(id(segment.ident.to_string().as_str())?);
+      }
+      Some(ast::Type {
+        path: ast::Path { components },
+      })
+    }
+    _ => None,
+  }
+}
+
+/// The arguments of a `#[boozle::export]` attribute.
+struct Export {
+  svc: Option<String>,
+}
+
+fn is_export_path(path: &syn::Path) -> bool {
+  let segments: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+  match segments.as_slice() {
+    [boozle, export] => boozle == "boozle" && export == "export",
+    [export] => export == "export",
+    _ => false,
+  }
+}
+
+fn parse_export(attrs: &[syn::Attribute]) -> Result<Option<Export>, String> {
+  for attr in attrs.iter() {
+    if !is_export_path(&attr.path) {
+      continue;
+    }
+    let mut export = Export { svc: None };
+    match attr.parse_meta().map_err(|err| err.to_string())? {
+      syn::Meta::Path(_) => {}
+      syn::Meta::List(list) => {
+        for nested in list.nested.iter() {
+          match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(pair)) if pair.path.is_ident("svc") => match &pair.lit {
+              syn::Lit::Str(svc) => export.svc = Some(svc.value()),
+              _ => return Err("`svc` must be a string".to_string()),
+            },
+            _ => return Err("unknown argument, expected `svc = \"...\"`".to_string()),
+          }
+        }
+      }
+      syn::Meta::NameValue(_) => return Err("expected `#[boozle::export(svc = \"...\")]`".to_string()),
+    }
+    return Ok(Some(export));
+  }
+  Ok(None)
+}
+
+/// Returns the trait named by a type that refers to an object, such as
+/// `Box<dyn Foo>`, `Arc<dyn Foo>`, `impl Foo` or `FooClient`.
+fn object_trait(traits: &HashSet<String>, ty: &syn::Type) -> Option<String> {
+  let bound_trait = |bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>| {
+    bounds.iter().find_map(|bound| match bound {
+      syn::TypeParamBound::Trait(bound) => {
+        let name = bound.path.segments.last()?.ident.to_string();
+        if traits.contains(&name) {
+          Some(name)
+        } else {
+          None
+        }
+      }
+      _ => None,
+    })
+  };
+
+  match ty {
+    syn::Type::TraitObject(object) => bound_trait(&object.bounds),
+    syn::Type::ImplTrait(impl_trait) => bound_trait(&impl_trait.bounds),
+    syn::Type::Path(path) if path.qself.is_none() => {
+      let last = path.path.segments.last()?;
+      let name = last.ident.to_string();
+      match &last.arguments {
+        syn::PathArguments::None => {
+          let name = name.strip_suffix("Client")?;
+          if traits.contains(name) {
+            Some(name.to_string())
+          } else {
+            None
+          }
+        }
+        // Smart pointers to trait objects, e.g. `Box<dyn Foo>`.
+        syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+          match arguments.args.first()? {
+            syn::GenericArgument::Type(inner @ syn::Type::TraitObject(_)) => object_trait(traits, inner),
+            _ => None,
+          }
+        }
+        _ => None,
+      }
+    }
+    _ => None,
+  }
+}
+
+/// Extracts the result of a method. `Result<T, _>` maps to `T`, and `()` to no
+/// result at all.
+fn result_type(output: &syn::ReturnType) -> Option<&syn::Type> {
+  let ty = match output {
+    syn::ReturnType::Default => return None,
+    syn::ReturnType::Type(_, ty) => ty.as_ref(),
+  };
+  let inner = match ty {
+    syn::Type::Path(path) => match path.path.segments.last() {
+      Some(segment) if segment.ident == "Result" => match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+          Some(syn::GenericArgument::Type(inner)) => inner,
+          _ => ty,
+        },
+        _ => ty,
+      },
+      _ => ty,
+    },
+    _ => ty,
+  };
+  match inner {
+    syn::Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+    inner => Some(inner),
+  }
+}
+
+struct Importer {
+  traits: HashSet<String>,
+  /// The opaque types used by the scope being imported.
+  types: Vec<ast::TypeDecl>,
+  warnings: Vec<Warning>,
+}
+
+impl Importer {
+  fn warn(&mut self, location: String, message: String) {
+    self.warnings.push(Warning { location, message });
+  }
+
+  fn map(&mut self, ty: &syn::Type) -> Result<ast::Type, String> {
+    let traits = &self.traits;
+    if let Some(mapped) = map_type(ty, &|ty: &syn::Type| object_trait(traits, ty)) {
+      return Ok(mapped);
+    }
+    self
+      .opaque(ty)
+      .ok_or_else(|| format!("`{}` has no .boozle equivalent", type_name(ty)))
+  }
+
+  /// Declares a generic type such as `Vec<u8>` as an opaque type named after
+  /// it, e.g. `VecU8`, in the current scope.
+  fn opaque(&mut self, ty: &syn::Type) -> Option<ast::Type> {
+    let generic = match ty {
+      syn::Type::Path(path) => path.path.segments.iter().any(|segment| !segment.arguments.is_empty()),
+      _ => false,
+    };
+    if !generic {
+      return None;
+    }
+    let name = id(self.opaque_name(ty)?.as_str())?;
+    if !self.types.iter().any(|decl| decl.name.text == name.text) {
+      self.types.push(ast::TypeDecl {
+        attrs: vec![ast::Attr {
+          name: ast::Id {
+            text: "rust".to_string(),
+          },
+          args: vec![ast::AttrArg {
+            key: ast::Id {
+              text: "type".to_string(),
+            },
+            value: type_name(ty),
+          }],
+        }],
+        name: ast::Id {
+          text: name.text.clone(),
+        },
+      });
+    }
+    Some(ast::Type {
+      path: ast::Path {
+        components: vec![name],
+      },
+    })
+  }
+
+  /// Names a type made only of paths to literal types, e.g. `HashMapStringU32`
+  /// for `HashMap<String, u32>`. Objects can't be nested in literals.
+  fn opaque_name(&self, ty: &syn::Type) -> Option<String> {
+    if object_trait(&self.traits, ty).is_some() {
+      return None;
+    }
+    let last = match ty {
+      syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+      _ => return None,
+    };
+    let ident = last.ident.to_string();
+    let mut chars = ident.chars();
+    let mut name: String = chars.next()?.to_uppercase().chain(chars).collect();
+    match &last.arguments {
+      syn::PathArguments::None => {}
+      syn::PathArguments::AngleBracketed(arguments) => {
+        for argument in arguments.args.iter() {
+          match argument {
+            syn::GenericArgument::Type(ty) => name.push_str(self.opaque_name(ty)?.as_str()),
+            _ => return None,
+          }
+        }
+      }
+      syn::PathArguments::Parenthesized(_) => return None,
+    }
+    Some(name)
+  }
+
+  fn method(&mut self, location: &str, method: &syn::TraitItemMethod) -> Option<ast::Fn> {
+    let sig = &method.sig;
+    let location = format!("{}::{}", location, sig.ident);
+    match sig.receiver() {
+      Some(syn::FnArg::Receiver(receiver)) if receiver.reference.is_some() => {}
+      _ => {
+        self.warn(location, "skipped, methods must take `&self` or `&mut self`".to_string());
+        return None;
+      }
+    }
+    if !sig.generics.params.is_empty() {
+      self.warn(location, "skipped, generic methods can't be mapped".to_string());
+      return None;
+    }
+    let name = match id(sig.ident.to_string().as_str()) {
+      Some(name) => name,
+      None => {
+        self.warn(location, "skipped, name isn't a valid .boozle identifier".to_string());
+        return None;
+      }
+    };
+
+    let mut params = Vec::new();
+    let mut errors = Vec::new();
+    for input in sig.inputs.iter() {
+      let typed = match input {
+        syn::FnArg::Typed(typed) => typed,
+        syn::FnArg::Receiver(_) => continue,
+      };
+      let param_name = match typed.pat.as_ref() {
+        syn::Pat::Ident(pat) => id(pat.ident.to_string().as_str()),
+        _ => None,
+      };
+      let param_name = match param_name {
+        Some(param_name) => param_name,
+        None => {
+          errors.push(format!(
+            "parameter `{}` isn't a valid .boozle identifier",
+            typed.pat.to_token_stream()
+          ));
+          continue;
+        }
+      };
+      match self.map(&typed.ty) {
+        Ok(ty) => params.push(ast::Param { name: param_name, ty }),
+        Err(error) => errors.push(error),
+      }
+    }
+
+    let result = match result_type(&sig.output) {
+      Some(ty) => match self.map(ty) {
+        Ok(ty) => Some(ty),
+        Err(error) => {
+          errors.push(error);
+          None
+        }
+      },
+      None => None,
+    };
+
+    if !errors.is_empty() {
+      for error in errors {
+        self.warn(location.clone(), format!("skipped, {}", error));
+      }
+      return None;
+    }
+
+    Some(ast::Fn {
+      attrs: Vec::new(),
+      name,
+      params,
+      result,
+    })
+  }
+
+  fn items(&mut self, location: &str, items: &[syn::Item]) -> Vec<ast::Decl> {
+    let outer = std::mem::take(&mut self.types);
+    let mut decls = Vec::new();
+    for item in items.iter() {
+      match item {
+        syn::Item::Trait(item) => decls.extend(self.trait_(location, item)),
+        syn::Item::Mod(item) => {
+          let (_, items) = match &item.content {
+            Some(content) => content,
+            None => continue,
+          };
+          let location = if location.is_empty() {
+            item.ident.to_string()
+          } else {
+            format!("{}::{}", location, item.ident)
+          };
+          let children = self.items(location.as_str(), items);
+          if children.is_empty() {
+            continue;
+          }
+          match id(item.ident.to_string().as_str()) {
+            Some(name) => decls.push(ast::Decl::Mod(ast::Mod {
+              attrs: Vec::new(),
+              name,
+              decls: Some(children),
+            })),
+            None => self.warn(location, "skipped, name isn't a valid .boozle identifier".to_string()),
+          }
+        }
+        _ => {}
+      }
+    }
+    let types = std::mem::replace(&mut self.types, outer);
+    types.into_iter().map(ast::Decl::Type).chain(decls).collect()
+  }
+
+  fn trait_(&mut self, location: &str, item: &syn::ItemTrait) -> Vec<ast::Decl> {
+    let location = if location.is_empty() {
+      item.ident.to_string()
+    } else {
+      format!("{}::{}", location, item.ident)
+    };
+    let export = match parse_export(&item.attrs) {
+      Ok(Some(export)) => export,
+      Ok(None) => return Vec::new(),
+      Err(error) => {
+        self.warn(location, error);
+        return Vec::new();
+      }
+    };
+    let name = match id(item.ident.to_string().as_str()) {
+      Some(name) => name,
+      None => {
+        self.warn(location, "skipped, name isn't a valid .boozle identifier".to_string());
+        return Vec::new();
+      }
+    };
+
+    let mut members = Vec::new();
+    for trait_item in item.items.iter() {
+      match trait_item {
+        syn::TraitItem::Method(method) => members.extend(self.method(location.as_str(), method)),
+        syn::TraitItem::Type(ty) => self.warn(
+          format!("{}::{}", location, ty.ident),
+          "skipped, associated types can't be mapped".to_string(),
+        ),
+        syn::TraitItem::Const(constant) => self.warn(
+          format!("{}::{}", location, constant.ident),
+          "skipped, associated constants can't be mapped".to_string(),
+        ),
+        _ => {}
+      }
+    }
+
+    let mut decls = Vec::new();
+    if let Some(svc) = export.svc {
+      match id(svc.as_str()) {
+        Some(svc) => decls.push(ast::Decl::Svc(ast::Svc {
+          attrs: Vec::new(),
+          name: svc,
+          ty: ast::Type {
+            path: ast::Path {
+              components: vec![ast::Id {
+                text: name.text.clone(),
+              }],
+            },
+          },
+        })),
+        None => self.warn(location, format!("svc `{}` isn't a valid .boozle identifier", svc)),
+      }
+    }
+    decls.insert(
+      0,
+      ast::Decl::Trait(ast::Trait {
+        attrs: Vec::new(),
+        name,
+        members,
+      }),
+    );
+    decls
+  }
+}
+
+fn collect_traits(items: &[syn::Item], traits: &mut HashSet<String>) {
+  for item in items.iter() {
+    match item {
+      syn::Item::Trait(item) => {
+        if let Ok(Some(_)) = parse_export(&item.attrs) {
+          traits.insert(item.ident.to_string());
+        }
+      }
+      syn::Item::Mod(item) => {
+        if let Some((_, items)) = &item.content {
+          collect_traits(items, traits);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Imports the exported traits of a parsed Rust file as a unit named `name`.
+pub fn import(name: String, file: &syn::File) -> Import {
+  let mut traits = HashSet::new();
+  collect_traits(&file.items, &mut traits);
+  let mut importer = Importer {
+    traits,
+    types: Vec::new(),
+    warnings: Vec::new(),
+  };
+  let decls = importer.items("", &file.items);
+  Import {
+    unit: Unit { name, decls },
+    warnings: importer.warnings,
+  }
+}
+
+/// Like `import`, but parses the Rust source first.
+pub fn import_str(name: String, text: &str) -> syn::Result<Import> {
+  let file = syn::parse_file(text)?;
+  Ok(import(name, &file))
+}
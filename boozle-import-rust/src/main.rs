@@ -0,0 +1,30 @@
+use std::path::Path;
+use std::process::exit;
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.is_empty() || args.len() > 2 {
+    eprintln!("usage: boozle-import-rust <input.rs> [output.boozle]");
+    exit(2);
+  }
+
+  let input = Path::new(&args[0]);
+  let text = match std::fs::read_to_string(input) {
+    Ok(text) => text,
+    Err(err) => {
+      eprintln!("{}: {}", input.display(), err);
+      exit(1);
+    }
+  };
+
+  let name = input.file_stem().unwrap().to_string_lossy().to_string();
+  let import = match boozle_import_rust::import_str(name, text.as_str()) {
+    Ok(import) => import,
+    Err(err) => {
+      eprintln!("{}: {}", input.display(), err);
+      exit(1);
+    }
+  };
+
+  import.write(args.get(1).map(String::as_str));
+}
@@ -0,0 +1,162 @@
+use boozle_import_rust::{id, import_str, map_type};
+
+const SOURCE: &str = r#"
+use std::collections::HashMap;
+
+#[boozle::export(svc = "counter")]
+pub trait Counter {
+  async fn add(&mut self, amount: u32) -> Result<u32, CallError>;
+  async fn name(&self) -> Result<String, CallError>;
+  async fn rename(&self, name: &str) -> Result<(), CallError>;
+  async fn history(&self) -> Result<Vec<u32>, CallError>;
+  async fn labels(&self, labels: HashMap<String, Option<String>>);
+}
+
+/// Not exported, so neither imported nor treated as an object.
+pub trait Hidden {
+  fn secret(&self) -> u32;
+}
+
+pub mod registry {
+  #[export]
+  pub trait Registry {
+    fn create(&self, name: String) -> Result<Box<dyn Counter>, CallError>;
+    fn register(&self, counter: CounterClient);
+    fn forget(&self, counter: impl Counter);
+    fn raw(&self, bytes: Vec<u8>) -> Vec<u8>;
+  }
+}
+"#;
+
+#[test]
+fn imports_exported_traits() {
+  let import = import_str("counter".to_string(), SOURCE).unwrap();
+  let text = import.unit.to_string();
+  assert_eq!(
+    text,
+    r#"#[rust(type = "Vec<u32>")]
+type VecU32;
+
+#[rust(type = "HashMap<String,Option<String>>")]
+type HashMapStringOptionString;
+
+trait Counter {
+  fn add(amount: u32) -> u32;
+  fn name() -> String;
+  fn rename(name: String);
+  fn history() -> VecU32;
+  fn labels(labels: HashMapStringOptionString);
+}
+
+svc counter: Counter;
+
+mod registry {
+  #[rust(type = "Vec<u8>")]
+  type VecU8;
+
+  trait Registry {
+    fn create(name: String) -> Counter;
+    fn register(counter: Counter);
+    fn forget(counter: Counter);
+    fn raw(bytes: VecU8) -> VecU8;
+  }
+}
+"#
+  );
+  boozle_parser::parse("counter".to_string(), text.as_str()).unwrap();
+  assert!(import.warnings.is_empty());
+}
+
+#[test]
+fn warns_about_skipped_items() {
+  let source = r#"
+    #[boozle::export(svc = "fn")]
+    trait Skips {
+      type Item;
+      const LIMIT: u32;
+      fn owned(self);
+      fn free();
+      fn generic<T>(&self, value: T);
+      fn tuple(&self, pair: (u32, u32));
+      fn objects(&self, counters: Vec<Box<dyn Skips>>);
+      fn pattern(&self, (a, b): (u32, u32));
+      fn r#type(&self);
+      fn kept(&self, r#fn: u32);
+    }
+
+    #[boozle::export(name = "other")]
+    trait Invalid {}
+
+    #[boozle::export(svc = 1)]
+    trait Unnamed {}
+  "#;
+  let import = import_str("skips".to_string(), source).unwrap();
+  let warnings: Vec<String> = import.warnings.iter().map(|warning| warning.to_string()).collect();
+  assert_eq!(
+    warnings,
+    [
+      "Skips::Item: skipped, associated types can't be mapped",
+      "Skips::LIMIT: skipped, associated constants can't be mapped",
+      "Skips::owned: skipped, methods must take `&self` or `&mut self`",
+      "Skips::free: skipped, methods must take `&self` or `&mut self`",
+      "Skips::generic: skipped, generic methods can't be mapped",
+      "Skips::tuple: skipped, `(u32,u32)` has no .boozle equivalent",
+      "Skips::objects: skipped, `Vec<Box<dynSkips>>` has no .boozle equivalent",
+      "Skips::pattern: skipped, parameter `(a , b)` isn't a valid .boozle identifier",
+      "Skips::r#type: skipped, name isn't a valid .boozle identifier",
+      "Skips::kept: skipped, parameter `r#fn` isn't a valid .boozle identifier",
+      "Skips: svc `fn` isn't a valid .boozle identifier",
+      "Invalid: unknown argument, expected `svc = \"...\"`",
+      "Unnamed: `svc` must be a string",
+    ]
+  );
+  assert_eq!(import.unit.to_string(), "trait Skips {\n}\n");
+}
+
+#[test]
+fn maps_types() {
+  let no_objects = |_: &syn::Type| None;
+  let map = |text: &str| {
+    let ty: syn::Type = syn::parse_str(text).unwrap();
+    map_type(&ty, &no_objects).map(|ty| {
+      let components: Vec<String> = ty.path.components.into_iter().map(|id| id.text).collect();
+      components.join(".")
+    })
+  };
+
+  assert_eq!(map("u32").as_deref(), Some("u32"));
+  assert_eq!(map("&str").as_deref(), Some("String"));
+  assert_eq!(map("&'a mut String").as_deref(), Some("String"));
+  assert_eq!(map("foo::Bar").as_deref(), Some("foo.Bar"));
+
+  // Generics are left to the importer, which declares them as opaque types.
+  for rejected in [
+    "Vec<u8>",
+    "Option<T>",
+    "(u32, u32)",
+    "[u8; 4]",
+    "fn(u32)",
+    "<T as Trait>::Item",
+    "r#fn",
+  ]
+  .iter()
+  {
+    assert_eq!(map(rejected), None, "{} was mapped", rejected);
+  }
+
+  let object = |ty: &syn::Type| match ty {
+    syn::Type::TraitObject(_) => Some("Counter".to_string()),
+    _ => None,
+  };
+  let ty: syn::Type = syn::parse_str("&dyn Counter").unwrap();
+  assert_eq!(map_type(&ty, &object).unwrap().path.components[0].text, "Counter");
+}
+
+#[test]
+fn validates_identifiers() {
+  assert_eq!(id("counter").unwrap().text, "counter");
+  assert_eq!(id("r#match").unwrap().text, "match");
+  for invalid in ["", "_private", "1st", "trait", "r#mod", "naïve"].iter() {
+    assert!(id(invalid).is_none(), "{} is valid", invalid);
+  }
+}
@@ -0,0 +1,92 @@
+//! What the importers, which derive .boozle declarations from other interface
+//! definitions, have in common.
+
+use boozle_parser::ast;
+use boozle_parser::Unit;
+use std::process::exit;
+
+pub use boozle_parser::id;
+
+/// Something an importer skipped or had to approximate.
+#[derive(Debug)]
+pub struct Warning {
+  /// Where the problem was found, e.g. `foo::Counter::add`.
+  pub location: String,
+  pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.location, self.message)
+  }
+}
+
+pub struct Import {
+  pub unit: Unit,
+  pub warnings: Vec<Warning>,
+}
+
+impl Import {
+  /// Finishes an importer's command-line tool: prints the warnings and writes
+  /// the unit to `output`, or to stdout if there's none. Exits on failure.
+  pub fn write(&self, output: Option<&str>) {
+    for warning in self.warnings.iter() {
+      eprintln!("warning: {}", warning);
+    }
+
+    let text = self.unit.to_string();
+    // The printer should only ever produce valid .boozle. Check before writing anything.
+    if let Err(err) = boozle_parser::parse(self.unit.name.clone(), text.as_str()) {
+      eprintln!("internal error: generated .boozle doesn't parse: {:?}", err);
+      exit(1);
+    }
+
+    match output {
+      Some(path) => {
+        if let Err(err) = std::fs::write(path, text) {
+          eprintln!("{}: {}", path, err);
+          exit(1);
+        }
+      }
+      None => print!("{}", text),
+    }
+  }
+}
+
+/// Converts a name such as `GetHTTPStatus` to `get_http_status`.
+pub fn snake_case(name: &str) -> String {
+  let chars: Vec<char> = name.chars().collect();
+  let mut snake = String::new();
+  for (i, &c) in chars.iter().enumerate() {
+    if !c.is_ascii_uppercase() {
+      snake.push(c);
+      continue;
+    }
+    let boundary = match i.checked_sub(1).map(|i| chars[i]) {
+      Some(prev) if prev.is_ascii_lowercase() || prev.is_ascii_digit() => true,
+      Some(prev) if prev.is_ascii_uppercase() => matches!(chars.get(i + 1), Some(next) if next.is_ascii_lowercase()),
+      _ => false,
+    };
+    if boundary && !snake.ends_with('_') {
+      snake.push('_');
+    }
+    snake.push(c.to_ascii_lowercase());
+  }
+  snake
+}
+
+/// The type named `name`, which must be a valid identifier.
+pub fn named(name: &str) -> ast::Type {
+  ast::Type {
+    path: ast::Path {
+      components: vec![ast::Id { text: name.to_string() }],
+    },
+  }
+}
+
+pub fn param(name: &str, ty: &str) -> ast::Param {
+  ast::Param {
+    name: ast::Id { text: name.to_string() },
+    ty: named(ty),
+  }
+}
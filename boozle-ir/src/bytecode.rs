@@ -0,0 +1,403 @@
+//! The binary form of a `Unit`. Integers are little-endian.
+//!
+//! ```text
+//! unit        = "BZIR" version:u16 inputs:u16 outputs:u16
+//!               count:u32 value* count:u32 instruction*
+//! value       = tag:u8 payload
+//! instruction = 0 const:u16 | 1 | 2 slot:u16 | 3 method_id:u64 argument:u8
+//!             | 4 const:u16
+//! ```
+//!
+//! Decoding never trusts a length before checking enough bytes are left, and
+//! reserves no more memory for a sequence than there are bytes left. Memory
+//! use stays proportional to the size of the unit, even for hostile ones.
+
+use super::{Instruction, MethodId, Object, RemoteId, Unit, Value};
+
+pub const MAGIC: &[u8; 4] = b"BZIR";
+/// Version 2 added `Free`.
+pub const VERSION: u16 = 2;
+
+/// Values nested deeper than this are rejected.
+pub const MAX_NESTING: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+  BadMagic,
+  UnsupportedVersion(u16),
+  Truncated,
+  UnknownValue(u8),
+  UnknownInstruction(u8),
+  InvalidString,
+  TooDeep,
+  TrailingBytes,
+}
+
+impl std::fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::BadMagic => write!(f, "not a boozle unit"),
+      Self::UnsupportedVersion(version) => write!(f, "unsupported bytecode version {}", version),
+      Self::Truncated => write!(f, "unexpected end of unit"),
+      Self::UnknownValue(tag) => write!(f, "unknown value tag {}", tag),
+      Self::UnknownInstruction(tag) => write!(f, "unknown instruction {}", tag),
+      Self::InvalidString => write!(f, "string isn't valid UTF-8"),
+      Self::TooDeep => write!(f, "values nested too deeply"),
+      Self::TrailingBytes => write!(f, "trailing bytes after unit"),
+    }
+  }
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value) {
+  match value {
+    Value::U8(value) => {
+      out.push(0);
+      out.push(*value);
+    }
+    Value::U16(value) => {
+      out.push(1);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::U32(value) => {
+      out.push(2);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::U64(value) => {
+      out.push(3);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::I8(value) => {
+      out.push(4);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::I16(value) => {
+      out.push(5);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::I32(value) => {
+      out.push(6);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::I64(value) => {
+      out.push(7);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::F32(value) => {
+      out.push(8);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::F64(value) => {
+      out.push(9);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    Value::String(value) => {
+      out.push(10);
+      put_bytes(out, value.as_bytes());
+    }
+    Value::Array(values) => {
+      out.push(11);
+      out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+      for value in values.iter() {
+        put_value(out, value);
+      }
+    }
+    Value::Object(object) => {
+      out.push(12);
+      put_object(out, object);
+    }
+    Value::Method(object, method_id) => {
+      out.push(13);
+      put_object(out, object);
+      out.extend_from_slice(&method_id.0.to_le_bytes());
+    }
+    Value::Lit(value) => {
+      out.push(14);
+      put_bytes(out, value);
+    }
+    Value::Unit => out.push(15),
+    Value::Bool(value) => {
+      out.push(16);
+      out.push(*value as u8);
+    }
+    Value::Char(value) => {
+      out.push(17);
+      out.extend_from_slice(&(*value as u32).to_le_bytes());
+    }
+    Value::Bytes(value) => {
+      out.push(18);
+      put_bytes(out, value);
+    }
+    Value::Option(value) => {
+      out.push(19);
+      match value {
+        Some(value) => {
+          out.push(1);
+          put_value(out, value);
+        }
+        None => out.push(0),
+      }
+    }
+    Value::Tuple(values) => {
+      out.push(20);
+      out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+      for value in values.iter() {
+        put_value(out, value);
+      }
+    }
+    Value::Map(entries) => {
+      out.push(21);
+      out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+      for (key, value) in entries.iter() {
+        put_value(out, key);
+        put_value(out, value);
+      }
+    }
+    Value::Struct(fields) => {
+      out.push(22);
+      out.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+      for (name, value) in fields.iter() {
+        put_bytes(out, name.as_bytes());
+        put_value(out, value);
+      }
+    }
+    Value::Variant { index, name, value } => {
+      out.push(23);
+      out.extend_from_slice(&index.to_le_bytes());
+      put_bytes(out, name.as_bytes());
+      put_value(out, value);
+    }
+  }
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+  out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+  out.extend_from_slice(bytes);
+}
+
+fn put_object(out: &mut Vec<u8>, object: &Object) {
+  out.extend_from_slice(&object.owner.0.to_le_bytes());
+  out.extend_from_slice(&object.addr.to_le_bytes());
+}
+
+impl Unit {
+  pub fn encode(&self) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&self.inputs.to_le_bytes());
+    out.extend_from_slice(&self.outputs.to_le_bytes());
+    out.extend_from_slice(&(self.consts.len() as u32).to_le_bytes());
+    for value in self.consts.iter() {
+      put_value(&mut out, value);
+    }
+    out.extend_from_slice(&(self.instructions.len() as u32).to_le_bytes());
+    for instruction in self.instructions.iter() {
+      match instruction {
+        Instruction::Push(index) => {
+          out.push(0);
+          out.extend_from_slice(&index.to_le_bytes());
+        }
+        Instruction::Pop => out.push(1),
+        Instruction::Load(slot) => {
+          out.push(2);
+          out.extend_from_slice(&slot.to_le_bytes());
+        }
+        Instruction::Invoke {
+          method_id,
+          argument,
+        } => {
+          out.push(3);
+          out.extend_from_slice(&method_id.0.to_le_bytes());
+          out.push(*argument as u8);
+        }
+        Instruction::Free(index) => {
+          out.push(4);
+          out.extend_from_slice(&index.to_le_bytes());
+        }
+      }
+    }
+    out
+  }
+
+  /// Decodes a unit. The result still has to be verified before it is run.
+  pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+    let mut reader = Reader { data };
+    if reader.take(4).map_err(|_| DecodeError::BadMagic)? != MAGIC {
+      return Err(DecodeError::BadMagic);
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+      return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let inputs = reader.u16()?;
+    let outputs = reader.u16()?;
+
+    let count = reader.count(1)?;
+    let mut consts = reader.reserve(count);
+    for _ in 0..count {
+      consts.push(reader.value(0)?);
+    }
+
+    let count = reader.count(1)?;
+    let mut instructions = reader.reserve(count);
+    for _ in 0..count {
+      instructions.push(match reader.u8()? {
+        0 => Instruction::Push(reader.u16()?),
+        1 => Instruction::Pop,
+        2 => Instruction::Load(reader.u16()?),
+        3 => Instruction::Invoke {
+          method_id: MethodId(reader.u64()?),
+          argument: reader.u8()? != 0,
+        },
+        4 => Instruction::Free(reader.u16()?),
+        tag => return Err(DecodeError::UnknownInstruction(tag)),
+      });
+    }
+
+    if !reader.data.is_empty() {
+      return Err(DecodeError::TrailingBytes);
+    }
+    Ok(Self {
+      inputs,
+      outputs,
+      consts,
+      instructions,
+    })
+  }
+}
+
+struct Reader<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+    if self.data.len() < len {
+      return Err(DecodeError::Truncated);
+    }
+    let (taken, rest) = self.data.split_at(len);
+    self.data = rest;
+    Ok(taken)
+  }
+
+  fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(self.take(N)?);
+    Ok(bytes)
+  }
+
+  fn u8(&mut self) -> Result<u8, DecodeError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, DecodeError> {
+    self.array().map(u16::from_le_bytes)
+  }
+
+  fn u32(&mut self) -> Result<u32, DecodeError> {
+    self.array().map(u32::from_le_bytes)
+  }
+
+  fn u64(&mut self) -> Result<u64, DecodeError> {
+    self.array().map(u64::from_le_bytes)
+  }
+
+  /// Reads the length of a sequence whose items take at least `min_size`
+  /// bytes each.
+  fn count(&mut self, min_size: usize) -> Result<usize, DecodeError> {
+    let count = self.u32()? as usize;
+    if count.saturating_mul(min_size) > self.data.len() {
+      return Err(DecodeError::Truncated);
+    }
+    Ok(count)
+  }
+
+  /// Makes room for `count` items, which may be larger in memory than their
+  /// encoding. More room is only made as items are actually read.
+  fn reserve<T>(&self, count: usize) -> Vec<T> {
+    let size = std::mem::size_of::<T>().max(1);
+    Vec::with_capacity(count.min(self.data.len() / size))
+  }
+
+  fn object(&mut self) -> Result<Object, DecodeError> {
+    Ok(Object::new(RemoteId(self.u32()?), self.u64()?))
+  }
+
+  fn value(&mut self, nesting: usize) -> Result<Value, DecodeError> {
+    Ok(match self.u8()? {
+      0 => Value::U8(self.u8()?),
+      1 => Value::U16(self.u16()?),
+      2 => Value::U32(self.u32()?),
+      3 => Value::U64(self.u64()?),
+      4 => Value::I8(self.array().map(i8::from_le_bytes)?),
+      5 => Value::I16(self.array().map(i16::from_le_bytes)?),
+      6 => Value::I32(self.array().map(i32::from_le_bytes)?),
+      7 => Value::I64(self.array().map(i64::from_le_bytes)?),
+      8 => Value::F32(self.array().map(f32::from_le_bytes)?),
+      9 => Value::F64(self.array().map(f64::from_le_bytes)?),
+      10 => Value::String(self.string()?),
+      11 => Value::Array(self.values(nesting)?),
+      12 => Value::Object(self.object()?),
+      13 => Value::Method(self.object()?, MethodId(self.u64()?)),
+      14 => Value::Lit(self.bytes()?.into()),
+      15 => Value::Unit,
+      16 => Value::Bool(self.u8()? != 0),
+      17 => Value::Char(std::char::from_u32(self.u32()?).ok_or(DecodeError::InvalidString)?),
+      18 => Value::Bytes(self.bytes()?.into()),
+      19 => match self.u8()? {
+        0 => Value::Option(None),
+        _ => Value::Option(Some(Box::new(self.nested(nesting)?))),
+      },
+      20 => Value::Tuple(self.values(nesting)?),
+      21 => {
+        let count = self.count(2)?;
+        let mut entries = self.reserve(count);
+        for _ in 0..count {
+          entries.push((self.nested(nesting)?, self.nested(nesting)?));
+        }
+        Value::Map(entries.into_boxed_slice())
+      }
+      22 => {
+        let count = self.count(5)?;
+        let mut fields = self.reserve(count);
+        for _ in 0..count {
+          fields.push((self.string()?, self.nested(nesting)?));
+        }
+        Value::Struct(fields.into_boxed_slice())
+      }
+      23 => Value::Variant {
+        index: self.u32()?,
+        name: self.string()?,
+        value: Box::new(self.nested(nesting)?),
+      },
+      tag => return Err(DecodeError::UnknownValue(tag)),
+    })
+  }
+
+  /// Reads a value contained in one at `nesting`.
+  fn nested(&mut self, nesting: usize) -> Result<Value, DecodeError> {
+    if nesting >= MAX_NESTING {
+      return Err(DecodeError::TooDeep);
+    }
+    self.value(nesting + 1)
+  }
+
+  fn values(&mut self, nesting: usize) -> Result<Box<[Value]>, DecodeError> {
+    let count = self.count(1)?;
+    let mut values = self.reserve(count);
+    for _ in 0..count {
+      values.push(self.nested(nesting)?);
+    }
+    Ok(values.into_boxed_slice())
+  }
+
+  fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+    let len = self.count(1)?;
+    self.take(len)
+  }
+
+  fn string(&mut self) -> Result<String, DecodeError> {
+    let bytes = self.bytes()?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidString)
+  }
+}
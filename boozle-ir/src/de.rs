@@ -0,0 +1,322 @@
+use serde::de::{
+  self, Deserialize, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess,
+  SeqAccess, VariantAccess, Visitor,
+};
+
+use super::{Error, Value};
+
+/// Builds a `Value` from a self-describing format such as JSON. Formats that
+/// don't describe their contents, such as bincode, are decoded with a `Type`
+/// instead.
+impl<'de> Deserialize<'de> for Value {
+  fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ValueVisitor)
+  }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+  type Value = Value;
+
+  fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("any value")
+  }
+
+  fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+    Ok(Value::Bool(value))
+  }
+
+  fn visit_i8<E>(self, value: i8) -> Result<Value, E> {
+    Ok(Value::I8(value))
+  }
+
+  fn visit_i16<E>(self, value: i16) -> Result<Value, E> {
+    Ok(Value::I16(value))
+  }
+
+  fn visit_i32<E>(self, value: i32) -> Result<Value, E> {
+    Ok(Value::I32(value))
+  }
+
+  fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+    Ok(Value::I64(value))
+  }
+
+  fn visit_u8<E>(self, value: u8) -> Result<Value, E> {
+    Ok(Value::U8(value))
+  }
+
+  fn visit_u16<E>(self, value: u16) -> Result<Value, E> {
+    Ok(Value::U16(value))
+  }
+
+  fn visit_u32<E>(self, value: u32) -> Result<Value, E> {
+    Ok(Value::U32(value))
+  }
+
+  fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+    Ok(Value::U64(value))
+  }
+
+  fn visit_f32<E>(self, value: f32) -> Result<Value, E> {
+    Ok(Value::F32(value))
+  }
+
+  fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+    Ok(Value::F64(value))
+  }
+
+  fn visit_char<E>(self, value: char) -> Result<Value, E> {
+    Ok(Value::Char(value))
+  }
+
+  fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+    Ok(Value::String(value.to_string()))
+  }
+
+  fn visit_string<E>(self, value: String) -> Result<Value, E> {
+    Ok(Value::String(value))
+  }
+
+  fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+    Ok(Value::Bytes(value.into()))
+  }
+
+  fn visit_unit<E>(self) -> Result<Value, E> {
+    Ok(Value::Unit)
+  }
+
+  fn visit_none<E>(self) -> Result<Value, E> {
+    Ok(Value::Option(None))
+  }
+
+  fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    Ok(Value::Option(Some(Box::new(Value::deserialize(
+      deserializer,
+    )?))))
+  }
+
+  fn visit_newtype_struct<D: de::Deserializer<'de>>(
+    self,
+    deserializer: D,
+  ) -> Result<Value, D::Error> {
+    Value::deserialize(deserializer)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+    let mut values = Vec::new();
+    while let Some(value) = seq.next_element()? {
+      values.push(value);
+    }
+    Ok(Value::Array(values.into_boxed_slice()))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+    let mut entries = Vec::new();
+    while let Some(entry) = map.next_entry()? {
+      entries.push(entry);
+    }
+    Ok(Value::Map(entries.into_boxed_slice()))
+  }
+}
+
+/// Converts a `Value` to `T`. Structs and variants may be given by position or
+/// by name, so values read from JSON convert too.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+  T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+  type Deserializer = Self;
+
+  fn into_deserializer(self) -> Self {
+    self
+  }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+  type Error = Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    match self {
+      Self::Unit => visitor.visit_unit(),
+      Self::Bool(value) => visitor.visit_bool(value),
+      Self::U8(value) => visitor.visit_u8(value),
+      Self::U16(value) => visitor.visit_u16(value),
+      Self::U32(value) => visitor.visit_u32(value),
+      Self::U64(value) => visitor.visit_u64(value),
+      Self::I8(value) => visitor.visit_i8(value),
+      Self::I16(value) => visitor.visit_i16(value),
+      Self::I32(value) => visitor.visit_i32(value),
+      Self::I64(value) => visitor.visit_i64(value),
+      Self::F32(value) => visitor.visit_f32(value),
+      Self::F64(value) => visitor.visit_f64(value),
+      Self::Char(value) => visitor.visit_char(value),
+      Self::String(value) => visitor.visit_string(value),
+      Self::Bytes(value) => visitor.visit_byte_buf(value.into_vec()),
+      Self::Option(None) => visitor.visit_none(),
+      Self::Option(Some(value)) => visitor.visit_some(*value),
+      Self::Array(values) | Self::Tuple(values) => {
+        visitor.visit_seq(Seq(values.into_vec().into_iter()))
+      }
+      Self::Map(entries) => visitor.visit_map(Map {
+        entries: entries.into_vec().into_iter(),
+        value: None,
+      }),
+      Self::Struct(fields) => visitor.visit_map(Map {
+        entries: fields
+          .into_vec()
+          .into_iter()
+          .map(|(name, value)| (Value::String(name), value))
+          .collect::<Vec<_>>()
+          .into_iter(),
+        value: None,
+      }),
+      Self::Variant { index, value, .. } => visitor.visit_enum(Enum {
+        variant: Self::U32(index),
+        value: *value,
+      }),
+      Self::Object(_) | Self::Method(..) => Err(de::Error::custom("objects can't be deserialized")),
+      Self::Lit(_) => Err(de::Error::custom("encoded literals can't be deserialized")),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    match self {
+      Self::Option(None) | Self::Unit => visitor.visit_none(),
+      Self::Option(Some(value)) => visitor.visit_some(*value),
+      value => visitor.visit_some(value),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _: &'static str,
+    _: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    match self {
+      Self::Variant { index, value, .. } => visitor.visit_enum(Enum {
+        variant: Self::U32(index),
+        value: *value,
+      }),
+      // How human-readable formats write variants.
+      Self::String(_) => visitor.visit_enum(Enum {
+        variant: self,
+        value: Self::Unit,
+      }),
+      Self::Map(entries) if entries.len() == 1 => {
+        let (variant, value) = entries.into_vec().pop().unwrap();
+        visitor.visit_enum(Enum { variant, value })
+      }
+      value => Err(de::Error::custom(format!(
+        "expected a variant, found {:?}",
+        value
+      ))),
+    }
+  }
+
+  serde::forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+    identifier ignored_any
+  }
+}
+
+struct Seq(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for Seq {
+  type Error = Error;
+
+  fn next_element_seed<T: DeserializeSeed<'de>>(
+    &mut self,
+    seed: T,
+  ) -> Result<Option<T::Value>, Error> {
+    match self.0.next() {
+      Some(value) => seed.deserialize(value).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.0.len())
+  }
+}
+
+struct Map {
+  entries: std::vec::IntoIter<(Value, Value)>,
+  value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for Map {
+  type Error = Error;
+
+  fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+    match self.entries.next() {
+      Some((key, value)) => {
+        self.value = Some(value);
+        seed.deserialize(key).map(Some)
+      }
+      None => Ok(None),
+    }
+  }
+
+  fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+    match self.value.take() {
+      Some(value) => seed.deserialize(value),
+      None => Err(de::Error::custom("map value without a key")),
+    }
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.entries.len())
+  }
+}
+
+/// A variant being deserialized. `variant` is its index or its name.
+struct Enum {
+  variant: Value,
+  value: Value,
+}
+
+impl<'de> EnumAccess<'de> for Enum {
+  type Error = Error;
+  type Variant = Value;
+
+  fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Value), Error> {
+    Ok((seed.deserialize(self.variant)?, self.value))
+  }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+  type Error = Error;
+
+  fn unit_variant(self) -> Result<(), Error> {
+    Ok(())
+  }
+
+  fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+    seed.deserialize(self)
+  }
+
+  fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+    de::Deserializer::deserialize_any(self, visitor)
+  }
+
+  fn struct_variant<V: Visitor<'de>>(
+    self,
+    _: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    de::Deserializer::deserialize_any(self, visitor)
+  }
+}
@@ -1,87 +1,123 @@
-// use std::collections::HashMap;
-
-// mod builtin;
-// mod value;
-
-// // use value::Value;
-
-// pub struct RemoteId(u32);
-// pub struct MethodId(u32);
-// pub struct PromiseId(u32);
-
-// impl From<u32> for RemoteId {
-//   fn from(value: u32) -> Self {
-//     Self(value)
-//   }
-// }
-
-// impl From<u32> for MethodId {
-//   fn from(value: u32) -> Self {
-//     Self(value)
-//   }
-// }
-
-// impl From<u32> for PromiseId {
-//   fn from(value: u32) -> Self {
-//     Self(value)
-//   }
-// }
-
-// pub enum Expr {
-//   Method(Box<Expr>, MethodId),
-//   Call {
-//     method: Box<Expr>,
-//     argument: Box<Expr>,
-//   },
-//   Value(Value),
-//   Promise(PromiseId),
-// }
-
-// impl From<Value> for Expr {
-//   fn from(value: Value) -> Self {
-//     Self::Value(value.into())
-//   }
-// }
-
-// impl From<Value> for Box<Expr> {
-//   fn from(value: Value) -> Self {
-//     Box::new(Expr::Value(value.into()))
-//   }
-// }
-
-// fn test() {
-//   let call = Expr::Call {
-//     method: Box::new(Expr::Method(
-//       Value::Object(Object::new(0, 1)).into(),
-//       1u32.into(),
-//     )),
-//     argument: Value::U8(1u8).into(),
-//   };
-// }
-
-// pub fn segment(expr: Expr) -> HashMap<RemoteId, (PromiseId, Expr)> {
-//   HashMap::new()
-// }
-
-// pub struct Object {
-//   owner: u32,
-//   addr: u32,
-// }
-
-// impl Object {
-//   pub fn new(owner: u32, addr: u32) -> Self {
-//     Self { owner, addr }
-//   }
-// }
-
-// pub struct Unit {
-//   consts: Vec<Value>,
-//   instructions: Vec<Instruction>,
-// }
-
-// pub enum Instruction {
-//   Push(Value),
-//   Pop,
-//   Load(u16),
-//   Invoke { method_id: u32 },
-// }
+//! The intermediate representation of pipelined calls.
+//!
+//! A client describes a chain of calls such as `a.foo().bar(b.baz())` as an
+//! `Expr` graph without waiting for any result. `segment` splits the graph
+//! into one `Segment` per owner and round, so each segment can be sent to its
+//! owner in a single message and evaluated there against the results it
+//! promised earlier.
+//!
+//! Segments travel as `Unit`s: small stack programs in a versioned bytecode
+//! (see `bytecode`) that the receiver verifies against its `Limits` before
+//! running them. `optimize` removes repeated and unused pure calls from a
+//! plan, and `merge_frees` batches the objects a unit releases.
+//!
+//! `Value` doubles as a dynamic, self-describing value: `to_value` and
+//! `from_value` convert it from and to serde types, and a `Type` decodes a
+//! bincode literal into one, so calls can be made and inspected without
+//! generated stubs.
+
+use serde::{Deserialize, Serialize};
+
+pub mod bytecode;
+mod de;
+mod opt;
+mod segment;
+mod ser;
+mod ty;
+mod unit;
+mod value;
+
+pub use bytecode::DecodeError;
+pub use de::from_value;
+pub use opt::{eliminate_common_calls, eliminate_dead_calls, merge_frees, optimize};
+pub use segment::{segment, Plan, Segment, SegmentError, Step};
+pub use ser::{to_value, Error};
+pub use ty::Type;
+pub use unit::{compile, objects, size, Instruction, Limits, Unit, VerifyError};
+pub use value::Value;
+
+/// Identifies the node owning an object. `RemoteId::LOCAL` is always the node
+/// holding the expression; other ids are assigned by that node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RemoteId(pub u32);
+
+impl RemoteId {
+  pub const LOCAL: Self = Self(0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MethodId(pub u64);
+
+/// Names the result of a call that hasn't necessarily been made yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PromiseId(pub u32);
+
+impl From<u32> for RemoteId {
+  fn from(value: u32) -> Self {
+    Self(value)
+  }
+}
+
+impl From<u64> for MethodId {
+  fn from(value: u64) -> Self {
+    Self(value)
+  }
+}
+
+impl From<u32> for PromiseId {
+  fn from(value: u32) -> Self {
+    Self(value)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Object {
+  pub owner: RemoteId,
+  pub addr: u64,
+}
+
+impl Object {
+  pub fn new(owner: RemoteId, addr: u64) -> Self {
+    Self { owner, addr }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+  /// A method of the object the inner expression evaluates to.
+  Method(Box<Expr>, MethodId),
+  Call {
+    method: Box<Expr>,
+    argument: Option<Box<Expr>>,
+  },
+  Value(Value),
+  Promise(PromiseId),
+}
+
+impl Expr {
+  /// Calls method `method_id` of the object this expression evaluates to.
+  pub fn call<M: Into<MethodId>>(self, method_id: M, argument: Option<Expr>) -> Self {
+    Self::Call {
+      method: Box::new(Self::Method(Box::new(self), method_id.into())),
+      argument: argument.map(Box::new),
+    }
+  }
+}
+
+impl From<Value> for Expr {
+  fn from(value: Value) -> Self {
+    Self::Value(value)
+  }
+}
+
+impl From<Value> for Box<Expr> {
+  fn from(value: Value) -> Self {
+    Box::new(Expr::Value(value))
+  }
+}
+
+impl From<Object> for Expr {
+  fn from(object: Object) -> Self {
+    Self::Value(Value::Object(object))
+  }
+}
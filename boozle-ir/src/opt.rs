@@ -0,0 +1,193 @@
+//! Passes that make plans and units cheaper to run without changing their
+//! results or which calls with side effects they make.
+//!
+//! A method is pure if calling it has no side effects and returns the same
+//! result for the same target and argument. The IR can't tell, so the passes
+//! over calls take a predicate for it.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Expr, Instruction, MethodId, Plan, PromiseId, Step, Unit, Value};
+
+/// Runs every pass over `plan`.
+pub fn optimize<P: Fn(MethodId) -> bool>(plan: &mut Plan, pure: P) {
+  eliminate_common_calls(plan, &pure);
+  eliminate_dead_calls(plan, &pure);
+}
+
+/// Replaces each pure call that repeats an earlier call of its segment with
+/// the result of the earlier call.
+pub fn eliminate_common_calls<P: Fn(MethodId) -> bool>(plan: &mut Plan, pure: P) {
+  let mut renames: HashMap<PromiseId, PromiseId> = HashMap::new();
+  // Segments are ordered by round, so every use of a promise comes after the
+  // call binding it.
+  for segment in plan.segments.iter_mut() {
+    let mut steps: Vec<Step> = Vec::with_capacity(segment.steps.len());
+    for mut step in std::mem::take(&mut segment.steps) {
+      for operand in std::iter::once(&mut step.target).chain(step.argument.iter_mut()) {
+        rename(operand, &renames);
+      }
+      let earlier = steps.iter().find(|earlier| {
+        earlier.method == step.method
+          && earlier.target == step.target
+          && earlier.argument == step.argument
+      });
+      match earlier {
+        Some(earlier) if pure(step.method) => {
+          renames.insert(step.promise, earlier.promise);
+        }
+        _ => steps.push(step),
+      }
+    }
+    segment.steps = steps;
+  }
+  if let Some(promise) = renames.get(&plan.result) {
+    plan.result = *promise;
+  }
+  relink(plan);
+}
+
+/// Removes pure calls whose results are never used.
+pub fn eliminate_dead_calls<P: Fn(MethodId) -> bool>(plan: &mut Plan, pure: P) {
+  // Removing a call can leave the calls it used dead too.
+  loop {
+    let used: HashSet<PromiseId> = plan
+      .segments
+      .iter()
+      .flat_map(|segment| segment.steps.iter().flat_map(Step::promises))
+      .chain(std::iter::once(plan.result))
+      .collect();
+    let mut removed = false;
+    for segment in plan.segments.iter_mut() {
+      let len = segment.steps.len();
+      segment
+        .steps
+        .retain(|step| used.contains(&step.promise) || !pure(step.method));
+      removed |= segment.steps.len() != len;
+    }
+    if !removed {
+      break;
+    }
+  }
+  relink(plan);
+}
+
+fn rename(operand: &mut Expr, renames: &HashMap<PromiseId, PromiseId>) {
+  if let Expr::Promise(promise) = operand {
+    if let Some(renamed) = renames.get(promise) {
+      *promise = *renamed;
+    }
+  }
+}
+
+fn push_new(promises: &mut Vec<PromiseId>, promise: PromiseId) {
+  if !promises.contains(&promise) {
+    promises.push(promise);
+  }
+}
+
+/// Drops empty segments and recomputes the imports and exports of the rest
+/// after calls were removed.
+fn relink(plan: &mut Plan) {
+  plan.segments.retain(|segment| !segment.steps.is_empty());
+  let mut bound = HashMap::new();
+  for (i, segment) in plan.segments.iter_mut().enumerate() {
+    segment.imports.clear();
+    segment.exports.clear();
+    for step in segment.steps.iter() {
+      bound.insert(step.promise, i);
+    }
+  }
+  for i in 0..plan.segments.len() {
+    let used: Vec<PromiseId> = plan.segments[i]
+      .steps
+      .iter()
+      .flat_map(Step::promises)
+      .collect();
+    for promise in used {
+      let owner = bound[&promise];
+      if owner != i {
+        push_new(&mut plan.segments[i].imports, promise);
+        push_new(&mut plan.segments[owner].exports, promise);
+      }
+    }
+  }
+  let owner = bound[&plan.result];
+  push_new(&mut plan.segments[owner].exports, plan.result);
+}
+
+/// Merges runs of adjacent `Free` instructions into one, and drops the
+/// constants nothing uses anymore. Runs that free anything but objects are
+/// left as they are, for `verify` to reject.
+pub fn merge_frees(unit: &mut Unit) {
+  let mut instructions = Vec::with_capacity(unit.instructions.len());
+  let mut run = Vec::new();
+  for instruction in std::mem::take(&mut unit.instructions) {
+    match instruction {
+      Instruction::Free(index) => run.push(index),
+      instruction => {
+        flush(unit, &mut instructions, &mut run);
+        instructions.push(instruction);
+      }
+    }
+  }
+  flush(unit, &mut instructions, &mut run);
+  unit.instructions = instructions;
+
+  let mut used = vec![false; unit.consts.len()];
+  for instruction in unit.instructions.iter() {
+    if let Instruction::Push(index) | Instruction::Free(index) = instruction {
+      if let Some(used) = used.get_mut(*index as usize) {
+        *used = true;
+      }
+    }
+  }
+  let mut indices = Vec::with_capacity(used.len());
+  let mut consts = Vec::new();
+  for (value, used) in std::mem::take(&mut unit.consts).into_iter().zip(used) {
+    indices.push(consts.len() as u16);
+    if used {
+      consts.push(value);
+    }
+  }
+  unit.consts = consts;
+  for instruction in unit.instructions.iter_mut() {
+    if let Instruction::Push(index) | Instruction::Free(index) = instruction {
+      if let Some(new) = indices.get(*index as usize) {
+        *index = *new;
+      }
+    }
+  }
+}
+
+/// Emits the run of `Free` instructions in `run` as a single instruction.
+fn flush(unit: &mut Unit, instructions: &mut Vec<Instruction>, run: &mut Vec<u16>) {
+  if run.len() < 2 {
+    instructions.extend(run.drain(..).map(Instruction::Free));
+    return;
+  }
+  let mut objects = Vec::new();
+  for index in run.iter() {
+    match unit.consts.get(*index as usize).and_then(super::objects) {
+      Some(freed) => objects.extend(freed.into_iter().map(Value::Object)),
+      None => {
+        instructions.extend(run.drain(..).map(Instruction::Free));
+        return;
+      }
+    }
+  }
+  let objects = Value::Array(objects.into_boxed_slice());
+  let index = match unit.consts.iter().position(|known| *known == objects) {
+    Some(index) => index,
+    None if unit.consts.len() <= u16::MAX as usize => {
+      unit.consts.push(objects);
+      unit.consts.len() - 1
+    }
+    None => {
+      instructions.extend(run.drain(..).map(Instruction::Free));
+      return;
+    }
+  };
+  run.clear();
+  instructions.push(Instruction::Free(index as u16));
+}
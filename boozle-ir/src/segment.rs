@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use super::{Expr, MethodId, PromiseId, RemoteId, Value};
+
+#[derive(Debug, PartialEq)]
+pub enum SegmentError {
+  /// The expression doesn't call anything.
+  NoCall,
+  /// A call's method isn't a `Method` expression or value.
+  NotCallable,
+  /// A method was looked up on something other than an object or a promise.
+  NotAnObject,
+  /// A method was used as a value instead of being called.
+  UncalledMethod,
+  /// The expression refers to a promise it doesn't create.
+  UnknownPromise(PromiseId),
+}
+
+impl std::fmt::Display for SegmentError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NoCall => write!(f, "the expression doesn't call anything"),
+      Self::NotCallable => write!(f, "only methods can be called"),
+      Self::NotAnObject => write!(f, "methods can only be looked up on objects"),
+      Self::UncalledMethod => write!(f, "methods can't be used as values"),
+      Self::UnknownPromise(promise) => write!(f, "unknown promise {}", promise.0),
+    }
+  }
+}
+
+/// A single call of a segment. `target` and `argument` are operands: either
+/// an `Expr::Value` or an `Expr::Promise`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+  /// The promise the result of this call is bound to.
+  pub promise: PromiseId,
+  pub target: Expr,
+  pub method: MethodId,
+  pub argument: Option<Expr>,
+}
+
+impl Step {
+  /// The promises this call depends on.
+  pub fn promises(&self) -> impl Iterator<Item = PromiseId> + '_ {
+    std::iter::once(&self.target)
+      .chain(self.argument.iter())
+      .filter_map(|operand| match operand {
+        Expr::Promise(promise) => Some(*promise),
+        _ => None,
+      })
+  }
+}
+
+/// The calls of an expression that one owner can evaluate without waiting on
+/// anybody else.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+  pub owner: RemoteId,
+  /// Segments of the same round don't depend on each other. Each segment only
+  /// depends on segments of earlier rounds.
+  pub round: u32,
+  /// Calls in evaluation order. A call may use the promises bound by the calls
+  /// before it and the promises in `imports`.
+  pub steps: Vec<Step>,
+  /// Promises of other segments this segment uses.
+  pub imports: Vec<PromiseId>,
+  /// Promises of this segment that other segments use, or that hold the
+  /// result of the expression.
+  pub exports: Vec<PromiseId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+  /// Segments ordered by round.
+  pub segments: Vec<Segment>,
+  /// The promise holding the result of the expression.
+  pub result: PromiseId,
+}
+
+/// Splits `expr` into segments. Calls are bound to promises in the order they
+/// have to be made, and each is evaluated by the owner of its target object.
+/// A call is put in the same segment as the call using its result whenever
+/// they have the same owner, so a chain of calls on one node takes a single
+/// message however long it is.
+pub fn segment(expr: Expr) -> Result<Plan, SegmentError> {
+  let mut steps = Vec::new();
+  let mut owners = Vec::new();
+  let result = match flatten(expr, &mut steps, &mut owners)? {
+    Expr::Promise(promise) => promise,
+    _ => return Err(SegmentError::NoCall),
+  };
+
+  // The earliest round each call can be made in: one after the latest call of
+  // another owner it depends on.
+  let mut rounds: Vec<u32> = Vec::with_capacity(steps.len());
+  for (i, step) in steps.iter().enumerate() {
+    let round = step
+      .promises()
+      .map(|promise| {
+        let dep = promise.0 as usize;
+        rounds[dep] + (owners[dep] != owners[i]) as u32
+      })
+      .max()
+      .unwrap_or(0);
+    rounds.push(round);
+  }
+
+  // Expressions are trees, so every call but the last has exactly one user,
+  // which always comes after it. Delay calls to the round of their user when
+  // it has the same owner.
+  let mut users = vec![None; steps.len()];
+  for (i, step) in steps.iter().enumerate() {
+    for promise in step.promises() {
+      users[promise.0 as usize] = Some(i);
+    }
+  }
+  for i in (0..steps.len()).rev() {
+    if let Some(user) = users[i] {
+      if owners[user] == owners[i] {
+        rounds[i] = rounds[user];
+      }
+    }
+  }
+
+  let mut segments: BTreeMap<(u32, RemoteId), Segment> = BTreeMap::new();
+  for (i, step) in steps.into_iter().enumerate() {
+    let key = (rounds[i], owners[i]);
+    let mut imports = Vec::new();
+    for promise in step.promises() {
+      let dep = promise.0 as usize;
+      if (rounds[dep], owners[dep]) != key {
+        imports.push(promise);
+        let export = &mut segments
+          .get_mut(&(rounds[dep], owners[dep]))
+          .unwrap()
+          .exports;
+        if !export.contains(&promise) {
+          export.push(promise);
+        }
+      }
+    }
+    let segment = segments.entry(key).or_insert_with(|| Segment {
+      owner: key.1,
+      round: key.0,
+      steps: Vec::new(),
+      imports: Vec::new(),
+      exports: Vec::new(),
+    });
+    for promise in imports {
+      if !segment.imports.contains(&promise) {
+        segment.imports.push(promise);
+      }
+    }
+    segment.steps.push(step);
+  }
+  let last = (rounds[result.0 as usize], owners[result.0 as usize]);
+  segments.get_mut(&last).unwrap().exports.push(result);
+
+  Ok(Plan {
+    segments: segments.into_values().collect(),
+    result,
+  })
+}
+
+/// Appends the calls of `expr` to `steps` in evaluation order, and returns
+/// the operand standing for its value.
+fn flatten(
+  expr: Expr,
+  steps: &mut Vec<Step>,
+  owners: &mut Vec<RemoteId>,
+) -> Result<Expr, SegmentError> {
+  match expr {
+    Expr::Call { method, argument } => {
+      let (target, method) = match *method {
+        Expr::Method(target, method) => (*target, method),
+        Expr::Value(Value::Method(object, method)) => (Expr::Value(Value::Object(object)), method),
+        _ => return Err(SegmentError::NotCallable),
+      };
+      let target = flatten(target, steps, owners)?;
+      let owner = match &target {
+        Expr::Value(Value::Object(object)) => object.owner,
+        Expr::Promise(promise) => owners[promise.0 as usize],
+        _ => return Err(SegmentError::NotAnObject),
+      };
+      let argument = match argument {
+        Some(argument) => Some(flatten(*argument, steps, owners)?),
+        None => None,
+      };
+
+      let promise = PromiseId(steps.len() as u32);
+      steps.push(Step {
+        promise,
+        target,
+        method,
+        argument,
+      });
+      owners.push(owner);
+      Ok(Expr::Promise(promise))
+    }
+    Expr::Method(..) | Expr::Value(Value::Method(..)) => Err(SegmentError::UncalledMethod),
+    Expr::Value(value) => Ok(Expr::Value(value)),
+    Expr::Promise(promise) => Err(SegmentError::UnknownPromise(promise)),
+  }
+}
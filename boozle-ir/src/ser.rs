@@ -0,0 +1,423 @@
+use serde::ser::{
+  self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+  SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+
+use super::Value;
+
+/// Why a value couldn't be converted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error(pub String);
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+  fn custom<T: std::fmt::Display>(msg: T) -> Self {
+    Self(msg.to_string())
+  }
+}
+
+impl serde::de::Error for Error {
+  fn custom<T: std::fmt::Display>(msg: T) -> Self {
+    Self(msg.to_string())
+  }
+}
+
+impl Serialize for Value {
+  fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self {
+      Self::Unit => serializer.serialize_unit(),
+      Self::Bool(value) => serializer.serialize_bool(*value),
+      Self::U8(value) => serializer.serialize_u8(*value),
+      Self::U16(value) => serializer.serialize_u16(*value),
+      Self::U32(value) => serializer.serialize_u32(*value),
+      Self::U64(value) => serializer.serialize_u64(*value),
+      Self::I8(value) => serializer.serialize_i8(*value),
+      Self::I16(value) => serializer.serialize_i16(*value),
+      Self::I32(value) => serializer.serialize_i32(*value),
+      Self::I64(value) => serializer.serialize_i64(*value),
+      Self::F32(value) => serializer.serialize_f32(*value),
+      Self::F64(value) => serializer.serialize_f64(*value),
+      Self::Char(value) => serializer.serialize_char(*value),
+      Self::String(value) => serializer.serialize_str(value),
+      Self::Bytes(value) => serializer.serialize_bytes(value),
+      Self::Option(None) => serializer.serialize_none(),
+      Self::Option(Some(value)) => serializer.serialize_some(value.as_ref()),
+      Self::Array(values) => {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values.iter() {
+          seq.serialize_element(value)?;
+        }
+        seq.end()
+      }
+      Self::Tuple(values) => {
+        let mut tuple = serializer.serialize_tuple(values.len())?;
+        for value in values.iter() {
+          tuple.serialize_element(value)?;
+        }
+        tuple.end()
+      }
+      Self::Map(entries) => {
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries.iter() {
+          map.serialize_entry(key, value)?;
+        }
+        map.end()
+      }
+      // Serde only takes static field and variant names, so names are
+      // written as map keys.
+      Self::Struct(fields) if serializer.is_human_readable() => {
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for (name, value) in fields.iter() {
+          map.serialize_entry(name, value)?;
+        }
+        map.end()
+      }
+      Self::Struct(fields) => {
+        let mut tuple = serializer.serialize_tuple(fields.len())?;
+        for (_, value) in fields.iter() {
+          tuple.serialize_element(value)?;
+        }
+        tuple.end()
+      }
+      Self::Variant { name, value, .. } if serializer.is_human_readable() => match value.as_ref() {
+        Self::Unit => serializer.serialize_str(name),
+        value => {
+          let mut map = serializer.serialize_map(Some(1))?;
+          map.serialize_entry(name, value)?;
+          map.end()
+        }
+      },
+      Self::Variant { index, value, .. } => {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(index)?;
+        tuple.serialize_element(value.as_ref())?;
+        tuple.end()
+      }
+      Self::Object(_) | Self::Method(..) => Err(ser::Error::custom("objects can't be serialized")),
+      Self::Lit(_) => Err(ser::Error::custom("encoded literals can't be serialized")),
+    }
+  }
+}
+
+/// Converts `value` to a `Value`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+  value.serialize(Serializer)
+}
+
+struct Serializer;
+
+/// Collects the values of a sequence, tuple or struct.
+struct Collect {
+  values: Vec<Value>,
+  names: Vec<String>,
+  variant: Option<(u32, &'static str)>,
+  key: Option<Value>,
+  entries: Vec<(Value, Value)>,
+}
+
+impl Collect {
+  fn new(variant: Option<(u32, &'static str)>) -> Self {
+    Self {
+      values: Vec::new(),
+      names: Vec::new(),
+      variant,
+      key: None,
+      entries: Vec::new(),
+    }
+  }
+
+  fn wrap(variant: Option<(u32, &'static str)>, value: Value) -> Value {
+    match variant {
+      Some((index, name)) => Value::Variant {
+        index,
+        name: name.to_string(),
+        value: Box::new(value),
+      },
+      None => value,
+    }
+  }
+
+  fn tuple(self) -> Value {
+    Self::wrap(self.variant, Value::Tuple(self.values.into_boxed_slice()))
+  }
+
+  fn fields(self) -> Value {
+    let fields = self.names.into_iter().zip(self.values).collect::<Vec<_>>();
+    Self::wrap(self.variant, Value::Struct(fields.into_boxed_slice()))
+  }
+}
+
+impl ser::Serializer for Serializer {
+  type Ok = Value;
+  type Error = Error;
+  type SerializeSeq = Collect;
+  type SerializeTuple = Collect;
+  type SerializeTupleStruct = Collect;
+  type SerializeTupleVariant = Collect;
+  type SerializeMap = Collect;
+  type SerializeStruct = Collect;
+  type SerializeStructVariant = Collect;
+
+  fn serialize_bool(self, value: bool) -> Result<Value, Error> {
+    Ok(Value::Bool(value))
+  }
+
+  fn serialize_i8(self, value: i8) -> Result<Value, Error> {
+    Ok(Value::I8(value))
+  }
+
+  fn serialize_i16(self, value: i16) -> Result<Value, Error> {
+    Ok(Value::I16(value))
+  }
+
+  fn serialize_i32(self, value: i32) -> Result<Value, Error> {
+    Ok(Value::I32(value))
+  }
+
+  fn serialize_i64(self, value: i64) -> Result<Value, Error> {
+    Ok(Value::I64(value))
+  }
+
+  fn serialize_u8(self, value: u8) -> Result<Value, Error> {
+    Ok(Value::U8(value))
+  }
+
+  fn serialize_u16(self, value: u16) -> Result<Value, Error> {
+    Ok(Value::U16(value))
+  }
+
+  fn serialize_u32(self, value: u32) -> Result<Value, Error> {
+    Ok(Value::U32(value))
+  }
+
+  fn serialize_u64(self, value: u64) -> Result<Value, Error> {
+    Ok(Value::U64(value))
+  }
+
+  fn serialize_f32(self, value: f32) -> Result<Value, Error> {
+    Ok(Value::F32(value))
+  }
+
+  fn serialize_f64(self, value: f64) -> Result<Value, Error> {
+    Ok(Value::F64(value))
+  }
+
+  fn serialize_char(self, value: char) -> Result<Value, Error> {
+    Ok(Value::Char(value))
+  }
+
+  fn serialize_str(self, value: &str) -> Result<Value, Error> {
+    Ok(Value::String(value.to_string()))
+  }
+
+  fn serialize_bytes(self, value: &[u8]) -> Result<Value, Error> {
+    Ok(Value::Bytes(value.into()))
+  }
+
+  fn serialize_none(self) -> Result<Value, Error> {
+    Ok(Value::Option(None))
+  }
+
+  fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+    Ok(Value::Option(Some(Box::new(to_value(value)?))))
+  }
+
+  fn serialize_unit(self) -> Result<Value, Error> {
+    Ok(Value::Unit)
+  }
+
+  fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Error> {
+    Ok(Value::Unit)
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _: &'static str,
+    index: u32,
+    variant: &'static str,
+  ) -> Result<Value, Error> {
+    Ok(Collect::wrap(Some((index, variant)), Value::Unit))
+  }
+
+  fn serialize_newtype_struct<T: Serialize + ?Sized>(
+    self,
+    _: &'static str,
+    value: &T,
+  ) -> Result<Value, Error> {
+    to_value(value)
+  }
+
+  fn serialize_newtype_variant<T: Serialize + ?Sized>(
+    self,
+    _: &'static str,
+    index: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<Value, Error> {
+    Ok(Collect::wrap(Some((index, variant)), to_value(value)?))
+  }
+
+  fn serialize_seq(self, _: Option<usize>) -> Result<Collect, Error> {
+    Ok(Collect::new(None))
+  }
+
+  fn serialize_tuple(self, _: usize) -> Result<Collect, Error> {
+    Ok(Collect::new(None))
+  }
+
+  fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Collect, Error> {
+    Ok(Collect::new(None))
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _: &'static str,
+    index: u32,
+    variant: &'static str,
+    _: usize,
+  ) -> Result<Collect, Error> {
+    Ok(Collect::new(Some((index, variant))))
+  }
+
+  fn serialize_map(self, _: Option<usize>) -> Result<Collect, Error> {
+    Ok(Collect::new(None))
+  }
+
+  fn serialize_struct(self, _: &'static str, _: usize) -> Result<Collect, Error> {
+    Ok(Collect::new(None))
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _: &'static str,
+    index: u32,
+    variant: &'static str,
+    _: usize,
+  ) -> Result<Collect, Error> {
+    Ok(Collect::new(Some((index, variant))))
+  }
+}
+
+impl SerializeSeq for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(Value::Array(self.values.into_boxed_slice()))
+  }
+}
+
+impl SerializeTuple for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(self.tuple())
+  }
+}
+
+impl SerializeTupleStruct for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(self.tuple())
+  }
+}
+
+impl SerializeTupleVariant for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(self.tuple())
+  }
+}
+
+impl SerializeMap for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+    self.key = Some(to_value(key)?);
+    Ok(())
+  }
+
+  fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+    let key = self
+      .key
+      .take()
+      .ok_or_else(|| Error("map value without a key".to_string()))?;
+    self.entries.push((key, to_value(value)?));
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(Value::Map(self.entries.into_boxed_slice()))
+  }
+}
+
+impl SerializeStruct for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    name: &'static str,
+    value: &T,
+  ) -> Result<(), Error> {
+    self.names.push(name.to_string());
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(self.fields())
+  }
+}
+
+impl SerializeStructVariant for Collect {
+  type Ok = Value;
+  type Error = Error;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    name: &'static str,
+    value: &T,
+  ) -> Result<(), Error> {
+    self.names.push(name.to_string());
+    self.values.push(to_value(value)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Value, Error> {
+    Ok(self.fields())
+  }
+}
@@ -0,0 +1,241 @@
+use serde::de::{
+  self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+};
+
+use super::Value;
+
+/// The shape of a value. Formats such as bincode don't describe what they
+/// hold, so decoding a literal into a `Value` needs its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+  Unit,
+  Bool,
+  U8,
+  U16,
+  U32,
+  U64,
+  I8,
+  I16,
+  I32,
+  I64,
+  F32,
+  F64,
+  Char,
+  String,
+  Bytes,
+  Option(Box<Type>),
+  Array(Box<Type>),
+  Tuple(Vec<Type>),
+  Map(Box<Type>, Box<Type>),
+  Struct(Vec<(String, Type)>),
+  /// The variants of an enum, in order. Variants without a payload have the
+  /// `Unit` type and variants with several fields a `Tuple` or `Struct`.
+  Enum(Vec<(String, Type)>),
+}
+
+impl Type {
+  /// The type a primitive is called in .boozle files, if `name` is one.
+  pub fn named(name: &str) -> Option<Self> {
+    Some(match name {
+      "()" => Self::Unit,
+      "bool" => Self::Bool,
+      "u8" => Self::U8,
+      "u16" => Self::U16,
+      "u32" => Self::U32,
+      "u64" | "usize" => Self::U64,
+      "i8" => Self::I8,
+      "i16" => Self::I16,
+      "i32" => Self::I32,
+      "i64" | "isize" => Self::I64,
+      "f32" => Self::F32,
+      "f64" => Self::F64,
+      "char" => Self::Char,
+      "String" => Self::String,
+      _ => return None,
+    })
+  }
+}
+
+/// Decodes a value of this type, for example from the bincode of a literal.
+impl<'de> DeserializeSeed<'de> for &Type {
+  type Value = Value;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    Ok(match self {
+      Type::Unit => <()>::deserialize(deserializer).map(|_| Value::Unit)?,
+      Type::Bool => Value::Bool(bool::deserialize(deserializer)?),
+      Type::U8 => Value::U8(u8::deserialize(deserializer)?),
+      Type::U16 => Value::U16(u16::deserialize(deserializer)?),
+      Type::U32 => Value::U32(u32::deserialize(deserializer)?),
+      Type::U64 => Value::U64(u64::deserialize(deserializer)?),
+      Type::I8 => Value::I8(i8::deserialize(deserializer)?),
+      Type::I16 => Value::I16(i16::deserialize(deserializer)?),
+      Type::I32 => Value::I32(i32::deserialize(deserializer)?),
+      Type::I64 => Value::I64(i64::deserialize(deserializer)?),
+      Type::F32 => Value::F32(f32::deserialize(deserializer)?),
+      Type::F64 => Value::F64(f64::deserialize(deserializer)?),
+      Type::Char => Value::Char(char::deserialize(deserializer)?),
+      Type::String => Value::String(String::deserialize(deserializer)?),
+      Type::Bytes => deserializer.deserialize_byte_buf(Visitor(self))?,
+      Type::Option(_) => deserializer.deserialize_option(Visitor(self))?,
+      Type::Array(_) => deserializer.deserialize_seq(Visitor(self))?,
+      Type::Tuple(types) => deserializer.deserialize_tuple(types.len(), Visitor(self))?,
+      Type::Map(..) => deserializer.deserialize_map(Visitor(self))?,
+      // Human-readable formats may write structs as maps.
+      Type::Struct(_) if deserializer.is_human_readable() => {
+        deserializer.deserialize_any(Visitor(self))?
+      }
+      Type::Struct(fields) => deserializer.deserialize_tuple(fields.len(), Visitor(self))?,
+      Type::Enum(_) => deserializer.deserialize_enum("", &[], Visitor(self))?,
+    })
+  }
+}
+
+struct Visitor<'a>(&'a Type);
+
+impl<'a, 'de> de::Visitor<'de> for Visitor<'a> {
+  type Value = Value;
+
+  fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "a value of type {:?}", self.0)
+  }
+
+  fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+    Ok(Value::Bytes(value.into()))
+  }
+
+  fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E> {
+    Ok(Value::Bytes(value.into_boxed_slice()))
+  }
+
+  fn visit_none<E>(self) -> Result<Value, E> {
+    Ok(Value::Option(None))
+  }
+
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    match self.0 {
+      Type::Option(ty) => Ok(Value::Option(Some(Box::new(
+        ty.as_ref().deserialize(deserializer)?,
+      )))),
+      _ => Err(de::Error::custom("unexpected option")),
+    }
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+    let missing = || de::Error::custom("missing element");
+    Ok(match self.0 {
+      Type::Bytes => {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+          bytes.push(byte);
+        }
+        Value::Bytes(bytes.into_boxed_slice())
+      }
+      Type::Array(ty) => {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element_seed(ty.as_ref())? {
+          values.push(value);
+        }
+        Value::Array(values.into_boxed_slice())
+      }
+      Type::Tuple(types) => {
+        let mut values = Vec::with_capacity(types.len());
+        for ty in types.iter() {
+          values.push(seq.next_element_seed(ty)?.ok_or_else(missing)?);
+        }
+        Value::Tuple(values.into_boxed_slice())
+      }
+      Type::Struct(fields) => {
+        let mut values = Vec::with_capacity(fields.len());
+        for (name, ty) in fields.iter() {
+          values.push((
+            name.clone(),
+            seq.next_element_seed(ty)?.ok_or_else(missing)?,
+          ));
+        }
+        Value::Struct(values.into_boxed_slice())
+      }
+      _ => return Err(de::Error::custom("unexpected sequence")),
+    })
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+    Ok(match self.0 {
+      Type::Map(key, value) => {
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key_seed(key.as_ref())? {
+          entries.push((key, map.next_value_seed(value.as_ref())?));
+        }
+        Value::Map(entries.into_boxed_slice())
+      }
+      Type::Struct(fields) => {
+        let mut values: Vec<Option<Value>> = vec![None; fields.len()];
+        while let Some(name) = map.next_key::<String>()? {
+          let index = fields
+            .iter()
+            .position(|(field, _)| *field == name)
+            .ok_or_else(|| de::Error::custom(format!("unknown field {}", name)))?;
+          values[index] = Some(map.next_value_seed(&fields[index].1)?);
+        }
+        let mut named = Vec::with_capacity(fields.len());
+        for ((name, _), value) in fields.iter().zip(values) {
+          let value = value.ok_or_else(|| de::Error::custom(format!("missing field {}", name)))?;
+          named.push((name.clone(), value));
+        }
+        Value::Struct(named.into_boxed_slice())
+      }
+      _ => return Err(de::Error::custom("unexpected map")),
+    })
+  }
+
+  fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Value, A::Error> {
+    let variants = match self.0 {
+      Type::Enum(variants) => variants,
+      _ => return Err(de::Error::custom("unexpected enum")),
+    };
+    let (index, variant) = data.variant_seed(VariantSeed(variants))?;
+    let (name, ty) = &variants[index as usize];
+    let value = match ty {
+      Type::Unit => variant.unit_variant().map(|_| Value::Unit)?,
+      ty => variant.newtype_variant_seed(ty)?,
+    };
+    Ok(Value::Variant {
+      index,
+      name: name.clone(),
+      value: Box::new(value),
+    })
+  }
+}
+
+/// Identifies a variant by its index or its name.
+struct VariantSeed<'a>(&'a [(String, Type)]);
+
+impl<'a, 'de> DeserializeSeed<'de> for VariantSeed<'a> {
+  type Value = u32;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+    deserializer.deserialize_identifier(self)
+  }
+}
+
+impl<'a, 'de> de::Visitor<'de> for VariantSeed<'a> {
+  type Value = u32;
+
+  fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("a variant")
+  }
+
+  fn visit_u64<E: de::Error>(self, index: u64) -> Result<u32, E> {
+    if index as usize >= self.0.len() {
+      return Err(E::custom(format!("unknown variant {}", index)));
+    }
+    Ok(index as u32)
+  }
+
+  fn visit_str<E: de::Error>(self, name: &str) -> Result<u32, E> {
+    match self.0.iter().position(|(variant, _)| variant == name) {
+      Some(index) => Ok(index as u32),
+      None => Err(E::custom(format!("unknown variant {}", name))),
+    }
+  }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use super::{Expr, MethodId, Object, PromiseId, Segment, Value};
+
+/// A straight-line stack program. It starts with `inputs` values on its stack
+/// and its results are the top `outputs` values of the stack when it ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+  pub inputs: u16,
+  pub outputs: u16,
+  pub consts: Vec<Value>,
+  pub instructions: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+  /// Pushes a constant.
+  Push(u16),
+  /// Drops the top of the stack.
+  Pop,
+  /// Pushes a copy of a stack slot, counted from the bottom.
+  Load(u16),
+  /// Pops the argument if there is one, then the object to call, and pushes
+  /// the result. Calls without a result push nothing in particular, which
+  /// can't be called or passed.
+  Invoke { method_id: MethodId, argument: bool },
+  /// Releases the objects in a constant, either an object or an array of
+  /// objects. The stack is left as it is.
+  Free(u16),
+}
+
+/// What a unit may use while running. Memory is an estimate of the size of
+/// the constants and the values on the stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+  pub max_instructions: usize,
+  pub max_depth: usize,
+  pub max_memory: usize,
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Self {
+      max_instructions: 4096,
+      max_depth: 256,
+      max_memory: 16 * 1024 * 1024,
+    }
+  }
+}
+
+impl Limits {
+  /// No limits, for units from a trusted source.
+  pub fn unlimited() -> Self {
+    Self {
+      max_instructions: usize::MAX,
+      max_depth: usize::MAX,
+      max_memory: usize::MAX,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+  TooManyInstructions,
+  TooMuchMemory,
+  /// The instruction at the index would make the stack too deep.
+  TooDeep(usize),
+  /// The instruction at the index needs more values than are on the stack.
+  Underflow(usize),
+  /// The instruction at the index refers to a constant that doesn't exist.
+  UnknownConst(usize),
+  /// The instruction at the index loads a slot above the top of the stack.
+  UnknownSlot(usize),
+  /// The instruction at the index frees a constant that isn't an object or
+  /// an array of objects.
+  NotObjects(usize),
+  /// The stack ends up with fewer values than the unit's outputs.
+  MissingOutputs,
+}
+
+impl std::fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TooManyInstructions => write!(f, "too many instructions"),
+      Self::TooMuchMemory => write!(f, "constants too large"),
+      Self::TooDeep(at) => write!(f, "instruction {}: stack too deep", at),
+      Self::Underflow(at) => write!(f, "instruction {}: stack underflow", at),
+      Self::UnknownConst(at) => write!(f, "instruction {}: unknown constant", at),
+      Self::UnknownSlot(at) => write!(f, "instruction {}: unknown stack slot", at),
+      Self::NotObjects(at) => write!(f, "instruction {}: can only free objects", at),
+      Self::MissingOutputs => write!(f, "the stack ends with fewer values than the outputs"),
+    }
+  }
+}
+
+/// Estimates the memory a value takes.
+pub fn size(value: &Value) -> usize {
+  std::mem::size_of::<Value>()
+    + match value {
+      Value::String(value) => value.len(),
+      Value::Lit(value) | Value::Bytes(value) => value.len(),
+      Value::Option(Some(value)) => size(value),
+      Value::Array(values) | Value::Tuple(values) => values.iter().map(size).sum(),
+      Value::Map(entries) => entries
+        .iter()
+        .map(|(key, value)| size(key) + size(value))
+        .sum(),
+      Value::Struct(fields) => fields
+        .iter()
+        .map(|(name, value)| name.len() + size(value))
+        .sum(),
+      Value::Variant { name, value, .. } => name.len() + size(value),
+      _ => 0,
+    }
+}
+
+/// The objects a `Free` instruction releases, if `value` can be freed.
+pub fn objects(value: &Value) -> Option<Vec<Object>> {
+  match value {
+    Value::Object(object) => Some(vec![*object]),
+    Value::Array(values) => values
+      .iter()
+      .map(|value| match value {
+        Value::Object(object) => Some(*object),
+        _ => None,
+      })
+      .collect(),
+    _ => None,
+  }
+}
+
+impl Unit {
+  /// Checks that the unit runs within `limits` whatever its calls return.
+  /// Units have no branches, so this only needs to follow the stack depth.
+  pub fn verify(&self, limits: &Limits) -> Result<(), VerifyError> {
+    if self.instructions.len() > limits.max_instructions {
+      return Err(VerifyError::TooManyInstructions);
+    }
+    if self.consts.iter().map(size).sum::<usize>() > limits.max_memory {
+      return Err(VerifyError::TooMuchMemory);
+    }
+
+    let mut depth = self.inputs as usize;
+    if depth > limits.max_depth {
+      return Err(VerifyError::TooDeep(0));
+    }
+    for (at, instruction) in self.instructions.iter().enumerate() {
+      depth = match instruction {
+        Instruction::Push(index) if *index as usize >= self.consts.len() => {
+          return Err(VerifyError::UnknownConst(at))
+        }
+        Instruction::Push(_) => depth + 1,
+        Instruction::Pop if depth == 0 => return Err(VerifyError::Underflow(at)),
+        Instruction::Pop => depth - 1,
+        Instruction::Load(slot) if *slot as usize >= depth => {
+          return Err(VerifyError::UnknownSlot(at))
+        }
+        Instruction::Load(_) => depth + 1,
+        Instruction::Free(index) => match self.consts.get(*index as usize) {
+          None => return Err(VerifyError::UnknownConst(at)),
+          Some(value) if objects(value).is_none() => return Err(VerifyError::NotObjects(at)),
+          Some(_) => depth,
+        },
+        Instruction::Invoke { argument, .. } => {
+          let popped = 1 + *argument as usize;
+          if depth < popped {
+            return Err(VerifyError::Underflow(at));
+          }
+          depth + 1 - popped
+        }
+      };
+      if depth > limits.max_depth {
+        return Err(VerifyError::TooDeep(at));
+      }
+    }
+    if depth < self.outputs as usize {
+      return Err(VerifyError::MissingOutputs);
+    }
+    Ok(())
+  }
+}
+
+/// Compiles a segment made by `segment`. The unit's inputs are the segment's
+/// imports and its outputs are the segment's exports, in order.
+pub fn compile(segment: &Segment) -> Unit {
+  let mut unit = Unit {
+    inputs: segment.imports.len() as u16,
+    outputs: segment.exports.len() as u16,
+    consts: Vec::new(),
+    instructions: Vec::new(),
+  };
+  let mut slots: HashMap<PromiseId, u16> = HashMap::new();
+  for (slot, promise) in segment.imports.iter().enumerate() {
+    slots.insert(*promise, slot as u16);
+  }
+  // Each call leaves its result on the stack, above the imports.
+  for (i, step) in segment.steps.iter().enumerate() {
+    operand(&mut unit, &slots, &step.target);
+    if let Some(argument) = &step.argument {
+      operand(&mut unit, &slots, argument);
+    }
+    unit.instructions.push(Instruction::Invoke {
+      method_id: step.method,
+      argument: step.argument.is_some(),
+    });
+    slots.insert(step.promise, (segment.imports.len() + i) as u16);
+  }
+  for promise in segment.exports.iter() {
+    unit.instructions.push(Instruction::Load(slots[promise]));
+  }
+  unit
+}
+
+/// Emits the instruction pushing an operand of a step.
+fn operand(unit: &mut Unit, slots: &HashMap<PromiseId, u16>, expr: &Expr) {
+  let instruction = match expr {
+    Expr::Promise(promise) => Instruction::Load(slots[promise]),
+    Expr::Value(value) => {
+      let index = match unit.consts.iter().position(|known| known == value) {
+        Some(index) => index,
+        None => {
+          unit.consts.push(value.clone());
+          unit.consts.len() - 1
+        }
+      };
+      Instruction::Push(index as u16)
+    }
+    Expr::Method(..) | Expr::Call { .. } => unreachable!("segments only contain operands"),
+  };
+  unit.instructions.push(instruction);
+}
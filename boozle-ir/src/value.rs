@@ -1,88 +1,159 @@
-// pub enum Value {
-//   U8(u8),
-//   U16(u16),
-//   U32(u32),
-//   U64(u64),
-//   I8(i8),
-//   I16(i16),
-//   I32(i32),
-//   I64(i64),
-//   F32(f32),
-//   F64(f64),
-//   String(String),
-//   Array(Box<[Value]>),
-//   Object(Object),
-//   Method(Object, u32),
-// }
-
-// impl From<u8> for Value {
-//   fn from(value: u8) -> Self {
-//     Self::U8(value)
-//   }
-// }
-
-// impl From<u16> for Value {
-//   fn from(value: u16) -> Self {
-//     Self::U16(value)
-//   }
-// }
-
-// impl From<u32> for Value {
-//   fn from(value: u32) -> Self {
-//     Self::U32(value)
-//   }
-// }
-
-// impl From<u64> for Value {
-//   fn from(value: u64) -> Self {
-//     Self::U64(value)
-//   }
-// }
-
-// impl From<i8> for Value {
-//   fn from(value: i8) -> Self {
-//     Self::I8(value)
-//   }
-// }
-
-// impl From<i16> for Value {
-//   fn from(value: i16) -> Self {
-//     Self::I16(value)
-//   }
-// }
-
-// impl From<i32> for Value {
-//   fn from(value: i32) -> Self {
-//     Self::I32(value)
-//   }
-// }
-
-// impl From<i64> for Value {
-//   fn from(value: i64) -> Self {
-//     Self::I64(value)
-//   }
-// }
-
-// impl From<String> for Value {
-//   fn from(value: String) -> Self {
-//     Self::String(value)
-//   }
-// }
-
-// impl From<Object> for Value {
-//   fn from(value: Object) -> Self {
-//     Self::Object(value)
-//   }
-// }
-
-// impl<T: Into<Value>> From<Vec<T>> for Value {
-//   fn from(value: Vec<T>) -> Self {
-//     Self::Array(
-//       value
-//         .into_iter()
-//         .map(|v| v.into())
-//         .collect::<Vec<Value>>()
-//         .into_boxed_slice(),
-//     )
-//   }
-// }
+use super::{MethodId, Object};
+
+/// A self-describing value. Besides objects and methods, it covers the serde
+/// data model, so any serializable type can be converted to a `Value` and
+/// back (see `to_value` and `from_value`).
+///
+/// A value serializes as the type it stands for: encoding `to_value(&x)` with
+/// bincode gives the same literal as encoding `x`. Structs and variants
+/// serialize by position, or by name in human-readable formats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Unit,
+  Bool(bool),
+  U8(u8),
+  U16(u16),
+  U32(u32),
+  U64(u64),
+  I8(i8),
+  I16(i16),
+  I32(i32),
+  I64(i64),
+  F32(f32),
+  F64(f64),
+  Char(char),
+  String(String),
+  Bytes(Box<[u8]>),
+  Option(Option<Box<Value>>),
+  /// A sequence, which is encoded with its length.
+  Array(Box<[Value]>),
+  /// A fixed number of values, such as the parameters of a method.
+  Tuple(Box<[Value]>),
+  Map(Box<[(Value, Value)]>),
+  Struct(Box<[(String, Value)]>),
+  /// A variant of an enum. `index` is its position in the enum.
+  Variant {
+    index: u32,
+    name: String,
+    value: Box<Value>,
+  },
+  Object(Object),
+  Method(Object, MethodId),
+  /// A literal that has already been encoded by its sender. The IR doesn't
+  /// look inside it.
+  Lit(Box<[u8]>),
+}
+
+impl From<()> for Value {
+  fn from(_: ()) -> Self {
+    Self::Unit
+  }
+}
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Self {
+    Self::Bool(value)
+  }
+}
+
+impl From<char> for Value {
+  fn from(value: char) -> Self {
+    Self::Char(value)
+  }
+}
+
+impl From<u8> for Value {
+  fn from(value: u8) -> Self {
+    Self::U8(value)
+  }
+}
+
+impl From<u16> for Value {
+  fn from(value: u16) -> Self {
+    Self::U16(value)
+  }
+}
+
+impl From<u32> for Value {
+  fn from(value: u32) -> Self {
+    Self::U32(value)
+  }
+}
+
+impl From<u64> for Value {
+  fn from(value: u64) -> Self {
+    Self::U64(value)
+  }
+}
+
+impl From<i8> for Value {
+  fn from(value: i8) -> Self {
+    Self::I8(value)
+  }
+}
+
+impl From<i16> for Value {
+  fn from(value: i16) -> Self {
+    Self::I16(value)
+  }
+}
+
+impl From<i32> for Value {
+  fn from(value: i32) -> Self {
+    Self::I32(value)
+  }
+}
+
+impl From<i64> for Value {
+  fn from(value: i64) -> Self {
+    Self::I64(value)
+  }
+}
+
+impl From<f32> for Value {
+  fn from(value: f32) -> Self {
+    Self::F32(value)
+  }
+}
+
+impl From<f64> for Value {
+  fn from(value: f64) -> Self {
+    Self::F64(value)
+  }
+}
+
+impl From<String> for Value {
+  fn from(value: String) -> Self {
+    Self::String(value)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Self {
+    Self::String(value.to_string())
+  }
+}
+
+impl From<Object> for Value {
+  fn from(value: Object) -> Self {
+    Self::Object(value)
+  }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+  fn from(value: Vec<T>) -> Self {
+    Self::Array(
+      value
+        .into_iter()
+        .map(|v| v.into())
+        .collect::<Vec<Value>>()
+        .into_boxed_slice(),
+    )
+  }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+  fn from(value: Option<T>) -> Self {
+    Self::Option(value.map(|value| Box::new(value.into())))
+  }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use boozle_ir::{
+  eliminate_common_calls, eliminate_dead_calls, merge_frees, objects, optimize, segment, Expr,
+  Instruction, Limits, MethodId, Object, Plan, PromiseId, RemoteId, Step, Unit, Value, VerifyError,
+};
+
+const ADD: u64 = 1;
+const NEG: u64 = 2;
+/// Records its target and argument, and returns its target.
+const LOG: u64 = 3;
+
+fn pure(method: MethodId) -> bool {
+  method.0 != LOG
+}
+
+fn object(owner: u32, addr: u64) -> Expr {
+  Object::new(RemoteId(owner), addr).into()
+}
+
+/// Runs `plan` over objects standing for their address. Each segment only
+/// sees its own results and its imports, which must have been exported by an
+/// earlier round. Returns the result and the calls to `LOG`.
+fn eval(plan: &Plan) -> (i64, Vec<(i64, i64)>) {
+  let mut exported: HashMap<PromiseId, (u32, i64)> = HashMap::new();
+  let mut log = Vec::new();
+  for segment in plan.segments.iter() {
+    let mut promises: HashMap<PromiseId, i64> = HashMap::new();
+    for import in segment.imports.iter() {
+      let (round, value) = exported[import];
+      assert!(round < segment.round);
+      promises.insert(*import, value);
+    }
+    let operand = |promises: &HashMap<PromiseId, i64>, expr: &Expr| match expr {
+      Expr::Promise(promise) => promises[promise],
+      Expr::Value(Value::Object(object)) => object.addr as i64,
+      Expr::Value(Value::I64(value)) => *value,
+      expr => panic!("unexpected operand {:?}", expr),
+    };
+    for step in segment.steps.iter() {
+      let target = operand(&promises, &step.target);
+      let argument = step
+        .argument
+        .as_ref()
+        .map(|argument| operand(&promises, argument));
+      let value = match step.method.0 {
+        ADD => target + argument.unwrap(),
+        NEG => -target,
+        LOG => {
+          log.push((target, argument.unwrap_or(0)));
+          target
+        }
+        method => panic!("unknown method {}", method),
+      };
+      promises.insert(step.promise, value);
+    }
+    for export in segment.exports.iter() {
+      exported.insert(*export, (segment.round, promises[export]));
+    }
+  }
+  (exported[&plan.result].1, log)
+}
+
+fn steps(plan: &Plan) -> usize {
+  plan
+    .segments
+    .iter()
+    .map(|segment| segment.steps.len())
+    .sum()
+}
+
+#[test]
+fn eliminates_common_pure_calls() {
+  // a.add(b.neg()).add(b.neg()).log(b.log(1)).add(b.log(1)), with `a` on
+  // node 1 and `b` on node 2. Only b.neg() can be made once.
+  let a = object(1, 10);
+  let b = object(2, 20);
+  let expr = a
+    .call(ADD, Some(b.clone().call(NEG, None)))
+    .call(ADD, Some(b.clone().call(NEG, None)))
+    .call(LOG, Some(b.clone().call(LOG, Some(Value::I64(1).into()))))
+    .call(ADD, Some(b.call(LOG, Some(Value::I64(1).into()))));
+  let original = segment(expr).unwrap();
+
+  let mut plan = original.clone();
+  eliminate_common_calls(&mut plan, pure);
+  assert_eq!(steps(&plan), steps(&original) - 1);
+  assert_eq!(eval(&plan), eval(&original));
+  assert_eq!(eval(&plan), (-10, vec![(20, 1), (20, 1), (-30, 20)]));
+}
+
+#[test]
+fn eliminates_dead_pure_calls() {
+  // a.add(1), plus calls nobody uses: a.neg().neg() and b.log().
+  let a = object(1, 10);
+  let mut original = segment(a.clone().call(ADD, Some(Value::I64(1).into()))).unwrap();
+  original.segments[0].steps.extend(vec![
+    Step {
+      promise: PromiseId(1),
+      target: a.clone(),
+      method: NEG.into(),
+      argument: None,
+    },
+    Step {
+      promise: PromiseId(2),
+      target: Expr::Promise(PromiseId(1)),
+      method: NEG.into(),
+      argument: None,
+    },
+  ]);
+  let mut log = segment(object(2, 20).call(LOG, None)).unwrap().segments;
+  log[0].steps[0].promise = PromiseId(3);
+  log[0].exports.clear();
+  original.segments.extend(log);
+
+  let mut plan = original.clone();
+  eliminate_dead_calls(&mut plan, pure);
+  assert_eq!(steps(&plan), 2);
+  assert_eq!(plan.segments[0].steps[0].promise, PromiseId(0));
+  assert_eq!(plan.segments[1].steps[0].method, MethodId(LOG));
+  assert_eq!(eval(&plan), eval(&original));
+  assert_eq!(eval(&plan), (11, vec![(20, 0)]));
+}
+
+#[test]
+fn keeps_results_shared_across_segments() {
+  // c.add(b.neg().add(a.neg())).add(b.neg().add(a.neg())): the repeated
+  // calls on `a` and `b` are made once and exported once.
+  let a = object(1, 10);
+  let b = object(2, 20);
+  let c = object(3, 30);
+  let sum = || {
+    b.clone()
+      .call(NEG, None)
+      .call(ADD, Some(a.clone().call(NEG, None)))
+  };
+  let original = segment(c.call(ADD, Some(sum())).call(ADD, Some(sum()))).unwrap();
+
+  let mut plan = original.clone();
+  optimize(&mut plan, pure);
+  assert_eq!(steps(&plan), steps(&original) - 3);
+  for segment in plan.segments.iter() {
+    assert!(segment.exports.len() <= 1);
+  }
+  assert_eq!(eval(&plan), eval(&original));
+  assert_eq!(eval(&plan).0, 30 - 60);
+}
+
+/// The objects a unit frees, in order.
+fn freed(unit: &Unit) -> Vec<Object> {
+  unit
+    .instructions
+    .iter()
+    .filter_map(|instruction| match instruction {
+      Instruction::Free(index) => objects(&unit.consts[*index as usize]),
+      _ => None,
+    })
+    .flatten()
+    .collect()
+}
+
+#[test]
+fn merges_adjacent_frees() {
+  let object = |addr| Value::Object(Object::new(RemoteId::LOCAL, addr));
+  let original = Unit {
+    inputs: 0,
+    outputs: 1,
+    consts: vec![
+      object(1),
+      Value::Array(vec![object(2), object(3)].into_boxed_slice()),
+      Value::I64(5),
+      object(4),
+    ],
+    instructions: vec![
+      Instruction::Free(0),
+      Instruction::Free(1),
+      Instruction::Push(2),
+      Instruction::Free(3),
+      Instruction::Free(0),
+      Instruction::Free(3),
+    ],
+  };
+
+  let mut unit = original.clone();
+  merge_frees(&mut unit);
+  assert_eq!(unit.instructions.len(), 3);
+  assert_eq!(unit.consts.len(), 3);
+  assert_eq!(freed(&unit), freed(&original));
+  assert_eq!(unit.verify(&Limits::default()), Ok(()));
+  assert_eq!(Unit::decode(&unit.encode()).unwrap(), unit);
+
+  // Units freeing anything else are left for `verify` to reject.
+  let mut invalid = original;
+  invalid.instructions.push(Instruction::Free(2));
+  let mut merged = invalid.clone();
+  merge_frees(&mut merged);
+  assert_eq!(
+    merged.verify(&Limits::default()),
+    Err(VerifyError::NotObjects(5))
+  );
+  assert_eq!(
+    invalid.verify(&Limits::default()),
+    Err(VerifyError::NotObjects(6))
+  );
+}
@@ -0,0 +1,114 @@
+use boozle_ir::{segment, Expr, Object, PromiseId, RemoteId, SegmentError, Step, Value};
+
+const FOO: u64 = 1;
+const BAR: u64 = 2;
+const BAZ: u64 = 3;
+
+fn object(owner: u32, addr: u64) -> Expr {
+  Object::new(RemoteId(owner), addr).into()
+}
+
+#[test]
+fn splits_calls_by_owner() {
+  // a.foo().bar(b.baz()), with `a` on node 1 and `b` on node 2.
+  let a = object(1, 10);
+  let b = object(2, 20);
+  let plan = segment(a.clone().call(FOO, None).call(BAR, Some(b.call(BAZ, None)))).unwrap();
+  assert_eq!(plan.result, PromiseId(2));
+  assert_eq!(plan.segments.len(), 2);
+
+  // b.baz() is needed first, and `a` waits for it before calling anything.
+  let first = &plan.segments[0];
+  assert_eq!((first.owner, first.round), (RemoteId(2), 0));
+  assert_eq!(
+    first.steps,
+    [Step {
+      promise: PromiseId(1),
+      target: object(2, 20),
+      method: BAZ.into(),
+      argument: None,
+    }]
+  );
+  assert!(first.imports.is_empty());
+  assert_eq!(first.exports, [PromiseId(1)]);
+
+  let second = &plan.segments[1];
+  assert_eq!((second.owner, second.round), (RemoteId(1), 1));
+  assert_eq!(
+    second.steps,
+    [
+      Step {
+        promise: PromiseId(0),
+        target: a,
+        method: FOO.into(),
+        argument: None,
+      },
+      Step {
+        promise: PromiseId(2),
+        target: Expr::Promise(PromiseId(0)),
+        method: BAR.into(),
+        argument: Some(Expr::Promise(PromiseId(1))),
+      },
+    ]
+  );
+  assert_eq!(second.imports, [PromiseId(1)]);
+  assert_eq!(second.exports, [PromiseId(2)]);
+}
+
+#[test]
+fn keeps_chains_on_one_owner_in_one_segment() {
+  let expr = object(1, 10)
+    .call(FOO, Some(Value::from(7u32).into()))
+    .call(BAR, Some(object(1, 11).call(BAZ, None)))
+    .call(FOO, None);
+  let plan = segment(expr).unwrap();
+  assert_eq!(plan.segments.len(), 1);
+  let segment = &plan.segments[0];
+  let promises: Vec<PromiseId> = segment.steps.iter().map(|step| step.promise).collect();
+  assert_eq!(
+    promises,
+    [PromiseId(0), PromiseId(1), PromiseId(2), PromiseId(3)]
+  );
+  assert_eq!(segment.steps[0].argument, Some(Value::U32(7).into()));
+  assert_eq!(segment.exports, [PromiseId(3)]);
+}
+
+#[test]
+fn independent_segments_share_a_round() {
+  // a.foo(b.baz()).bar(c.baz()): b and c can be called at the same time.
+  let expr = object(1, 10)
+    .call(FOO, Some(object(2, 20).call(BAZ, None)))
+    .call(BAR, Some(object(3, 30).call(BAZ, None)));
+  let plan = segment(expr).unwrap();
+  let rounds: Vec<(u32, RemoteId)> = plan
+    .segments
+    .iter()
+    .map(|segment| (segment.round, segment.owner))
+    .collect();
+  assert_eq!(
+    rounds,
+    [(0, RemoteId(2)), (0, RemoteId(3)), (1, RemoteId(1))]
+  );
+  assert_eq!(plan.segments[2].imports, [PromiseId(0), PromiseId(2)]);
+}
+
+#[test]
+fn rejects_malformed_expressions() {
+  assert_eq!(segment(object(1, 10)), Err(SegmentError::NoCall));
+  assert_eq!(
+    segment(Value::from(1u8).into()).map(|plan| plan.result),
+    Err(SegmentError::NoCall)
+  );
+  assert_eq!(
+    segment(Expr::from(Value::from(1u8)).call(FOO, None)),
+    Err(SegmentError::NotAnObject)
+  );
+  assert_eq!(
+    segment(object(1, 10).call(FOO, Some(Expr::Method(Box::new(object(1, 11)), BAR.into())))),
+    Err(SegmentError::UncalledMethod)
+  );
+  assert_eq!(
+    segment(Expr::Promise(PromiseId(4)).call(FOO, None)),
+    Err(SegmentError::UnknownPromise(PromiseId(4)))
+  );
+}
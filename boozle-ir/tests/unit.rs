@@ -0,0 +1,181 @@
+use boozle_ir::bytecode::{MAGIC, VERSION};
+use boozle_ir::{
+  compile, segment, DecodeError, Expr, Instruction, Limits, MethodId, Object, RemoteId, Unit,
+  Value, VerifyError,
+};
+
+fn object(owner: u32, addr: u64) -> Expr {
+  Object::new(RemoteId(owner), addr).into()
+}
+
+fn invoke(method_id: u64, argument: bool) -> Instruction {
+  Instruction::Invoke {
+    method_id: MethodId(method_id),
+    argument,
+  }
+}
+
+#[test]
+fn compiles_segments() {
+  // a.foo().bar(b.baz()): the second segment imports b.baz().
+  let expr = object(1, 10)
+    .call(1, Some(Value::from("x").into()))
+    .call(2, Some(object(2, 20).call(3, None)));
+  let plan = segment(expr).unwrap();
+  let unit = compile(&plan.segments[1]);
+  assert_eq!(
+    unit,
+    Unit {
+      inputs: 1,
+      outputs: 1,
+      consts: vec![Object::new(RemoteId(1), 10).into(), Value::from("x")],
+      instructions: vec![
+        Instruction::Push(0),
+        Instruction::Push(1),
+        invoke(1, true),
+        Instruction::Load(1),
+        Instruction::Load(0),
+        invoke(2, true),
+        Instruction::Load(2),
+      ],
+    }
+  );
+  assert_eq!(unit.verify(&Limits::default()), Ok(()));
+}
+
+#[test]
+fn round_trips_bytecode() {
+  let unit = Unit {
+    inputs: 2,
+    outputs: 2,
+    consts: vec![
+      Value::U8(1),
+      Value::I64(-2),
+      Value::F32(0.5),
+      Value::Array(vec![Value::from("nested"), Value::Array(Box::new([]))].into_boxed_slice()),
+      Value::Method(Object::new(RemoteId::LOCAL, 7), MethodId(9)),
+      Value::Lit(vec![1, 2, 3].into_boxed_slice()),
+      // Takes more memory than its encoding, so it outgrows what's reserved.
+      Value::Array(vec![Value::Unit; 1000].into_boxed_slice()),
+    ],
+    instructions: vec![
+      Instruction::Push(5),
+      Instruction::Pop,
+      Instruction::Load(1),
+      invoke(u64::MAX, false),
+    ],
+  };
+  let data = unit.encode();
+  assert_eq!(&data[..4], MAGIC);
+  assert_eq!(data[4..6], VERSION.to_le_bytes());
+  assert_eq!(Unit::decode(&data), Ok(unit));
+}
+
+#[test]
+fn rejects_malformed_bytecode() {
+  let data = Unit {
+    inputs: 0,
+    outputs: 0,
+    consts: vec![Value::from("text")],
+    instructions: vec![Instruction::Push(0), Instruction::Pop],
+  }
+  .encode();
+
+  assert_eq!(Unit::decode(b"BZ"), Err(DecodeError::BadMagic));
+  let mut future = data.clone();
+  future[4] = 3;
+  assert_eq!(
+    Unit::decode(&future),
+    Err(DecodeError::UnsupportedVersion(3))
+  );
+  assert_eq!(
+    Unit::decode(&data[..data.len() - 1]),
+    Err(DecodeError::Truncated)
+  );
+  let mut trailing = data.clone();
+  trailing.push(0);
+  assert_eq!(Unit::decode(&trailing), Err(DecodeError::TrailingBytes));
+  let mut unknown = data.clone();
+  let last = unknown.len() - 1;
+  unknown[last] = 9;
+  assert_eq!(
+    Unit::decode(&unknown),
+    Err(DecodeError::UnknownInstruction(9))
+  );
+
+  // A huge count isn't trusted.
+  let mut huge = data[..10].to_vec();
+  huge.extend_from_slice(&u32::MAX.to_le_bytes());
+  assert_eq!(Unit::decode(&huge), Err(DecodeError::Truncated));
+
+  // Neither is deep nesting.
+  let mut deep = data[..10].to_vec();
+  deep.extend_from_slice(&1u32.to_le_bytes());
+  for _ in 0..1000 {
+    deep.push(11);
+    deep.extend_from_slice(&1u32.to_le_bytes());
+  }
+  assert_eq!(Unit::decode(&deep), Err(DecodeError::TooDeep));
+}
+
+#[test]
+fn verifies_units() {
+  let limits = Limits {
+    max_instructions: 8,
+    max_depth: 3,
+    max_memory: 1024,
+  };
+  let unit = |inputs: u16, outputs: u16, instructions: Vec<Instruction>| Unit {
+    inputs,
+    outputs,
+    consts: vec![Value::U8(0)],
+    instructions,
+  };
+
+  assert_eq!(
+    unit(1, 1, vec![Instruction::Load(0), invoke(1, false)]).verify(&limits),
+    Ok(())
+  );
+  assert_eq!(
+    unit(0, 0, vec![Instruction::Pop; 9]).verify(&limits),
+    Err(VerifyError::TooManyInstructions)
+  );
+  assert_eq!(
+    unit(0, 0, vec![Instruction::Push(0); 4]).verify(&limits),
+    Err(VerifyError::TooDeep(3))
+  );
+  assert_eq!(
+    unit(0, 0, vec![invoke(1, false)]).verify(&limits),
+    Err(VerifyError::Underflow(0))
+  );
+  assert_eq!(
+    unit(
+      1,
+      0,
+      vec![
+        Instruction::Load(0),
+        invoke(1, true),
+        Instruction::Pop,
+        invoke(1, false)
+      ]
+    )
+    .verify(&limits),
+    Err(VerifyError::Underflow(3))
+  );
+  assert_eq!(
+    unit(0, 0, vec![Instruction::Push(1)]).verify(&limits),
+    Err(VerifyError::UnknownConst(0))
+  );
+  assert_eq!(
+    unit(1, 0, vec![Instruction::Load(1)]).verify(&limits),
+    Err(VerifyError::UnknownSlot(0))
+  );
+  assert_eq!(
+    unit(1, 2, vec![]).verify(&limits),
+    Err(VerifyError::MissingOutputs)
+  );
+
+  let mut large = unit(0, 0, vec![]);
+  large.consts.push(Value::String("x".repeat(2048)));
+  assert_eq!(large.verify(&limits), Err(VerifyError::TooMuchMemory));
+}
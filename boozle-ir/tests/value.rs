@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use bincode::Options;
+use boozle_ir::{from_value, to_value, Type, Unit, Value};
+use serde::{de::DeserializeSeed, Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Shape {
+  Empty,
+  Circle(f64),
+  Rect(u32, u32),
+  Named { name: String, sides: Option<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Scene {
+  title: String,
+  shapes: Vec<Shape>,
+  tags: BTreeMap<String, i64>,
+  origin: (i16, char),
+  visible: bool,
+}
+
+fn scene() -> Scene {
+  let mut tags = BTreeMap::new();
+  tags.insert("depth".to_string(), -3);
+  Scene {
+    title: "boozle".to_string(),
+    shapes: vec![
+      Shape::Empty,
+      Shape::Circle(1.5),
+      Shape::Rect(2, 3),
+      Shape::Named {
+        name: "hex".to_string(),
+        sides: Some(6),
+      },
+    ],
+    tags,
+    origin: (-1, 'z'),
+    visible: true,
+  }
+}
+
+fn scene_type() -> Type {
+  Type::Struct(vec![
+    ("title".to_string(), Type::String),
+    (
+      "shapes".to_string(),
+      Type::Array(Box::new(Type::Enum(vec![
+        ("Empty".to_string(), Type::Unit),
+        ("Circle".to_string(), Type::F64),
+        ("Rect".to_string(), Type::Tuple(vec![Type::U32, Type::U32])),
+        (
+          "Named".to_string(),
+          Type::Struct(vec![
+            ("name".to_string(), Type::String),
+            ("sides".to_string(), Type::Option(Box::new(Type::U8))),
+          ]),
+        ),
+      ]))),
+    ),
+    (
+      "tags".to_string(),
+      Type::Map(Box::new(Type::String), Box::new(Type::I64)),
+    ),
+    (
+      "origin".to_string(),
+      Type::Tuple(vec![Type::I16, Type::Char]),
+    ),
+    ("visible".to_string(), Type::Bool),
+  ])
+}
+
+#[test]
+fn converts_serde_types() {
+  let value = to_value(&scene()).unwrap();
+  match &value {
+    Value::Struct(fields) => {
+      assert_eq!(fields[0], ("title".to_string(), Value::from("boozle")));
+      assert_eq!(fields[4], ("visible".to_string(), Value::Bool(true)));
+    }
+    value => panic!("expected a struct, got {:?}", value),
+  }
+  assert_eq!(from_value::<Scene>(value).unwrap(), scene());
+
+  assert_eq!(to_value(&Some(3u16)).unwrap(), Value::from(Some(3u16)));
+  assert!(from_value::<u8>(Value::from("x")).is_err());
+}
+
+#[test]
+fn serializes_as_the_original_type() {
+  let value = to_value(&scene()).unwrap();
+  assert_eq!(
+    bincode::serialize(&value).unwrap(),
+    bincode::serialize(&scene()).unwrap()
+  );
+  assert_eq!(
+    serde_json::to_string(&value).unwrap(),
+    serde_json::to_string(&scene()).unwrap()
+  );
+
+  // JSON numbers lose their width, but still convert back.
+  let json: Value = serde_json::from_str(&serde_json::to_string(&scene()).unwrap()).unwrap();
+  assert_eq!(from_value::<Scene>(json).unwrap(), scene());
+}
+
+#[test]
+fn decodes_literals_by_type() {
+  let ty = scene_type();
+  let lit = bincode::serialize(&scene()).unwrap();
+  // The options `bincode::serialize` uses.
+  let options = bincode::DefaultOptions::new()
+    .with_fixint_encoding()
+    .allow_trailing_bytes();
+  let decoded = ty
+    .deserialize(&mut bincode::Deserializer::from_slice(&lit, options))
+    .unwrap();
+  assert_eq!(decoded, to_value(&scene()).unwrap());
+
+  let json = serde_json::to_string(&scene()).unwrap();
+  let decoded = ty
+    .deserialize(&mut serde_json::Deserializer::from_str(&json))
+    .unwrap();
+  assert_eq!(from_value::<Scene>(decoded).unwrap(), scene());
+}
+
+#[test]
+fn encodes_values_in_units() {
+  let unit = Unit {
+    inputs: 0,
+    outputs: 0,
+    consts: vec![to_value(&scene()).unwrap(), Value::Bytes(vec![1, 2].into())],
+    instructions: Vec::new(),
+  };
+  assert_eq!(Unit::decode(&unit.encode()).unwrap(), unit);
+}
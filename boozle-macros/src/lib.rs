@@ -0,0 +1,409 @@
+//! Procedural macros for boozle. These are re-exported by the `boozle` crate and
+//! shouldn't be depended on directly.
+
+extern crate proc_macro;
+
+use boozle_gen_rust::snippets::{self, Slot};
+use boozle_parser as ast;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+
+/// Suffix of generated client types. A parameter or result whose type is
+/// `FooClient` is passed as a live object implementing `Foo`.
+const CLIENT_SUFFIX: &str = "Client";
+
+struct Param {
+  ident: syn::Ident,
+  ty: syn::Type,
+  object: bool,
+}
+
+struct Method {
+  ident: syn::Ident,
+  mutable: bool,
+  params: Vec<Param>,
+  /// The `T` of the method's `Result<T, _>`, or `None` for `()`.
+  result: Option<syn::Type>,
+  result_object: bool,
+  decl: ast::Fn,
+}
+
+type Error = (Span, String);
+
+fn id(span: Span, text: &str) -> Result<ast::Id, Error> {
+  boozle_import_rust::id(text).ok_or((span, format!("`{}` isn't a valid .boozle identifier", text)))
+}
+
+/// Returns the .boozle trait name of `ty` if it is a generated client type.
+fn object_name(ty: &syn::Type) -> Option<String> {
+  match ty {
+    syn::Type::Path(path) if path.qself.is_none() => {
+      let last = path.path.segments.last()?;
+      let name = last.ident.to_string();
+      if !last.arguments.is_empty() || !name.ends_with(CLIENT_SUFFIX) || name == CLIENT_SUFFIX {
+        return None;
+      }
+      Some(name[..name.len() - CLIENT_SUFFIX.len()].to_string())
+    }
+    _ => None,
+  }
+}
+
+/// Names the macro is expanding with. The client of the annotated type is
+/// named after the type, but it may be exported under a different name.
+struct Names {
+  self_ident: String,
+  name: String,
+}
+
+/// Maps a Rust type onto the equivalent .boozle type.
+fn map_type(names: &Names, ty: &syn::Type) -> Result<ast::Type, Error> {
+  let object = |ty: &syn::Type| {
+    object_name(ty).map(|name| if name == names.self_ident { names.name.clone() } else { name })
+  };
+  boozle_import_rust::map_type(ty, &object).ok_or_else(|| {
+    (
+      ty.span(),
+      format!("`{}` has no .boozle equivalent", boozle_import_rust::type_name(ty)),
+    )
+  })
+}
+
+/// Extracts `T` from a return type of `Result<T, _>`. `Result<(), _>` yields `None`.
+fn result_type(output: &syn::ReturnType) -> Result<Option<syn::Type>, Error> {
+  let message = "boozle methods must return `Result<T, boozle::object::CallError>`";
+  let ty = match output {
+    syn::ReturnType::Type(_, ty) => ty,
+    syn::ReturnType::Default => return Err((output.span(), message.to_string())),
+  };
+  let segment = match ty.as_ref() {
+    syn::Type::Path(path) => path.path.segments.last(),
+    _ => None,
+  };
+  let arguments = match segment {
+    Some(segment) if segment.ident == "Result" => &segment.arguments,
+    _ => return Err((ty.span(), message.to_string())),
+  };
+  match arguments {
+    syn::PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+      Some(syn::GenericArgument::Type(syn::Type::Tuple(tuple))) if tuple.elems.is_empty() => Ok(None),
+      Some(syn::GenericArgument::Type(ty)) => Ok(Some(ty.clone())),
+      _ => Err((ty.span(), message.to_string())),
+    },
+    _ => Err((ty.span(), message.to_string())),
+  }
+}
+
+fn parse_method(names: &Names, method: &syn::ImplItemMethod) -> Result<Option<Method>, Error> {
+  let sig = &method.sig;
+  if sig.asyncness.is_none() {
+    return Ok(None);
+  }
+  let mutable = match sig.receiver() {
+    Some(syn::FnArg::Receiver(receiver)) if receiver.reference.is_some() => receiver.mutability.is_some(),
+    _ => return Ok(None),
+  };
+  if !sig.generics.params.is_empty() {
+    return Err((sig.generics.span(), "boozle methods can't be generic".to_string()));
+  }
+
+  let mut params = Vec::new();
+  let mut decl_params = Vec::new();
+  for input in sig.inputs.iter() {
+    let typed = match input {
+      syn::FnArg::Typed(typed) => typed,
+      syn::FnArg::Receiver(_) => continue,
+    };
+    let ident = match typed.pat.as_ref() {
+      syn::Pat::Ident(pat) => pat.ident.clone(),
+      pat => return Err((pat.span(), "boozle parameters must be plain identifiers".to_string())),
+    };
+    let ty = typed.ty.as_ref().clone();
+    decl_params.push(ast::Param {
+      name: id(ident.span(), ident.to_string().as_str())?,
+      ty: map_type(names, &ty)?,
+    });
+    params.push(Param {
+      object: object_name(&ty).is_some(),
+      ident,
+      ty,
+    });
+  }
+
+  if params.len() > 1 {
+    if let Some(param) = params.iter().find(|param| param.object) {
+      return Err((
+        param.ty.span(),
+        "an object parameter must be the method's only parameter".to_string(),
+      ));
+    }
+  }
+
+  let result = result_type(&sig.output)?;
+  let decl_result = match &result {
+    Some(ty) => Some(map_type(names, ty)?),
+    None => None,
+  };
+
+  Ok(Some(Method {
+    ident: sig.ident.clone(),
+    mutable,
+    result_object: result.as_ref().map(|ty| object_name(ty).is_some()).unwrap_or(false),
+    params,
+    result,
+    decl: ast::Fn {
+      attrs: Vec::new(),
+      name: id(sig.ident.span(), sig.ident.to_string().as_str())?,
+      params: decl_params,
+      result: decl_result,
+    },
+  }))
+}
+
+/// Reads `name = "..."` from the attribute's arguments, if present.
+fn parse_name(args: &syn::AttributeArgs) -> Result<Option<String>, Error> {
+  let mut name = None;
+  for arg in args.iter() {
+    match arg {
+      syn::NestedMeta::Meta(syn::Meta::NameValue(pair)) if pair.path.is_ident("name") => match &pair.lit {
+        syn::Lit::Str(value) => name = Some(value.value()),
+        lit => return Err((lit.span(), "expected a string".to_string())),
+      },
+      arg => return Err((arg.span(), "unknown argument, expected `name = \"...\"`".to_string())),
+    }
+  }
+  Ok(name)
+}
+
+/// The parameters of `method`, as the generated code passes them.
+fn param_slots(method: &Method) -> Vec<(syn::Ident, Slot)> {
+  method
+    .params
+    .iter()
+    .map(|param| {
+      let ty = &param.ty;
+      let slot = Slot {
+        ty: quote! { #ty },
+        object: param.object,
+      };
+      (param.ident.clone(), slot)
+    })
+    .collect()
+}
+
+fn result_slot(method: &Method) -> Option<Slot> {
+  method.result.as_ref().map(|ty| Slot {
+    ty: quote! { #ty },
+    object: method.result_object,
+  })
+}
+
+fn dispatch_arm(method: &Method, method_id: u64) -> TokenStream {
+  let params = param_slots(method);
+  snippets::dispatch_arm(method_id, quote! { self }, &method.ident, &params, result_slot(method).as_ref())
+}
+
+fn client_method(method: &Method, method_id: u64) -> TokenStream {
+  let ident = &method.ident;
+  let params: Vec<TokenStream> = method
+    .params
+    .iter()
+    .map(|param| {
+      let ident = &param.ident;
+      let ty = &param.ty;
+      quote! { #ident: #ty }
+    })
+    .collect();
+  let result_ty = match &method.result {
+    Some(ty) => quote! { #ty },
+    None => quote! { () },
+  };
+  let body = snippets::client_body(method_id, method.mutable, &param_slots(method), result_slot(method).as_ref());
+  quote! {
+    #[allow(unused_variables)]
+    pub async fn #ident(&self, #(#params),*) -> Result<#result_ty, boozle::object::CallError> {
+      #body
+    }
+  }
+}
+
+fn expand(args: syn::AttributeArgs, item: syn::ItemImpl) -> Result<TokenStream, Error> {
+  if item.trait_.is_some() {
+    return Err((item.span(), "#[boozle::object] must be applied to an inherent impl".to_string()));
+  }
+  if !item.generics.params.is_empty() {
+    return Err((item.generics.span(), "#[boozle::object] can't be applied to a generic impl".to_string()));
+  }
+
+  let self_ty = &item.self_ty;
+  let self_ident = match self_ty.as_ref() {
+    syn::Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.clone()),
+    _ => None,
+  };
+  let self_ident = self_ident.ok_or((self_ty.span(), "expected a named type".to_string()))?;
+  // Method IDs depend on the trait's full path, while its declaration only
+  // names the trait.
+  let path = parse_name(&args)?.unwrap_or_else(|| self_ident.to_string());
+  let name = path.rsplit('.').next().unwrap().to_string();
+  for component in path.split('.') {
+    id(self_ty.span(), component)?;
+  }
+  let names = Names {
+    self_ident: self_ident.to_string(),
+    name: name.clone(),
+  };
+
+  let mut methods = Vec::new();
+  for impl_item in item.items.iter() {
+    if let syn::ImplItem::Method(method) = impl_item {
+      if let Some(method) = parse_method(&names, method)? {
+        methods.push(method);
+      }
+    }
+  }
+
+  let method_ids: Vec<u64> = methods
+    .iter()
+    .map(|method| boozle_gen_rust::method_id(path.as_str(), method.ident.to_string().as_str()))
+    .collect();
+  let call_arms: Vec<TokenStream> = methods
+    .iter()
+    .zip(method_ids.iter())
+    .filter(|(method, _)| !method.mutable)
+    .map(|(method, method_id)| dispatch_arm(method, *method_id))
+    .collect();
+  let call_mut_arms: Vec<TokenStream> = methods
+    .iter()
+    .zip(method_ids.iter())
+    .filter(|(method, _)| method.mutable)
+    .map(|(method, method_id)| dispatch_arm(method, *method_id))
+    .collect();
+  let mutable_arms: Vec<TokenStream> = methods
+    .iter()
+    .zip(method_ids.iter())
+    .filter(|(method, _)| method.mutable)
+    .map(|(_, method_id)| quote! { #method_id => true, })
+    .collect();
+  let client_methods: Vec<TokenStream> = methods
+    .iter()
+    .zip(method_ids.iter())
+    .map(|(method, method_id)| client_method(method, *method_id))
+    .collect();
+
+  let client = format_ident!("{}{}", self_ident, CLIENT_SUFFIX);
+  let client_name = client.to_string();
+  let decl = ast::Trait {
+    attrs: Vec::new(),
+    name: id(self_ty.span(), name.as_str())?,
+    members: methods.into_iter().map(|method| method.decl).collect(),
+  }
+  .to_string();
+
+  Ok(quote! {
+    #item
+
+    impl #self_ty {
+      /// The .boozle declaration of this object's interface.
+      pub const BOOZLE: &'static str = #decl;
+    }
+
+    #[async_trait::async_trait]
+    impl boozle::object::Object for #self_ty {
+      #[allow(unused_variables)]
+      async fn call(
+        &self,
+        call: boozle::object::Call,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call.argument;
+        match call.method_id {
+          #(#call_arms)*
+          _ => Err(boozle::object::CallError::UnknownMethod),
+        }
+      }
+
+      #[allow(unused_variables)]
+      async fn call_mut(
+        &mut self,
+        call_mut: boozle::object::CallMut,
+      ) -> Result<boozle::object::Return, boozle::object::CallError> {
+        let argument = call_mut.argument;
+        match call_mut.method_id {
+          #(#call_mut_arms)*
+          method_id => self.call(boozle::object::Call::new(method_id, argument)).await,
+        }
+      }
+
+      fn proxy_info(&self) -> Option<boozle::object::ProxyInfo> {
+        None
+      }
+
+      fn is_mutable(&self, method_id: u64) -> bool {
+        match method_id {
+          #(#mutable_arms)*
+          _ => false,
+        }
+      }
+    }
+
+    /// A handle to a (possibly remote) object with the same interface.
+    #[derive(Clone)]
+    pub struct #client {
+      actor: actix::Addr<boozle::object::ObjectActor>,
+    }
+
+    impl #client {
+      pub fn new(actor: actix::Addr<boozle::object::ObjectActor>) -> Self {
+        Self { actor }
+      }
+
+      /// Wraps an object value. Returns `None` if the value is a literal.
+      pub fn from_value(value: boozle::value::LocalValue) -> Option<Self> {
+        value.into_actor().map(Self::new)
+      }
+
+      pub fn into_value(self) -> boozle::value::LocalValue {
+        boozle::value::LocalValue::Actor(self.actor)
+      }
+
+      #(#client_methods)*
+    }
+
+    impl std::fmt::Debug for #client {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(#client_name).finish()
+      }
+    }
+  })
+}
+
+/// Marks a trait for export by `boozle-import-rust`. Optionally declares a
+/// service of the trait with `#[boozle::export(svc = "name")]`. The attribute
+/// has no effect on compilation.
+#[proc_macro_attribute]
+pub fn export(_args: proc_macro::TokenStream, input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  input
+}
+
+/// Exposes the `async` methods of an inherent impl block over boozle.
+///
+/// Every `async fn` taking `&self` or `&mut self` becomes a method of a boozle
+/// trait named after the type (or `#[boozle::object(name = "...")]`, which may
+/// be a dotted path such as `"store.Store"` for a trait declared in a module,
+/// so that the method IDs match). Methods
+/// must return `Result<T, boozle::object::CallError>`. Parameters and results
+/// of a type named `FooClient` are passed as live objects.
+///
+/// The macro implements `boozle::object::Object` for the type, generates a
+/// `<Type>Client` proxy with the same methods, and adds a `BOOZLE` constant
+/// holding the equivalent .boozle declaration. Method IDs match those used by
+/// `boozle-gen-rust` for that declaration.
+#[proc_macro_attribute]
+pub fn object(args: proc_macro::TokenStream, input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+  let item = syn::parse_macro_input!(input as syn::ItemImpl);
+  match expand(args, item) {
+    Ok(stream) => stream.into(),
+    Err((span, message)) => quote_spanned!(span => compile_error!(#message);).into(),
+  }
+}
@@ -0,0 +1,105 @@
+use boozle::object::{Call, CallError, ObjectActorHelpers};
+use boozle::value::LocalValue;
+use boozle_gen_rust::method_id;
+use boozle_parser::Decl;
+
+#[derive(Debug, Default)]
+pub struct Counter {
+  value: u32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn increment(&mut self, by: u32) -> Result<u32, CallError> {
+    self.value += by;
+    Ok(self.value)
+  }
+
+  async fn get(&self) -> Result<u32, CallError> {
+    Ok(self.value)
+  }
+
+  async fn fork(&self) -> Result<CounterClient, CallError> {
+    let counter = Counter { value: self.value };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+}
+
+/// Implements the trait `Gauge` of the module `stats`.
+#[derive(Debug, Default)]
+pub struct Meter;
+
+#[boozle::object(name = "stats.Gauge")]
+impl Meter {
+  async fn read(&self) -> Result<u32, CallError> {
+    Ok(7)
+  }
+}
+
+async fn call(
+  counter: &LocalValue,
+  method: &str,
+  argument: Option<LocalValue>,
+) -> Result<Option<LocalValue>, CallError> {
+  let actor = counter.clone().into_actor().unwrap();
+  let ret = actor.call(Call::new(method_id("Counter", method), argument)).await?;
+  Ok(ret.result)
+}
+
+#[test]
+fn dispatches_generator_method_ids() {
+  actix::System::new("object").block_on(async {
+    let counter = LocalValue::from_object(Counter::default());
+
+    // Immutable calls of `&mut self` methods, as every generated client sends them.
+    let result = call(&counter, "increment", Some(LocalValue::from_lit(&2u32))).await;
+    assert_eq!(result.unwrap().unwrap().to_lit::<u32>(), Some(2));
+    let result = call(&counter, "get", None).await;
+    assert_eq!(result.unwrap().unwrap().to_lit::<u32>(), Some(2));
+
+    let result = call(&counter, "reset", None).await;
+    assert!(matches!(result, Err(CallError::UnknownMethod)));
+    let result = call(&counter, "increment", None).await;
+    assert!(matches!(result, Err(CallError::InvalidArgument)));
+  });
+}
+
+#[test]
+fn client_calls_object() {
+  actix::System::new("object").block_on(async {
+    let counter = CounterClient::from_value(LocalValue::from_object(Counter::default())).unwrap();
+    assert_eq!(counter.increment(3).await.unwrap(), 3);
+
+    let fork = counter.fork().await.unwrap();
+    assert_eq!(fork.increment(1).await.unwrap(), 4);
+    assert_eq!(counter.get().await.unwrap(), 3);
+  });
+}
+
+#[test]
+fn declares_interface() {
+  let unit = boozle_parser::parse("counter".to_string(), Counter::BOOZLE).unwrap();
+  match unit.decls.as_slice() {
+    [Decl::Trait(trait_)] => {
+      assert_eq!(trait_.name.text, "Counter");
+      let names: Vec<&str> = trait_.members.iter().map(|fn_| fn_.name.text.as_str()).collect();
+      assert_eq!(names, ["increment", "get", "fork"]);
+    }
+    _ => panic!("unexpected declaration: {}", Counter::BOOZLE),
+  }
+}
+
+#[test]
+fn uses_full_trait_paths_for_method_ids() {
+  actix::System::new("object").block_on(async {
+    let actor = LocalValue::from_object(Meter).into_actor().unwrap();
+    let ret = actor
+      .call(Call::new(method_id("stats.Gauge", "read"), None))
+      .await
+      .unwrap();
+    assert_eq!(ret.result.unwrap().to_lit::<u32>(), Some(7));
+    let ret = actor.call(Call::new(method_id("Gauge", "read"), None)).await;
+    assert!(matches!(ret, Err(CallError::UnknownMethod)));
+  });
+  assert_eq!(Meter::BOOZLE, "trait Gauge {\n  fn read() -> u32;\n}\n");
+}
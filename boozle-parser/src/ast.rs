@@ -3,14 +3,63 @@ pub struct Id {
   pub text: String,
 }
 
+/// An argument of an attribute, e.g. `type = "uuid::Uuid"`.
+#[derive(Debug)]
+pub struct AttrArg {
+  pub key: Id,
+  pub value: String,
+}
+
+/// An attribute such as `#[rust(type = "uuid::Uuid")]`. Attributes are namespaced
+/// by `name`, usually after the target language, and are ignored by generators
+/// they don't concern.
+#[derive(Debug)]
+pub struct Attr {
+  pub name: Id,
+  pub args: Vec<AttrArg>,
+}
+
+impl Attr {
+  /// A `#[doc(text = "...")]` attribute, which is how doc comments are parsed.
+  pub fn doc(text: &str) -> Self {
+    Self {
+      name: Id {
+        text: "doc".to_string(),
+      },
+      args: vec![AttrArg {
+        key: Id {
+          text: "text".to_string(),
+        },
+        value: text.to_string(),
+      }],
+    }
+  }
+
+  /// Returns the value of the argument named `key`, if present.
+  pub fn arg(&self, key: &str) -> Option<&str> {
+    self
+      .args
+      .iter()
+      .find(|arg| arg.key.text == key)
+      .map(|arg| arg.value.as_str())
+  }
+}
+
+/// Returns the first attribute named `name`.
+pub fn find_attr<'a>(attrs: &'a [Attr], name: &str) -> Option<&'a Attr> {
+  attrs.iter().find(|attr| attr.name.text == name)
+}
+
 #[derive(Debug)]
 pub struct Mod {
+  pub attrs: Vec<Attr>,
   pub name: Id,
   pub decls: Option<Vec<Decl>>,
 }
 
 #[derive(Debug)]
 pub struct Trait {
+  pub attrs: Vec<Attr>,
   pub name: Id,
   pub members: Vec<Fn>,
 }
@@ -33,6 +82,7 @@ pub struct Param {
 
 #[derive(Debug)]
 pub struct Fn {
+  pub attrs: Vec<Attr>,
   pub name: Id,
   pub params: Vec<Param>,
   pub result: Option<Type>,
@@ -40,19 +90,30 @@ pub struct Fn {
 
 #[derive(Debug)]
 pub struct Svc {
+  pub attrs: Vec<Attr>,
   pub name: Id,
   pub ty: Type,
 }
 
 #[derive(Debug)]
 pub struct Use {
+  pub attrs: Vec<Attr>,
   pub path: Path,
 }
 
+/// An opaque protocol type, e.g. `type Uuid;`. Its representation is given
+/// per language, typically with an attribute such as `#[rust(type = "...")]`.
+#[derive(Debug)]
+pub struct TypeDecl {
+  pub attrs: Vec<Attr>,
+  pub name: Id,
+}
+
 #[derive(Debug)]
 pub enum Decl {
   Trait(Trait),
   Svc(Svc),
   Mod(Mod),
   Use(Use),
+  Type(TypeDecl),
 }
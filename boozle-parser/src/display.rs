@@ -0,0 +1,220 @@
+//! Prints AST values back out as .boozle source. The output is accepted by the parser.
+
+use super::ast::*;
+use super::Unit;
+use std::fmt::{Display, Formatter, Result};
+
+const INDENT: &str = "  ";
+
+impl Display for Id {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write!(f, "{}", self.text)
+  }
+}
+
+impl Display for Path {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    for (i, component) in self.components.iter().enumerate() {
+      if i > 0 {
+        write!(f, ".")?;
+      }
+      write!(f, "{}", component)?;
+    }
+    Ok(())
+  }
+}
+
+impl Display for Type {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write!(f, "{}", self.path)
+  }
+}
+
+impl Display for AttrArg {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    let value = self.value.replace('\\', "\\\\").replace('"', "\\\"");
+    write!(f, "{} = \"{}\"", self.key, value)
+  }
+}
+
+impl Display for Attr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write!(f, "#[{}", self.name)?;
+    if !self.args.is_empty() {
+      write!(f, "(")?;
+      for (i, arg) in self.args.iter().enumerate() {
+        if i > 0 {
+          write!(f, ", ")?;
+        }
+        write!(f, "{}", arg)?;
+      }
+      write!(f, ")")?;
+    }
+    write!(f, "]")
+  }
+}
+
+impl Display for Param {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write!(f, "{}: {}", self.name, self.ty)
+  }
+}
+
+fn write_indent(f: &mut Formatter<'_>, depth: usize) -> Result {
+  for _ in 0..depth {
+    write!(f, "{}", INDENT)?;
+  }
+  Ok(())
+}
+
+/// Returns the text of a `#[doc(text = "...")]` attribute that can be written
+/// as a doc comment.
+fn doc_comment(attr: &Attr) -> Option<&str> {
+  match attr.args.as_slice() {
+    [arg] if attr.name.text == "doc" && arg.key.text == "text" && !arg.value.contains('\n') => Some(arg.value.as_str()),
+    _ => None,
+  }
+}
+
+/// Writes each attribute on its own line. Docs are written as doc comments,
+/// since their text may contain quotes.
+fn write_attrs(f: &mut Formatter<'_>, attrs: &[Attr], depth: usize) -> Result {
+  for attr in attrs.iter() {
+    write_indent(f, depth)?;
+    match doc_comment(attr) {
+      Some("") => writeln!(f, "///")?,
+      Some(text) => writeln!(f, "/// {}", text)?,
+      None => writeln!(f, "{}", attr)?,
+    }
+  }
+  Ok(())
+}
+
+fn write_fn(f: &mut Formatter<'_>, fn_: &Fn, depth: usize) -> Result {
+  write_attrs(f, &fn_.attrs, depth)?;
+  write_indent(f, depth)?;
+  write!(f, "fn {}(", fn_.name)?;
+  // Parameters are whitespace separated in the grammar.
+  for (i, param) in fn_.params.iter().enumerate() {
+    if i > 0 {
+      write!(f, " ")?;
+    }
+    write!(f, "{}", param)?;
+  }
+  write!(f, ")")?;
+  if let Some(result) = &fn_.result {
+    write!(f, " -> {}", result)?;
+  }
+  writeln!(f, ";")
+}
+
+fn write_trait(f: &mut Formatter<'_>, trait_: &Trait, depth: usize) -> Result {
+  write_attrs(f, &trait_.attrs, depth)?;
+  write_indent(f, depth)?;
+  writeln!(f, "trait {} {{", trait_.name)?;
+  for member in trait_.members.iter() {
+    write_fn(f, member, depth + 1)?;
+  }
+  write_indent(f, depth)?;
+  writeln!(f, "}}")
+}
+
+fn write_svc(f: &mut Formatter<'_>, svc: &Svc, depth: usize) -> Result {
+  write_attrs(f, &svc.attrs, depth)?;
+  write_indent(f, depth)?;
+  writeln!(f, "svc {}: {};", svc.name, svc.ty)
+}
+
+fn write_use(f: &mut Formatter<'_>, use_: &Use, depth: usize) -> Result {
+  write_attrs(f, &use_.attrs, depth)?;
+  write_indent(f, depth)?;
+  writeln!(f, "use {};", use_.path)
+}
+
+fn write_type_decl(f: &mut Formatter<'_>, type_: &TypeDecl, depth: usize) -> Result {
+  write_attrs(f, &type_.attrs, depth)?;
+  write_indent(f, depth)?;
+  writeln!(f, "type {};", type_.name)
+}
+
+fn write_mod(f: &mut Formatter<'_>, module: &Mod, depth: usize) -> Result {
+  write_attrs(f, &module.attrs, depth)?;
+  write_indent(f, depth)?;
+  match &module.decls {
+    Some(decls) => {
+      writeln!(f, "mod {} {{", module.name)?;
+      write_decls(f, decls, depth + 1)?;
+      write_indent(f, depth)?;
+      writeln!(f, "}}")
+    }
+    None => writeln!(f, "mod {};", module.name),
+  }
+}
+
+fn write_decl(f: &mut Formatter<'_>, decl: &Decl, depth: usize) -> Result {
+  match decl {
+    Decl::Trait(trait_) => write_trait(f, trait_, depth),
+    Decl::Mod(module) => write_mod(f, module, depth),
+    Decl::Svc(svc) => write_svc(f, svc, depth),
+    Decl::Use(use_) => write_use(f, use_, depth),
+    Decl::Type(type_) => write_type_decl(f, type_, depth),
+  }
+}
+
+fn write_decls(f: &mut Formatter<'_>, decls: &[Decl], depth: usize) -> Result {
+  for (i, decl) in decls.iter().enumerate() {
+    if i > 0 {
+      writeln!(f)?;
+    }
+    write_decl(f, decl, depth)?;
+  }
+  Ok(())
+}
+
+impl Display for Fn {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_fn(f, self, 0)
+  }
+}
+
+impl Display for Svc {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_svc(f, self, 0)
+  }
+}
+
+impl Display for Use {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_use(f, self, 0)
+  }
+}
+
+impl Display for TypeDecl {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_type_decl(f, self, 0)
+  }
+}
+
+impl Display for Trait {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_trait(f, self, 0)
+  }
+}
+
+impl Display for Mod {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_mod(f, self, 0)
+  }
+}
+
+impl Display for Decl {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_decl(f, self, 0)
+  }
+}
+
+impl Display for Unit {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    write_decls(f, &self.decls, 0)
+  }
+}
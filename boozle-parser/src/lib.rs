@@ -1,7 +1,8 @@
 #[macro_use]
 extern crate lalrpop_util;
 
-mod ast;
+pub mod ast;
+mod display;
 
 pub use ast::*;
 
@@ -14,9 +15,38 @@ pub struct Unit {
   pub decls: Vec<Decl>,
 }
 
+/// Words reserved by the grammar. They can't be used as identifiers.
+pub const KEYWORDS: &[&str] = &["fn", "mod", "svc", "trait", "type", "use"];
+
+/// Returns `text` as an identifier, or `None` if the grammar wouldn't accept it.
+pub fn id(text: &str) -> Option<Id> {
+  let mut chars = text.chars();
+  let valid = match chars.next() {
+    Some(first) => first.is_ascii_alphabetic() && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+    None => false,
+  };
+  if !valid || KEYWORDS.contains(&text) {
+    return None;
+  }
+  Some(Id { text: text.to_string() })
+}
+
 pub fn parse<'a>(name: String, text: &'a str) -> Result<Unit, ParseError<'a>> {
   Ok(Unit {
     name,
     decls: boozle::UnitParser::new().parse(text)?,
   })
 }
+
+/// Resolves the escapes of a string literal's contents.
+fn unescape(text: &str) -> String {
+  let mut unescaped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => unescaped.extend(chars.next()),
+      c => unescaped.push(c),
+    }
+  }
+  unescaped
+}
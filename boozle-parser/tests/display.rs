@@ -0,0 +1,44 @@
+use boozle_parser::{parse, Decl};
+
+const SOURCE: &str = r#"/// A clock.
+#[rust(type = "std::time::SystemTime")]
+type Instant;
+
+mod time {
+  #[note(text = "say \"now\"", path = "C:\\time\\")]
+  trait Clock {
+    #[deprecated]
+    fn now() -> super.Instant;
+    fn sleep(millis: u64 until: super.Instant);
+  }
+
+  svc clock: Clock;
+}
+
+use time.Clock;
+"#;
+
+#[test]
+fn prints_what_it_parsed() {
+  let unit = parse("time".to_string(), SOURCE).unwrap();
+  assert_eq!(unit.to_string(), SOURCE);
+}
+
+#[test]
+fn escapes_strings() {
+  let unit = parse("time".to_string(), SOURCE).unwrap();
+  let clock = match &unit.decls[1] {
+    Decl::Mod(time) => match &time.decls.as_ref().unwrap()[0] {
+      Decl::Trait(clock) => clock,
+      _ => panic!("expected a trait"),
+    },
+    _ => panic!("expected a module"),
+  };
+  let note = &clock.attrs[0];
+  assert_eq!(note.arg("text"), Some(r#"say "now""#));
+  assert_eq!(note.arg("path"), Some(r"C:\time\"));
+
+  // Only `\"` and `\\` are escapes.
+  assert!(parse("bad".to_string(), r#"#[doc(text = "\n")] type Bad;"#).is_err());
+  assert!(parse("bad".to_string(), r#"#[doc(text = "\")] type Bad;"#).is_err());
+}
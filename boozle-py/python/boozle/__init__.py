@@ -0,0 +1,129 @@
+"""Python bindings for boozle.
+
+Connect with `connect_unix` or `connect_tcp` from a running asyncio event
+loop, then wrap `Connection.remote(object_id)` in a client class generated by
+`boozle-gen-py`. Calls are coroutines of that event loop.
+
+Literal values travel as `bytes`, encoded with `boozle.bincode`. Objects are
+`RemoteObject`s when owned by the peer, and otherwise anything with an async
+`boozle_dispatch(method_id, argument)` method, such as a generated
+`FooObject`.
+"""
+
+from typing import Callable, Optional, Tuple, TypeVar, Union
+
+from ._boozle import CallError, Connection, RemoteObject
+from .bincode import (
+    Codec,
+    DecodeError,
+    Reader,
+    Writer,
+    boolean,
+    f32,
+    f64,
+    i8,
+    i16,
+    i32,
+    i64,
+    raw,
+    string,
+    u8,
+    u16,
+    u32,
+    u64,
+)
+
+T = TypeVar("T")
+
+Value = Union[None, bytes, RemoteObject, object]
+
+
+async def connect_unix(path: str) -> Connection:
+    """Connects to a peer listening on the Unix socket at `path`."""
+    return await Connection.connect_unix(path)
+
+
+async def connect_tcp(host: str, port: int) -> Connection:
+    """Connects to a peer listening on TCP at `host` and `port`."""
+    return await Connection.connect_tcp(host, port)
+
+
+def lit(codec: Codec[T], value: T) -> bytes:
+    writer = Writer()
+    codec.encode(writer, value)
+    return writer.finish()
+
+
+def write_lit(*fields: Tuple[Codec, object]) -> bytes:
+    """Encodes `(codec, value)` pairs, in order, as one literal."""
+    writer = Writer()
+    for codec, value in fields:
+        codec.encode(writer, value)
+    return writer.finish()
+
+
+def read_lit(value: Value, kind: str, *codecs: Codec) -> tuple:
+    """Decodes a literal holding exactly one value per codec. Raises a
+    `CallError` of `kind` if it doesn't."""
+    if not isinstance(value, bytes):
+        raise CallError(kind)
+    reader = Reader(value)
+    try:
+        fields = tuple(codec.decode(reader) for codec in codecs)
+    except DecodeError:
+        raise CallError(kind) from None
+    if not reader.done():
+        raise CallError(kind)
+    return fields
+
+
+def from_lit(codec: Codec[T], value: Value, kind: str) -> T:
+    return read_lit(value, kind, codec)[0]
+
+
+def to_remote(value: Value, kind: str) -> RemoteObject:
+    if not isinstance(value, RemoteObject):
+        raise CallError(kind)
+    return value
+
+
+def object_value(target: T, wrap: Callable[[T], object]) -> object:
+    """The value to send for `target`. Generated clients send the object they
+    call rather than a wrapper around themselves."""
+    remote: Optional[RemoteObject] = getattr(target, "remote", None)
+    if isinstance(remote, RemoteObject):
+        return remote
+    return wrap(target)
+
+
+__all__ = [
+    "CallError",
+    "Codec",
+    "Connection",
+    "DecodeError",
+    "Reader",
+    "RemoteObject",
+    "Value",
+    "Writer",
+    "boolean",
+    "connect_tcp",
+    "connect_unix",
+    "f32",
+    "f64",
+    "from_lit",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "lit",
+    "object_value",
+    "raw",
+    "read_lit",
+    "string",
+    "to_remote",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "write_lit",
+]
@@ -0,0 +1,175 @@
+"""The subset of bincode (default options) that boozle literals use.
+
+Integers are little-endian and fixed width, lengths are u64 and booleans are
+one byte. Literals of several values, such as method arguments, are their
+encodings one after another.
+"""
+
+import struct
+from typing import Callable, Generic, TypeVar
+
+T = TypeVar("T")
+
+
+class DecodeError(Exception):
+    pass
+
+
+class Writer:
+    def __init__(self) -> None:
+        self._buffer = bytearray()
+
+    def _pack(self, fmt: str, value) -> "Writer":
+        try:
+            self._buffer += struct.pack(fmt, value)
+        except struct.error as err:
+            raise ValueError(f"{value!r} doesn't fit: {err}") from None
+        return self
+
+    def u8(self, value: int) -> "Writer":
+        return self._pack("<B", value)
+
+    def u16(self, value: int) -> "Writer":
+        return self._pack("<H", value)
+
+    def u32(self, value: int) -> "Writer":
+        return self._pack("<I", value)
+
+    def u64(self, value: int) -> "Writer":
+        return self._pack("<Q", value)
+
+    def i8(self, value: int) -> "Writer":
+        return self._pack("<b", value)
+
+    def i16(self, value: int) -> "Writer":
+        return self._pack("<h", value)
+
+    def i32(self, value: int) -> "Writer":
+        return self._pack("<i", value)
+
+    def i64(self, value: int) -> "Writer":
+        return self._pack("<q", value)
+
+    def f32(self, value: float) -> "Writer":
+        return self._pack("<f", value)
+
+    def f64(self, value: float) -> "Writer":
+        return self._pack("<d", value)
+
+    def boolean(self, value: bool) -> "Writer":
+        return self.u8(1 if value else 0)
+
+    def raw(self, value: bytes) -> "Writer":
+        """Writes `value` as is, without a length."""
+        self._buffer += value
+        return self
+
+    def bytes(self, value: bytes) -> "Writer":
+        return self.u64(len(value)).raw(value)
+
+    def string(self, value: str) -> "Writer":
+        return self.bytes(value.encode("utf-8"))
+
+    def finish(self) -> bytes:
+        return bytes(self._buffer)
+
+
+class Reader:
+    def __init__(self, data: bytes) -> None:
+        self._data = data
+        self._offset = 0
+
+    def _unpack(self, fmt: str):
+        size = struct.calcsize(fmt)
+        if self.remaining < size:
+            raise DecodeError("unexpected end of input")
+        (value,) = struct.unpack_from(fmt, self._data, self._offset)
+        self._offset += size
+        return value
+
+    @property
+    def remaining(self) -> int:
+        return len(self._data) - self._offset
+
+    def done(self) -> bool:
+        return self.remaining == 0
+
+    def u8(self) -> int:
+        return self._unpack("<B")
+
+    def u16(self) -> int:
+        return self._unpack("<H")
+
+    def u32(self) -> int:
+        return self._unpack("<I")
+
+    def u64(self) -> int:
+        return self._unpack("<Q")
+
+    def i8(self) -> int:
+        return self._unpack("<b")
+
+    def i16(self) -> int:
+        return self._unpack("<h")
+
+    def i32(self) -> int:
+        return self._unpack("<i")
+
+    def i64(self) -> int:
+        return self._unpack("<q")
+
+    def f32(self) -> float:
+        return self._unpack("<f")
+
+    def f64(self) -> float:
+        return self._unpack("<d")
+
+    def boolean(self) -> bool:
+        value = self.u8()
+        if value > 1:
+            raise DecodeError(f"invalid bool {value}")
+        return value == 1
+
+    def raw(self, size: int) -> bytes:
+        if self.remaining < size:
+            raise DecodeError("unexpected end of input")
+        value = self._data[self._offset : self._offset + size]
+        self._offset += size
+        return bytes(value)
+
+    def rest(self) -> bytes:
+        return self.raw(self.remaining)
+
+    def bytes(self) -> bytes:
+        return self.raw(self.u64())
+
+    def string(self) -> str:
+        try:
+            return self.bytes().decode("utf-8")
+        except UnicodeDecodeError as err:
+            raise DecodeError(str(err)) from None
+
+
+class Codec(Generic[T]):
+    """Writes and reads values of one type."""
+
+    def __init__(self, encode: Callable[[Writer, T], object], decode: Callable[[Reader], T]) -> None:
+        self.encode = encode
+        self.decode = decode
+
+
+boolean: Codec[bool] = Codec(Writer.boolean, Reader.boolean)
+u8: Codec[int] = Codec(Writer.u8, Reader.u8)
+u16: Codec[int] = Codec(Writer.u16, Reader.u16)
+u32: Codec[int] = Codec(Writer.u32, Reader.u32)
+u64: Codec[int] = Codec(Writer.u64, Reader.u64)
+i8: Codec[int] = Codec(Writer.i8, Reader.i8)
+i16: Codec[int] = Codec(Writer.i16, Reader.i16)
+i32: Codec[int] = Codec(Writer.i32, Reader.i32)
+i64: Codec[int] = Codec(Writer.i64, Reader.i64)
+f32: Codec[float] = Codec(Writer.f32, Reader.f32)
+f64: Codec[float] = Codec(Writer.f64, Reader.f64)
+string: Codec[str] = Codec(Writer.string, Reader.string)
+# Types without a Python mapping are passed through as their encoding, which
+# must come last.
+raw: Codec[bytes] = Codec(Writer.raw, Reader.rest)
@@ -0,0 +1,378 @@
+//! Python bindings for boozle, built as the `boozle._boozle` extension module.
+//! The `boozle` package in `python/` wraps it with the encoding helpers used
+//! by generated clients (see `boozle-gen-py`).
+//!
+//! Each `Connection` runs on its own runtime thread (see `boozle::blocking`).
+//! Methods that talk to the peer return futures of the asyncio event loop the
+//! connection was opened from, completed from the runtime thread.
+
+mod value;
+
+use actix::Addr;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use boozle::blocking;
+use boozle::connection::{self, req, res, ConnectionHelpers};
+use boozle::object;
+use boozle::pool::PoolHelpers;
+use boozle::transport::stream;
+use boozle::value::LocalValue;
+
+use value::{Arg, Context, Out};
+
+/// Raised when a call fails. `kind` names a `boozle::object::CallError`
+/// variant, such as `"Failed"` or `"Timeout"`.
+#[pyclass(extends = PyException, module = "boozle")]
+pub struct CallError {
+  #[pyo3(get)]
+  kind: String,
+}
+
+#[pymethods]
+impl CallError {
+  #[new]
+  fn new(kind: String) -> Self {
+    Self { kind }
+  }
+
+  fn __str__(&self) -> String {
+    self.kind.clone()
+  }
+}
+
+impl CallError {
+  fn new_err(py: Python, err: object::CallError) -> PyErr {
+    match Py::new(py, Self::new(format!("{:?}", err))) {
+      Ok(err) => PyErr::from_value(err.as_ref(py)),
+      Err(err) => err,
+    }
+  }
+
+  /// The `boozle::object::CallError` a Python exception stands for. Anything
+  /// but a `CallError` is printed and reported as `Failed`.
+  fn from_err(py: Python, err: PyErr) -> object::CallError {
+    let kind = match err.value(py).extract::<PyRef<Self>>() {
+      Ok(err) => err.kind.clone(),
+      Err(_) => {
+        err.print(py);
+        return object::CallError::Failed;
+      }
+    };
+    match kind.as_str() {
+      "InvalidResponse" => object::CallError::InvalidResponse,
+      "Comm" => object::CallError::Comm,
+      "UnknownMethod" => object::CallError::UnknownMethod,
+      "InvalidArgument" => object::CallError::InvalidArgument,
+      "Timeout" => object::CallError::Timeout,
+      "Internal" => object::CallError::Internal,
+      "LimitExceeded" => object::CallError::LimitExceeded,
+      "Deadlock" => object::CallError::Deadlock,
+      _ => object::CallError::Failed,
+    }
+  }
+}
+
+#[pyfunction]
+fn complete(future: &PyAny, value: PyObject, error: PyObject) -> PyResult<()> {
+  // The future may have been cancelled while the call ran.
+  if future.call_method0("done")?.is_true()? {
+    return Ok(());
+  }
+  if error.is_none(future.py()) {
+    future.call_method1("set_result", (value,))?;
+  } else {
+    future.call_method1("set_exception", (error,))?;
+  }
+  Ok(())
+}
+
+/// Completes an asyncio future from any thread.
+struct Completer {
+  event_loop: PyObject,
+  future: PyObject,
+}
+
+impl Completer {
+  /// Creates a future of `event_loop`, returning it and its completer.
+  fn new(py: Python, event_loop: &PyObject) -> PyResult<(PyObject, Self)> {
+    let future: PyObject = event_loop.call_method0(py, "create_future")?;
+    let completer = Self {
+      event_loop: event_loop.clone_ref(py),
+      future: future.clone_ref(py),
+    };
+    Ok((future, completer))
+  }
+
+  fn complete<T, C>(self, result: Result<T, object::CallError>, convert: C)
+  where
+    C: FnOnce(Python, T) -> PyResult<PyObject>,
+  {
+    Python::with_gil(|py| {
+      let result = result
+        .map_err(|err| CallError::new_err(py, err))
+        .and_then(|value| convert(py, value));
+      let (value, error) = match result {
+        Ok(value) => (value, py.None()),
+        Err(err) => (py.None(), err.into_value(py).into_py(py)),
+      };
+      let scheduled = wrap_pyfunction!(complete, py).and_then(|complete| {
+        self
+          .event_loop
+          .call_method1(py, "call_soon_threadsafe", (complete, self.future, value, error))
+      });
+      // Fails only if the event loop is closed, in which case nobody is
+      // waiting for the result.
+      drop(scheduled);
+    });
+  }
+}
+
+/// Runs `f` on the runtime of `context`, returning a future for its result.
+fn spawn<F, Fut, T, C>(py: Python, context: &Context, f: F, convert: C) -> PyResult<PyObject>
+where
+  F: FnOnce() -> Fut + Send + 'static,
+  Fut: Future<Output = Result<T, object::CallError>> + 'static,
+  T: 'static,
+  C: FnOnce(Python, T) -> PyResult<PyObject> + Send + 'static,
+{
+  let (future, completer) = Completer::new(py, &context.event_loop)?;
+  context.handle.spawn(f, move |result| completer.complete(result, convert));
+  Ok(future)
+}
+
+/// A connection to a boozle peer.
+#[pyclass(module = "boozle")]
+pub struct Connection {
+  runtime: Option<blocking::Connection>,
+  context: Arc<Context>,
+}
+
+impl Connection {
+  /// Starts a connection and returns a future completing once `connect` has
+  /// attached it to a transport.
+  fn open<F, Fut>(py: Python, connect: F) -> PyResult<PyObject>
+  where
+    F: FnOnce(mpsc::Sender<Box<[u8]>>, mpsc::Receiver<Box<[u8]>>) -> Fut + Send + 'static,
+    Fut: Future<Output = std::io::Result<()>> + 'static,
+  {
+    let event_loop: PyObject = py.import("asyncio")?.call_method0("get_running_loop")?.into();
+    let (tx, outgoing) = mpsc::channel(16);
+    let (incoming, rx) = mpsc::channel(16);
+    let runtime = blocking::Connection::new(tx, rx);
+    let context = Arc::new(Context {
+      handle: runtime.handle(),
+      event_loop,
+    });
+    let connection = Py::new(
+      py,
+      Self {
+        runtime: Some(runtime),
+        context: context.clone(),
+      },
+    )?;
+    spawn(
+      py,
+      &context,
+      move || async move { connect(incoming, outgoing).await.map_err(|_| object::CallError::Comm) },
+      move |py, ()| Ok(connection.into_py(py)),
+    )
+  }
+}
+
+#[pymethods]
+impl Connection {
+  /// Connects to a peer listening on the Unix socket at `path`.
+  #[staticmethod]
+  fn connect_unix(py: Python, path: String) -> PyResult<PyObject> {
+    Self::open(py, move |incoming, outgoing| async move {
+      let socket = tokio::net::UnixStream::connect(path).await?;
+      let (reader, writer) = tokio::io::split(socket);
+      stream::bridge(reader, writer, incoming, outgoing);
+      Ok(())
+    })
+  }
+
+  /// Connects to a peer listening on TCP at `host` and `port`.
+  #[staticmethod]
+  fn connect_tcp(py: Python, host: String, port: u16) -> PyResult<PyObject> {
+    Self::open(py, move |incoming, outgoing| async move {
+      let socket = tokio::net::TcpStream::connect((host.as_str(), port)).await?;
+      socket.set_nodelay(true)?;
+      let (reader, writer) = tokio::io::split(socket);
+      stream::bridge(reader, writer, incoming, outgoing);
+      Ok(())
+    })
+  }
+
+  /// A handle to the object the peer has exposed under `object_id`.
+  fn remote(&self, py: Python, object_id: u64) -> PyResult<RemoteObject> {
+    let handle = self.context.handle.clone();
+    let value = py
+      .allow_threads(move || handle.remote(object_id))
+      .map_err(|err| CallError::new_err(py, err))?;
+    Ok(RemoteObject {
+      context: self.context.clone(),
+      connection: self.context.handle.connection(),
+      object_id,
+      value: Some(value),
+    })
+  }
+
+  /// Makes `object` callable by the peer. Returns a future for its key in the
+  /// pool.
+  fn expose(&self, py: Python, object: &PyAny) -> PyResult<PyObject> {
+    let arg = match Arg::extract(object)? {
+      Some(Arg::Lit(_)) | None => return Err(PyValueError::new_err("only objects can be exposed")),
+      Some(arg) => arg,
+    };
+    let context = self.context.clone();
+    let connection = context.handle.connection();
+    spawn(
+      py,
+      &self.context,
+      move || async move {
+        let pool = connection.pool().await;
+        let value = arg.into_local(&context);
+        pool
+          .expose(value)
+          .await
+          .map(|result| result.key)
+          .map_err(|_| object::CallError::Failed)
+      },
+      |py, key| Ok(key.into_py(py)),
+    )
+  }
+
+  /// Removes `key` from the pool, so the peer can no longer call it.
+  fn remove(&self, py: Python, key: u64) -> PyResult<PyObject> {
+    let connection = self.context.handle.connection();
+    spawn(
+      py,
+      &self.context,
+      move || async move {
+        let pool = connection.pool().await;
+        pool.remove(key).await.map(drop).map_err(|_| object::CallError::Failed)
+      },
+      |py, ()| Ok(py.None()),
+    )
+  }
+
+  /// Sets how long calls may take before failing with `Timeout`.
+  fn set_timeout(&mut self, seconds: f64) -> PyResult<()> {
+    if !(seconds > 0.0 && seconds.is_finite()) {
+      return Err(PyValueError::new_err("timeout must be positive"));
+    }
+    self.context = Arc::new(Context {
+      handle: self.context.handle.with_timeout(Duration::from_secs_f64(seconds)),
+      event_loop: Python::with_gil(|py| self.context.event_loop.clone_ref(py)),
+    });
+    Ok(())
+  }
+
+  /// Shuts down the connection. Outstanding and later calls fail with `Comm`.
+  fn close(&mut self, py: Python) {
+    if let Some(runtime) = self.runtime.take() {
+      py.allow_threads(move || drop(runtime));
+    }
+  }
+}
+
+impl Drop for Connection {
+  fn drop(&mut self) {
+    // Shutting down joins the runtime thread, which may be waiting for the
+    // GIL or be the current thread, so it's done on a thread of its own.
+    if let Some(runtime) = self.runtime.take() {
+      std::thread::spawn(move || drop(runtime));
+    }
+  }
+}
+
+/// A handle to an object owned by the peer. The peer frees the object once
+/// the handle is released or garbage collected.
+#[pyclass(module = "boozle")]
+pub struct RemoteObject {
+  context: Arc<Context>,
+  connection: Addr<connection::Connection>,
+  object_id: u64,
+  value: Option<LocalValue>,
+}
+
+#[pymethods]
+impl RemoteObject {
+  #[getter]
+  fn object_id(&self) -> u64 {
+    self.object_id
+  }
+
+  #[getter]
+  fn released(&self) -> bool {
+    self.value.is_none()
+  }
+
+  /// Calls method `method_id` with `argument`, which is `None`, `bytes` or an
+  /// object. Returns a future for the result, which is `None`, `bytes` or a
+  /// `RemoteObject`. Objects are only returned if `returns_object` is set.
+  #[pyo3(signature = (method_id, argument = None, returns_object = false))]
+  fn call(&self, py: Python, method_id: u64, argument: Option<&PyAny>, returns_object: bool) -> PyResult<PyObject> {
+    if self.value.is_none() {
+      return Err(CallError::new_err(py, object::CallError::Failed));
+    }
+    let argument = match argument {
+      Some(argument) => Arg::extract(argument)?,
+      None => None,
+    };
+    let context = self.context.clone();
+    let connection = self.connection.clone();
+    let object_id = self.object_id;
+    spawn(
+      py,
+      &self.context,
+      move || async move {
+        let res = connection
+          .req(req::Req::Call(req::Call {
+            mutable: false,
+            object_id,
+            method_id,
+            argument: argument.map(|argument| argument.into_local(&context)),
+            store_result: returns_object,
+            timeout: None,
+            metadata: Default::default(),
+          }))
+          .await
+          .map_err(object::CallError::from)?;
+        match res {
+          res::Res::Call(ret) => match ret.value {
+            Some(value) => Out::new(value).await.map(Some),
+            None => Ok(None),
+          },
+          res::Res::Free(_) | res::Res::Eval(_) => Err(object::CallError::InvalidResponse),
+        }
+      },
+      {
+        let context = self.context.clone();
+        move |py, out: Option<Out>| match out {
+          Some(out) => out.into_py(py, &context),
+          None => Ok(py.None()),
+        }
+      },
+    )
+  }
+
+  /// Releases the handle now rather than when it's garbage collected.
+  fn release(&mut self) {
+    self.value = None;
+  }
+}
+
+#[pymodule]
+fn _boozle(py: Python, module: &PyModule) -> PyResult<()> {
+  module.add("CallError", py.get_type::<CallError>())?;
+  module.add_class::<Connection>()?;
+  module.add_class::<RemoteObject>()?;
+  Ok(())
+}
@@ -0,0 +1,190 @@
+//! Conversions between Python values and `LocalValue`s. Python sees literals
+//! as `bytes` and the peer's objects as `RemoteObject`s. Local objects are any
+//! Python objects with an async `boozle_dispatch(method_id, argument)` method,
+//! such as the `FooObject` classes generated by `boozle-gen-py`.
+
+use actix::Addr;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+use boozle::blocking::Handle;
+use boozle::connection::Connection;
+use boozle::object::{Call, CallError, CallMut, Object, ObjectActorHelpers, ProxyInfo, Return};
+use boozle::value::LocalValue;
+
+/// What Python objects created for a connection need.
+pub struct Context {
+  pub handle: Handle,
+  /// The event loop that runs Python's side of calls.
+  pub event_loop: PyObject,
+}
+
+/// A value from Python, converted to a `LocalValue` on the runtime.
+pub enum Arg {
+  Lit(Box<[u8]>),
+  Remote(LocalValue),
+  Local(PyObject),
+}
+
+impl Arg {
+  pub fn extract(value: &PyAny) -> PyResult<Option<Self>> {
+    if value.is_none() {
+      return Ok(None);
+    }
+    if let Ok(bytes) = value.downcast::<PyBytes>() {
+      return Ok(Some(Self::Lit(bytes.as_bytes().into())));
+    }
+    if let Ok(remote) = value.extract::<PyRef<super::RemoteObject>>() {
+      return match &remote.value {
+        Some(value) => Ok(Some(Self::Remote(value.clone()))),
+        None => Err(super::CallError::new_err(value.py(), CallError::Failed)),
+      };
+    }
+    if value.hasattr("boozle_dispatch")? {
+      return Ok(Some(Self::Local(value.into())));
+    }
+    Err(PyTypeError::new_err(format!(
+      "expected None, bytes, a RemoteObject or an object with boozle_dispatch, got {}",
+      value.get_type().name()?
+    )))
+  }
+
+  /// Must be called on the runtime, where local objects' actors are started.
+  pub fn into_local(self, context: &Arc<Context>) -> LocalValue {
+    match self {
+      Self::Lit(lit) => LocalValue::lit(lit),
+      Self::Remote(value) => value,
+      Self::Local(target) => LocalValue::from_object(Exposed {
+        target,
+        context: context.clone(),
+      }),
+    }
+  }
+}
+
+/// A value for Python, converted from a `LocalValue` on the runtime.
+pub enum Out {
+  Lit(Arc<[u8]>),
+  Remote {
+    connection: Addr<Connection>,
+    object_id: u64,
+    value: LocalValue,
+  },
+}
+
+impl Out {
+  /// Objects from the peer arrive as proxies. Anything else can't be named
+  /// from Python and is rejected.
+  pub async fn new(value: LocalValue) -> Result<Self, CallError> {
+    match value {
+      LocalValue::Lit(lit) => Ok(Self::Lit(lit)),
+      LocalValue::Actor(actor) => match actor.proxy_info().await {
+        Some(ProxyInfo { connection, object_id }) => Ok(Self::Remote {
+          connection,
+          object_id,
+          value: LocalValue::Actor(actor),
+        }),
+        None => Err(CallError::InvalidResponse),
+      },
+    }
+  }
+
+  pub fn into_py(self, py: Python, context: &Arc<Context>) -> PyResult<PyObject> {
+    match self {
+      Self::Lit(lit) => Ok(PyBytes::new(py, &lit).into_py(py)),
+      Self::Remote {
+        connection,
+        object_id,
+        value,
+      } => {
+        let remote = super::RemoteObject {
+          context: context.clone(),
+          connection,
+          object_id,
+          value: Some(value),
+        };
+        Ok(Py::new(py, remote)?.into_py(py))
+      }
+    }
+  }
+}
+
+/// Completes a call to a Python object once its coroutine finishes.
+#[pyclass]
+struct Dispatched {
+  tx: Option<oneshot::Sender<Result<Option<Arg>, CallError>>>,
+}
+
+#[pymethods]
+impl Dispatched {
+  fn __call__(&mut self, py: Python, future: &PyAny) {
+    let result = match future.call_method0("result") {
+      Ok(value) => Arg::extract(value).map_err(|err| super::CallError::from_err(py, err)),
+      Err(err) => Err(super::CallError::from_err(py, err)),
+    };
+    if let Some(tx) = self.tx.take() {
+      let _ = tx.send(result);
+    }
+  }
+}
+
+/// A Python object exposed to the peer. Calls run as coroutines on the
+/// connection's event loop.
+#[derive(Debug)]
+pub struct Exposed {
+  target: PyObject,
+  context: Arc<Context>,
+}
+
+impl std::fmt::Debug for Context {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Context").finish()
+  }
+}
+
+impl Exposed {
+  async fn dispatch(&self, method_id: u64, argument: Option<LocalValue>) -> Result<Return, CallError> {
+    let argument = match argument {
+      Some(argument) => Some(Out::new(argument).await.map_err(|_| CallError::InvalidArgument)?),
+      None => None,
+    };
+    let (tx, rx) = oneshot::channel();
+    Python::with_gil(|py| -> PyResult<()> {
+      let argument = match argument {
+        Some(argument) => argument.into_py(py, &self.context)?,
+        None => py.None(),
+      };
+      let coroutine = self.target.call_method1(py, "boozle_dispatch", (method_id, argument))?;
+      let future = py.import("asyncio")?.call_method1(
+        "run_coroutine_threadsafe",
+        (coroutine, self.context.event_loop.clone_ref(py)),
+      )?;
+      future.call_method1("add_done_callback", (Dispatched { tx: Some(tx) },))?;
+      Ok(())
+    })
+    .map_err(|err| Python::with_gil(|py| super::CallError::from_err(py, err)))?;
+
+    let result = rx.await.map_err(|_| CallError::Failed)??;
+    Ok(Return {
+      result: result.map(|result| result.into_local(&self.context)),
+    })
+  }
+}
+
+#[async_trait::async_trait]
+impl Object for Exposed {
+  async fn call(&self, call: Call) -> Result<Return, CallError> {
+    self.dispatch(call.method_id, call.argument).await
+  }
+
+  async fn call_mut(&mut self, call: CallMut) -> Result<Return, CallError> {
+    self.dispatch(call.method_id, call.argument).await
+  }
+
+  fn proxy_info(&self) -> Option<ProxyInfo> {
+    None
+  }
+}
@@ -0,0 +1,173 @@
+//! Calls a Rust `Connection` from Python through the extension module and a
+//! generated client, over a Unix socket. Needs `python3` (3.7 or later).
+//! Ignored by default, run it with
+//! `cargo test -p boozle-py --test asyncio -- --ignored`. Fails without Python.
+
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::CallError;
+use boozle::pool::PoolHelpers;
+use boozle::transport::stream;
+use boozle::value::LocalValue;
+use boozle_gen::descriptor::Descriptor;
+use boozle_gen::plugin::write_files;
+use boozle_gen_py::{generate, Options};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use actix::Actor;
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn increment(&self) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(1, Ordering::SeqCst) + 1)
+  }
+
+  async fn get(&self) -> Result<u32, CallError> {
+    Ok(self.value.load(Ordering::SeqCst))
+  }
+}
+
+#[derive(Debug)]
+pub struct Calc;
+
+#[boozle::object]
+impl Calc {
+  async fn add(&self, a: i64, b: i64) -> Result<i64, CallError> {
+    Ok(a + b)
+  }
+
+  async fn greet(&self, name: String) -> Result<String, CallError> {
+    Ok(format!("hello, {}", name))
+  }
+
+  async fn mix(&self, a: bool, b: u8, c: u64, d: f64) -> Result<f64, CallError> {
+    Ok(a as u8 as f64 + b as f64 + c as f64 + d)
+  }
+
+  async fn ping(&self) -> Result<(), CallError> {
+    Ok(())
+  }
+
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+
+  async fn adopt(&self, counter: CounterClient) -> Result<bool, CallError> {
+    Ok(counter.increment().await.is_ok())
+  }
+}
+
+fn python() -> bool {
+  let script = "import sys; sys.exit(0 if sys.version_info >= (3, 7) else 1)";
+  match Command::new("python3").arg("-c").arg(script).status() {
+    Ok(status) => status.success(),
+    Err(_) => false,
+  }
+}
+
+/// The extension module, built alongside this test.
+fn extension() -> PathBuf {
+  let deps = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+  let name = if cfg!(target_os = "macos") {
+    "lib_boozle.dylib"
+  } else {
+    "lib_boozle.so"
+  };
+  [deps.join(name), deps.parent().unwrap().join(name)]
+    .iter()
+    .find(|path| path.exists())
+    .unwrap_or_else(|| panic!("{} wasn't built", name))
+    .clone()
+}
+
+/// Lays out the runtime package, the generated package and the test script
+/// in `dir`.
+fn build(dir: &Path) {
+  let runtime = dir.join("boozle");
+  std::fs::create_dir_all(&runtime).unwrap();
+  let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+  for entry in std::fs::read_dir(manifest.join("python/boozle")).unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().and_then(|extension| extension.to_str()) == Some("py") {
+      std::fs::copy(&path, runtime.join(path.file_name().unwrap())).unwrap();
+    }
+  }
+  std::fs::copy(extension(), runtime.join("_boozle.so")).unwrap();
+  std::fs::copy(manifest.join("tests/asyncio/test.py"), dir.join("test.py")).unwrap();
+
+  let root = dir.join("calc.boozle");
+  std::fs::write(&root, format!("{}\n{}", Counter::BOOZLE, Calc::BOOZLE)).unwrap();
+  let descriptor = Descriptor::new(&boozle_gen::load(&root).unwrap()).unwrap();
+  let files = generate(&descriptor, &Options::default()).unwrap();
+  write_files(dir.join("calc"), files.as_slice()).unwrap();
+}
+
+/// Serves on a new thread, exposing a `Calc` to the first client. Returns the
+/// `Calc`'s ID once the socket is listening.
+fn serve(socket: PathBuf) -> u64 {
+  let (key_tx, key_rx) = std::sync::mpsc::channel();
+  std::thread::spawn(move || {
+    let mut system = actix::System::new("asyncio");
+    system.block_on(async move {
+      let (tx, outgoing) = mpsc::channel(16);
+      let (incoming, rx) = mpsc::channel(16);
+      let connection = Connection::new(tx, rx).start();
+      let pool = connection.pool().await;
+      let key = pool.expose(LocalValue::from_object(Calc)).await.unwrap().key;
+
+      let mut listener = tokio::net::UnixListener::bind(socket).unwrap();
+      key_tx.send(key).unwrap();
+      let (stream, _) = listener.accept().await.unwrap();
+      let (reader, writer) = tokio::io::split(stream);
+      stream::bridge(reader, writer, incoming, outgoing);
+      std::future::pending::<()>().await;
+    });
+  });
+  key_rx.recv_timeout(Duration::from_secs(30)).unwrap()
+}
+
+#[test]
+#[ignore]
+fn python_client() {
+  assert!(python(), "needs python3, 3.7 or later");
+
+  let dir = std::env::temp_dir().join(format!("boozle-py-asyncio-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  build(&dir);
+
+  let socket = dir.join("socket");
+  let key = serve(socket.clone());
+  let mut python = Command::new("python3")
+    .arg(dir.join("test.py"))
+    .arg(&socket)
+    .arg(key.to_string())
+    .arg(boozle_gen::method_id("Calc", "add").to_string())
+    .env("PYTHONPATH", &dir)
+    .stdin(Stdio::null())
+    .spawn()
+    .unwrap();
+
+  let deadline = Instant::now() + Duration::from_secs(60);
+  let status = loop {
+    if let Some(status) = python.try_wait().unwrap() {
+      break status;
+    }
+    if Instant::now() > deadline {
+      let _ = python.kill();
+      panic!("python client timed out");
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  };
+  assert!(status.success(), "python client failed");
+}
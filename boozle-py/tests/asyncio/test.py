@@ -0,0 +1,69 @@
+# Client half of the asyncio test in `asyncio.rs`. Connects to the Rust server
+# on the Unix socket given as the first argument and exercises the `Calc` it
+# exposes under the ID given as the second through the generated client. The
+# third argument is the method ID of `Calc.add`, for calling it dynamically.
+
+import asyncio
+import sys
+
+import boozle
+from calc import CalcClient, Counter
+
+
+class LocalCounter(Counter):
+    def __init__(self) -> None:
+        self.value = 0
+
+    async def increment(self) -> int:
+        self.value += 1
+        return self.value
+
+    async def get(self) -> int:
+        return self.value
+
+
+async def main() -> None:
+    connection = await boozle.connect_unix(sys.argv[1])
+    connection.set_timeout(10)
+    calc = CalcClient(connection.remote(int(sys.argv[2])))
+
+    assert await calc.add(40, 2) == 42
+    assert await calc.add(-5, 2) == -3
+    assert await calc.greet("boozle") == "hello, boozle"
+    assert await calc.mix(True, 2, 3, 0.5) == 6.5
+    assert await calc.ping() is None
+
+    # Calls run concurrently on the connection.
+    sums = await asyncio.gather(*(calc.add(i, i) for i in range(20)))
+    assert sums == [2 * i for i in range(20)]
+
+    # Objects returned by the server are kept in its pool until released.
+    counter = await calc.counter(10)
+    assert await counter.increment() == 11
+    assert await counter.get() == 11
+    counter.release()
+    try:
+        await counter.get()
+        raise AssertionError("call on a released handle succeeded")
+    except boozle.CallError as err:
+        assert err.kind == "Failed"
+
+    # Local objects are served on this event loop, and the server sees the
+    # results of the calls it makes on them.
+    local = LocalCounter()
+    assert await calc.adopt(local) is True
+    assert local.value == 1
+
+    # Dynamic invocation, as generated clients do it.
+    ret = await calc.remote.call(int(sys.argv[3]), boozle.write_lit((boozle.i64, 1), (boozle.i64, 2)))
+    assert boozle.from_lit(boozle.i64, ret, "InvalidResponse") == 3
+
+    connection.close()
+    try:
+        await calc.ping()
+        raise AssertionError("call on a closed connection succeeded")
+    except boozle.CallError as err:
+        assert err.kind == "Comm"
+
+
+asyncio.run(main())
@@ -0,0 +1,246 @@
+// Reads and writes the bincode 1 format used by the Rust implementation:
+// little-endian fixed-size integers, `u64` length prefixes, `u32` enum variant
+// indices and a one-byte tag for `Option`.
+
+export class DecodeError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "DecodeError";
+  }
+}
+
+const utf8Encoder = new TextEncoder();
+const utf8Decoder = new TextDecoder("utf-8", { fatal: true });
+
+export class Writer {
+  private buffer: Uint8Array;
+  private view: DataView;
+  private length: number;
+
+  constructor(capacity = 64) {
+    this.buffer = new Uint8Array(capacity);
+    this.view = new DataView(this.buffer.buffer);
+    this.length = 0;
+  }
+
+  private reserve(size: number): number {
+    const offset = this.length;
+    if (offset + size > this.buffer.length) {
+      const buffer = new Uint8Array(Math.max(this.buffer.length * 2, offset + size));
+      buffer.set(this.buffer.subarray(0, offset));
+      this.buffer = buffer;
+      this.view = new DataView(buffer.buffer);
+    }
+    this.length += size;
+    return offset;
+  }
+
+  u8(value: number): this {
+    this.view.setUint8(this.reserve(1), value);
+    return this;
+  }
+
+  u16(value: number): this {
+    this.view.setUint16(this.reserve(2), value, true);
+    return this;
+  }
+
+  u32(value: number): this {
+    this.view.setUint32(this.reserve(4), value, true);
+    return this;
+  }
+
+  u64(value: bigint): this {
+    this.view.setBigUint64(this.reserve(8), value, true);
+    return this;
+  }
+
+  i8(value: number): this {
+    this.view.setInt8(this.reserve(1), value);
+    return this;
+  }
+
+  i16(value: number): this {
+    this.view.setInt16(this.reserve(2), value, true);
+    return this;
+  }
+
+  i32(value: number): this {
+    this.view.setInt32(this.reserve(4), value, true);
+    return this;
+  }
+
+  i64(value: bigint): this {
+    this.view.setBigInt64(this.reserve(8), value, true);
+    return this;
+  }
+
+  f32(value: number): this {
+    this.view.setFloat32(this.reserve(4), value, true);
+    return this;
+  }
+
+  f64(value: number): this {
+    this.view.setFloat64(this.reserve(8), value, true);
+    return this;
+  }
+
+  bool(value: boolean): this {
+    return this.u8(value ? 1 : 0);
+  }
+
+  /** Writes bytes without a length prefix. */
+  raw(bytes: Uint8Array): this {
+    this.buffer.set(bytes, this.reserve(bytes.length));
+    return this;
+  }
+
+  /** Writes a length-prefixed byte sequence (`Vec<u8>`, `Box<[u8]>`). */
+  bytes(bytes: Uint8Array): this {
+    return this.u64(BigInt(bytes.length)).raw(bytes);
+  }
+
+  string(value: string): this {
+    return this.bytes(utf8Encoder.encode(value));
+  }
+
+  finish(): Uint8Array {
+    return this.buffer.slice(0, this.length);
+  }
+}
+
+export class Reader {
+  private readonly bytes: Uint8Array;
+  private readonly view: DataView;
+  private offset: number;
+
+  constructor(bytes: Uint8Array) {
+    this.bytes = bytes;
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    this.offset = 0;
+  }
+
+  private take(size: number): number {
+    const offset = this.offset;
+    if (offset + size > this.bytes.length) {
+      throw new DecodeError("unexpected end of input");
+    }
+    this.offset += size;
+    return offset;
+  }
+
+  u8(): number {
+    return this.view.getUint8(this.take(1));
+  }
+
+  u16(): number {
+    return this.view.getUint16(this.take(2), true);
+  }
+
+  u32(): number {
+    return this.view.getUint32(this.take(4), true);
+  }
+
+  u64(): bigint {
+    return this.view.getBigUint64(this.take(8), true);
+  }
+
+  i8(): number {
+    return this.view.getInt8(this.take(1));
+  }
+
+  i16(): number {
+    return this.view.getInt16(this.take(2), true);
+  }
+
+  i32(): number {
+    return this.view.getInt32(this.take(4), true);
+  }
+
+  i64(): bigint {
+    return this.view.getBigInt64(this.take(8), true);
+  }
+
+  f32(): number {
+    return this.view.getFloat32(this.take(4), true);
+  }
+
+  f64(): number {
+    return this.view.getFloat64(this.take(8), true);
+  }
+
+  bool(): boolean {
+    const value = this.u8();
+    if (value > 1) {
+      throw new DecodeError(`invalid bool ${value}`);
+    }
+    return value === 1;
+  }
+
+  /** Reads `length` bytes without a length prefix. */
+  raw(length: number): Uint8Array {
+    const offset = this.take(length);
+    return this.bytes.slice(offset, offset + length);
+  }
+
+  /** Reads everything that's left. */
+  rest(): Uint8Array {
+    return this.raw(this.bytes.length - this.offset);
+  }
+
+  bytesPrefixed(): Uint8Array {
+    const length = this.u64();
+    if (length > BigInt(this.bytes.length - this.offset)) {
+      throw new DecodeError("length prefix exceeds input");
+    }
+    return this.raw(Number(length));
+  }
+
+  string(): string {
+    try {
+      return utf8Decoder.decode(this.bytesPrefixed());
+    } catch (err) {
+      if (err instanceof DecodeError) {
+        throw err;
+      }
+      throw new DecodeError("invalid UTF-8");
+    }
+  }
+
+  /** Throws unless all input has been read. */
+  finish(): void {
+    if (this.offset !== this.bytes.length) {
+      throw new DecodeError(`${this.bytes.length - this.offset} trailing bytes`);
+    }
+  }
+}
+
+/** Encodes and decodes values of one literal type. */
+export interface Codec<T> {
+  encode(writer: Writer, value: T): void;
+  decode(reader: Reader): T;
+}
+
+function codec<T>(encode: (writer: Writer, value: T) => void, decode: (reader: Reader) => T): Codec<T> {
+  return { encode, decode };
+}
+
+export const bool = codec<boolean>((w, v) => w.bool(v), (r) => r.bool());
+export const u8 = codec<number>((w, v) => w.u8(v), (r) => r.u8());
+export const u16 = codec<number>((w, v) => w.u16(v), (r) => r.u16());
+export const u32 = codec<number>((w, v) => w.u32(v), (r) => r.u32());
+export const u64 = codec<bigint>((w, v) => w.u64(v), (r) => r.u64());
+export const i8 = codec<number>((w, v) => w.i8(v), (r) => r.i8());
+export const i16 = codec<number>((w, v) => w.i16(v), (r) => r.i16());
+export const i32 = codec<number>((w, v) => w.i32(v), (r) => r.i32());
+export const i64 = codec<bigint>((w, v) => w.i64(v), (r) => r.i64());
+export const f32 = codec<number>((w, v) => w.f32(v), (r) => r.f32());
+export const f64 = codec<number>((w, v) => w.f64(v), (r) => r.f64());
+export const string = codec<string>((w, v) => w.string(v), (r) => r.string());
+
+/**
+ * Passes already-encoded bytes through unchanged. Used for types the generator
+ * has no mapping for. Decoding consumes the rest of the input, so a raw value
+ * can only be the last of several.
+ */
+export const raw = codec<Uint8Array>((w, v) => w.raw(v), (r) => r.rest());
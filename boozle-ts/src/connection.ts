@@ -0,0 +1,565 @@
+// A boozle connection over a WebSocket. Each binary WebSocket message carries
+// one protocol `Msg`.
+
+import { Codec, DecodeError, Reader, Writer } from "./bincode";
+import {
+  accepts,
+  Call,
+  decodeMsg,
+  describeIncompatible,
+  encodeMsg,
+  ErrorCode,
+  hello,
+  Hello,
+  Msg,
+  negotiate,
+  Req,
+  Res,
+  Value,
+  Welcome,
+} from "./protocol";
+
+/** Mirrors `boozle::object::CallError`. */
+export type CallErrorKind =
+  | "InvalidResponse"
+  | "Failed"
+  | "Comm"
+  | "UnknownMethod"
+  | "InvalidArgument"
+  | "Timeout"
+  | "Internal"
+  | "LimitExceeded"
+  | "Deadlock";
+
+export class CallError extends Error {
+  readonly kind: CallErrorKind;
+
+  constructor(kind: CallErrorKind, message?: string) {
+    super(message === undefined ? kind : `${kind}: ${message}`);
+    this.name = "CallError";
+    this.kind = kind;
+  }
+}
+
+/**
+ * An object implemented in this process that the peer may call. Generated
+ * `FooObject` classes implement it on top of a `Foo`.
+ */
+export interface LocalObject {
+  dispatch(methodId: bigint, argument: LocalValue | undefined): Promise<LocalValue | undefined>;
+}
+
+/** A literal, as its encoded bytes, or an object. */
+export type LocalValue = Uint8Array | RemoteObject | LocalObject;
+
+/** The parts of the WebSocket API used here. Browser and `ws` sockets both fit. */
+export interface WebSocketLike {
+  binaryType: string;
+  send(data: Uint8Array): void;
+  close(): void;
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  addEventListener(type: string, listener: (event: any) => void): void;
+}
+
+export interface ConnectionOptions {
+  /** Calls without a response after this long fail with `Timeout`. Defaults to 30 seconds. 0 waits forever. */
+  timeoutMs?: number;
+  /** The ID this end introduces itself with. Defaults to a random one. */
+  nodeId?: bigint;
+  /** Metadata sent with every call. */
+  metadata?: Map<string, string>;
+}
+
+export interface ConnectOptions extends ConnectionOptions {
+  /** The WebSocket implementation to use. Defaults to the global `WebSocket`. */
+  WebSocket?: new (url: string) => WebSocketLike;
+}
+
+export const DEFAULT_TIMEOUT_MS = 30000;
+
+/** Keys of exposed objects count down from here, like the Rust pool's. */
+const EXPOSE_START = (1n << 64n) - 1n;
+
+interface Pending {
+  resolve(value: Value | undefined): void;
+  reject(err: CallError): void;
+  timer?: ReturnType<typeof setTimeout>;
+}
+
+function toBytes(data: unknown): Uint8Array {
+  if (data instanceof ArrayBuffer) {
+    return new Uint8Array(data);
+  }
+  if (ArrayBuffer.isView(data)) {
+    return new Uint8Array(data.buffer, data.byteOffset, data.byteLength);
+  }
+  throw new CallError("Comm", "received a non-binary message");
+}
+
+/** The kind of error a failed request is reported as, like the Rust implementation's. */
+function errorKind(code: ErrorCode): CallErrorKind {
+  switch (code) {
+    case "UnknownMethod":
+    case "InvalidArgument":
+    case "Internal":
+    case "LimitExceeded":
+      return code;
+    case "DeadlineExceeded":
+      return "Timeout";
+    case "UnknownObject":
+    case "Application":
+      return "Failed";
+  }
+}
+
+/** The code a failed call is reported to the peer with. */
+function errorCode(err: unknown): ErrorCode {
+  if (!(err instanceof CallError)) {
+    return "Application";
+  }
+  switch (err.kind) {
+    case "UnknownMethod":
+    case "InvalidArgument":
+    case "Internal":
+    case "LimitExceeded":
+      return err.kind;
+    default:
+      return "Application";
+  }
+}
+
+/**
+ * Forwards calls to an object owned by the peer. Used when such an object is
+ * passed back to it, since references always name an object in the sender's pool.
+ */
+class Forward implements LocalObject {
+  private readonly target: RemoteObject;
+
+  constructor(target: RemoteObject) {
+    this.target = target;
+  }
+
+  dispatch(methodId: bigint, argument: LocalValue | undefined): Promise<LocalValue | undefined> {
+    return this.target.call(methodId, argument);
+  }
+}
+
+/** A handle to an object owned by the peer. */
+export class RemoteObject {
+  readonly connection: Connection;
+  readonly id: bigint;
+  private released = false;
+
+  constructor(connection: Connection, id: bigint) {
+    this.connection = connection;
+    this.id = id;
+  }
+
+  /**
+   * Calls a method. Set `returnsObject` for methods that return an object, so
+   * the peer keeps the result and replies with a reference to it.
+   */
+  call(methodId: bigint, argument?: LocalValue, returnsObject = false): Promise<LocalValue | undefined> {
+    if (this.released) {
+      return Promise.reject(new CallError("Failed", "object was released"));
+    }
+    return this.connection.call(this.id, methodId, argument, returnsObject);
+  }
+
+  /** Tells the peer this handle is no longer needed. Further calls fail. */
+  release(): Promise<void> {
+    if (this.released) {
+      return Promise.resolve();
+    }
+    this.released = true;
+    return this.connection.free(this);
+  }
+
+  get isReleased(): boolean {
+    return this.released;
+  }
+}
+
+export class Connection {
+  private readonly socket: WebSocketLike;
+  private readonly timeoutMs: number;
+  private readonly metadata: Map<string, string>;
+  private readonly pending = new Map<bigint, Pending>();
+  /** Objects and stored results the peer may refer to. */
+  private readonly pool = new Map<bigint, LocalValue>();
+  private nextRequestId = 1n;
+  private nextObjectId = 0n;
+  private nextExposeId = EXPOSE_START;
+  private closedError?: CallError;
+  private readonly finalizer?: FinalizationRegistry<bigint>;
+  private readonly hello: Hello;
+  /** Whether the peer's `Hello` was accepted. Requests are only handled after. */
+  private accepted = false;
+  private welcome?: (welcome: Welcome) => void;
+  private rejectWelcome?: (err: CallError) => void;
+  /** Resolves with what the peer agreed to, once it answered this end's `Hello`. */
+  readonly handshake: Promise<Welcome>;
+
+  /** Opens a WebSocket to `url` and resolves once it is connected. */
+  static connect(url: string, options: ConnectOptions = {}): Promise<Connection> {
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const WebSocketImpl = options.WebSocket ?? (globalThis as any).WebSocket;
+    if (WebSocketImpl === undefined) {
+      return Promise.reject(new Error("no WebSocket implementation available, pass options.WebSocket"));
+    }
+    const socket: WebSocketLike = new WebSocketImpl(url);
+    return new Promise((resolve, reject) => {
+      let opened = false;
+      socket.addEventListener("open", () => {
+        opened = true;
+        resolve(new Connection(socket, options));
+      });
+      socket.addEventListener("error", () => {
+        if (!opened) {
+          reject(new CallError("Comm", `couldn't connect to ${url}`));
+        }
+      });
+    });
+  }
+
+  /** Wraps an open WebSocket. */
+  constructor(socket: WebSocketLike, options: ConnectionOptions = {}) {
+    this.socket = socket;
+    this.timeoutMs = options.timeoutMs ?? DEFAULT_TIMEOUT_MS;
+    this.metadata = options.metadata ?? new Map();
+    socket.binaryType = "arraybuffer";
+    socket.addEventListener("message", (event) => this.onMessage(event.data));
+    socket.addEventListener("close", () => this.shutdown(new CallError("Comm", "connection closed")));
+    socket.addEventListener("error", () => this.shutdown(new CallError("Comm", "connection failed")));
+    if (typeof FinalizationRegistry !== "undefined") {
+      this.finalizer = new FinalizationRegistry((id) => this.sendFree(id));
+    }
+    this.handshake = new Promise((resolve, reject) => {
+      this.welcome = resolve;
+      this.rejectWelcome = reject;
+    });
+    // Failures also reach every request, so they needn't be handled here.
+    this.handshake.catch(() => undefined);
+    const nodeId = options.nodeId ?? BigInt(Math.floor(Math.random() * Number.MAX_SAFE_INTEGER));
+    // Pipelined programs (`Eval`) aren't supported, so no features are offered.
+    this.hello = hello(nodeId, []);
+    this.send({ dir: "hello", hello: this.hello });
+  }
+
+  /** A handle to an object the peer has exposed under `objectId`. */
+  remote(objectId: bigint): RemoteObject {
+    return new RemoteObject(this, objectId);
+  }
+
+  /** Makes `object` callable by the peer and returns its ID. */
+  expose(object: LocalObject): bigint {
+    const id = this.nextExposeId;
+    this.nextExposeId -= 1n;
+    this.pool.set(id, object);
+    return id;
+  }
+
+  /** The number of objects and stored results the peer holds references to. */
+  get exposedCount(): number {
+    return this.pool.size;
+  }
+
+  close(): void {
+    this.socket.close();
+    this.shutdown(new CallError("Comm", "connection closed"));
+  }
+
+  /** Used by `RemoteObject`. */
+  async call(
+    objectId: bigint,
+    methodId: bigint,
+    argument: LocalValue | undefined,
+    returnsObject: boolean
+  ): Promise<LocalValue | undefined> {
+    let toObjectId: bigint | undefined;
+    if (returnsObject) {
+      toObjectId = this.nextObjectId;
+      this.nextObjectId += 1n;
+    }
+    const call: Call = {
+      kind: "call",
+      mutable: false,
+      objectId,
+      methodId,
+      argument: argument === undefined ? undefined : this.toWire(argument),
+      toObjectId,
+      // The peer gives up when this end does.
+      deadline: this.timeoutMs > 0 ? BigInt(this.timeoutMs) : undefined,
+      metadata: this.metadata,
+    };
+    const value = await this.request(call);
+    return value === undefined ? undefined : this.fromWire(value, true);
+  }
+
+  /** Used by `RemoteObject`. */
+  async free(object: RemoteObject): Promise<void> {
+    this.finalizer?.unregister(object);
+    await this.request({ kind: "free", objectId: object.id });
+  }
+
+  private sendFree(objectId: bigint): void {
+    this.request({ kind: "free", objectId }).catch(() => undefined);
+  }
+
+  private request(ty: Req["ty"]): Promise<Value | undefined> {
+    if (this.closedError !== undefined) {
+      return Promise.reject(this.closedError);
+    }
+    const id = this.nextRequestId;
+    this.nextRequestId += 1n;
+    return new Promise((resolve, reject) => {
+      const pending: Pending = { resolve, reject };
+      if (this.timeoutMs > 0) {
+        pending.timer = setTimeout(() => {
+          this.pending.delete(id);
+          reject(new CallError("Timeout"));
+          // Follows the request, if it was sent at all.
+          const cancel: Req = { id: this.nextRequestId, ty: { kind: "cancel", reqId: id } };
+          this.nextRequestId += 1n;
+          this.handshake.then(
+            () => this.send({ dir: "req", req: cancel }),
+            () => undefined
+          );
+        }, this.timeoutMs);
+      }
+      this.pending.set(id, pending);
+      // Nothing is sent before the peer welcomed this end.
+      this.handshake.then(
+        () => this.send({ dir: "req", req: { id, ty } }),
+        () => undefined
+      );
+    });
+  }
+
+  private send(msg: Msg): void {
+    try {
+      this.socket.send(encodeMsg(msg));
+    } catch (err) {
+      this.shutdown(new CallError("Comm", String(err)));
+    }
+  }
+
+  /** Converts an outgoing value, exposing any local objects it holds. */
+  private toWire(value: LocalValue): Value {
+    if (value instanceof Uint8Array) {
+      return { kind: "lit", bytes: value };
+    }
+    const object = value instanceof RemoteObject ? new Forward(value) : value;
+    return { kind: "ref", owner: 0, id: this.expose(object) };
+  }
+
+  /**
+   * Converts an incoming value. References received in responses are owned by
+   * this side and are freed automatically once garbage collected.
+   */
+  private fromWire(value: Value, owned: boolean): LocalValue {
+    if (value.kind === "lit") {
+      return value.bytes;
+    }
+    const remote = this.remote(value.id);
+    if (owned) {
+      this.finalizer?.register(remote, value.id, remote);
+    }
+    return remote;
+  }
+
+  private onMessage(data: unknown): void {
+    let msg: Msg;
+    try {
+      msg = decodeMsg(toBytes(data));
+    } catch (err) {
+      this.socket.close();
+      this.shutdown(new CallError("Comm", `invalid message: ${String(err)}`));
+      return;
+    }
+    switch (msg.dir) {
+      case "hello": {
+        const answer = negotiate(this.hello, msg.hello);
+        if ("welcome" in answer) {
+          this.accepted = true;
+          this.send({ dir: "welcome", welcome: answer.welcome });
+        } else {
+          this.send({ dir: "reject", reason: answer.reason });
+          this.fail(`rejected the peer: ${describeIncompatible(answer.reason)}`);
+        }
+        return;
+      }
+      case "welcome":
+        if (accepts(this.hello, msg.welcome)) {
+          this.welcome?.(msg.welcome);
+        } else {
+          this.fail("the peer didn't follow the handshake");
+        }
+        return;
+      case "reject":
+        this.fail(`rejected by the peer: ${describeIncompatible(msg.reason)}`);
+        return;
+      case "req":
+      case "res":
+        if (!this.accepted) {
+          this.fail("the peer didn't follow the handshake");
+          return;
+        }
+    }
+
+    if (msg.dir === "res") {
+      const pending = this.pending.get(msg.res.id);
+      if (pending === undefined) {
+        return;
+      }
+      this.pending.delete(msg.res.id);
+      if (pending.timer !== undefined) {
+        clearTimeout(pending.timer);
+      }
+      const ty = msg.res.ty;
+      if (ty.kind === "error") {
+        pending.reject(new CallError(errorKind(ty.code), ty.message));
+      } else {
+        pending.resolve(ty.kind === "call" ? ty.value : undefined);
+      }
+      return;
+    }
+
+    const req = msg.req;
+    if (req.ty.kind === "cancel") {
+      // Calls can't be interrupted here, and late responses are ignored by the peer.
+      return;
+    }
+    if (req.ty.kind === "free") {
+      const ty: Res["ty"] = this.pool.delete(req.ty.objectId)
+        ? { kind: "free" }
+        : { kind: "error", code: "UnknownObject", message: `no object ${req.ty.objectId}` };
+      this.send({ dir: "res", res: { id: req.id, ty } });
+      return;
+    }
+    const deadline = req.ty.deadline;
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    let call = this.onCall(req.ty);
+    if (deadline !== undefined) {
+      // The call keeps running, but the peer is answered when its deadline passes.
+      const exceeded = new Promise<Res["ty"]>((resolve) => {
+        timer = setTimeout(
+          () => resolve({ kind: "error", code: "DeadlineExceeded", message: `deadline of ${deadline} ms exceeded` }),
+          Number(deadline)
+        );
+      });
+      call = Promise.race([call, exceeded]);
+    }
+    call.then((ty) => {
+      clearTimeout(timer);
+      this.send({ dir: "res", res: { id: req.id, ty } });
+    });
+  }
+
+  /**
+   * Handles a call from the peer. Like the Rust implementation, failures are
+   * reported as errors, literal results are returned as they are, and object
+   * results are only returned when the caller asked for the result to be
+   * stored.
+   */
+  private async onCall(call: Call): Promise<Res["ty"]> {
+    const target = this.pool.get(call.objectId);
+    if (target === undefined || target instanceof Uint8Array || target instanceof RemoteObject) {
+      return { kind: "error", code: "UnknownObject", message: `no object ${call.objectId}` };
+    }
+    let result: LocalValue | undefined;
+    try {
+      const argument = call.argument === undefined ? undefined : this.fromWire(call.argument, false);
+      result = await target.dispatch(call.methodId, argument);
+    } catch (err) {
+      return { kind: "error", code: errorCode(err), message: String(err) };
+    }
+    if (result === undefined) {
+      return { kind: "call" };
+    }
+    if (result instanceof Uint8Array) {
+      return { kind: "call", value: { kind: "lit", bytes: result } };
+    }
+    if (call.toObjectId !== undefined) {
+      this.pool.set(call.toObjectId, result instanceof RemoteObject ? new Forward(result) : result);
+      return { kind: "call", value: { kind: "ref", owner: 0, id: call.toObjectId } };
+    }
+    return { kind: "call" };
+  }
+
+  /** Closes the connection after a failed handshake. */
+  private fail(message: string): void {
+    this.socket.close();
+    this.shutdown(new CallError("Comm", message));
+  }
+
+  private shutdown(err: CallError): void {
+    if (this.closedError !== undefined) {
+      return;
+    }
+    this.closedError = err;
+    this.rejectWelcome?.(err);
+    for (const pending of this.pending.values()) {
+      if (pending.timer !== undefined) {
+        clearTimeout(pending.timer);
+      }
+      pending.reject(err);
+    }
+    this.pending.clear();
+  }
+}
+
+/** Encodes a literal of one value. */
+export function lit<T>(codec: Codec<T>, value: T): Uint8Array {
+  return writeLit((writer) => codec.encode(writer, value));
+}
+
+/** Encodes a literal written by `write`, such as a tuple of arguments. */
+export function writeLit(write: (writer: Writer) => void): Uint8Array {
+  const writer = new Writer();
+  write(writer);
+  return writer.finish();
+}
+
+/** Decodes a literal of one value, failing with `kind` if `value` isn't a valid one. */
+export function fromLit<T>(codec: Codec<T>, value: LocalValue | undefined, kind: CallErrorKind): T {
+  return readLit(value, kind, (reader) => codec.decode(reader));
+}
+
+/**
+ * Decodes a literal with `read`, failing with `kind` if `value` isn't a
+ * literal or `read` doesn't consume it exactly.
+ */
+export function readLit<T>(value: LocalValue | undefined, kind: CallErrorKind, read: (reader: Reader) => T): T {
+  if (!(value instanceof Uint8Array)) {
+    throw new CallError(kind, "expected a literal");
+  }
+  try {
+    const reader = new Reader(value);
+    const result = read(reader);
+    reader.finish();
+    return result;
+  } catch (err) {
+    if (err instanceof DecodeError) {
+      throw new CallError(kind, err.message);
+    }
+    throw err;
+  }
+}
+
+/** Returns `value` as a handle to a peer's object, failing with `kind` if it isn't one. */
+export function toRemote(value: LocalValue | undefined, kind: CallErrorKind): RemoteObject {
+  if (!(value instanceof RemoteObject)) {
+    throw new CallError(kind, "expected an object");
+  }
+  return value;
+}
+
+/**
+ * Converts an implementation of a trait into a value. Generated clients are
+ * passed by their remote handle; anything else is wrapped with `wrap`.
+ */
+export function objectValue<T>(target: T, wrap: (target: T) => LocalObject): RemoteObject | LocalObject {
+  const remote = (target as unknown as { remote?: unknown }).remote;
+  return remote instanceof RemoteObject ? remote : wrap(target);
+}
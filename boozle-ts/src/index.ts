@@ -0,0 +1,3 @@
+export * from "./bincode";
+export * from "./protocol";
+export * from "./connection";
@@ -0,0 +1,417 @@
+// The boozle wire protocol, mirroring `boozle::protocol` in Rust. Every
+// transport message is a bincode `Msg` whose payload is a bincode `Req` or `Res`.
+
+import { DecodeError, Reader, Writer } from "./bincode";
+
+/** A value on the wire: literal bytes, or an object in the sender's pool. */
+export type Value = { kind: "lit"; bytes: Uint8Array } | { kind: "ref"; owner: number; id: bigint };
+
+export interface Call {
+  kind: "call";
+  mutable: boolean;
+  objectId: bigint;
+  methodId: bigint;
+  argument?: Value;
+  /** Asks the callee to keep the result in its pool under this ID. */
+  toObjectId?: bigint;
+  /** How many milliseconds the caller waits for the response, counted from when the call arrives. */
+  deadline?: bigint;
+  /** Key/value pairs the callee may look at, e.g. to trace or authenticate the call. */
+  metadata: Map<string, string>;
+}
+
+export interface Free {
+  kind: "free";
+  objectId: bigint;
+}
+
+/** Tells the peer to stop handling request `reqId`, which won't get a response. */
+export interface Cancel {
+  kind: "cancel";
+  reqId: bigint;
+}
+
+export interface Req {
+  id: bigint;
+  ty: Call | Free | Cancel;
+}
+
+/** Mirrors `boozle::protocol::res::ErrorCode`, in order. */
+export const ERROR_CODES = ["UnknownObject", "UnknownMethod", "InvalidArgument", "Application", "Internal", "DeadlineExceeded", "LimitExceeded"] as const;
+
+export type ErrorCode = (typeof ERROR_CODES)[number];
+
+export interface Res {
+  id: bigint;
+  ty: { kind: "call"; value?: Value } | { kind: "free" } | { kind: "error"; code: ErrorCode; message: string };
+}
+
+/** Starts the handshake, mirroring `boozle::protocol::hello::Hello`. */
+export interface Hello {
+  minVersion: number;
+  maxVersion: number;
+  nodeId: bigint;
+  /** Supported codecs, most preferred first. */
+  codecs: string[];
+  /** Supported compressors, most preferred first. */
+  compressors: string[];
+  features: string[];
+}
+
+/** What the sender of a `Hello` may use for the rest of the connection. */
+export interface Welcome {
+  version: number;
+  /** The node answering the `Hello`. */
+  nodeId: bigint;
+  codec: string;
+  compressor: string;
+  /** The features both ends support. */
+  features: string[];
+}
+
+/** Why a `Hello` was rejected. `version` carries the versions the rejecting end supports. */
+export type Incompatible = { kind: "version"; min: number; max: number } | { kind: "codec" } | { kind: "compressor" };
+
+export type Msg =
+  | { dir: "req"; req: Req }
+  | { dir: "res"; res: Res }
+  | { dir: "hello"; hello: Hello }
+  | { dir: "welcome"; welcome: Welcome }
+  | { dir: "reject"; reason: Incompatible };
+
+/** The newest and oldest protocol versions this implementation speaks. */
+export const VERSION = 1;
+export const MIN_VERSION = 1;
+
+export const CODEC_BINCODE = "bincode";
+export const COMPRESSOR_NONE = "none";
+
+const DIR_REQ = 0;
+const DIR_RES = 1;
+const DIR_HELLO = 2;
+const DIR_WELCOME = 3;
+const DIR_REJECT = 4;
+
+// Variants of `Res`. `Eval` (2) isn't supported.
+const RES_CALL = 0;
+const RES_FREE = 1;
+const RES_ERROR = 3;
+
+function writeOption<T>(writer: Writer, value: T | undefined, write: (value: T) => void): void {
+  if (value === undefined) {
+    writer.u8(0);
+  } else {
+    writer.u8(1);
+    write(value);
+  }
+}
+
+function readOption<T>(reader: Reader, read: () => T): T | undefined {
+  const tag = reader.u8();
+  switch (tag) {
+    case 0:
+      return undefined;
+    case 1:
+      return read();
+    default:
+      throw new DecodeError(`invalid option tag ${tag}`);
+  }
+}
+
+function writeValue(writer: Writer, value: Value): void {
+  if (value.kind === "lit") {
+    writer.u32(0).bytes(value.bytes);
+  } else {
+    writer.u32(1).u32(value.owner).u64(value.id);
+  }
+}
+
+function readValue(reader: Reader): Value {
+  const variant = reader.u32();
+  switch (variant) {
+    case 0:
+      return { kind: "lit", bytes: reader.bytesPrefixed() };
+    case 1:
+      return { kind: "ref", owner: reader.u32(), id: reader.u64() };
+    default:
+      throw new DecodeError(`invalid value variant ${variant}`);
+  }
+}
+
+function writeStrings(writer: Writer, values: string[]): void {
+  writer.u64(BigInt(values.length));
+  for (const value of values) {
+    writer.string(value);
+  }
+}
+
+function writeMetadata(writer: Writer, metadata: Map<string, string>): void {
+  writer.u64(BigInt(metadata.size));
+  for (const [key, value] of metadata) {
+    writer.string(key).string(value);
+  }
+}
+
+function readMetadata(reader: Reader): Map<string, string> {
+  const length = reader.u64();
+  const metadata = new Map<string, string>();
+  for (let i = 0n; i < length; i++) {
+    const key = reader.string();
+    metadata.set(key, reader.string());
+  }
+  return metadata;
+}
+
+function readStrings(reader: Reader): string[] {
+  const length = reader.u64();
+  const values: string[] = [];
+  for (let i = 0n; i < length; i++) {
+    values.push(reader.string());
+  }
+  return values;
+}
+
+/** A `Hello` offering every version, codec and compressor this implementation supports. */
+export function hello(nodeId: bigint, features: string[]): Hello {
+  return {
+    minVersion: MIN_VERSION,
+    maxVersion: VERSION,
+    nodeId,
+    codecs: [CODEC_BINCODE],
+    compressors: [COMPRESSOR_NONE],
+    features,
+  };
+}
+
+/**
+ * Answers `theirs` given what `ours` supports, like the Rust implementation:
+ * the highest common version, and the codec and compressor the sender of
+ * `theirs` prefers most.
+ */
+export function negotiate(ours: Hello, theirs: Hello): { welcome: Welcome } | { reason: Incompatible } {
+  const version = Math.min(ours.maxVersion, theirs.maxVersion);
+  if (version < Math.max(ours.minVersion, theirs.minVersion)) {
+    return { reason: { kind: "version", min: ours.minVersion, max: ours.maxVersion } };
+  }
+  const codec = theirs.codecs.find((codec) => ours.codecs.includes(codec));
+  if (codec === undefined) {
+    return { reason: { kind: "codec" } };
+  }
+  const compressor = theirs.compressors.find((compressor) => ours.compressors.includes(compressor));
+  if (compressor === undefined) {
+    return { reason: { kind: "compressor" } };
+  }
+  const features = theirs.features.filter((feature) => ours.features.includes(feature));
+  return { welcome: { version, nodeId: ours.nodeId, codec, compressor, features } };
+}
+
+/** Whether `welcome` is a valid answer to `hello`. */
+export function accepts(hello: Hello, welcome: Welcome): boolean {
+  return (
+    welcome.version >= hello.minVersion &&
+    welcome.version <= hello.maxVersion &&
+    hello.codecs.includes(welcome.codec) &&
+    hello.compressors.includes(welcome.compressor) &&
+    welcome.features.every((feature) => hello.features.includes(feature))
+  );
+}
+
+export function describeIncompatible(reason: Incompatible): string {
+  switch (reason.kind) {
+    case "version":
+      return `no common protocol version, expected ${reason.min} to ${reason.max}`;
+    case "codec":
+      return "no common codec";
+    case "compressor":
+      return "no common compressor";
+  }
+}
+
+function encodeHello(hello: Hello): Uint8Array {
+  const writer = new Writer();
+  writer.u16(hello.minVersion).u16(hello.maxVersion).u64(hello.nodeId);
+  writeStrings(writer, hello.codecs);
+  writeStrings(writer, hello.compressors);
+  writeStrings(writer, hello.features);
+  return writer.finish();
+}
+
+function decodeHello(reader: Reader): Hello {
+  const minVersion = reader.u16();
+  const maxVersion = reader.u16();
+  const nodeId = reader.u64();
+  const codecs = readStrings(reader);
+  const compressors = readStrings(reader);
+  return { minVersion, maxVersion, nodeId, codecs, compressors, features: readStrings(reader) };
+}
+
+function encodeWelcome(welcome: Welcome): Uint8Array {
+  const writer = new Writer();
+  writer.u16(welcome.version).u64(welcome.nodeId).string(welcome.codec).string(welcome.compressor);
+  writeStrings(writer, welcome.features);
+  return writer.finish();
+}
+
+function decodeWelcome(reader: Reader): Welcome {
+  const version = reader.u16();
+  const nodeId = reader.u64();
+  const codec = reader.string();
+  const compressor = reader.string();
+  return { version, nodeId, codec, compressor, features: readStrings(reader) };
+}
+
+function encodeIncompatible(reason: Incompatible): Uint8Array {
+  const writer = new Writer();
+  switch (reason.kind) {
+    case "version":
+      writer.u32(0).u16(reason.min).u16(reason.max);
+      break;
+    case "codec":
+      writer.u32(1);
+      break;
+    case "compressor":
+      writer.u32(2);
+      break;
+  }
+  return writer.finish();
+}
+
+function decodeIncompatible(reader: Reader): Incompatible {
+  const variant = reader.u32();
+  switch (variant) {
+    case 0:
+      return { kind: "version", min: reader.u16(), max: reader.u16() };
+    case 1:
+      return { kind: "codec" };
+    case 2:
+      return { kind: "compressor" };
+    default:
+      throw new DecodeError(`invalid rejection variant ${variant}`);
+  }
+}
+
+function encodeReq(req: Req): Uint8Array {
+  const writer = new Writer();
+  writer.u64(req.id);
+  const ty = req.ty;
+  if (ty.kind === "call") {
+    writer.u32(0).bool(ty.mutable).u64(ty.objectId).u64(ty.methodId);
+    writeOption(writer, ty.argument, (argument) => writeValue(writer, argument));
+    writeOption(writer, ty.toObjectId, (id) => writer.u64(id));
+    writeOption(writer, ty.deadline, (deadline) => writer.u64(deadline));
+    writeMetadata(writer, ty.metadata);
+  } else if (ty.kind === "free") {
+    writer.u32(1).u64(ty.objectId);
+  } else {
+    writer.u32(3).u64(ty.reqId);
+  }
+  return writer.finish();
+}
+
+function decodeReq(reader: Reader): Req {
+  const id = reader.u64();
+  const variant = reader.u32();
+  switch (variant) {
+    case 0: {
+      const mutable = reader.bool();
+      const objectId = reader.u64();
+      const methodId = reader.u64();
+      const argument = readOption(reader, () => readValue(reader));
+      const toObjectId = readOption(reader, () => reader.u64());
+      const deadline = readOption(reader, () => reader.u64());
+      const metadata = readMetadata(reader);
+      return { id, ty: { kind: "call", mutable, objectId, methodId, argument, toObjectId, deadline, metadata } };
+    }
+    case 1:
+      return { id, ty: { kind: "free", objectId: reader.u64() } };
+    case 3:
+      return { id, ty: { kind: "cancel", reqId: reader.u64() } };
+    default:
+      throw new DecodeError(`invalid request variant ${variant}`);
+  }
+}
+
+function encodeRes(res: Res): Uint8Array {
+  const writer = new Writer();
+  writer.u64(res.id);
+  const ty = res.ty;
+  if (ty.kind === "call") {
+    writer.u32(RES_CALL);
+    writeOption(writer, ty.value, (value) => writeValue(writer, value));
+  } else if (ty.kind === "error") {
+    writer.u32(RES_ERROR).u32(ERROR_CODES.indexOf(ty.code)).string(ty.message);
+  } else {
+    writer.u32(RES_FREE);
+  }
+  return writer.finish();
+}
+
+function decodeRes(reader: Reader): Res {
+  const id = reader.u64();
+  const variant = reader.u32();
+  switch (variant) {
+    case RES_CALL:
+      return { id, ty: { kind: "call", value: readOption(reader, () => readValue(reader)) } };
+    case RES_FREE:
+      return { id, ty: { kind: "free" } };
+    case RES_ERROR: {
+      const index = reader.u32();
+      const code = ERROR_CODES[index];
+      if (code === undefined) {
+        throw new DecodeError(`invalid error code ${index}`);
+      }
+      return { id, ty: { kind: "error", code, message: reader.string() } };
+    }
+    default:
+      throw new DecodeError(`invalid response variant ${variant}`);
+  }
+}
+
+function encodeData(msg: Msg): [number, Uint8Array] {
+  switch (msg.dir) {
+    case "req":
+      return [DIR_REQ, encodeReq(msg.req)];
+    case "res":
+      return [DIR_RES, encodeRes(msg.res)];
+    case "hello":
+      return [DIR_HELLO, encodeHello(msg.hello)];
+    case "welcome":
+      return [DIR_WELCOME, encodeWelcome(msg.welcome)];
+    case "reject":
+      return [DIR_REJECT, encodeIncompatible(msg.reason)];
+  }
+}
+
+export function encodeMsg(msg: Msg): Uint8Array {
+  const [dir, data] = encodeData(msg);
+  return new Writer(data.length + 12).u32(dir).bytes(data).finish();
+}
+
+export function decodeMsg(bytes: Uint8Array): Msg {
+  const reader = new Reader(bytes);
+  const dir = reader.u32();
+  const data = new Reader(reader.bytesPrefixed());
+  reader.finish();
+  let msg: Msg;
+  switch (dir) {
+    case DIR_REQ:
+      msg = { dir: "req", req: decodeReq(data) };
+      break;
+    case DIR_RES:
+      msg = { dir: "res", res: decodeRes(data) };
+      break;
+    case DIR_HELLO:
+      msg = { dir: "hello", hello: decodeHello(data) };
+      break;
+    case DIR_WELCOME:
+      msg = { dir: "welcome", welcome: decodeWelcome(data) };
+      break;
+    case DIR_REJECT:
+      msg = { dir: "reject", reason: decodeIncompatible(data) };
+      break;
+    default:
+      throw new DecodeError(`invalid direction ${dir}`);
+  }
+  data.finish();
+  return msg;
+}
@@ -0,0 +1,227 @@
+//! Synchronous access to boozle for code that doesn't run inside an async runtime.
+//!
+//! A blocking `Connection` owns a thread running an actix system. Calls made through
+//! a `Handle` are shipped to that thread, executed there, and waited on by the caller.
+//! Code running on that thread, e.g. methods of exposed objects, must not block on a
+//! `Handle`, as nothing could run the call meanwhile. Use `spawn` there instead.
+
+use actix::prelude::*;
+use crossbeam::channel::bounded;
+use tokio::sync::{mpsc, oneshot};
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{JoinHandle, ThreadId};
+use std::time::Duration;
+
+use super::connection::{self, ConnectionHelpers};
+use super::object::{CallError, Object};
+use super::pool::PoolHelpers;
+use super::value::LocalValue;
+
+type Job = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()>>> + Send>;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A timeout that clones share, in nanoseconds.
+#[derive(Clone)]
+struct SharedTimeout(Arc<AtomicU64>);
+
+impl SharedTimeout {
+  fn new(timeout: Duration) -> Self {
+    let shared = Self(Arc::new(AtomicU64::new(0)));
+    shared.set(timeout);
+    shared
+  }
+
+  fn get(&self) -> Duration {
+    Duration::from_nanos(self.0.load(Ordering::Relaxed))
+  }
+
+  fn set(&self, timeout: Duration) {
+    self.0.store(timeout.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+  }
+}
+
+/// A cloneable reference to the runtime of a blocking `Connection`. Clones
+/// share their timeout.
+#[derive(Clone)]
+pub struct Handle {
+  jobs: mpsc::UnboundedSender<Job>,
+  connection: Addr<connection::Connection>,
+  timeout: SharedTimeout,
+  runtime: ThreadId,
+}
+
+impl Handle {
+  /// Runs the future returned by `f` on the connection's runtime and blocks until it
+  /// completes. If it doesn't complete within the handle's timeout, it is dropped and
+  /// `CallError::Timeout` is returned. Fails with `CallError::Deadlock` on the
+  /// connection's runtime thread, which couldn't run the future meanwhile.
+  pub fn call<F, Fut, T>(&self, f: F) -> Result<T, CallError>
+  where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, CallError>> + 'static,
+    T: Send + 'static
+  {
+    if std::thread::current().id() == self.runtime {
+      return Err(CallError::Deadlock);
+    }
+    let (tx, rx) = bounded(1);
+    self.spawn(f, move |result| {
+      let _ = tx.send(result);
+    });
+    rx.recv().map_err(|_| CallError::Comm)?
+  }
+
+  /// Like `call`, but returns immediately. `done` receives the result on the
+  /// connection's runtime, or `CallError::Comm` wherever the job is dropped if the
+  /// runtime shuts down before it completes.
+  pub fn spawn<F, Fut, T, D>(&self, f: F, done: D)
+  where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, CallError>> + 'static,
+    T: 'static,
+    D: FnOnce(Result<T, CallError>) + Send + 'static
+  {
+    let done = Done::new(done);
+    let timeout = self.timeout.get();
+    let _ = self.jobs.send(Box::new(move || Box::pin(async move {
+      let result = match tokio::time::timeout(timeout, f()).await {
+        Ok(result) => result,
+        Err(_) => Err(CallError::Timeout)
+      };
+      done.complete(result);
+    })));
+  }
+
+  pub fn connection(&self) -> Addr<connection::Connection> {
+    self.connection.clone()
+  }
+
+  pub fn timeout(&self) -> Duration {
+    self.timeout.get()
+  }
+
+  /// Returns a copy of this handle with a different timeout. Unlike a clone,
+  /// the copy doesn't follow `Connection::set_timeout`.
+  pub fn with_timeout(&self, timeout: Duration) -> Self {
+    Self {
+      jobs: self.jobs.clone(),
+      connection: self.connection.clone(),
+      timeout: SharedTimeout::new(timeout),
+      runtime: self.runtime
+    }
+  }
+
+  /// Creates a proxy value for an object owned by the remote end of the connection.
+  pub fn remote(&self, object_id: u64) -> Result<LocalValue, CallError> {
+    let connection = self.connection.clone();
+    self.call(move || async move {
+      Ok(connection.remote(object_id))
+    })
+  }
+
+  /// Makes a local object available to the remote end of the connection, returning
+  /// its object ID.
+  pub fn expose<O: Object + Send + Sync + 'static>(&self, object: O) -> Result<u64, CallError> {
+    let connection = self.connection.clone();
+    self.call(move || async move {
+      let pool = connection.pool().await;
+      pool.expose(LocalValue::from_object(object)).await
+        .map(|result| result.key)
+        .map_err(|_| CallError::Failed)
+    })
+  }
+}
+
+/// Calls its function exactly once: with a job's result, or with `CallError::Comm`
+/// if the job is dropped first.
+struct Done<T, D: FnOnce(Result<T, CallError>)>(Option<D>, PhantomData<fn(T)>);
+
+impl<T, D: FnOnce(Result<T, CallError>)> Done<T, D> {
+  fn new(done: D) -> Self {
+    Self(Some(done), PhantomData)
+  }
+
+  fn complete(mut self, result: Result<T, CallError>) {
+    if let Some(done) = self.0.take() {
+      done(result);
+    }
+  }
+}
+
+impl<T, D: FnOnce(Result<T, CallError>)> Drop for Done<T, D> {
+  fn drop(&mut self) {
+    if let Some(done) = self.0.take() {
+      done(Err(CallError::Comm));
+    }
+  }
+}
+
+/// A `connection::Connection` running on a runtime owned by this value. The runtime
+/// is shut down when it is dropped, failing any outstanding calls.
+pub struct Connection {
+  handle: Handle,
+  shutdown: Option<oneshot::Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Connection {
+  pub fn new(tx: mpsc::Sender<Box<[u8]>>, rx: mpsc::Receiver<Box<[u8]>>) -> Self {
+    let (jobs_tx, mut jobs_rx) = mpsc::unbounded_channel::<Job>();
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+    let (connection_tx, connection_rx) = bounded(1);
+
+    let thread = std::thread::spawn(move || {
+      let mut system = System::new("boozle-blocking");
+      system.block_on(async move {
+        let _ = connection_tx.send(connection::Connection::new(tx, rx).start());
+        loop {
+          tokio::select! {
+            job = jobs_rx.recv() => match job {
+              Some(job) => actix::spawn(job()),
+              None => break
+            },
+            _ = &mut shutdown_rx => break
+          }
+        }
+      });
+    });
+
+    Self {
+      handle: Handle {
+        jobs: jobs_tx,
+        connection: connection_rx.recv().unwrap(),
+        timeout: SharedTimeout::new(DEFAULT_TIMEOUT),
+        runtime: thread.thread().id()
+      },
+      shutdown: Some(shutdown_tx),
+      thread: Some(thread),
+    }
+  }
+
+  pub fn handle(&self) -> Handle {
+    self.handle.clone()
+  }
+
+  /// Sets the timeout of the connection's handles, including those handed out
+  /// already. Handles made with `Handle::with_timeout` keep theirs.
+  pub fn set_timeout(&mut self, timeout: Duration) {
+    self.handle.timeout.set(timeout);
+  }
+}
+
+impl Drop for Connection {
+  fn drop(&mut self) {
+    if let Some(shutdown) = self.shutdown.take() {
+      let _ = shutdown.send(());
+    }
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
@@ -1,19 +1,31 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use tokio::sync::{mpsc, oneshot};
+use std::future::Future;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures::future::{abortable, AbortHandle};
+use tokio::sync::{mpsc, oneshot, watch};
 
 use super::object::{ObjectActor, Call, ObjectActorHelpers, CallMut};
 use super::pool::{Pool, InsertUnresolved, InsertResolved, Resolve, Get, PoolHelpers};
 use super::value::{Value, LocalValue, Lit};
 use super::protocol as proto;
+use super::pipeline;
+use super::context::{CallContext, Metadata};
+use boozle_ir::{Instruction, Limits, Object, RemoteId, Unit};
 
 use std::sync::{Arc, atomic::AtomicU64};
+use std::hash::{BuildHasher, Hasher};
 
+pub mod handshake;
 pub mod req;
 pub mod res;
 mod remote;
 
+pub use handshake::HandshakeError;
+
 
 #[derive(Debug)]
 struct CompleteResult;
@@ -27,24 +39,74 @@ enum CompleteError {
 #[rtype(result = "Result<(), CompleteError>")]
 struct Complete {
   req_id: u64,
-  result: Result<res::Res, ()>
+  result: Result<res::Res, res::Error>
+}
+
+/// Gives up on an outstanding request. The remote end is told to stop
+/// handling it if it was sent already.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Abandon {
+  req_id: u64
+}
+
+/// Sends a request without waiting for it. Returns its ID and where its
+/// response arrives.
+#[derive(Message)]
+#[rtype(result = "(u64, oneshot::Receiver<Result<res::Res, res::Error>>)")]
+struct Start {
+  req: req::Req,
+  /// The context of the call being handled when the request was made.
+  context: Option<CallContext>
 }
 
 #[derive(Message)]
 #[rtype(result = "Addr<Pool<u64, LocalValue>>")]
 struct GetPool;
 
+/// Tells the connection that nothing arrives from the remote end anymore.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Closed;
+
+/// Frees an object of the remote end whose proxy is gone. Objects released
+/// together are freed by a single request.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Release {
+  object_id: u64
+}
+
+/// Frees the objects released since the last flush.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct FlushReleased;
+
+#[derive(Message)]
+#[rtype(result = "Result<proto::hello::Welcome, HandshakeError>")]
+struct GetHandshake;
+
 pub struct Connection {
   request_iter: u64,
   object_iter: Arc<AtomicU64>,
   tx: mpsc::Sender<Box<[u8]>>,
   rx: Option<mpsc::Receiver<Box<[u8]>>>,
   pool: Addr<Pool<u64, LocalValue>>,
-  outstanding: HashMap<u64, oneshot::Sender<Result<res::Res, ()>>>,
+  outstanding: HashMap<u64, oneshot::Sender<Result<res::Res, res::Error>>>,
+  limits: Limits,
+  timeout: Option<Duration>,
+  metadata: Metadata,
+  node_id: u64,
+  features: Vec<String>,
+  handshake_tx: Option<watch::Sender<handshake::State>>,
+  handshake: watch::Receiver<handshake::State>,
+  released: Vec<u64>,
+  closed: bool,
 }
 
 impl Connection {
   pub fn new(tx: mpsc::Sender<Box<[u8]>>, rx: mpsc::Receiver<Box<[u8]>>) -> Self {
+    let (handshake_tx, handshake) = watch::channel(None);
     Self {
       request_iter: 0,
       object_iter: Arc::new(AtomicU64::new(0)),
@@ -52,187 +114,319 @@ impl Connection {
       rx: Some(rx),
       pool: Pool::new().start(),
       outstanding: HashMap::new(),
+      limits: Limits::default(),
+      timeout: None,
+      metadata: Metadata::new(),
+      // Random, as every `RandomState` is seeded differently.
+      node_id: std::collections::hash_map::RandomState::new().build_hasher().finish(),
+      features: vec![proto::hello::FEATURE_EVAL.to_string()],
+      handshake_tx: Some(handshake_tx),
+      handshake,
+      released: Vec::new(),
+      closed: false,
     }
   }
 
-  async fn on_req(mut tx: mpsc::Sender<Box<[u8]>>, addr: Addr<Self>, pool: Addr<Pool<u64, LocalValue>>, req: proto::Req) {
-    
-    let res = match req.ty {
-      proto::req::Ty::Call(call) => {
-        let value = pool.get(call.object_id).await.unwrap();
-        match value {
-          LocalValue::Lit(_) => {
-            // panic!("Remote tried to call a method on a literal");
-            proto::Res {
-              id: req.id,
-              ty: proto::res::Ty::Call(proto::res::Return {
-                value: None
-              })
-            }
-          },
-          LocalValue::Actor(actor) => {
-            let argument = match call.argument {
-              Some(argument) => Some(match argument {
-                Value::Lit(lit) => LocalValue::Lit(lit.0),
-                Value::Ref { owner, id } => LocalValue::from_object(remote::Remote::new(id, addr))
-              }),
-              None => None
-            };
-            let result = if call.mutable {
-              actor.call_mut(CallMut {
-                method_id: call.method_id,
-                argument,
-              }).await.unwrap()
-            } else {
-              actor.call(Call {
-                method_id: call.method_id,
-                argument,
-              }).await.unwrap()
-            };
+  /// Sets what programs sent by the remote end may use.
+  pub fn with_limits(mut self, limits: Limits) -> Self {
+    self.limits = limits;
+    self
+  }
 
-            let return_value = if let Some(to_object_id) = call.to_object_id {
-              match result.result {
-                Some(value) => {
-                  let key = pool.insert_resolved(to_object_id, value).await.unwrap();
-                  Some(Value::Ref { owner: 0, id: to_object_id })
-                },
-                None => None
-              }
-            } else {
-              match result.result {
-                Some(value) => {
-                  match value {
-                    LocalValue::Actor(_) => None,
-                    LocalValue::Lit(lit) => Some(Value::Lit(Lit(lit)))
-                  }
-                },
-                None => None
-              }
-            };
-            
-            proto::Res {
-              id: req.id,
-              ty: proto::res::Ty::Call(proto::res::Return {
-                value: return_value
-              })
-            }
+  /// Sets how long requests wait for their response, unless they set their
+  /// own timeout. Requests wait forever by default.
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  /// Sets metadata sent with every call. Calls may replace its values.
+  pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+    self.metadata = metadata;
+    self
+  }
+
+  /// Sets the ID this end introduces itself with.
+  pub fn with_node_id(mut self, node_id: u64) -> Self {
+    self.node_id = node_id;
+    self
+  }
+
+  /// Sets the optional features this end offers in its `Hello`.
+  pub fn with_features(mut self, features: Vec<String>) -> Self {
+    self.features = features;
+    self
+  }
+
+  /// Sends a message of the handshake. Returns false if the remote end went away.
+  async fn send_handshake<T: Serialize>(tx: &mut mpsc::Sender<Box<[u8]>>, dir: proto::Dir, payload: &T) -> bool {
+    let msg = proto::Msg {
+      dir,
+      data: bincode::serialize(payload).unwrap().into_boxed_slice()
+    };
+    tx.send(bincode::serialize(&msg).unwrap().into_boxed_slice()).await.is_ok()
+  }
+
+  /// Converts a value to send to the remote end, exposing actors in `pool`.
+  async fn to_wire(pool: &Addr<Pool<u64, LocalValue>>, value: LocalValue) -> Result<Value, res::Error> {
+    match value {
+      LocalValue::Lit(lit) => Ok(Value::Lit(Lit(lit))),
+      LocalValue::Actor(actor) => match pool.expose(LocalValue::Actor(actor)).await {
+        Ok(exposed) => Ok(Value::Ref {
+          owner: 0,
+          id: exposed.key
+        }),
+        Err(err) => Err(res::Error::Remote {
+          code: res::ErrorCode::Internal,
+          message: format!("couldn't expose an object: {:?}", err)
+        })
+      }
+    }
+  }
+
+  /// Converts a value received from the remote end. References become proxies.
+  fn from_wire(addr: &Addr<Self>, value: Value) -> LocalValue {
+    match value {
+      Value::Lit(lit) => LocalValue::Lit(lit.0),
+      Value::Ref { id, .. } => LocalValue::from_object(remote::Remote::new(id, addr.clone()))
+    }
+  }
+
+  /// Runs a program of pipelined calls on objects of `pool`.
+  async fn eval(addr: &Addr<Self>, pool: &Addr<Pool<u64, LocalValue>>, limits: Limits, eval: proto::req::Eval) -> Result<Vec<proto::res::Return>, proto::res::Error> {
+    let proto::req::Eval { program, imports, to_object_ids, .. } = eval;
+    let unit = Unit::decode(&program).map_err(|err| {
+      proto::res::Error::new(proto::res::ErrorCode::InvalidArgument, format!("invalid program: {}", err))
+    })?;
+    if to_object_ids.len() != unit.outputs as usize {
+      return Err(proto::res::Error::new(proto::res::ErrorCode::InvalidArgument, format!("{} object IDs for {} outputs", to_object_ids.len(), unit.outputs)));
+    }
+
+    let inputs = imports.into_iter().map(|value| Some(Connection::from_wire(addr, value))).collect();
+    let outputs = pipeline::interpret(&unit, inputs, pool, &limits).await?;
+
+    let mut results = Vec::with_capacity(to_object_ids.len());
+    for (output, to_object_id) in outputs.into_iter().zip(to_object_ids) {
+      let value = match output {
+        Some(LocalValue::Lit(lit)) => Some(Value::Lit(Lit(lit))),
+        Some(actor) => {
+          if let Err(err) = pool.insert_resolved(to_object_id, actor).await {
+            return Err(proto::res::Error::new(proto::res::ErrorCode::Internal, format!("couldn't store an output as {}: {:?}", to_object_id, err)));
           }
+          Some(Value::Ref { owner: 0, id: to_object_id })
+        },
+        None => None
+      };
+      results.push(proto::res::Return { value });
+    }
+    Ok(results)
+  }
+
+  /// Calls a method of an object of `pool` for the remote end.
+  async fn call(addr: &Addr<Self>, pool: &Addr<Pool<u64, LocalValue>>, call: proto::req::Call) -> Result<proto::res::Return, proto::res::Error> {
+    let actor = match pool.get(call.object_id).await {
+      Ok(LocalValue::Actor(actor)) => actor,
+      Ok(LocalValue::Lit(_)) => {
+        return Err(proto::res::Error::new(proto::res::ErrorCode::UnknownObject, format!("{} is a literal, not an object", call.object_id)))
+      },
+      Err(_) => {
+        return Err(proto::res::Error::new(proto::res::ErrorCode::UnknownObject, format!("no object {}", call.object_id)))
+      }
+    };
+
+    let argument = call.argument.map(|argument| Connection::from_wire(addr, argument));
+    let result = if call.mutable {
+      actor.call_mut(CallMut {
+        method_id: call.method_id,
+        argument,
+      }).await?
+    } else {
+      actor.call(Call {
+        method_id: call.method_id,
+        argument,
+      }).await?
+    };
+
+    // Literals are returned as they are. Objects are stored if the caller
+    // asked for it, and dropped otherwise.
+    let value = match (result.result, call.to_object_id) {
+      (Some(LocalValue::Lit(lit)), _) => Some(Value::Lit(Lit(lit))),
+      (Some(actor), Some(to_object_id)) => {
+        if let Err(err) = pool.insert_resolved(to_object_id, actor).await {
+          return Err(proto::res::Error::new(proto::res::ErrorCode::Internal, format!("couldn't store the result as {}: {:?}", to_object_id, err)));
         }
+        Some(Value::Ref { owner: 0, id: to_object_id })
       },
-      proto::req::Ty::Free(free) => {
-        pool.remove(free.object_id).await.unwrap();
-        proto::Res {
-          id: req.id,
-          ty: proto::res::Ty::Free
+      (Some(LocalValue::Actor(_)), None) | (None, _) => None
+    };
+    Ok(proto::res::Return { value })
+  }
+
+  /// Handles a request of the remote end, giving up once `deadline` (in
+  /// milliseconds) passed. Requests made meanwhile inherit the deadline and
+  /// `metadata`. They're cancelled along with the request, as dropping them
+  /// abandons them.
+  async fn within<T>(deadline: Option<u64>, metadata: Metadata, handling: impl Future<Output = Result<T, proto::res::Error>>) -> Result<T, proto::res::Error> {
+    let context = CallContext {
+      deadline: deadline.map(|deadline| Instant::now() + Duration::from_millis(deadline)),
+      metadata
+    };
+    let handling = context.scope(handling);
+    match deadline {
+      // The request is dropped once nobody waits for it anymore.
+      Some(deadline) => tokio::time::timeout(Duration::from_millis(deadline), handling).await.unwrap_or_else(|_| {
+        Err(proto::res::Error::new(proto::res::ErrorCode::DeadlineExceeded, format!("deadline of {} ms exceeded", deadline)))
+      }),
+      None => handling.await
+    }
+  }
+
+  /// Handles a request of the remote end. Programs are only run if `eval`
+  /// was negotiated.
+  async fn on_req(mut tx: mpsc::Sender<Box<[u8]>>, addr: Addr<Self>, pool: Addr<Pool<u64, LocalValue>>, limits: Limits, eval: bool, req: proto::Req) {
+    // Failures are reported to the remote end rather than ending the task.
+    let ty = match req.ty {
+      proto::req::Ty::Call(mut call) => {
+        let (deadline, metadata) = (call.deadline, std::mem::take(&mut call.metadata));
+        match Connection::within(deadline, metadata, Connection::call(&addr, &pool, call)).await {
+          Ok(ret) => proto::res::Ty::Call(ret),
+          Err(err) => proto::res::Ty::Error(err)
         }
-      }
+      },
+      proto::req::Ty::Free(free) => match pool.remove(free.object_id).await {
+        Ok(_) => proto::res::Ty::Free,
+        Err(_) => proto::res::Ty::Error(proto::res::Error::new(proto::res::ErrorCode::UnknownObject, format!("no object {}", free.object_id)))
+      },
+      proto::req::Ty::Eval(_) if !eval => {
+        proto::res::Ty::Error(proto::res::Error::new(proto::res::ErrorCode::UnknownMethod, format!("{} wasn't negotiated", proto::hello::FEATURE_EVAL)))
+      },
+      proto::req::Ty::Eval(mut eval) => {
+        let (deadline, metadata) = (eval.deadline, std::mem::take(&mut eval.metadata));
+        match Connection::within(deadline, metadata, Connection::eval(&addr, &pool, limits, eval)).await {
+          Ok(results) => proto::res::Ty::Eval(proto::res::Eval { results }),
+          Err(err) => proto::res::Ty::Error(err)
+        }
+      },
+      // Handled by the read loop, and never answered.
+      proto::req::Ty::Cancel(_) => return
+    };
+    let res = proto::Res {
+      id: req.id,
+      ty
     };
 
     let data = bincode::serialize(&res).unwrap().into_boxed_slice();
     let msg = bincode::serialize(&proto::Msg::res(data)).unwrap().into_boxed_slice();
-    tx.send(msg).await.unwrap();
-    
+    // The remote end may have gone away meanwhile.
+    let _ = tx.send(msg).await;
   }
 
   async fn on_res(tx: mpsc::Sender<Box<[u8]>>, addr: Addr<Self>, pool: Addr<Pool<u64, LocalValue>>, res: proto::Res) {
-    match res.ty {
+    let result = match res.ty {
       proto::res::Ty::Call(call) => {
-        let value = match call.value {
-          Some(value) => Some(match value {
-            Value::Lit(lit) => LocalValue::Lit(lit.0),
-            Value::Ref { owner, id } => LocalValue::from_object(remote::Remote::new(id, addr.clone()))
-          }),
-          None => None
-        };
-        addr.send(Complete {
-          req_id: res.id,
-          result: Ok(res::Res::Call(res::Return {
-            value
-          }))
-        }).await.unwrap().unwrap();
+        let value = call.value.map(|value| Connection::from_wire(&addr, value));
+        Ok(res::Res::Call(res::Return {
+          value
+        }))
       },
-      _ => {}
-    }
+      proto::res::Ty::Eval(eval) => {
+        let results = eval.results.into_iter().map(|result| res::Return {
+          value: result.value.map(|value| Connection::from_wire(&addr, value))
+        }).collect();
+        Ok(res::Res::Eval(res::Eval {
+          results
+        }))
+      },
+      proto::res::Ty::Free => Ok(res::Res::Free(res::Free)),
+      proto::res::Ty::Error(err) => Err(res::Error::Remote {
+        code: err.code,
+        message: err.message
+      })
+    };
+    // Responses to abandoned requests arrive late and are dropped.
+    let _ = addr.send(Complete {
+      req_id: res.id,
+      result
+    }).await;
   }
-}
-
-impl Actor for Connection {
-  type Context = Context<Self>;
-
-  fn started(&mut self, ctx: &mut Self::Context) {
-    let mut rx = self.rx.take().unwrap();
-    let tx = self.tx.clone();
 
-    let pool = self.pool.clone();
-    let addr = ctx.address().clone();
-    
-    actix::spawn(async move {
-      loop {
-        match rx.recv().await {
-          Some(data) => {
-            let msg: proto::Msg = bincode::deserialize(&data).unwrap();
-            match msg.dir {
-              proto::Dir::Req => {
-                let req: proto::Req = bincode::deserialize(&msg.data).unwrap();
-                actix::spawn(Connection::on_req(tx.clone(), addr.clone(), pool.clone(), req));
-              },
-              proto::Dir::Res => {
-                let res: proto::Res = bincode::deserialize(&msg.data).unwrap();
-                actix::spawn(Connection::on_res(tx.clone(), addr.clone(), pool.clone(), res));
-              }
-            }
-          },
-          None => {
-            break
-          }
-        }
-      }
-    })
-    
+  /// A program freeing objects of the remote end.
+  fn free_unit(object_ids: &[u64]) -> Unit {
+    let mut unit = Unit {
+      inputs: 0,
+      outputs: 0,
+      consts: Vec::with_capacity(object_ids.len()),
+      instructions: Vec::with_capacity(object_ids.len())
+    };
+    for (index, object_id) in object_ids.iter().enumerate() {
+      unit.consts.push(boozle_ir::Value::Object(Object::new(RemoteId::LOCAL, *object_id)));
+      unit.instructions.push(Instruction::Free(index as u16));
+    }
+    boozle_ir::merge_frees(&mut unit);
+    unit
   }
-}
 
-impl Handler<req::Req> for Connection {
-  type Result = ResponseFuture<Result<res::Res, ()>>;
+  /// Answers a request that couldn't be decoded, if its ID could.
+  async fn on_undecodable_req(mut tx: mpsc::Sender<Box<[u8]>>, data: Box<[u8]>) {
+    // The ID comes first, so it may be intact even if the rest isn't.
+    let id: u64 = match bincode::deserialize(&data) {
+      Ok(id) => id,
+      Err(_) => return
+    };
+    let res = proto::Res {
+      id,
+      ty: proto::res::Ty::Error(proto::res::Error::new(proto::res::ErrorCode::InvalidArgument, "undecodable request".to_string()))
+    };
+    let data = bincode::serialize(&res).unwrap().into_boxed_slice();
+    let msg = bincode::serialize(&proto::Msg::res(data)).unwrap().into_boxed_slice();
+    let _ = tx.send(msg).await;
+  }
 
-  fn handle(&mut self, msg: req::Req, _: &mut Context<Self>) -> Self::Result {
+  /// Prepares `msg` to be sent. Returns its ID and a future that sends it and
+  /// waits for the response, giving up once the request's timeout or the
+  /// deadline of `context` passed.
+  fn request(&mut self, msg: req::Req, context: Option<CallContext>, ctx: &mut Context<Self>) -> (u64, impl Future<Output = Result<res::Res, res::Error>>) {
     let (tx, rx) = oneshot::channel();
 
     let pool = self.pool.clone();
     let object_iter = self.object_iter.clone();
     let mut conn_tx = self.tx.clone();
+    let handshake = self.handshake.clone();
+    let mut metadata = self.metadata.clone();
+    let addr = ctx.address();
     self.request_iter += 1;
     let request_iter = self.request_iter;
-    self.outstanding.insert(request_iter, tx);
+    // Responses can't arrive anymore once the connection closed.
+    if !self.closed {
+      self.outstanding.insert(request_iter, tx);
+    }
 
-    Box::pin(async move {
+    let timeout = match &msg {
+      req::Req::Call(call) => call.timeout.or(self.timeout),
+      req::Req::Eval(eval) => eval.timeout.or(self.timeout),
+      req::Req::Free(_) => self.timeout
+    };
+    let timeout = match (timeout, context.and_then(|context| context.remaining())) {
+      (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+      (timeout, remaining) => timeout.or(remaining)
+    };
+
+    let response = async move {
       let data = match msg {
         req::Req::Call(call) => {
           let argument = match call.argument {
-            Some(argument) => {
-              Some(match argument {
-                LocalValue::Lit(lit) => {
-                  Value::Lit(Lit(lit))
-                },
-                LocalValue::Actor(actor) => {
-                  let proxy_info = actor.proxy_info().await;
-                  let key = pool.expose(LocalValue::Actor(actor)).await.unwrap().key;
-
-                  Value::Ref {
-                    owner: 0,
-                    id: key
-                  }
-                }
-              })
-            },
+            Some(argument) => Some(Connection::to_wire(&pool, argument).await?),
             None => None
           };
+          metadata.extend(call.metadata);
           let call = proto::req::Call {
             mutable: call.mutable,
             to_object_id: if call.store_result { Some(object_iter.fetch_add(1, std::sync::atomic::Ordering::Relaxed)) } else { None },
             object_id: call.object_id,
             method_id: call.method_id,
-            argument
+            argument,
+            deadline: timeout.map(|timeout| timeout.as_millis() as u64),
+            metadata
           };
 
           bincode::serialize(&proto::Req {
@@ -247,6 +441,27 @@ impl Handler<req::Req> for Connection {
               object_id: free.object_id
             })
           }).unwrap().into_boxed_slice()
+        },
+        req::Req::Eval(eval) => {
+          let mut imports = Vec::with_capacity(eval.imports.len());
+          for import in eval.imports {
+            imports.push(Connection::to_wire(&pool, import).await?);
+          }
+          let to_object_ids = (0..eval.unit.outputs).map(|_| {
+            object_iter.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+          }).collect();
+          metadata.extend(eval.metadata);
+
+          bincode::serialize(&proto::Req {
+            id: request_iter,
+            ty: proto::req::Ty::Eval(proto::req::Eval {
+              program: eval.unit.encode().into_boxed_slice(),
+              imports,
+              to_object_ids,
+              deadline: timeout.map(|timeout| timeout.as_millis() as u64),
+              metadata
+            })
+          }).unwrap().into_boxed_slice()
         }
       };
 
@@ -255,15 +470,208 @@ impl Handler<req::Req> for Connection {
       let msg = bincode::serialize(&proto::Msg::req(data)).unwrap().into_boxed_slice();
 
 
-      conn_tx.send(msg).await.unwrap();
+      // Nothing is sent before the remote end welcomed this end.
+      handshake::wait(handshake).await.map_err(res::Error::Handshake)?;
 
       // TODO: If send fails, clean up pool allocations
+      conn_tx.send(msg).await.map_err(|_| res::Error::Comm)?;
+
+      rx.await.map_err(|_| res::Error::Comm)?
+    };
 
-      rx.await.unwrap()
+    (request_iter, async move {
+      let result = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, response).await.unwrap_or(Err(res::Error::Timeout)),
+        None => response.await
+      };
+      // Does nothing if the response arrived.
+      if result.is_err() {
+        addr.do_send(Abandon { req_id: request_iter });
+      }
+      result
     })
   }
 }
 
+impl Actor for Connection {
+  type Context = Context<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    let mut rx = self.rx.take().unwrap();
+    let mut tx = self.tx.clone();
+    let handshake = self.handshake_tx.take().unwrap();
+    let hello = proto::hello::Hello::new(self.node_id, self.features.clone());
+
+    let pool = self.pool.clone();
+    let limits = self.limits;
+    let addr = ctx.address().clone();
+    
+    actix::spawn(async move {
+      // Dropping `handshake` without an outcome fails it as closed.
+      if !Connection::send_handshake(&mut tx, proto::Dir::Hello, &hello).await {
+        addr.do_send(Closed);
+        return;
+      }
+      let fail = |err| {
+        let _ = handshake.broadcast(Some(Err(err)));
+      };
+
+      // Requests are only handled once the remote end's `Hello` was accepted.
+      let mut accepted = false;
+      let mut eval = false;
+      // Requests being handled, so they can be cancelled.
+      let running: Rc<RefCell<HashMap<u64, AbortHandle>>> = Rc::new(RefCell::new(HashMap::new()));
+      loop {
+        match rx.recv().await {
+          Some(data) => {
+            let msg: proto::Msg = match bincode::deserialize(&data) {
+              Ok(msg) => msg,
+              // Frames that can't be decoded are dropped once the handshake is over.
+              Err(_) if accepted => continue,
+              Err(_) => {
+                fail(HandshakeError::Unexpected);
+                break
+              }
+            };
+            match msg.dir {
+              proto::Dir::Hello => {
+                let theirs: proto::hello::Hello = match bincode::deserialize(&msg.data) {
+                  Ok(theirs) => theirs,
+                  Err(_) => {
+                    fail(HandshakeError::Unexpected);
+                    break
+                  }
+                };
+                match proto::hello::negotiate(&hello, &theirs) {
+                  Ok(welcome) => {
+                    eval = welcome.supports(proto::hello::FEATURE_EVAL);
+                    accepted = Connection::send_handshake(&mut tx, proto::Dir::Welcome, &welcome).await;
+                  },
+                  Err(reason) => {
+                    Connection::send_handshake(&mut tx, proto::Dir::Reject, &reason).await;
+                    fail(HandshakeError::Incompatible(reason));
+                    break
+                  }
+                }
+              },
+              proto::Dir::Welcome => {
+                match bincode::deserialize::<proto::hello::Welcome>(&msg.data) {
+                  Ok(welcome) if hello.accepts(&welcome) => {
+                    let _ = handshake.broadcast(Some(Ok(welcome)));
+                  },
+                  _ => {
+                    fail(HandshakeError::Unexpected);
+                    break
+                  }
+                }
+              },
+              proto::Dir::Reject => {
+                let reason = bincode::deserialize(&msg.data).map(HandshakeError::Rejected).unwrap_or(HandshakeError::Unexpected);
+                fail(reason);
+                break
+              },
+              proto::Dir::Req if accepted => {
+                let req: proto::Req = match bincode::deserialize(&msg.data) {
+                  Ok(req) => req,
+                  Err(_) => {
+                    actix::spawn(Connection::on_undecodable_req(tx.clone(), msg.data));
+                    continue
+                  }
+                };
+                if let proto::req::Ty::Cancel(cancel) = &req.ty {
+                  if let Some(handle) = running.borrow_mut().remove(&cancel.req_id) {
+                    handle.abort();
+                  }
+                  continue
+                }
+                let id = req.id;
+                let (handling, handle) = abortable(Connection::on_req(tx.clone(), addr.clone(), pool.clone(), limits, eval, req));
+                running.borrow_mut().insert(id, handle);
+                let running = running.clone();
+                actix::spawn(async move {
+                  let _ = handling.await;
+                  running.borrow_mut().remove(&id);
+                });
+              },
+              proto::Dir::Res if accepted => {
+                let res: proto::Res = match bincode::deserialize(&msg.data) {
+                  Ok(res) => res,
+                  Err(_) => {
+                    // The request fails if its ID survived, and times out otherwise.
+                    if let Ok(req_id) = bincode::deserialize(&msg.data) {
+                      addr.do_send(Complete {
+                        req_id,
+                        result: Err(res::Error::Comm)
+                      });
+                    }
+                    continue
+                  }
+                };
+                actix::spawn(Connection::on_res(tx.clone(), addr.clone(), pool.clone(), res));
+              },
+              proto::Dir::Req | proto::Dir::Res => {
+                fail(HandshakeError::Unexpected);
+                break
+              }
+            }
+          },
+          None => {
+            break
+          }
+        }
+      }
+      addr.do_send(Closed);
+    })
+    
+  }
+}
+
+impl Handler<req::Req> for Connection {
+  type Result = ResponseFuture<Result<res::Res, res::Error>>;
+
+  fn handle(&mut self, msg: req::Req, ctx: &mut Context<Self>) -> Self::Result {
+    Box::pin(self.request(msg, None, ctx).1)
+  }
+}
+
+impl Handler<Start> for Connection {
+  type Result = MessageResult<Start>;
+
+  fn handle(&mut self, start: Start, ctx: &mut Context<Self>) -> Self::Result {
+    let (req_id, response) = self.request(start.req, start.context, ctx);
+    let (tx, rx) = oneshot::channel();
+    actix::spawn(async move {
+      let _ = tx.send(response.await);
+    });
+    MessageResult((req_id, rx))
+  }
+}
+
+impl Handler<Abandon> for Connection {
+  type Result = ();
+
+  fn handle(&mut self, abandon: Abandon, _: &mut Context<Self>) -> Self::Result {
+    if self.outstanding.remove(&abandon.req_id).is_none() {
+      return;
+    }
+    // Requests are only sent once the handshake succeeded.
+    if let Some(Ok(_)) = *self.handshake.borrow() {
+      let cancel = proto::req::Cancel {
+        req_id: abandon.req_id
+      };
+      let data = bincode::serialize(&proto::Req {
+        id: abandon.req_id,
+        ty: proto::req::Ty::Cancel(cancel)
+      }).unwrap().into_boxed_slice();
+      let msg = bincode::serialize(&proto::Msg::req(data)).unwrap().into_boxed_slice();
+      let mut tx = self.tx.clone();
+      actix::spawn(async move {
+        let _ = tx.send(msg).await;
+      });
+    }
+  }
+}
+
 impl Handler<Complete> for Connection {
   type Result = Result<(), CompleteError>;
 
@@ -280,6 +688,67 @@ impl Handler<Complete> for Connection {
   }
 }
 
+impl Handler<GetHandshake> for Connection {
+  type Result = ResponseFuture<Result<proto::hello::Welcome, HandshakeError>>;
+
+  fn handle(&mut self, _: GetHandshake, _: &mut Context<Self>) -> Self::Result {
+    Box::pin(handshake::wait(self.handshake.clone()))
+  }
+}
+
+impl Handler<Release> for Connection {
+  type Result = ();
+
+  fn handle(&mut self, release: Release, ctx: &mut Context<Self>) -> Self::Result {
+    // Proxies dropped before the flush runs are freed along with this one.
+    if self.released.is_empty() {
+      ctx.notify(FlushReleased);
+    }
+    self.released.push(release.object_id);
+  }
+}
+
+impl Handler<FlushReleased> for Connection {
+  type Result = ();
+
+  fn handle(&mut self, _: FlushReleased, ctx: &mut Context<Self>) -> Self::Result {
+    let released = std::mem::take(&mut self.released);
+    let eval = match &*self.handshake.borrow() {
+      Some(Ok(welcome)) => welcome.supports(proto::hello::FEATURE_EVAL),
+      _ => false
+    };
+    // Remote ends that don't run programs get one request per object.
+    let reqs: Vec<req::Req> = if eval && released.len() > 1 {
+      released.chunks(u16::MAX as usize).map(|object_ids| req::Req::Eval(req::Eval {
+        unit: Connection::free_unit(object_ids),
+        imports: Vec::new(),
+        timeout: None,
+        metadata: Metadata::new()
+      })).collect()
+    } else {
+      released.into_iter().map(|object_id| req::Req::Free(req::Free { object_id })).collect()
+    };
+    for req in reqs {
+      let (_, response) = self.request(req, None, ctx);
+      actix::spawn(async move {
+        // The remote end may have dropped the objects already.
+        let _ = response.await;
+      });
+    }
+  }
+}
+
+impl Handler<Closed> for Connection {
+  type Result = ();
+
+  fn handle(&mut self, _: Closed, _: &mut Context<Self>) -> Self::Result {
+    self.closed = true;
+    for (_, tx) in self.outstanding.drain() {
+      let _ = tx.send(Err(res::Error::Comm));
+    }
+  }
+}
+
 impl Handler<GetPool> for Connection {
   type Result = Addr<Pool<u64, LocalValue>>;
 
@@ -288,19 +757,61 @@ impl Handler<GetPool> for Connection {
   }
 }
 
+/// Abandons a request if the future waiting for it is dropped.
+struct AbandonOnDrop {
+  connection: Addr<Connection>,
+  req_id: Option<u64>
+}
+
+impl Drop for AbandonOnDrop {
+  fn drop(&mut self) {
+    if let Some(req_id) = self.req_id {
+      self.connection.do_send(Abandon { req_id });
+    }
+  }
+}
+
 #[async_trait::async_trait]
 pub trait ConnectionHelpers {
-  async fn req(&self, req: req::Req) -> Result<res::Res, ()>;
+  /// Sends a request and waits for its response. Dropping the returned future
+  /// cancels the request. Requests made while handling a call for the remote
+  /// end of a connection don't outlive that call's deadline.
+  async fn req(&self, req: req::Req) -> Result<res::Res, res::Error>;
   async fn pool(&self) -> Addr<Pool<u64, LocalValue>>;
+
+  /// Waits for the handshake, returning what the remote end agreed to.
+  async fn handshake(&self) -> Result<proto::hello::Welcome, HandshakeError>;
+
+  /// Creates a proxy value for an object owned by the remote end of this connection.
+  fn remote(&self, object_id: u64) -> LocalValue;
 }
 
 #[async_trait::async_trait]
 impl ConnectionHelpers for Addr<Connection> {
-  async fn req(&self, req: req::Req) -> Result<res::Res, ()> {
-    self.send(req).await.unwrap()
+  async fn req(&self, req: req::Req) -> Result<res::Res, res::Error> {
+    let start = Start {
+      req,
+      context: CallContext::current()
+    };
+    let (req_id, response) = self.send(start).await.map_err(|_| res::Error::Comm)?;
+    let mut abandon = AbandonOnDrop {
+      connection: self.clone(),
+      req_id: Some(req_id)
+    };
+    let result = response.await.map_err(|_| res::Error::Comm);
+    abandon.req_id = None;
+    result?
   }
 
   async fn pool(&self) -> Addr<Pool<u64, LocalValue>> {
     self.send(GetPool {}).await.unwrap()
   }
+
+  async fn handshake(&self) -> Result<proto::hello::Welcome, HandshakeError> {
+    self.send(GetHandshake {}).await.map_err(|_| HandshakeError::Closed)?
+  }
+
+  fn remote(&self, object_id: u64) -> LocalValue {
+    LocalValue::from_object(remote::Remote::new(object_id, self.clone()))
+  }
 }
\ No newline at end of file
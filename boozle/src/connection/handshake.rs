@@ -0,0 +1,44 @@
+use tokio::sync::watch;
+
+use crate::protocol::hello::{Incompatible, Welcome};
+
+/// Why a connection's handshake failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HandshakeError {
+  /// This end rejected the remote end's `Hello`.
+  Incompatible(Incompatible),
+  /// The remote end rejected this end's `Hello`.
+  Rejected(Incompatible),
+  /// The remote end didn't follow the handshake.
+  Unexpected,
+  /// The connection closed before the handshake finished.
+  Closed
+}
+
+impl std::fmt::Display for HandshakeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HandshakeError::Incompatible(reason) => write!(f, "rejected the remote end: {}", reason),
+      HandshakeError::Rejected(reason) => write!(f, "rejected by the remote end: {}", reason),
+      HandshakeError::Unexpected => write!(f, "the remote end didn't follow the handshake"),
+      HandshakeError::Closed => write!(f, "the connection closed during the handshake")
+    }
+  }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// The outcome of a handshake, once there is one.
+pub(crate) type State = Option<Result<Welcome, HandshakeError>>;
+
+/// Waits for the outcome of a handshake.
+pub(crate) async fn wait(mut state: watch::Receiver<State>) -> Result<Welcome, HandshakeError> {
+  loop {
+    if let Some(result) = state.borrow().clone() {
+      return result;
+    }
+    if state.recv().await.is_none() {
+      return Err(HandshakeError::Closed);
+    }
+  }
+}
@@ -1,10 +1,12 @@
 
 use actix::Addr;
 
-use super::{Response, Connection, ConnectionHelpers};
+use super::{Response, Connection, ConnectionHelpers, Release};
 
 use crate::object::{Object, Call, ProxyInfo, CallMut, CallError, Return};
-use crate::connection::{req};
+use crate::connection::{req, res};
+use crate::context;
+use crate::value::LocalValue;
 
 pub struct Remote {
   id: u64,
@@ -17,36 +19,43 @@ impl Remote {
   }
 }
 
-#[async_trait::async_trait]
-impl Object for Remote {
-  async fn call(&self, call: Call) -> Result<Return, CallError> {
-    let response = self.connection.send(req::Req::Call(req::Call {
-      mutable: false,
-      argument: call.argument,
-      method_id: call.method_id,
+impl Remote {
+  /// Calls the remote object. Results that are objects are stored by the
+  /// remote end and come back as further `Remote`s.
+  async fn req(&self, mutable: bool, method_id: u64, argument: Option<LocalValue>) -> Result<Return, CallError> {
+    let response = self.connection.req(req::Req::Call(req::Call {
+      mutable,
+      argument,
+      method_id,
       object_id: self.id,
-      store_result: true
-    })).await.unwrap();
+      store_result: true,
+      timeout: None,
+      metadata: context::outgoing_metadata()
+    })).await;
 
-    Err(CallError::Failed)
+    match response {
+      Ok(res::Res::Call(res::Return { value })) => Ok(Return { result: value }),
+      Ok(_) => Err(CallError::InvalidResponse),
+      Err(err) => Err(err.into())
+    }
   }
+}
 
-
+#[async_trait::async_trait]
+impl Object for Remote {
+  async fn call(&self, call: Call) -> Result<Return, CallError> {
+    self.req(false, call.method_id, call.argument).await
+  }
 
   async fn call_mut(&mut self, call: CallMut) -> Result<Return, CallError> {
-    let response = self.connection.send(req::Req::Call(req::Call {
-      mutable: true,
-      argument: call.argument,
-      method_id: call.method_id,
-      object_id: self.id,
-      store_result: true
-    })).await.unwrap();
-
-    Err(CallError::Failed)
+    self.req(true, call.method_id, call.argument).await
   }
 
   fn proxy_info(&self) -> Option<ProxyInfo> {
-      None
+    Some(ProxyInfo {
+      connection: self.connection.clone(),
+      object_id: self.id,
+    })
   }
 }
 
@@ -60,14 +69,8 @@ impl std::fmt::Debug for Remote {
 
 impl Drop for Remote {
   fn drop(&mut self) {
-    // FIXME: This naive implementation slows RPC throughput by more than 4000%
-    // These should be batched and sent periodically
-    let id = self.id;
-    let connection = self.connection.clone();
-    actix::spawn(async move {
-      connection.req(req::Req::Free(req::Free {
-        object_id: id
-      })).await.unwrap();
+    self.connection.do_send(Release {
+      object_id: self.id
     });
   }
-}
\ No newline at end of file
+}
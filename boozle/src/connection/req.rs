@@ -1,26 +1,48 @@
+use crate::context::Metadata;
 use crate::value::LocalValue;
+use boozle_ir::Unit;
 use super::res;
 use actix::Message;
+use std::time::Duration;
 
 #[derive(Message)]
-#[rtype(result = "Result<res::Return, ()>")]
+#[rtype(result = "Result<res::Return, res::Error>")]
 pub struct Call {
   pub mutable: bool,
   pub object_id: u64,
   pub method_id: u64,
   pub argument: Option<LocalValue>,
-  pub store_result: bool
+  pub store_result: bool,
+  /// Overrides the connection's timeout. The remote end is given the same
+  /// deadline.
+  pub timeout: Option<Duration>,
+  /// Sent over the connection's default metadata, replacing values of the
+  /// same keys.
+  pub metadata: Metadata
 }
 
 #[derive(Debug, Message)]
-#[rtype(result = "Result<res::Free, ()>")]
+#[rtype(result = "Result<res::Free, res::Error>")]
 pub struct Free {
   pub object_id: u64
 }
 
 #[derive(Message)]
-#[rtype(result = "Result<res::Res, ()>")]
+#[rtype(result = "Result<res::Eval, res::Error>")]
+pub struct Eval {
+  pub unit: Unit,
+  /// The inputs of the unit.
+  pub imports: Vec<LocalValue>,
+  /// Overrides the connection's timeout, like that of a `Call`.
+  pub timeout: Option<Duration>,
+  /// Sent over the connection's default metadata, like that of a `Call`.
+  pub metadata: Metadata
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<res::Res, res::Error>")]
 pub enum Req {
   Call(Call),
-  Free(Free)
+  Free(Free),
+  Eval(Eval)
 }
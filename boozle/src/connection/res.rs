@@ -1,4 +1,8 @@
+use crate::object::CallError;
 use crate::value::LocalValue;
+use super::HandshakeError;
+
+pub use crate::protocol::res::ErrorCode;
 
 pub struct Return {
   pub value: Option<LocalValue>
@@ -6,7 +10,47 @@ pub struct Return {
 
 pub struct Free;
 
+pub struct Eval {
+  /// The outputs of the program.
+  pub results: Vec<Return>
+}
+
 pub enum Res {
   Call(Return),
-  Free(Free)
-}
\ No newline at end of file
+  Free(Free),
+  Eval(Eval)
+}
+
+/// Why a request failed.
+#[derive(Debug)]
+pub enum Error {
+  /// The request couldn't be sent, or its response never arrived.
+  Comm,
+  /// The connection never got ready to send it.
+  Handshake(HandshakeError),
+  /// No response arrived in time. The request was cancelled.
+  Timeout,
+  /// The request failed with `code`, usually because the remote end couldn't
+  /// handle it.
+  Remote {
+    code: ErrorCode,
+    message: String
+  }
+}
+
+impl From<Error> for CallError {
+  fn from(err: Error) -> Self {
+    match err {
+      Error::Comm | Error::Handshake(_) => CallError::Comm,
+      Error::Timeout => CallError::Timeout,
+      Error::Remote { code, .. } => match code {
+        ErrorCode::UnknownMethod => CallError::UnknownMethod,
+        ErrorCode::InvalidArgument => CallError::InvalidArgument,
+        ErrorCode::Internal => CallError::Internal,
+        ErrorCode::DeadlineExceeded => CallError::Timeout,
+        ErrorCode::LimitExceeded => CallError::LimitExceeded,
+        ErrorCode::UnknownObject | ErrorCode::Application => CallError::Failed
+      }
+    }
+  }
+}
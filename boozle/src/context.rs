@@ -0,0 +1,54 @@
+//! What a node knows about the call it's handling for a remote caller. The
+//! context is set for the task handling the call, so objects can look at it
+//! and calls made while handling it, e.g. by proxies forwarding it to another
+//! node, inherit its deadline.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Key/value pairs sent along with a call.
+pub type Metadata = HashMap<String, String>;
+
+tokio::task_local! {
+  static CURRENT: CallContext;
+  static OUTGOING: Metadata;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CallContext {
+  /// When the caller stops waiting for the response.
+  pub deadline: Option<Instant>,
+  /// What the caller sent along with the call.
+  pub metadata: Metadata
+}
+
+impl CallContext {
+  /// The context of the call the current task is handling, if any.
+  pub fn current() -> Option<CallContext> {
+    CURRENT.try_with(|context| context.clone()).ok()
+  }
+
+  /// Runs `f` as the handling of a call with this context.
+  pub async fn scope<F: Future>(self, f: F) -> F::Output {
+    CURRENT.scope(self, f).await
+  }
+
+  /// How long until the deadline, if there is one. Zero once it passed.
+  pub fn remaining(&self) -> Option<Duration> {
+    self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+  }
+}
+
+/// Runs `f`, sending `metadata` with the calls it makes through proxies. It
+/// replaces values of the same keys set by enclosing scopes and connections.
+pub async fn with_metadata<F: Future>(metadata: Metadata, f: F) -> F::Output {
+  let mut outgoing = outgoing_metadata();
+  outgoing.extend(metadata);
+  OUTGOING.scope(outgoing, f).await
+}
+
+/// The metadata set by `with_metadata` for calls made by the current task.
+pub fn outgoing_metadata() -> Metadata {
+  OUTGOING.try_with(|metadata| metadata.clone()).unwrap_or_default()
+}
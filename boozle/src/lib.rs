@@ -1,11 +1,17 @@
+pub mod blocking;
 pub mod channel;
 pub mod message;
+pub mod mock;
 
 pub mod node;
 pub mod connection;
+pub mod context;
 pub mod object;
 pub mod protocol;
 pub mod park;
+pub mod pipeline;
 pub mod pool;
 pub mod value;
 pub mod transport;
+
+pub use boozle_macros::{export, object};
@@ -0,0 +1,112 @@
+//! Support for generated mock objects (see `boozle_gen_rust::Options::mocks`).
+//!
+//! Each method of a generated mock is a `MockMethod`, which records the arguments
+//! of every call and answers with programmed responses.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use super::object::CallError;
+
+type Responder<A, R> = Arc<dyn Fn(&A) -> Result<R, CallError> + Send + Sync>;
+
+/// A single mocked method taking arguments `A` (a tuple for several parameters)
+/// and returning `R`.
+///
+/// Calls are answered by the oldest queued response, if any. Otherwise the
+/// responder set with `respond_with` is used. If neither exists, the call fails
+/// with `CallError::Failed`.
+///
+/// Clones share their calls and responses, so a mock can still be programmed
+/// and inspected after it's been exposed.
+pub struct MockMethod<A, R> {
+  inner: Arc<Inner<A, R>>,
+}
+
+struct Inner<A, R> {
+  calls: Mutex<Vec<A>>,
+  responses: Mutex<VecDeque<Result<R, CallError>>>,
+  responder: Mutex<Option<Responder<A, R>>>,
+}
+
+impl<A, R> MockMethod<A, R> {
+  pub fn new() -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        calls: Mutex::new(Vec::new()),
+        responses: Mutex::new(VecDeque::new()),
+        responder: Mutex::new(None),
+      }),
+    }
+  }
+
+  /// Queues a response for a single call.
+  pub fn respond(&self, response: Result<R, CallError>) {
+    self.inner.responses.lock().unwrap().push_back(response);
+  }
+
+  /// Answers every call that has no queued response.
+  pub fn respond_with<F>(&self, responder: F)
+  where
+    F: Fn(&A) -> Result<R, CallError> + Send + Sync + 'static
+  {
+    *self.inner.responder.lock().unwrap() = Some(Arc::new(responder));
+  }
+
+  pub fn call_count(&self) -> usize {
+    self.inner.calls.lock().unwrap().len()
+  }
+
+  /// Removes and returns the recorded arguments of every call so far.
+  pub fn take_calls(&self) -> Vec<A> {
+    std::mem::take(&mut *self.inner.calls.lock().unwrap())
+  }
+
+  /// Records a call and produces its response. The responder runs without any
+  /// lock held, so it may use the mock itself.
+  pub fn invoke(&self, args: A) -> Result<R, CallError> {
+    let queued = self.inner.responses.lock().unwrap().pop_front();
+    let response = match queued {
+      Some(response) => response,
+      None => {
+        let responder = self.inner.responder.lock().unwrap().clone();
+        match responder {
+          Some(responder) => responder(&args),
+          None => Err(CallError::Failed),
+        }
+      }
+    };
+    self.inner.calls.lock().unwrap().push(args);
+    response
+  }
+}
+
+impl<A: Clone, R> MockMethod<A, R> {
+  /// Returns the recorded arguments of every call so far.
+  pub fn calls(&self) -> Vec<A> {
+    self.inner.calls.lock().unwrap().clone()
+  }
+}
+
+impl<A, R> Clone for MockMethod<A, R> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<A, R> Default for MockMethod<A, R> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<A, R> std::fmt::Debug for MockMethod<A, R> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MockMethod")
+     .field("calls", &self.call_count())
+     .field("responses", &self.inner.responses.lock().unwrap().len())
+     .finish()
+  }
+}
@@ -1,9 +1,14 @@
 use actix::prelude::*;
 use tokio::sync::{oneshot, RwLock};
 
+use futures::FutureExt;
+use futures::future::BoxFuture;
+
 use std::sync::Arc;
 use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
 
+use super::connection::Connection;
 use super::value::LocalValue;
 
 pub struct Ptr {
@@ -17,7 +22,14 @@ pub enum CallError {
   Failed,
   Comm,
   UnknownMethod,
-  InvalidArgument
+  InvalidArgument,
+  Timeout,
+  /// The callee failed unexpectedly, e.g. a method panicked.
+  Internal,
+  /// A program of pipelined calls went over the callee's limits.
+  LimitExceeded,
+  /// A blocking call was made on the runtime that would have to run it.
+  Deadlock
 }
 
 #[derive(Message)]
@@ -47,8 +59,11 @@ pub struct Return {
   pub result: Option<LocalValue>,
 }
 
+/// Where a proxy forwards its calls: the object `object_id` owned by the
+/// remote end of `connection`.
 pub struct ProxyInfo {
-
+  pub connection: Addr<Connection>,
+  pub object_id: u64,
 }
 
 #[async_trait::async_trait]
@@ -57,6 +72,12 @@ pub trait Object: std::fmt::Debug + Send {
   async fn call_mut(&mut self, call_mut: CallMut) -> Result<Return, CallError>;
 
   fn proxy_info(&self) -> Option<ProxyInfo>;
+
+  /// Whether the method `method_id` needs `&mut self`. Callers can't tell, so
+  /// immutable calls of such methods are run through `call_mut`.
+  fn is_mutable(&self, _method_id: u64) -> bool {
+    false
+  }
 }
 
 #[derive(Message)]
@@ -65,6 +86,16 @@ pub struct GetProxyInfo {
 
 }
 
+/// Hands out the future running a call instead of running it in the actor,
+/// so the call stops when its caller drops it.
+#[derive(Message)]
+#[rtype(result = "BoxFuture<'static, Result<Return, CallError>>")]
+struct Prepare(Call);
+
+#[derive(Message)]
+#[rtype(result = "BoxFuture<'static, Result<Return, CallError>>")]
+struct PrepareMut(CallMut);
+
 pub struct ObjectActor {
   backing: Arc<RwLock<dyn Object + Send + Sync + 'static>>,
 }
@@ -81,15 +112,33 @@ impl ObjectActor {
   }
 }
 
+impl ObjectActor {
+  fn run_call(&self, msg: Call) -> BoxFuture<'static, Result<Return, CallError>> {
+    let backing = self.backing.clone();
+    // A panicking method fails its call rather than the actor.
+    AssertUnwindSafe(async move {
+      let mutable = backing.read().await.is_mutable(msg.method_id);
+      if mutable {
+        backing.write().await.call_mut(CallMut { method_id: msg.method_id, argument: msg.argument }).await
+      } else {
+        backing.read().await.call(msg).await
+      }
+    }).catch_unwind().map(|result| result.unwrap_or(Err(CallError::Internal))).boxed()
+  }
+
+  fn run_call_mut(&self, msg: CallMut) -> BoxFuture<'static, Result<Return, CallError>> {
+    let backing = self.backing.clone();
+    AssertUnwindSafe(async move {
+      backing.write().await.call_mut(msg).await
+    }).catch_unwind().map(|result| result.unwrap_or(Err(CallError::Internal))).boxed()
+  }
+}
+
 impl Handler<Call> for ObjectActor {
   type Result = ResponseFuture<Result<Return, CallError>>;
 
   fn handle(&mut self, msg: Call, _: &mut Context<Self>) -> Self::Result {
-    let backing = self.backing.clone();
-    
-    Box::pin(async move {
-      backing.read().await.call(msg).await
-    })
+    self.run_call(msg)
   }
 }
 
@@ -97,10 +146,23 @@ impl Handler<CallMut> for ObjectActor {
   type Result = ResponseFuture<Result<Return, CallError>>;
 
   fn handle(&mut self, msg: CallMut, _: &mut Context<Self>) -> Self::Result {
-    let backing = self.backing.clone();
-    Box::pin(async move {
-      backing.write().await.call_mut(msg).await
-    })
+    self.run_call_mut(msg)
+  }
+}
+
+impl Handler<Prepare> for ObjectActor {
+  type Result = MessageResult<Prepare>;
+
+  fn handle(&mut self, Prepare(msg): Prepare, _: &mut Context<Self>) -> Self::Result {
+    MessageResult(self.run_call(msg))
+  }
+}
+
+impl Handler<PrepareMut> for ObjectActor {
+  type Result = MessageResult<PrepareMut>;
+
+  fn handle(&mut self, PrepareMut(msg): PrepareMut, _: &mut Context<Self>) -> Self::Result {
+    MessageResult(self.run_call_mut(msg))
   }
 }
 
@@ -117,6 +179,8 @@ impl Handler<GetProxyInfo> for ObjectActor {
 
 #[async_trait::async_trait]
 pub trait ObjectActorHelpers {
+  /// Calls a method. The call runs in the caller's task, so dropping the
+  /// returned future stops it.
   async fn call(&self, call: Call) -> Result<Return, CallError>;
   async fn call_mut(&self, call: CallMut) -> Result<Return, CallError>;
   async fn proxy_info(&self) -> Option<ProxyInfo>;
@@ -125,11 +189,17 @@ pub trait ObjectActorHelpers {
 #[async_trait::async_trait]
 impl ObjectActorHelpers for Addr<ObjectActor> {
   async fn call(&self, call: Call) -> Result<Return, CallError> {
-    self.send(call).await.unwrap()
+    match self.send(Prepare(call)).await {
+      Ok(call) => call.await,
+      Err(_) => Err(CallError::Internal)
+    }
   }
 
   async fn call_mut(&self, call: CallMut) -> Result<Return, CallError> {
-    self.send(call).await.unwrap()
+    match self.send(PrepareMut(call)).await {
+      Ok(call) => call.await,
+      Err(_) => Err(CallError::Internal)
+    }
   }
 
   async fn proxy_info(&self) -> Option<ProxyInfo> {
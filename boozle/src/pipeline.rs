@@ -0,0 +1,361 @@
+//! Promise pipelining. A `Pipeline` sends a whole expression such as
+//! `a.foo().bar(b.baz())` to the nodes owning its objects, one message per
+//! segment (see `boozle_ir::segment`), instead of waiting for each call to
+//! return before making the next. Each segment is compiled to a unit, which
+//! the receiver runs with `interpret`.
+//!
+//! ```ignore
+//! let mut pipeline = Pipeline::new();
+//! let a = pipeline.value(a).await;
+//! let b = pipeline.value(b).await;
+//! let result = pipeline.run(a.call(FOO, None).call(BAR, Some(b.call(BAZ, None)))).await?;
+//! ```
+
+use actix::Addr;
+use boozle_ir::{
+  compile, optimize, segment, size, Expr, Instruction, Limits, MethodId, Object, PromiseId, RemoteId, Segment, Unit,
+  Value, VerifyError,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::connection::{req, res, Connection, ConnectionHelpers};
+use super::context;
+use super::object::{CallError, CallMut, ObjectActorHelpers};
+use super::pool::{Pool, PoolHelpers};
+use super::protocol::hello::FEATURE_EVAL;
+use super::value::LocalValue;
+
+/// The results of the calls made so far, by promise. Calls without a result
+/// are bound to `None`.
+type Promises = HashMap<PromiseId, Option<LocalValue>>;
+
+/// Finds the objects an expression refers to.
+#[async_trait::async_trait]
+pub trait Objects: Sync {
+  async fn object(&self, object: Object) -> Result<LocalValue, CallError>;
+
+  /// Releases an object, for the `Free` instruction.
+  async fn free(&self, object: Object) -> Result<(), CallError>;
+}
+
+/// The objects of a connection's pool, which the remote end refers to as
+/// `RemoteId::LOCAL`.
+#[async_trait::async_trait]
+impl Objects for Addr<Pool<u64, LocalValue>> {
+  async fn object(&self, object: Object) -> Result<LocalValue, CallError> {
+    if object.owner != RemoteId::LOCAL {
+      return Err(CallError::InvalidArgument);
+    }
+    self.get(object.addr).await.map_err(|_| CallError::InvalidArgument)
+  }
+
+  async fn free(&self, object: Object) -> Result<(), CallError> {
+    if object.owner != RemoteId::LOCAL {
+      return Err(CallError::InvalidArgument);
+    }
+    self
+      .remove(object.addr)
+      .await
+      .map(|_| ())
+      .map_err(|_| CallError::InvalidArgument)
+  }
+}
+
+#[async_trait::async_trait]
+impl Objects for HashMap<Object, LocalValue> {
+  async fn object(&self, object: Object) -> Result<LocalValue, CallError> {
+    self.get(&object).cloned().ok_or(CallError::InvalidArgument)
+  }
+
+  /// The objects of a pipeline live as long as the pipeline.
+  async fn free(&self, object: Object) -> Result<(), CallError> {
+    self.get(&object).map(|_| ()).ok_or(CallError::InvalidArgument)
+  }
+}
+
+/// Converts a constant of a unit.
+async fn constant(value: &Value, objects: &dyn Objects) -> Result<LocalValue, CallError> {
+  match value {
+    Value::Object(object) => objects.object(*object).await,
+    value => LocalValue::from_value(value).ok_or(CallError::InvalidArgument),
+  }
+}
+
+/// Estimates the memory a value on the stack takes.
+fn footprint(value: &Option<LocalValue>) -> usize {
+  std::mem::size_of::<Option<LocalValue>>()
+    + match value {
+      Some(LocalValue::Lit(lit)) => lit.len(),
+      _ => 0,
+    }
+}
+
+/// The error a unit that fails verification is rejected with.
+fn rejected(err: VerifyError) -> CallError {
+  match err {
+    VerifyError::TooManyInstructions | VerifyError::TooMuchMemory | VerifyError::TooDeep(_) => CallError::LimitExceeded,
+    _ => CallError::InvalidArgument,
+  }
+}
+
+/// Runs `unit` with `inputs` on its stack and returns its outputs. The unit is
+/// verified against `limits` first. Memory is also checked while it runs,
+/// since calls may return values of any size. Going over `limits` fails with
+/// `CallError::LimitExceeded`.
+///
+/// Calls are made with `call_mut`, which also dispatches immutable methods.
+pub async fn interpret(
+  unit: &Unit,
+  inputs: Vec<Option<LocalValue>>,
+  objects: &dyn Objects,
+  limits: &Limits,
+) -> Result<Vec<Option<LocalValue>>, CallError> {
+  unit.verify(limits).map_err(rejected)?;
+  if inputs.len() != unit.inputs as usize {
+    return Err(CallError::InvalidArgument);
+  }
+
+  let mut memory = unit.consts.iter().map(size).sum::<usize>() + inputs.iter().map(footprint).sum::<usize>();
+  let mut stack = inputs;
+  for instruction in unit.instructions.iter() {
+    // Verification guarantees every index is in range and the stack never
+    // underflows.
+    let value = match instruction {
+      Instruction::Push(index) => Some(constant(&unit.consts[*index as usize], objects).await?),
+      Instruction::Pop => {
+        memory -= footprint(&stack.pop().unwrap());
+        continue;
+      }
+      Instruction::Load(slot) => stack[*slot as usize].clone(),
+      Instruction::Free(index) => {
+        // Every object is released, even if some of them are gone already.
+        let mut freed = Ok(());
+        for object in boozle_ir::objects(&unit.consts[*index as usize]).unwrap() {
+          freed = freed.and(objects.free(object).await);
+        }
+        freed?;
+        continue;
+      }
+      Instruction::Invoke { method_id, argument } => {
+        let argument = match argument {
+          true => {
+            let argument = stack.pop().unwrap();
+            memory -= footprint(&argument);
+            Some(argument.ok_or(CallError::InvalidArgument)?)
+          }
+          false => None,
+        };
+        let target = stack.pop().unwrap();
+        memory -= footprint(&target);
+        let target = target
+          .and_then(LocalValue::into_actor)
+          .ok_or(CallError::InvalidArgument)?;
+        let ret = target
+          .call_mut(CallMut {
+            method_id: method_id.0,
+            argument,
+          })
+          .await?;
+        ret.result
+      }
+    };
+    memory += footprint(&value);
+    if memory > limits.max_memory {
+      return Err(CallError::LimitExceeded);
+    }
+    stack.push(value);
+  }
+  Ok(stack.split_off(stack.len() - unit.outputs as usize))
+}
+
+/// Builds and runs pipelined expressions over any number of connections.
+pub struct Pipeline {
+  connections: Vec<Addr<Connection>>,
+  objects: HashMap<Object, LocalValue>,
+  pure: HashSet<MethodId>,
+}
+
+impl Default for Pipeline {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Pipeline {
+  pub fn new() -> Self {
+    Self {
+      connections: Vec::new(),
+      objects: HashMap::new(),
+      pure: HashSet::new(),
+    }
+  }
+
+  /// Declares methods pure: calling them has no side effects and returns the
+  /// same result for the same target and argument. Repeated calls of pure
+  /// methods are made once, and those whose results are unused not at all.
+  pub fn with_pure_methods<M: Into<MethodId>, I: IntoIterator<Item = M>>(mut self, methods: I) -> Self {
+    self.pure.extend(methods.into_iter().map(Into::into));
+    self
+  }
+
+  fn owner(&mut self, connection: Addr<Connection>) -> RemoteId {
+    let index = match self.connections.iter().position(|known| *known == connection) {
+      Some(index) => index,
+      None => {
+        self.connections.push(connection);
+        self.connections.len() - 1
+      }
+    };
+    RemoteId(index as u32 + 1)
+  }
+
+  /// Returns an expression standing for `value`. Objects owned by the remote
+  /// end of a connection are called by that end.
+  pub async fn value(&mut self, value: LocalValue) -> Expr {
+    let actor = match value {
+      LocalValue::Lit(lit) => return Value::Lit(lit.to_vec().into_boxed_slice()).into(),
+      LocalValue::Actor(actor) => actor,
+    };
+    let object = match actor.proxy_info().await {
+      Some(info) => Object::new(self.owner(info.connection), info.object_id),
+      None => Object::new(RemoteId::LOCAL, self.objects.len() as u64),
+    };
+    self.objects.insert(object, LocalValue::Actor(actor));
+    object.into()
+  }
+
+  /// Runs `expr` and returns its result. Segments of the same round are sent
+  /// at the same time, and each round waits for the previous one.
+  pub async fn run(&self, expr: Expr) -> Result<Option<LocalValue>, CallError> {
+    let mut plan = segment(expr).map_err(|_| CallError::InvalidArgument)?;
+    optimize(&mut plan, |method| self.pure.contains(&method));
+    // Promises above those of the plan are free for `run_segment` to use.
+    let next_promise = plan
+      .segments
+      .iter()
+      .flat_map(|segment| segment.steps.iter())
+      .map(|step| step.promise.0 + 1)
+      .max()
+      .unwrap_or(0);
+    let mut promises = Promises::new();
+    let mut segments = plan.segments.into_iter().peekable();
+    while let Some(first) = segments.next() {
+      let mut round = vec![first];
+      while segments.peek().map(|segment| segment.round) == Some(round[0].round) {
+        round.push(segments.next().unwrap());
+      }
+      let results = {
+        let promises = &promises;
+        futures::future::join_all(
+          round
+            .into_iter()
+            .map(|segment| self.run_segment(segment, promises, next_promise)),
+        )
+        .await
+      };
+      for result in results {
+        promises.extend(result?);
+      }
+    }
+    promises.remove(&plan.result).ok_or(CallError::InvalidResponse)
+  }
+
+  /// Runs a single segment and returns its exports. Promises from
+  /// `next_promise` on aren't used by the plan.
+  async fn run_segment(
+    &self,
+    mut segment: Segment,
+    promises: &Promises,
+    next_promise: u32,
+  ) -> Result<Vec<(PromiseId, Option<LocalValue>)>, CallError> {
+    let mut imports = Vec::new();
+    for promise in segment.imports.iter() {
+      let value = promises.get(promise).cloned().ok_or(CallError::InvalidArgument)?;
+      imports.push((*promise, value));
+    }
+
+    if segment.owner == RemoteId::LOCAL {
+      let inputs = imports.into_iter().map(|(_, value)| value).collect();
+      let outputs = interpret(&compile(&segment), inputs, &self.objects, &Limits::unlimited()).await?;
+      return Ok(segment.exports.into_iter().zip(outputs).collect());
+    }
+
+    let connection = &self.connections[segment.owner.0 as usize - 1];
+    // Only ends that agreed to run programs are sent them.
+    let welcome = connection.handshake().await.map_err(|_| CallError::Comm)?;
+    if !welcome.supports(FEATURE_EVAL) {
+      return Err(CallError::UnknownMethod);
+    }
+    // The receiver knows its own objects as `RemoteId::LOCAL`. Anything else it
+    // gets as an import: objects of other nodes, and the results of earlier
+    // segments unless they are objects of the receiver.
+    let mut results: HashMap<PromiseId, Expr> = HashMap::new();
+    let mut objects: HashMap<Object, Expr> = HashMap::new();
+    let mut values = Vec::new();
+    let mut next_promise = next_promise;
+    for (promise, value) in imports {
+      let value = value.ok_or(CallError::InvalidArgument)?;
+      let replacement = match &value {
+        LocalValue::Lit(lit) => Some(Value::Lit(lit.to_vec().into_boxed_slice()).into()),
+        LocalValue::Actor(actor) => match actor.proxy_info().await {
+          Some(info) if info.connection == *connection => Some(Object::new(RemoteId::LOCAL, info.object_id).into()),
+          _ => None,
+        },
+      };
+      match replacement {
+        Some(replacement) => {
+          results.insert(promise, replacement);
+        }
+        None => values.push((promise, value)),
+      }
+    }
+    for step in segment.steps.iter() {
+      for operand in std::iter::once(&step.target).chain(step.argument.iter()) {
+        if let Expr::Value(Value::Object(object)) = operand {
+          if object.owner == segment.owner {
+            objects.insert(*object, Object::new(RemoteId::LOCAL, object.addr).into());
+          } else if !objects.contains_key(object) {
+            let promise = PromiseId(next_promise);
+            next_promise += 1;
+            values.push((promise, self.objects.object(*object).await?));
+            objects.insert(*object, Expr::Promise(promise));
+          }
+        }
+      }
+    }
+    for step in segment.steps.iter_mut() {
+      for operand in std::iter::once(&mut step.target).chain(step.argument.iter_mut()) {
+        let replacement = match operand {
+          Expr::Promise(promise) => results.get(promise),
+          Expr::Value(Value::Object(object)) => objects.get(object),
+          _ => None,
+        };
+        if let Some(replacement) = replacement {
+          *operand = replacement.clone();
+        }
+      }
+    }
+    segment.imports = values.iter().map(|(promise, _)| *promise).collect();
+
+    let exports = segment.exports.clone();
+    let response = connection
+      .req(req::Req::Eval(req::Eval {
+        unit: compile(&segment),
+        imports: values.into_iter().map(|(_, value)| value).collect(),
+        timeout: None,
+        metadata: context::outgoing_metadata(),
+      }))
+      .await
+      .map_err(CallError::from)?;
+    match response {
+      res::Res::Eval(res::Eval { results }) if results.len() == exports.len() => Ok(
+        exports
+          .into_iter()
+          .zip(results)
+          .map(|(promise, result)| (promise, result.value))
+          .collect(),
+      ),
+      _ => Err(CallError::InvalidResponse),
+    }
+  }
+}
@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
 
+pub mod hello;
 pub mod req;
 pub mod res;
 
@@ -9,7 +10,11 @@ pub use res::Res;
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Dir {
   Req,
-  Res
+  Res,
+  Hello,
+  Welcome,
+  /// Answers a `Hello` with why it was rejected: an `hello::Incompatible`.
+  Reject
 }
 
 #[derive(Debug, Serialize, Deserialize)]
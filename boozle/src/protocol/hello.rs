@@ -0,0 +1,110 @@
+//! The handshake that starts every connection. Each end sends a `Hello` and
+//! answers the other's with a `Welcome`, or with an `Incompatible` before
+//! closing the connection. Requests are only sent once a `Welcome` arrived.
+
+use serde::{Serialize, Deserialize};
+
+/// The newest protocol version this implementation speaks.
+pub const VERSION: u16 = 1;
+/// The oldest protocol version this implementation speaks.
+pub const MIN_VERSION: u16 = 1;
+
+pub const CODEC_BINCODE: &str = "bincode";
+pub const COMPRESSOR_NONE: &str = "none";
+
+/// The peer runs pipelined programs (`req::Eval`).
+pub const FEATURE_EVAL: &str = "eval";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hello {
+  pub min_version: u16,
+  pub max_version: u16,
+  pub node_id: u64,
+  /// Supported codecs, most preferred first.
+  pub codecs: Vec<String>,
+  /// Supported compressors, most preferred first.
+  pub compressors: Vec<String>,
+  pub features: Vec<String>
+}
+
+impl Hello {
+  /// Offers every version, codec and compressor this implementation supports.
+  pub fn new(node_id: u64, features: Vec<String>) -> Self {
+    Self {
+      min_version: MIN_VERSION,
+      max_version: VERSION,
+      node_id,
+      codecs: vec![CODEC_BINCODE.to_string()],
+      compressors: vec![COMPRESSOR_NONE.to_string()],
+      features
+    }
+  }
+
+  /// Whether `welcome` is a valid answer to this `Hello`.
+  pub fn accepts(&self, welcome: &Welcome) -> bool {
+    (self.min_version..=self.max_version).contains(&welcome.version)
+      && self.codecs.contains(&welcome.codec)
+      && self.compressors.contains(&welcome.compressor)
+      && welcome.features.iter().all(|feature| self.features.contains(feature))
+  }
+}
+
+/// What the sender of a `Hello` may use for the rest of the connection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Welcome {
+  pub version: u16,
+  /// The node answering the `Hello`.
+  pub node_id: u64,
+  pub codec: String,
+  pub compressor: String,
+  /// The features both ends support.
+  pub features: Vec<String>
+}
+
+impl Welcome {
+  /// Whether both ends agreed to use `feature`.
+  pub fn supports(&self, feature: &str) -> bool {
+    self.features.iter().any(|supported| supported == feature)
+  }
+}
+
+/// Why a `Hello` was rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Incompatible {
+  /// The ends have no version in common. Carries the versions the rejecting
+  /// end supports.
+  Version { min: u16, max: u16 },
+  Codec,
+  Compressor
+}
+
+impl std::fmt::Display for Incompatible {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Incompatible::Version { min, max } => write!(f, "no common protocol version, expected {} to {}", min, max),
+      Incompatible::Codec => write!(f, "no common codec"),
+      Incompatible::Compressor => write!(f, "no common compressor")
+    }
+  }
+}
+
+/// Answers `theirs` given what `ours` supports: the highest common version,
+/// and the codec and compressor the sender of `theirs` prefers most.
+pub fn negotiate(ours: &Hello, theirs: &Hello) -> Result<Welcome, Incompatible> {
+  let version = ours.max_version.min(theirs.max_version);
+  if version < ours.min_version.max(theirs.min_version) {
+    return Err(Incompatible::Version {
+      min: ours.min_version,
+      max: ours.max_version
+    });
+  }
+  let codec = theirs.codecs.iter().find(|codec| ours.codecs.contains(codec)).ok_or(Incompatible::Codec)?;
+  let compressor = theirs.compressors.iter().find(|compressor| ours.compressors.contains(compressor)).ok_or(Incompatible::Compressor)?;
+  Ok(Welcome {
+    version,
+    node_id: ours.node_id,
+    codec: codec.clone(),
+    compressor: compressor.clone(),
+    features: theirs.features.iter().filter(|feature| ours.features.contains(feature)).cloned().collect()
+  })
+}
@@ -1,5 +1,6 @@
 use crate::value::Value;
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Call {
@@ -7,7 +8,13 @@ pub struct Call {
   pub object_id: u64,
   pub method_id: u64,
   pub argument: Option<Value>,
-  pub to_object_id: Option<u64>
+  pub to_object_id: Option<u64>,
+  /// How many milliseconds the caller waits for the response, counted from
+  /// when the call arrives. The callee gives up on the call after that.
+  pub deadline: Option<u64>,
+  /// Key/value pairs the callee may look at, e.g. to trace or authenticate the
+  /// call.
+  pub metadata: HashMap<String, String>
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,10 +22,34 @@ pub struct Free {
   pub object_id: u64
 }
 
+/// Runs a program of pipelined calls: an encoded `boozle_ir::Unit`. Objects
+/// of the program owned by `RemoteId::LOCAL` are objects of the receiver.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Eval {
+  pub program: Box<[u8]>,
+  /// The inputs of the program.
+  pub imports: Vec<Value>,
+  /// Where to store each output of the program if it's an object.
+  pub to_object_ids: Vec<u64>,
+  /// Like the deadline of a `Call`, for the whole program.
+  pub deadline: Option<u64>,
+  /// Like the metadata of a `Call`, for every call of the program.
+  pub metadata: HashMap<String, String>
+}
+
+/// Tells the receiver to stop handling request `req_id`, which won't get a
+/// response. Has no response itself.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cancel {
+  pub req_id: u64
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Ty {
   Call(Call),
-  Free(Free)
+  Free(Free),
+  Eval(Eval),
+  Cancel(Cancel)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
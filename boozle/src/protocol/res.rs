@@ -1,3 +1,4 @@
+use crate::object::CallError;
 use crate::value::Value;
 use serde::{Serialize, Deserialize};
 
@@ -6,10 +7,64 @@ pub struct Return {
   pub value: Option<Value>
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Eval {
+  /// The outputs of the program.
+  pub results: Vec<Return>
+}
+
+/// Why a request failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+  /// The request named an object that isn't in the pool.
+  UnknownObject,
+  UnknownMethod,
+  InvalidArgument,
+  /// The method ran and returned an error.
+  Application,
+  /// The callee failed unexpectedly, e.g. a method panicked.
+  Internal,
+  /// The call's deadline passed before it finished.
+  DeadlineExceeded,
+  /// A program of pipelined calls went over the callee's limits.
+  LimitExceeded
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Error {
+  pub code: ErrorCode,
+  pub message: String
+}
+
+impl Error {
+  pub fn new<M: Into<String>>(code: ErrorCode, message: M) -> Self {
+    Self {
+      code,
+      message: message.into()
+    }
+  }
+}
+
+impl From<CallError> for Error {
+  fn from(err: CallError) -> Self {
+    let code = match err {
+      CallError::UnknownMethod => ErrorCode::UnknownMethod,
+      CallError::InvalidArgument => ErrorCode::InvalidArgument,
+      CallError::Internal => ErrorCode::Internal,
+      CallError::LimitExceeded => ErrorCode::LimitExceeded,
+      // Failures of calls the method made itself are its own.
+      CallError::Failed | CallError::InvalidResponse | CallError::Comm | CallError::Timeout | CallError::Deadlock => ErrorCode::Application
+    };
+    Self::new(code, format!("{:?}", err))
+  }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Ty {
   Call(Return),
-  Free
+  Free,
+  Eval(Eval),
+  Error(Error)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
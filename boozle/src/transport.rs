@@ -1 +1,4 @@
-pub mod tcp;
\ No newline at end of file
+pub mod stream;
+pub mod tcp;
+#[cfg(feature = "websocket")]
+pub mod websocket;
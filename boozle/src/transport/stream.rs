@@ -0,0 +1,76 @@
+//! Runs a `Connection` over a byte stream such as a pipe or a socket. Each
+//! frame is a `u32` little-endian length followed by one protocol `Msg`.
+
+use actix::{Actor, Addr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::connection::Connection;
+
+/// Frames longer than this are treated as a broken stream.
+pub const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+/// Capacity of the channels between the stream and the connection.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Starts a connection over `reader` and `writer`. Must be called from within
+/// an actix system.
+pub fn start<R, W>(reader: R, writer: W) -> Addr<Connection>
+where
+  R: AsyncRead + Unpin + 'static,
+  W: AsyncWrite + Unpin + 'static,
+{
+  let (tx, outgoing) = mpsc::channel(CHANNEL_CAPACITY);
+  let (incoming, rx) = mpsc::channel(CHANNEL_CAPACITY);
+  bridge(reader, writer, incoming, outgoing);
+  Connection::new(tx, rx).start()
+}
+
+/// Moves frames between a stream and the channels of a `Connection`: frames
+/// read from `reader` are sent on `incoming`, and messages received on
+/// `outgoing` are written to `writer`. Useful when the connection must exist
+/// before the stream does, for example to expose objects to a child process.
+pub fn bridge<R, W>(
+  mut reader: R,
+  mut writer: W,
+  mut incoming: mpsc::Sender<Box<[u8]>>,
+  mut outgoing: mpsc::Receiver<Box<[u8]>>,
+) where
+  R: AsyncRead + Unpin + 'static,
+  W: AsyncWrite + Unpin + 'static,
+{
+  actix::spawn(async move {
+    while let Some(frame) = outgoing.recv().await {
+      if frame.len() > MAX_FRAME {
+        break;
+      }
+      let header = (frame.len() as u32).to_le_bytes();
+      let written = async {
+        writer.write_all(&header).await?;
+        writer.write_all(&frame).await?;
+        writer.flush().await
+      };
+      if written.await.is_err() {
+        break;
+      }
+    }
+    let _ = writer.shutdown().await;
+  });
+
+  actix::spawn(async move {
+    let mut header = [0u8; 4];
+    while reader.read_exact(&mut header).await.is_ok() {
+      let size = u32::from_le_bytes(header) as usize;
+      if size > MAX_FRAME {
+        break;
+      }
+      let mut frame = vec![0u8; size];
+      if reader.read_exact(&mut frame).await.is_err() {
+        break;
+      }
+      if incoming.send(frame.into_boxed_slice()).await.is_err() {
+        break;
+      }
+    }
+  });
+}
@@ -0,0 +1,64 @@
+//! Runs a `Connection` over a WebSocket. Each binary WebSocket message carries
+//! one protocol `Msg`, which is what the TypeScript runtime in `boozle-ts` speaks.
+
+use actix::{Actor, Addr};
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::WebSocketStream;
+
+use crate::connection::Connection;
+
+/// Capacity of the channels between the socket and the connection.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Completes the server side of the WebSocket handshake on `stream` and starts
+/// a connection over it.
+pub async fn accept(stream: TcpStream) -> Result<Addr<Connection>, Error> {
+  let socket = tokio_tungstenite::accept_async(stream).await?;
+  Ok(start(socket))
+}
+
+/// Connects to the WebSocket server at `url` and starts a connection over it.
+pub async fn connect(url: &str) -> Result<Addr<Connection>, Error> {
+  let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+  Ok(start(socket))
+}
+
+/// Starts a connection over an established WebSocket. Must be called from
+/// within an actix system.
+pub fn start<S>(socket: WebSocketStream<S>) -> Addr<Connection>
+where
+  S: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+  let (tx, mut outgoing) = mpsc::channel::<Box<[u8]>>(CHANNEL_CAPACITY);
+  let (mut incoming, rx) = mpsc::channel(CHANNEL_CAPACITY);
+  let (mut sink, mut stream) = socket.split();
+
+  actix::spawn(async move {
+    while let Some(data) = outgoing.recv().await {
+      if sink.send(Message::Binary(data.into_vec())).await.is_err() {
+        break;
+      }
+    }
+    let _ = sink.close().await;
+  });
+
+  actix::spawn(async move {
+    while let Some(Ok(message)) = stream.next().await {
+      let data = match message {
+        Message::Binary(data) => data,
+        Message::Close(_) => break,
+        // Pings are answered by tungstenite, and text isn't part of the protocol.
+        _ => continue,
+      };
+      if incoming.send(data.into_boxed_slice()).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  Connection::new(tx, rx).start()
+}
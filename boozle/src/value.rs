@@ -1,4 +1,7 @@
 use actix::Addr;
+use bincode::Options;
+use boozle_ir::Type;
+use serde::de::DeserializeSeed;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::any::Any;
 use std::sync::Arc;
@@ -41,6 +44,10 @@ impl<'de> Deserialize<'de> for Lit {
   }
 }
 
+/// Does nothing, but only compiles for types that can be sent as literals.
+/// Generated code uses it to check type mappings.
+pub fn assert_lit<T: Serialize + DeserializeOwned>() {}
+
 pub enum LocalValue {
   Lit(Arc<[u8]>),
   Actor(Addr<ObjectActor>)
@@ -58,6 +65,46 @@ impl LocalValue {
   pub fn from_object<O: Object + Send + Sync + 'static>(object: O) -> Self {
     Self::Actor(ObjectActor::new(object).start())
   }
+
+  /// Deserializes a literal value. Returns `None` if this value is an actor or
+  /// the literal isn't a valid `T`.
+  pub fn to_lit<T: DeserializeOwned>(&self) -> Option<T> {
+    match self {
+      Self::Lit(lit) => bincode::deserialize(lit).ok(),
+      Self::Actor(_) => None,
+    }
+  }
+
+  /// Encodes a dynamic value as the literal of the type it stands for.
+  /// Returns `None` for objects and methods, which aren't literals.
+  pub fn from_value(value: &boozle_ir::Value) -> Option<Self> {
+    match value {
+      boozle_ir::Value::Lit(lit) => Some(Self::lit(lit.clone())),
+      boozle_ir::Value::Object(_) | boozle_ir::Value::Method(..) => None,
+      value => bincode::serialize(value).ok().map(|lit| Self::lit(lit.into_boxed_slice())),
+    }
+  }
+
+  /// Decodes a literal of type `ty` into a dynamic value, so it can be
+  /// inspected without the Rust type it was encoded from. Returns `None` if
+  /// this value is an actor or the literal isn't a valid `ty`.
+  pub fn to_value(&self, ty: &Type) -> Option<boozle_ir::Value> {
+    match self {
+      Self::Lit(lit) => {
+        // The options `bincode::serialize` uses.
+        let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+        ty.deserialize(&mut bincode::Deserializer::from_slice(lit, options)).ok()
+      }
+      Self::Actor(_) => None,
+    }
+  }
+
+  pub fn into_actor(self) -> Option<Addr<ObjectActor>> {
+    match self {
+      Self::Lit(_) => None,
+      Self::Actor(actor) => Some(actor),
+    }
+  }
 }
 
 impl Clone for LocalValue {
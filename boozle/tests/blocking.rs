@@ -0,0 +1,104 @@
+use boozle::blocking::{self, Handle};
+use boozle::object::CallError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn add(&self, amount: u32) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(amount, Ordering::SeqCst) + amount)
+  }
+}
+
+/// Connects two blocking connections, each on its own runtime.
+fn pair() -> (blocking::Connection, blocking::Connection) {
+  let (client_tx, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  (
+    blocking::Connection::new(client_tx, client_rx),
+    blocking::Connection::new(server_tx, server_rx),
+  )
+}
+
+fn add(handle: &Handle, counter: &CounterClient, amount: u32) -> Result<u32, CallError> {
+  let counter = counter.clone();
+  handle.call(move || async move { counter.add(amount).await })
+}
+
+#[test]
+fn calls_remote_objects() {
+  let (client, server) = pair();
+  let key = server
+    .handle()
+    .expose(Counter {
+      value: AtomicU32::new(1),
+    })
+    .unwrap();
+
+  let handle = client.handle();
+  // The proxy frees the remote object once dropped, so it's kept for both calls.
+  let counter = CounterClient::from_value(handle.remote(key).unwrap()).unwrap();
+  assert_eq!(add(&handle, &counter, 2).unwrap(), 3);
+  assert_eq!(add(&handle, &counter, 4).unwrap(), 7);
+}
+
+#[test]
+fn times_out_calls() {
+  let (client, _server) = pair();
+  let handle = client.handle().with_timeout(Duration::from_millis(50));
+  assert_eq!(handle.timeout(), Duration::from_millis(50));
+  assert_eq!(client.handle().timeout(), blocking::DEFAULT_TIMEOUT);
+
+  let result = handle.call(|| async {
+    tokio::time::delay_for(Duration::from_secs(5)).await;
+    Ok(())
+  });
+  assert!(matches!(result, Err(CallError::Timeout)));
+}
+
+#[test]
+fn fails_outstanding_calls_on_drop() {
+  let (client, _server) = pair();
+  let handle = client.handle();
+
+  let (tx, rx) = crossbeam::channel::bounded(1);
+  handle.spawn(
+    || futures::future::pending::<Result<(), CallError>>(),
+    move |result| {
+      let _ = tx.send(result);
+    },
+  );
+  drop(client);
+  assert!(matches!(rx.recv().unwrap(), Err(CallError::Comm)));
+
+  // Calls after the runtime shut down fail right away.
+  assert!(matches!(handle.call(|| async { Ok(()) }), Err(CallError::Comm)));
+}
+
+#[test]
+fn refuses_to_block_the_runtime() {
+  let (client, _server) = pair();
+  let handle = client.handle();
+  let inner = handle.clone();
+
+  let result = handle.call(move || async move { Ok(inner.call(|| async { Ok(()) })) });
+  assert!(matches!(result, Ok(Err(CallError::Deadlock))));
+}
+
+#[test]
+fn shares_timeouts_between_clones() {
+  let (mut client, _server) = pair();
+  let handle = client.handle();
+  let custom = handle.with_timeout(Duration::from_millis(50));
+
+  client.set_timeout(Duration::from_secs(1));
+  assert_eq!(handle.timeout(), Duration::from_secs(1));
+  assert_eq!(client.handle().timeout(), Duration::from_secs(1));
+  assert_eq!(custom.timeout(), Duration::from_millis(50));
+}
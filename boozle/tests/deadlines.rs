@@ -0,0 +1,288 @@
+use actix::{Actor, Addr};
+use boozle::connection::res::ErrorCode;
+use boozle::connection::{req, res};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::context::CallContext;
+use boozle::object::CallError;
+use boozle::pool::PoolHelpers;
+use boozle::protocol::hello::{Hello, Welcome, FEATURE_EVAL};
+use boozle::protocol::{self as proto, Dir, Msg};
+use boozle::value::{Lit, LocalValue, Value};
+use boozle_gen::method_id;
+use boozle_ir::{compile, segment, Expr, Object, RemoteId};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Sleeps before answering, counting the calls that got to answer.
+#[derive(Debug)]
+pub struct Slow {
+  finished: Arc<AtomicU32>,
+}
+
+#[boozle::object]
+impl Slow {
+  async fn sleep(&self, millis: u64) -> Result<u64, CallError> {
+    tokio::time::delay_for(Duration::from_millis(millis)).await;
+    self.finished.fetch_add(1, Ordering::SeqCst);
+    Ok(millis)
+  }
+
+  /// How many milliseconds the caller still waits.
+  async fn remaining(&self) -> Result<u64, CallError> {
+    let remaining = CallContext::current().and_then(|context| context.remaining());
+    remaining
+      .map(|remaining| remaining.as_millis() as u64)
+      .ok_or(CallError::Failed)
+  }
+}
+
+/// Connects two new connections and returns the client end and the server end.
+fn connect(
+  client: impl FnOnce(mpsc::Sender<Box<[u8]>>, mpsc::Receiver<Box<[u8]>>) -> Connection,
+) -> (Addr<Connection>, Addr<Connection>) {
+  let (client_tx, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  let client = client(client_tx, client_rx).start();
+  (client, Connection::new(server_tx, server_rx).start())
+}
+
+/// Connects to a new node exposing a `Slow` and returns the client end of the
+/// connection, the object's key and its count of finished calls.
+async fn node(
+  client: impl FnOnce(mpsc::Sender<Box<[u8]>>, mpsc::Receiver<Box<[u8]>>) -> Connection,
+) -> (Addr<Connection>, u64, Arc<AtomicU32>) {
+  let (client, server) = connect(client);
+  let (key, finished) = serve(server).await;
+  (client, key, finished)
+}
+
+/// Like `node`, but the `Slow` is on a third node and called through a proxy.
+async fn mesh() -> (Addr<Connection>, u64, Arc<AtomicU32>) {
+  // client -> relay's node -> slow's node
+  let (relay, key, finished) = node(Connection::new).await;
+  let (client, server) = connect(Connection::new);
+  let key = server.pool().await.expose(relay.remote(key)).await.unwrap().key;
+  (client, key, finished)
+}
+
+/// Exposes a new `Slow` on `server`.
+async fn serve(server: Addr<Connection>) -> (u64, Arc<AtomicU32>) {
+  let finished = Arc::new(AtomicU32::new(0));
+  let slow = LocalValue::from_object(Slow {
+    finished: finished.clone(),
+  });
+  let key = server.pool().await.expose(slow).await.unwrap().key;
+  (key, finished)
+}
+
+fn encode<T: Serialize>(dir: Dir, payload: &T) -> Box<[u8]> {
+  let msg = Msg {
+    dir,
+    data: bincode::serialize(payload).unwrap().into_boxed_slice(),
+  };
+  bincode::serialize(&msg).unwrap().into_boxed_slice()
+}
+
+async fn receive<T: DeserializeOwned>(rx: &mut mpsc::Receiver<Box<[u8]>>) -> (Dir, T) {
+  let msg: Msg = bincode::deserialize(&rx.recv().await.unwrap()).unwrap();
+  (msg.dir, bincode::deserialize(&msg.data).unwrap())
+}
+
+/// Encodes a literal as it is sent, the way the remote end would decode it.
+fn lit<T: Serialize>(value: &T) -> Lit {
+  let bytes = bincode::serialize(value).unwrap();
+  bincode::deserialize(&bincode::serialize(&bytes).unwrap()).unwrap()
+}
+
+fn call(id: u64, object_id: u64, millis: u64, deadline: Option<u64>) -> proto::Req {
+  proto::Req {
+    id,
+    ty: proto::req::Ty::Call(proto::req::Call {
+      mutable: false,
+      object_id,
+      method_id: method_id("Slow", "sleep"),
+      argument: Some(Value::Lit(lit(&millis))),
+      to_object_id: None,
+      deadline,
+      metadata: Default::default(),
+    }),
+  }
+}
+
+fn sleep(object_id: u64, millis: u64, timeout: Option<Duration>) -> req::Req {
+  req::Req::Call(req::Call {
+    mutable: false,
+    object_id,
+    method_id: method_id("Slow", "sleep"),
+    argument: Some(LocalValue::from_lit(&millis)),
+    store_result: false,
+    timeout,
+    metadata: Default::default(),
+  })
+}
+
+async fn delay(millis: u64) {
+  tokio::time::delay_for(Duration::from_millis(millis)).await
+}
+
+#[test]
+fn times_out_slow_calls() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (connection, key, finished) =
+      node(|tx, rx| Connection::new(tx, rx).with_timeout(Duration::from_millis(50))).await;
+
+    match connection.req(sleep(key, 500, None)).await {
+      Err(err) => assert!(matches!(CallError::from(err), CallError::Timeout)),
+      Ok(_) => panic!("the call didn't time out"),
+    }
+
+    // The call's own timeout wins over the connection's.
+    let result = connection.req(sleep(key, 100, Some(Duration::from_secs(5)))).await;
+    assert!(matches!(result, Ok(res::Res::Call(_))));
+
+    // The remote end dropped the call that timed out.
+    delay(600).await;
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+  });
+}
+
+#[test]
+fn enforces_deadlines_and_cancellations() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, rx) = mpsc::channel(16);
+    let (key, finished) = serve(Connection::new(tx, rx).start()).await;
+
+    let _: (Dir, Hello) = receive(&mut outgoing).await;
+    incoming
+      .send(encode(Dir::Hello, &Hello::new(1, Vec::new())))
+      .await
+      .unwrap();
+    let _: (Dir, Welcome) = receive(&mut outgoing).await;
+
+    incoming
+      .send(encode(Dir::Req, &call(1, key, 500, Some(50))))
+      .await
+      .unwrap();
+    let (dir, res): (Dir, proto::Res) = receive(&mut outgoing).await;
+    assert!(matches!(dir, Dir::Res));
+    assert_eq!(res.id, 1);
+    assert!(matches!(res.ty, proto::res::Ty::Error(err) if err.code == ErrorCode::DeadlineExceeded));
+
+    // A cancelled call is never answered.
+    incoming.send(encode(Dir::Req, &call(2, key, 200, None))).await.unwrap();
+    let cancel = proto::Req {
+      id: 3,
+      ty: proto::req::Ty::Cancel(proto::req::Cancel { req_id: 2 }),
+    };
+    incoming.send(encode(Dir::Req, &cancel)).await.unwrap();
+    incoming.send(encode(Dir::Req, &call(4, key, 300, None))).await.unwrap();
+    let (_, res): (Dir, proto::Res) = receive(&mut outgoing).await;
+    assert_eq!(res.id, 4);
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+  });
+}
+
+#[test]
+fn enforces_deadlines_of_programs() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, rx) = mpsc::channel(16);
+    let (key, finished) = serve(Connection::new(tx, rx).start()).await;
+
+    let _: (Dir, Hello) = receive(&mut outgoing).await;
+    let hello = Hello::new(1, vec![FEATURE_EVAL.to_string()]);
+    incoming.send(encode(Dir::Hello, &hello)).await.unwrap();
+    let _: (Dir, Welcome) = receive(&mut outgoing).await;
+
+    let expr = Expr::from(Object::new(RemoteId::LOCAL, key))
+      .call(method_id("Slow", "sleep"), Some(boozle_ir::Value::U64(500).into()));
+    let plan = segment(expr).unwrap();
+    let eval = proto::Req {
+      id: 1,
+      ty: proto::req::Ty::Eval(proto::req::Eval {
+        program: compile(&plan.segments[0]).encode().into_boxed_slice(),
+        imports: Vec::new(),
+        to_object_ids: vec![100],
+        deadline: Some(50),
+        metadata: Default::default(),
+      }),
+    };
+    incoming.send(encode(Dir::Req, &eval)).await.unwrap();
+    let (_, res): (Dir, proto::Res) = receive(&mut outgoing).await;
+    assert_eq!(res.id, 1);
+    assert!(matches!(res.ty, proto::res::Ty::Error(err) if err.code == ErrorCode::DeadlineExceeded));
+
+    // The program's call was dropped along with it.
+    delay(600).await;
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+  });
+}
+
+#[test]
+fn cancels_dropped_calls() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (connection, key, finished) = node(Connection::new).await;
+
+    let call = connection.req(sleep(key, 200, None));
+    assert!(tokio::time::timeout(Duration::from_millis(50), call).await.is_err());
+
+    delay(300).await;
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+    let result = connection.req(sleep(key, 0, None)).await;
+    assert!(matches!(result, Ok(res::Res::Call(_))));
+  });
+}
+
+#[test]
+fn passes_deadlines_on_through_proxies() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (connection, key, _) = mesh().await;
+    let remaining = req::Req::Call(req::Call {
+      mutable: false,
+      object_id: key,
+      method_id: method_id("Slow", "remaining"),
+      argument: None,
+      store_result: false,
+      timeout: Some(Duration::from_secs(1)),
+      metadata: Default::default(),
+    });
+    match connection.req(remaining).await {
+      Ok(res::Res::Call(ret)) => {
+        let remaining: u64 = ret.value.unwrap().to_lit().unwrap();
+        assert!(remaining > 0 && remaining <= 1000);
+      }
+      _ => panic!("the deadline didn't reach the object"),
+    }
+
+    // Without a deadline, there's nothing to pass on.
+    let result = connection.req(sleep(key, 0, None)).await;
+    assert!(matches!(result, Ok(res::Res::Call(_))));
+  });
+}
+
+#[test]
+fn cascades_cancellations_through_proxies() {
+  let mut system = actix::System::new("deadlines");
+  system.block_on(async {
+    let (connection, key, finished) = mesh().await;
+
+    let call = connection.req(sleep(key, 200, None));
+    assert!(tokio::time::timeout(Duration::from_millis(50), call).await.is_err());
+    delay(300).await;
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+
+    let result = connection.req(sleep(key, 100, Some(Duration::from_millis(50)))).await;
+    assert!(result.is_err());
+    delay(200).await;
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+  });
+}
@@ -0,0 +1,289 @@
+use actix::{Actor, Addr};
+use boozle::connection::res::{Error, ErrorCode};
+use boozle::connection::{req, res};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::{CallError, ObjectActorHelpers};
+use boozle::pool::PoolHelpers;
+use boozle::protocol::hello::{Hello, Welcome};
+use boozle::protocol::{self as proto, Dir, Msg};
+use boozle::value::LocalValue;
+use boozle_gen::method_id;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct Faulty;
+
+#[boozle::object]
+impl Faulty {
+  async fn echo(&self, value: u32) -> Result<u32, CallError> {
+    Ok(value)
+  }
+
+  async fn fail(&self) -> Result<u32, CallError> {
+    Err(CallError::Failed)
+  }
+
+  async fn panic(&self) -> Result<u32, CallError> {
+    panic!("faulty")
+  }
+}
+
+fn encode<T: Serialize>(dir: Dir, payload: &T) -> Box<[u8]> {
+  let msg = Msg {
+    dir,
+    data: bincode::serialize(payload).unwrap().into_boxed_slice(),
+  };
+  bincode::serialize(&msg).unwrap().into_boxed_slice()
+}
+
+async fn receive<T: DeserializeOwned>(rx: &mut mpsc::Receiver<Box<[u8]>>) -> (Dir, T) {
+  let msg: Msg = bincode::deserialize(&rx.recv().await.unwrap()).unwrap();
+  (msg.dir, bincode::deserialize(&msg.data).unwrap())
+}
+
+/// Connects to a new node exposing `object` and returns the client end of the
+/// connection and the object's key.
+async fn node(object: LocalValue) -> (Addr<Connection>, u64) {
+  let (client_tx, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  let client: Addr<Connection> = Connection::new(client_tx, client_rx).start();
+  let server = Connection::new(server_tx, server_rx).start();
+  let key = server.pool().await.expose(object).await.unwrap().key;
+  (client, key)
+}
+
+async fn call(
+  connection: &Addr<Connection>,
+  object_id: u64,
+  method_id: u64,
+  argument: Option<LocalValue>,
+) -> Result<res::Res, Error> {
+  connection
+    .req(req::Req::Call(req::Call {
+      mutable: false,
+      object_id,
+      method_id,
+      argument,
+      store_result: false,
+      timeout: None,
+      metadata: Default::default(),
+    }))
+    .await
+}
+
+fn code(result: Result<res::Res, Error>) -> Option<ErrorCode> {
+  match result {
+    Err(Error::Remote { code, .. }) => Some(code),
+    _ => None,
+  }
+}
+
+#[test]
+fn reports_failed_requests() {
+  let mut system = actix::System::new("errors");
+  system.block_on(async {
+    let (connection, key) = node(LocalValue::from_object(Faulty)).await;
+    let echo = method_id("Faulty", "echo");
+
+    let result = call(&connection, key - 1, echo, Some(LocalValue::from_lit(&1u32))).await;
+    assert_eq!(code(result), Some(ErrorCode::UnknownObject));
+    assert_eq!(
+      code(call(&connection, key, 1, None).await),
+      Some(ErrorCode::UnknownMethod)
+    );
+    assert_eq!(
+      code(call(&connection, key, echo, None).await),
+      Some(ErrorCode::InvalidArgument)
+    );
+    let fail = method_id("Faulty", "fail");
+    assert_eq!(
+      code(call(&connection, key, fail, None).await),
+      Some(ErrorCode::Application)
+    );
+
+    let free = connection.req(req::Req::Free(req::Free { object_id: key - 1 })).await;
+    assert_eq!(code(free), Some(ErrorCode::UnknownObject));
+  });
+}
+
+#[test]
+fn survives_panicking_methods() {
+  let mut system = actix::System::new("errors");
+  system.block_on(async {
+    let (connection, key) = node(LocalValue::from_object(Faulty)).await;
+    let faulty = FaultyClient::from_value(connection.remote(key)).unwrap();
+
+    let panic = method_id("Faulty", "panic");
+    assert_eq!(
+      code(call(&connection, key, panic, None).await),
+      Some(ErrorCode::Internal)
+    );
+    assert!(matches!(faulty.panic().await, Err(CallError::Internal)));
+    assert!(matches!(faulty.fail().await, Err(CallError::Failed)));
+
+    // Neither the connection nor the object are affected.
+    assert_eq!(faulty.echo(7).await.unwrap(), 7);
+    let actor = faulty.into_value().into_actor().unwrap();
+    assert!(actor.proxy_info().await.is_some());
+  });
+}
+
+#[test]
+fn drops_undecodable_responses() {
+  let mut system = actix::System::new("errors");
+  system.block_on(async {
+    let (client_tx, server_rx) = mpsc::channel(16);
+    let (server_tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, client_rx) = mpsc::channel(16);
+    let client: Addr<Connection> = Connection::new(client_tx, client_rx).start();
+    let server = Connection::new(server_tx, server_rx).start();
+    let key = server
+      .pool()
+      .await
+      .expose(LocalValue::from_object(Faulty))
+      .await
+      .unwrap()
+      .key;
+
+    // Responses reach the client garbled: the first entirely, the others
+    // after their ID.
+    let garbled = Arc::new(AtomicU32::new(0));
+    let count = garbled.clone();
+    actix::spawn(async move {
+      while let Some(msg) = outgoing.recv().await {
+        let mut decoded: Msg = bincode::deserialize(&msg).unwrap();
+        if let Dir::Res = decoded.dir {
+          decoded.data = match count.fetch_add(1, Ordering::SeqCst) {
+            0 => vec![0xff; 3].into_boxed_slice(),
+            _ => [&decoded.data[..8], &[0xff; 3]].concat().into_boxed_slice(),
+          };
+        }
+        if incoming
+          .send(bincode::serialize(&decoded).unwrap().into_boxed_slice())
+          .await
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+
+    let echo = |timeout| {
+      client.req(req::Req::Call(req::Call {
+        mutable: false,
+        object_id: key,
+        method_id: method_id("Faulty", "echo"),
+        argument: Some(LocalValue::from_lit(&1u32)),
+        store_result: false,
+        timeout,
+        metadata: Default::default(),
+      }))
+    };
+    let result = echo(Some(Duration::from_millis(100))).await;
+    assert!(matches!(result, Err(Error::Timeout)));
+    // The connection is still open.
+    let result = echo(None).await;
+    assert!(matches!(result, Err(Error::Comm)));
+    assert_eq!(garbled.load(Ordering::SeqCst), 2);
+  });
+}
+
+#[test]
+fn answers_undecodable_requests() {
+  let mut system = actix::System::new("errors");
+  system.block_on(async {
+    let (mut incoming, server_rx) = mpsc::channel(16);
+    let (server_tx, mut outgoing) = mpsc::channel(16);
+    let _server = Connection::new(server_tx, server_rx).start();
+
+    let _: (Dir, Hello) = receive(&mut outgoing).await;
+    incoming
+      .send(encode(Dir::Hello, &Hello::new(1, Vec::new())))
+      .await
+      .unwrap();
+    let _: (Dir, Welcome) = receive(&mut outgoing).await;
+
+    // Garbled past its ID, the request is answered.
+    let data = [&bincode::serialize(&7u64).unwrap()[..], &[0xff; 3]].concat();
+    let msg = Msg {
+      dir: Dir::Req,
+      data: data.into_boxed_slice(),
+    };
+    incoming
+      .send(bincode::serialize(&msg).unwrap().into_boxed_slice())
+      .await
+      .unwrap();
+    let (dir, res): (Dir, proto::Res) = receive(&mut outgoing).await;
+    assert!(matches!(dir, Dir::Res));
+    assert_eq!(res.id, 7);
+    assert!(matches!(res.ty, proto::res::Ty::Error(err) if err.code == ErrorCode::InvalidArgument));
+
+    // Without an ID, it's dropped and the connection stays open.
+    let msg = Msg {
+      dir: Dir::Req,
+      data: vec![0xff; 3].into_boxed_slice(),
+    };
+    incoming
+      .send(bincode::serialize(&msg).unwrap().into_boxed_slice())
+      .await
+      .unwrap();
+    let free = proto::Req {
+      id: 8,
+      ty: proto::req::Ty::Free(proto::req::Free { object_id: 100 }),
+    };
+    incoming.send(encode(Dir::Req, &free)).await.unwrap();
+    let (_, res): (Dir, proto::Res) = receive(&mut outgoing).await;
+    assert_eq!(res.id, 8);
+    assert!(matches!(res.ty, proto::res::Ty::Error(err) if err.code == ErrorCode::UnknownObject));
+  });
+}
+
+#[test]
+fn fails_outstanding_requests_on_close() {
+  let mut system = actix::System::new("errors");
+  system.block_on(async {
+    let (client_tx, server_rx) = mpsc::channel(16);
+    let (server_tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, client_rx) = mpsc::channel(16);
+    let client: Addr<Connection> = Connection::new(client_tx, client_rx).start();
+    let server = Connection::new(server_tx, server_rx).start();
+    let key = server
+      .pool()
+      .await
+      .expose(LocalValue::from_object(Faulty))
+      .await
+      .unwrap()
+      .key;
+
+    // The server's end closes instead of responding.
+    actix::spawn(async move {
+      while let Some(msg) = outgoing.recv().await {
+        let decoded: Msg = bincode::deserialize(&msg).unwrap();
+        if let Dir::Res = decoded.dir {
+          break;
+        }
+        if incoming.send(msg).await.is_err() {
+          break;
+        }
+      }
+    });
+
+    let echo = || {
+      client.req(req::Req::Call(req::Call {
+        mutable: false,
+        object_id: key,
+        method_id: method_id("Faulty", "echo"),
+        argument: Some(LocalValue::from_lit(&1u32)),
+        store_result: false,
+        timeout: None,
+        metadata: Default::default(),
+      }))
+    };
+    assert!(matches!(echo().await, Err(Error::Comm)));
+    assert!(matches!(echo().await, Err(Error::Comm)));
+  });
+}
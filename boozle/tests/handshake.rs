@@ -0,0 +1,94 @@
+use actix::{Actor, Addr};
+use boozle::connection::{req, res};
+use boozle::connection::{Connection, ConnectionHelpers, HandshakeError};
+use boozle::protocol::hello::{self, Hello, Incompatible};
+use boozle::protocol::{Dir, Msg};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::mpsc;
+
+fn encode<T: Serialize>(dir: Dir, payload: &T) -> Box<[u8]> {
+  let msg = Msg {
+    dir,
+    data: bincode::serialize(payload).unwrap().into_boxed_slice(),
+  };
+  bincode::serialize(&msg).unwrap().into_boxed_slice()
+}
+
+async fn receive<T: DeserializeOwned>(rx: &mut mpsc::Receiver<Box<[u8]>>) -> (Dir, T) {
+  let msg: Msg = bincode::deserialize(&rx.recv().await.unwrap()).unwrap();
+  (msg.dir, bincode::deserialize(&msg.data).unwrap())
+}
+
+#[test]
+fn agrees_on_versions_and_features() {
+  let mut system = actix::System::new("handshake");
+  system.block_on(async {
+    let (left_tx, right_rx) = mpsc::channel(16);
+    let (right_tx, left_rx) = mpsc::channel(16);
+    let left: Addr<Connection> = Connection::new(left_tx, left_rx)
+      .with_node_id(1)
+      .with_features(vec![hello::FEATURE_EVAL.to_string(), "extra".to_string()])
+      .start();
+    let right = Connection::new(right_tx, right_rx).with_node_id(2).start();
+
+    let welcome = left.handshake().await.unwrap();
+    assert_eq!(welcome.node_id, 2);
+    assert_eq!(welcome.version, hello::VERSION);
+    assert_eq!(welcome.codec, hello::CODEC_BINCODE);
+    assert_eq!(welcome.features, vec![hello::FEATURE_EVAL.to_string()]);
+    assert_eq!(right.handshake().await.unwrap().node_id, 1);
+  });
+}
+
+#[test]
+fn rejects_incompatible_peers() {
+  let mut system = actix::System::new("handshake");
+  system.block_on(async {
+    let (tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, rx) = mpsc::channel(16);
+    let connection: Addr<Connection> = Connection::new(tx, rx).start();
+
+    let (dir, ours): (Dir, Hello) = receive(&mut outgoing).await;
+    assert!(matches!(dir, Dir::Hello));
+    assert_eq!(ours.max_version, hello::VERSION);
+
+    // A peer that only speaks a newer version.
+    let mut theirs = Hello::new(3, Vec::new());
+    theirs.min_version = hello::VERSION + 1;
+    theirs.max_version = hello::VERSION + 1;
+    incoming.send(encode(Dir::Hello, &theirs)).await.unwrap();
+    let (dir, reason): (Dir, Incompatible) = receive(&mut outgoing).await;
+    assert!(matches!(dir, Dir::Reject));
+    let expected = Incompatible::Version {
+      min: hello::MIN_VERSION,
+      max: hello::VERSION,
+    };
+    assert_eq!(reason, expected);
+
+    assert_eq!(
+      connection.handshake().await,
+      Err(HandshakeError::Incompatible(expected))
+    );
+    let free = connection.req(req::Req::Free(req::Free { object_id: 0 })).await;
+    assert!(matches!(
+      free,
+      Err(res::Error::Handshake(HandshakeError::Incompatible(_)))
+    ));
+  });
+}
+
+#[test]
+fn reports_rejections() {
+  let mut system = actix::System::new("handshake");
+  system.block_on(async {
+    let (tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, rx) = mpsc::channel(16);
+    let connection: Addr<Connection> = Connection::new(tx, rx).start();
+
+    let _: (Dir, Hello) = receive(&mut outgoing).await;
+    incoming.send(encode(Dir::Reject, &Incompatible::Codec)).await.unwrap();
+    let err = connection.handshake().await.unwrap_err();
+    assert_eq!(err, HandshakeError::Rejected(Incompatible::Codec));
+    assert_eq!(err.to_string(), "rejected by the remote end: no common codec");
+  });
+}
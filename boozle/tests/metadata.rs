@@ -0,0 +1,122 @@
+use actix::{Actor, Addr};
+use boozle::connection::{req, res};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::context::{self, CallContext, Metadata};
+use boozle::object::CallError;
+use boozle::pipeline::Pipeline;
+use boozle::pool::PoolHelpers;
+use boozle::value::LocalValue;
+use boozle_gen::method_id;
+use boozle_ir::Value;
+use tokio::sync::mpsc;
+
+/// Answers with the metadata of the call.
+#[derive(Debug)]
+pub struct Echo;
+
+#[boozle::object]
+impl Echo {
+  async fn get(&self, key: String) -> Result<String, CallError> {
+    let context = CallContext::current().ok_or(CallError::Failed)?;
+    context.metadata.get(&key).cloned().ok_or(CallError::Failed)
+  }
+}
+
+fn metadata(pairs: &[(&str, &str)]) -> Metadata {
+  pairs
+    .iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+/// Connects to a new node exposing an `Echo` and returns the client end of the
+/// connection and the object's key.
+async fn node(defaults: Metadata) -> (Addr<Connection>, u64) {
+  let (client_tx, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  let client = Connection::new(client_tx, client_rx).with_metadata(defaults).start();
+  let server = Connection::new(server_tx, server_rx).start();
+  let key = server
+    .pool()
+    .await
+    .expose(LocalValue::from_object(Echo))
+    .await
+    .unwrap()
+    .key;
+  (client, key)
+}
+
+#[test]
+fn sends_default_metadata() {
+  let mut system = actix::System::new("metadata");
+  system.block_on(async {
+    let (connection, key) = node(metadata(&[("user", "ada"), ("trace", "1")])).await;
+    let echo = EchoClient::from_value(connection.remote(key)).unwrap();
+
+    assert_eq!(echo.get("user".to_string()).await.unwrap(), "ada");
+    assert_eq!(echo.get("trace".to_string()).await.unwrap(), "1");
+    assert!(matches!(echo.get("missing".to_string()).await, Err(CallError::Failed)));
+  });
+}
+
+#[test]
+fn overrides_metadata_per_call() {
+  let mut system = actix::System::new("metadata");
+  system.block_on(async {
+    let (connection, key) = node(metadata(&[("user", "ada"), ("trace", "1")])).await;
+    let echo = EchoClient::from_value(connection.remote(key)).unwrap();
+
+    let user = context::with_metadata(metadata(&[("user", "bob")]), async {
+      let trace = echo.get("trace".to_string()).await.unwrap();
+      assert_eq!(trace, "1");
+      context::with_metadata(metadata(&[("user", "eve")]), echo.get("user".to_string()))
+        .await
+        .unwrap()
+    })
+    .await;
+    assert_eq!(user, "eve");
+    assert_eq!(echo.get("user".to_string()).await.unwrap(), "ada");
+
+    let call = req::Req::Call(req::Call {
+      mutable: false,
+      object_id: key,
+      method_id: method_id("Echo", "get"),
+      argument: Some(LocalValue::from_lit(&"user".to_string())),
+      store_result: false,
+      timeout: None,
+      metadata: metadata(&[("user", "mallory")]),
+    });
+    match connection.req(call).await {
+      Ok(res::Res::Call(ret)) => assert_eq!(ret.value.unwrap().to_lit::<String>().unwrap(), "mallory"),
+      _ => panic!("the call failed"),
+    }
+  });
+}
+
+#[test]
+fn sends_metadata_with_programs() {
+  let mut system = actix::System::new("metadata");
+  system.block_on(async {
+    let (connection, key) = node(metadata(&[("user", "ada"), ("trace", "1")])).await;
+    let mut pipeline = Pipeline::new();
+    let echo = pipeline.value(connection.remote(key)).await;
+    let get = |key: &str| {
+      echo
+        .clone()
+        .call(method_id("Echo", "get"), Some(Value::String(key.to_string()).into()))
+    };
+
+    let result = pipeline.run(get("user")).await.unwrap().unwrap();
+    assert_eq!(result.to_lit::<String>().unwrap(), "ada");
+
+    // Every call of the program runs in the caller's context.
+    let (user, trace) = context::with_metadata(metadata(&[("user", "bob")]), async {
+      let user = pipeline.run(get("user")).await.unwrap().unwrap();
+      let trace = pipeline.run(get("trace")).await.unwrap().unwrap();
+      (user, trace)
+    })
+    .await;
+    assert_eq!(user.to_lit::<String>().unwrap(), "bob");
+    assert_eq!(trace.to_lit::<String>().unwrap(), "1");
+  });
+}
@@ -0,0 +1,295 @@
+use actix::{Actor, Addr};
+use boozle::connection::{req, res};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::{CallError, ObjectActorHelpers};
+use boozle::pipeline::Pipeline;
+use boozle::pool::PoolHelpers;
+use boozle::protocol;
+use boozle::value::LocalValue;
+use boozle_gen::method_id;
+use boozle_ir::{to_value, Instruction, Limits, Type, Unit, Value};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn add(&self, amount: u32) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(amount, Ordering::SeqCst) + amount)
+  }
+}
+
+#[derive(Debug)]
+pub struct Store;
+
+#[boozle::object]
+impl Store {
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+}
+
+#[derive(Debug)]
+pub struct Source;
+
+#[boozle::object]
+impl Source {
+  async fn amount(&self) -> Result<u32, CallError> {
+    Ok(5)
+  }
+
+  async fn text(&self, len: u32) -> Result<String, CallError> {
+    Ok("x".repeat(len as usize))
+  }
+}
+
+/// Connects to a new node exposing `object`. Returns a proxy for the object
+/// and a count of the requests the node receives.
+async fn node(object: LocalValue) -> (LocalValue, Arc<AtomicUsize>) {
+  node_with(object, |connection| connection).await
+}
+
+/// Like `node`, but both ends of the connection are set up by `configure`.
+async fn node_with(object: LocalValue, configure: impl Fn(Connection) -> Connection) -> (LocalValue, Arc<AtomicUsize>) {
+  let (client_tx, mut outgoing) = mpsc::channel(16);
+  let (mut incoming, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  let client: Addr<Connection> = configure(Connection::new(client_tx, client_rx)).start();
+  let server = configure(Connection::new(server_tx, server_rx)).start();
+
+  let received = Arc::new(AtomicUsize::new(0));
+  let count = received.clone();
+  actix::spawn(async move {
+    while let Some(msg) = outgoing.recv().await {
+      let decoded: protocol::Msg = bincode::deserialize(&msg).unwrap();
+      if let protocol::Dir::Req = decoded.dir {
+        count.fetch_add(1, Ordering::SeqCst);
+      }
+      if incoming.send(msg).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  let key = server.pool().await.expose(object).await.unwrap().key;
+  (client.remote(key), received)
+}
+
+#[test]
+fn pipelines_calls_across_nodes() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let (store, store_received) = node(LocalValue::from_object(Store)).await;
+    let (source, source_received) = node(LocalValue::from_object(Source)).await;
+
+    // store.counter(10).add(source.amount())
+    let mut pipeline = Pipeline::new();
+    let store = pipeline.value(store).await;
+    let source = pipeline.value(source).await;
+    let expr = store
+      .clone()
+      .call(method_id("Store", "counter"), Some(Value::U32(10).into()))
+      .call(
+        method_id("Counter", "add"),
+        Some(source.call(method_id("Source", "amount"), None)),
+      );
+    let result = pipeline.run(expr).await.unwrap().unwrap();
+    assert_eq!(result.to_lit::<u32>(), Some(15));
+
+    // Neither node needed more than one request.
+    assert_eq!(store_received.load(Ordering::SeqCst), 1);
+    assert_eq!(source_received.load(Ordering::SeqCst), 1);
+
+    // Results that are objects stay with their owner.
+    let counter = pipeline
+      .run(store.call(method_id("Store", "counter"), Some(Value::U32(1).into())))
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(counter.into_actor().is_some());
+    assert_eq!(store_received.load(Ordering::SeqCst), 2);
+  });
+}
+
+#[test]
+fn mixes_local_and_remote_objects() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let (source, source_received) = node(LocalValue::from_object(Source)).await;
+    let local = LocalValue::from_object(Counter {
+      value: AtomicU32::new(100),
+    });
+
+    let mut pipeline = Pipeline::new();
+    let local = pipeline.value(local).await;
+    let source = pipeline.value(source).await;
+    let expr = local.call(
+      method_id("Counter", "add"),
+      Some(source.clone().call(method_id("Source", "amount"), None)),
+    );
+    let result = pipeline.run(expr).await.unwrap().unwrap();
+    assert_eq!(result.to_lit::<u32>(), Some(105));
+    assert_eq!(source_received.load(Ordering::SeqCst), 1);
+
+    // Unknown methods fail the whole expression.
+    let err = pipeline.run(source.call(1u64, None)).await.err();
+    assert!(matches!(err, Some(CallError::UnknownMethod)));
+  });
+}
+
+#[test]
+fn skips_repeated_pure_calls() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let (store, _) = node(LocalValue::from_object(Store)).await;
+    let (source, source_received) = node(LocalValue::from_object(Source)).await;
+
+    // store.counter(source.amount()).add(source.amount()), where only the
+    // first source.amount() is made.
+    let mut pipeline = Pipeline::new().with_pure_methods(vec![method_id("Source", "amount")]);
+    let store = pipeline.value(store).await;
+    let source = pipeline.value(source).await;
+    let amount = || source.clone().call(method_id("Source", "amount"), None);
+    let expr = store
+      .call(method_id("Store", "counter"), Some(amount()))
+      .call(method_id("Counter", "add"), Some(amount()));
+    let result = pipeline.run(expr).await.unwrap().unwrap();
+    assert_eq!(result.to_lit::<u32>(), Some(10));
+    assert_eq!(source_received.load(Ordering::SeqCst), 1);
+  });
+}
+
+#[test]
+fn calls_with_dynamic_values() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let (source, _) = node(LocalValue::from_object(Source)).await;
+
+    // No `SourceClient`: the argument and result are dynamic values.
+    let mut pipeline = Pipeline::new();
+    let source = pipeline.value(source).await;
+    let argument = to_value(&3u32).unwrap();
+    let result = pipeline
+      .run(source.call(method_id("Source", "text"), Some(argument.into())))
+      .await
+      .unwrap()
+      .unwrap();
+    let ty = Type::named("String").unwrap();
+    assert_eq!(result.to_value(&ty), Some(Value::from("xxx")));
+    assert_eq!(result.to_value(&Type::Bool), None);
+
+    let lit = LocalValue::from_value(&Value::from(vec![1u16, 2])).unwrap();
+    assert_eq!(lit.to_lit::<Vec<u16>>(), Some(vec![1, 2]));
+  });
+}
+
+#[test]
+fn rejects_programs_over_limits() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let limits = Limits {
+      max_instructions: 4,
+      max_depth: 4,
+      max_memory: 4096,
+    };
+    let (source, _) = node_with(LocalValue::from_object(Source), |connection| {
+      connection.with_limits(limits)
+    })
+    .await;
+    let connection = source.clone().into_actor().unwrap();
+    let mut pipeline = Pipeline::new();
+    let source = pipeline.value(source).await;
+
+    let text = |len: u32| {
+      source
+        .clone()
+        .call(method_id("Source", "text"), Some(Value::U32(len).into()))
+    };
+    let result = pipeline.run(text(16)).await.unwrap().unwrap();
+    assert_eq!(result.to_lit::<String>().unwrap().len(), 16);
+
+    // Results count towards the memory limit.
+    assert!(matches!(pipeline.run(text(8192)).await, Err(CallError::LimitExceeded)));
+
+    // So does the length of the program: five instructions.
+    let amount = source.clone().call(method_id("Source", "amount"), None);
+    let chain = source.clone().call(method_id("Source", "text"), Some(amount));
+    assert!(matches!(pipeline.run(chain).await, Err(CallError::LimitExceeded)));
+
+    // Malformed programs are rejected before they run.
+    let info = connection.proxy_info().await.unwrap();
+    let unit = Unit {
+      inputs: 0,
+      outputs: 0,
+      consts: Vec::new(),
+      instructions: vec![Instruction::Pop],
+    };
+    let response = info
+      .connection
+      .req(req::Req::Eval(req::Eval {
+        unit,
+        imports: Vec::new(),
+        timeout: None,
+        metadata: Default::default(),
+      }))
+      .await;
+    assert!(matches!(
+      response,
+      Err(res::Error::Remote {
+        code: res::ErrorCode::InvalidArgument,
+        ..
+      })
+    ));
+  });
+}
+
+#[test]
+fn only_sends_programs_to_nodes_running_them() {
+  let mut system = actix::System::new("pipeline");
+  system.block_on(async {
+    let (source, received) = node_with(LocalValue::from_object(Source), |connection| {
+      connection.with_features(Vec::new())
+    })
+    .await;
+    let connection = source.clone().into_actor().unwrap();
+    let mut pipeline = Pipeline::new();
+    let source = pipeline.value(source).await;
+
+    let amount = source.call(method_id("Source", "amount"), None);
+    assert!(matches!(pipeline.run(amount).await, Err(CallError::UnknownMethod)));
+    assert_eq!(received.load(Ordering::SeqCst), 0);
+
+    // Nor does the node run programs it gets anyway.
+    let info = connection.proxy_info().await.unwrap();
+    let unit = Unit {
+      inputs: 0,
+      outputs: 0,
+      consts: Vec::new(),
+      instructions: Vec::new(),
+    };
+    let response = info
+      .connection
+      .req(req::Req::Eval(req::Eval {
+        unit,
+        imports: Vec::new(),
+        timeout: None,
+        metadata: Default::default(),
+      }))
+      .await;
+    assert!(matches!(
+      response,
+      Err(res::Error::Remote {
+        code: res::ErrorCode::UnknownMethod,
+        ..
+      })
+    ));
+  });
+}
@@ -0,0 +1,160 @@
+use actix::{Actor, Addr};
+use boozle::connection::{Connection, ConnectionHelpers};
+use boozle::object::{CallError, ObjectActorHelpers};
+use boozle::pool::PoolHelpers;
+use boozle::protocol;
+use boozle::value::LocalValue;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct Counter {
+  value: AtomicU32,
+}
+
+#[boozle::object]
+impl Counter {
+  async fn add(&self, amount: u32) -> Result<u32, CallError> {
+    Ok(self.value.fetch_add(amount, Ordering::SeqCst) + amount)
+  }
+
+  async fn fork(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+}
+
+#[derive(Debug)]
+pub struct Store;
+
+#[boozle::object]
+impl Store {
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    let counter = Counter {
+      value: AtomicU32::new(start),
+    };
+    Ok(CounterClient::from_value(LocalValue::from_object(counter)).unwrap())
+  }
+}
+
+/// Hands out counters of a store on another node.
+#[derive(Debug)]
+pub struct Relay {
+  store: StoreClient,
+}
+
+#[boozle::object]
+impl Relay {
+  async fn counter(&self, start: u32) -> Result<CounterClient, CallError> {
+    self.store.counter(start).await
+  }
+
+  async fn add_to(&self, counter: CounterClient) -> Result<u32, CallError> {
+    counter.add(1).await
+  }
+}
+
+/// Connects to a new node exposing `object` and returns a proxy for it.
+async fn node(object: LocalValue) -> LocalValue {
+  let (client_tx, server_rx) = mpsc::channel(16);
+  let (server_tx, client_rx) = mpsc::channel(16);
+  let client: Addr<Connection> = Connection::new(client_tx, client_rx).start();
+  let server = Connection::new(server_tx, server_rx).start();
+  let key = server.pool().await.expose(object).await.unwrap().key;
+  client.remote(key)
+}
+
+#[test]
+fn returns_results_of_remote_calls() {
+  let mut system = actix::System::new("remote");
+  system.block_on(async {
+    let store = StoreClient::from_value(node(LocalValue::from_object(Store)).await).unwrap();
+
+    // The counter stays on the store's node and is called through a proxy.
+    let counter = store.counter(10).await.unwrap();
+    let actor = counter.clone().into_value().into_actor().unwrap();
+    assert!(actor.proxy_info().await.is_some());
+    assert_eq!(counter.add(5).await.unwrap(), 15);
+    assert_eq!(counter.add(1).await.unwrap(), 16);
+
+    // Chained: the fork is made by the remote counter and returned by reference.
+    let fork = counter.fork(100).await.unwrap();
+    assert_eq!(fork.add(1).await.unwrap(), 101);
+    assert_eq!(counter.add(0).await.unwrap(), 16);
+  });
+}
+
+#[test]
+fn chains_calls_across_two_connections() {
+  let mut system = actix::System::new("remote");
+  system.block_on(async {
+    // client -> relay's node -> store's node
+    let store = StoreClient::from_value(node(LocalValue::from_object(Store)).await).unwrap();
+    let relay = node(LocalValue::from_object(Relay { store })).await;
+    let relay = RelayClient::from_value(relay).unwrap();
+
+    // A proxy of a proxy: calls go through the relay's node to the store's.
+    let counter = relay.counter(20).await.unwrap();
+    assert_eq!(counter.add(2).await.unwrap(), 22);
+    let fork = counter.fork(7).await.unwrap();
+    assert_eq!(fork.add(3).await.unwrap(), 10);
+
+    // Objects passed to the relay are called back over the same connection.
+    let local = CounterClient::from_value(LocalValue::from_object(Counter {
+      value: AtomicU32::new(40),
+    }))
+    .unwrap();
+    assert_eq!(relay.add_to(local.clone()).await.unwrap(), 41);
+    assert_eq!(local.add(0).await.unwrap(), 41);
+    assert_eq!(relay.add_to(counter).await.unwrap(), 23);
+  });
+}
+
+#[test]
+fn frees_dropped_proxies_in_one_request() {
+  let mut system = actix::System::new("remote");
+  system.block_on(async {
+    let (client_tx, mut outgoing) = mpsc::channel(16);
+    let (mut incoming, server_rx) = mpsc::channel(16);
+    let (server_tx, client_rx) = mpsc::channel(16);
+    let client: Addr<Connection> = Connection::new(client_tx, client_rx).start();
+    let server = Connection::new(server_tx, server_rx).start();
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let count = received.clone();
+    actix::spawn(async move {
+      while let Some(msg) = outgoing.recv().await {
+        let decoded: protocol::Msg = bincode::deserialize(&msg).unwrap();
+        if let protocol::Dir::Req = decoded.dir {
+          count.fetch_add(1, Ordering::SeqCst);
+        }
+        if incoming.send(msg).await.is_err() {
+          break;
+        }
+      }
+    });
+
+    let pool = server.pool().await;
+    let mut keys = Vec::new();
+    for start in 0..3 {
+      let counter = Counter {
+        value: AtomicU32::new(start),
+      };
+      keys.push(pool.expose(LocalValue::from_object(counter)).await.unwrap().key);
+    }
+    client.handshake().await.unwrap();
+
+    let proxies: Vec<LocalValue> = keys.iter().map(|key| client.remote(*key)).collect();
+    drop(proxies);
+    for key in keys {
+      while pool.get(key).await.is_ok() {
+        tokio::time::delay_for(Duration::from_millis(1)).await;
+      }
+    }
+    assert_eq!(received.load(Ordering::SeqCst), 1);
+  });
+}
@@ -97,7 +97,9 @@ async fn main() {
       argument: Some(LocalValue::from_lit(&val)),
       method_id: 0,
       mutable: false,
-      store_result: true
+      store_result: true,
+      timeout: None,
+      metadata: Default::default()
     })).await.unwrap();
     val += 1;
   }